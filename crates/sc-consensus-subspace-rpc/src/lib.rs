@@ -19,7 +19,10 @@
 
 #![feature(try_blocks)]
 
-use futures::{future, FutureExt, StreamExt};
+mod metrics;
+
+pub use crate::metrics::SubspaceRpcMetrics;
+use futures::{future, stream, FutureExt, Stream, StreamExt};
 use jsonrpsee::core::{async_trait, Error as JsonRpseeError, RpcResult};
 use jsonrpsee::proc_macros::rpc;
 use jsonrpsee::types::SubscriptionResult;
@@ -31,7 +34,7 @@ use sc_consensus_subspace::notification::SubspaceNotificationStream;
 use sc_consensus_subspace::{
     ArchivedSegmentNotification, NewSlotNotification, RewardSigningNotification, SubspaceLink,
 };
-use sc_rpc::SubscriptionTaskExecutor;
+use sc_rpc::{DenyUnsafe, SubscriptionTaskExecutor};
 use sc_utils::mpsc::TracingUnboundedSender;
 use sp_api::{ApiError, ProvideRuntimeApi};
 use sp_blockchain::HeaderBackend;
@@ -43,22 +46,27 @@ use sp_runtime::traits::{Block as BlockT, Zero};
 use std::collections::hash_map::Entry;
 use std::collections::HashMap;
 use std::error::Error;
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use subspace_core_primitives::{
-    Piece, PieceIndex, SegmentCommitment, SegmentHeader, SegmentIndex, Solution,
+    Piece, PieceIndex, PublicKey, SegmentCommitment, SegmentHeader, SegmentIndex, Solution,
 };
 use subspace_farmer_components::FarmerProtocolInfo;
 use subspace_networking::libp2p::Multiaddr;
 use subspace_rpc_primitives::{
-    FarmerAppInfo, RewardSignatureResponse, RewardSigningInfo, SlotInfo, SolutionResponse,
-    MAX_SEGMENT_INDEXES_PER_REQUEST,
+    ConnectedFarmers, DeclaredFarm, FarmerAppInfo, FarmerDeclaration, RewardSignatureResponse,
+    RewardSigningInfo, SlotInfo, SolutionResponse, MAX_SEGMENT_INDEXES_PER_REQUEST,
 };
 use tracing::{debug, error, warn};
 
 const SOLUTION_TIMEOUT: Duration = Duration::from_secs(2);
 const REWARD_SIGNING_TIMEOUT: Duration = Duration::from_millis(500);
+/// Farms that were not declared for this long are considered to be detached from the node.
+const FARM_DECLARATION_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+/// Limit on the number of declared farms tracked, declarations of new farms beyond this limit are
+/// ignored.
+const MAX_DECLARED_FARMS: usize = 10_000;
 
 /// Provides rpc methods for interacting with Subspace.
 #[rpc(client, server)]
@@ -97,6 +105,17 @@ pub trait SubspaceRpcApi {
     )]
     fn subscribe_archived_segment_header(&self);
 
+    /// Archived segment header subscription that first replays headers of segments archived
+    /// starting with `from_segment_index` (that are still known to the node), which allows farmer
+    /// to backfill segments missed during downtime, then continues with newly archived segments
+    /// the same way as `subspace_subscribeArchivedSegmentHeader`.
+    #[subscription(
+        name = "subspace_resubscribeArchivedSegments" => "subspace_archived_segment_header",
+        unsubscribe = "subspace_unsubscribeArchivedSegments",
+        item = SegmentHeader,
+    )]
+    fn resubscribe_archived_segments(&self, from_segment_index: SegmentIndex);
+
     #[method(name = "subspace_segmentCommitments")]
     async fn segment_commitments(
         &self,
@@ -117,6 +136,20 @@ pub trait SubspaceRpcApi {
         &self,
         segment_index: SegmentIndex,
     ) -> RpcResult<()>;
+
+    /// Declare farm attached to the node, farmer is expected to call this periodically for every
+    /// farm.
+    ///
+    /// Declarations are self-reported and not authenticated: anyone with access to the RPC can
+    /// declare any public key with any plotted space, so they are informational only and must not
+    /// be relied upon for anything else. The method is not restricted to unsafe RPC since farmers
+    /// commonly connect to nodes over safe RPC.
+    #[method(name = "subspace_declareFarmer")]
+    fn declare_farmer(&self, farmer_declaration: FarmerDeclaration) -> RpcResult<()>;
+
+    /// Farmers connected to the node over RPC and DSN along with farms they have declared.
+    #[method(name = "subspace_connectedFarmers")]
+    fn connected_farmers(&self) -> RpcResult<ConnectedFarmers>;
 }
 
 #[derive(Default)]
@@ -137,11 +170,18 @@ struct BlockSignatureSenders {
     senders: Vec<async_oneshot::Sender<RewardSignatureResponse>>,
 }
 
+struct FarmDeclaration {
+    plotted_space: u64,
+    declared_at: Instant,
+}
+
 pub trait SegmentHeaderProvider {
     fn get_segment_header(
         &self,
         segment_index: SegmentIndex,
     ) -> Result<Option<SegmentHeader>, Box<dyn Error>>;
+
+    fn max_segment_index(&self) -> SegmentIndex;
 }
 
 pub trait PieceProvider {
@@ -167,6 +207,11 @@ pub struct SubspaceRpc<Block: BlockT, Client, RBP: SegmentHeaderProvider, PP: Pi
     archived_segment_acknowledgement_senders:
         Arc<Mutex<ArchivedSegmentHeaderAcknowledgementSenders>>,
     next_subscription_id: AtomicU64,
+    slot_info_subscriptions: Arc<AtomicUsize>,
+    declared_farms: Arc<Mutex<HashMap<PublicKey, FarmDeclaration>>>,
+    dsn_connected_peers: Arc<AtomicUsize>,
+    metrics: Option<SubspaceRpcMetrics>,
+    deny_unsafe: DenyUnsafe,
 }
 
 /// [`SubspaceRpc`] is used for notifying subscribers about arrival of new slots and for
@@ -193,6 +238,9 @@ impl<Block: BlockT, Client, RBP: SegmentHeaderProvider, PP: PieceProvider>
         subspace_link: SubspaceLink<Block>,
         segment_header_provider: RBP,
         piece_provider: Option<PP>,
+        dsn_connected_peers: Arc<AtomicUsize>,
+        metrics: Option<SubspaceRpcMetrics>,
+        deny_unsafe: DenyUnsafe,
     ) -> Self {
         Self {
             client,
@@ -208,8 +256,110 @@ impl<Block: BlockT, Client, RBP: SegmentHeaderProvider, PP: PieceProvider>
             piece_provider,
             archived_segment_acknowledgement_senders: Arc::default(),
             next_subscription_id: AtomicU64::default(),
+            slot_info_subscriptions: Arc::default(),
+            declared_farms: Arc::default(),
+            dsn_connected_peers,
+            metrics,
+            deny_unsafe,
         }
     }
+
+    /// Number of farmers subscribed to slot info, shared with this handler.
+    pub fn slot_info_subscriptions(&self) -> Arc<AtomicUsize> {
+        Arc::clone(&self.slot_info_subscriptions)
+    }
+
+    /// Stream of headers of newly archived segments, archiver waits for acknowledgement of each
+    /// segment from subscription with `subscription_id`.
+    ///
+    /// Segments for which `skip_segment` returns `true` are not sent to the subscriber and are
+    /// acknowledged right away.
+    fn archived_segment_header_stream<SkipSegment>(
+        &self,
+        subscription_id: u64,
+        skip_segment: SkipSegment,
+    ) -> impl Stream<Item = SegmentHeader> + Send + 'static
+    where
+        SkipSegment: Fn(SegmentIndex) -> bool + Send + 'static,
+    {
+        let archived_segment_acknowledgement_senders =
+            self.archived_segment_acknowledgement_senders.clone();
+
+        self.archived_segment_notification_stream
+            .subscribe()
+            .filter_map(move |archived_segment_notification| {
+                let ArchivedSegmentNotification {
+                    archived_segment,
+                    acknowledgement_sender,
+                } = archived_segment_notification;
+
+                let segment_index = archived_segment.segment_header.segment_index();
+
+                if skip_segment(segment_index) {
+                    // Dropping acknowledgement sender acknowledges segment to the archiver
+                    return future::ready(None);
+                }
+
+                // Store acknowledgment sender so that we can retrieve it when acknowledgement
+                // comes from the farmer
+                {
+                    let mut archived_segment_acknowledgement_senders =
+                        archived_segment_acknowledgement_senders.lock();
+
+                    if archived_segment_acknowledgement_senders.segment_index != segment_index {
+                        archived_segment_acknowledgement_senders.segment_index = segment_index;
+                        archived_segment_acknowledgement_senders.senders.clear();
+                    }
+
+                    let maybe_archived_segment_header =
+                        match archived_segment_acknowledgement_senders
+                            .senders
+                            .entry(subscription_id)
+                        {
+                            Entry::Occupied(_) => {
+                                // No need to do anything, farmer is processing request
+                                None
+                            }
+                            Entry::Vacant(entry) => {
+                                entry.insert(acknowledgement_sender);
+
+                                // This will be sent to the farmer
+                                Some(archived_segment.segment_header)
+                            }
+                        };
+
+                    future::ready(maybe_archived_segment_header)
+                }
+            })
+    }
+
+    /// Pipe archived segment headers to the subscriber, cleaning up pending acknowledgement of
+    /// subscription with `subscription_id` once subscriber is gone.
+    fn spawn_archived_segment_header_subscription(
+        &self,
+        mut sink: SubscriptionSink,
+        stream: impl Stream<Item = SegmentHeader> + Send + 'static,
+        subscription_id: u64,
+    ) {
+        let archived_segment_acknowledgement_senders =
+            self.archived_segment_acknowledgement_senders.clone();
+        let fut = async move {
+            sink.pipe_from_stream(stream).await;
+
+            let mut archived_segment_acknowledgement_senders =
+                archived_segment_acknowledgement_senders.lock();
+
+            archived_segment_acknowledgement_senders
+                .senders
+                .remove(&subscription_id);
+        };
+
+        self.executor.spawn(
+            "subspace-archived-segment-header-subscription",
+            Some("rpc"),
+            fut.boxed(),
+        );
+    }
 }
 
 #[async_trait]
@@ -223,7 +373,7 @@ where
         + Sync
         + 'static,
     Client::Api: SubspaceRuntimeApi<Block, FarmerPublicKey>,
-    RBP: SegmentHeaderProvider + Send + Sync + 'static,
+    RBP: SegmentHeaderProvider + Clone + Send + Sync + 'static,
     PP: PieceProvider + Send + Sync + 'static,
 {
     fn get_farmer_app_info(&self) -> RpcResult<FarmerAppInfo> {
@@ -362,8 +512,20 @@ where
                     }
                 });
 
+        let slot_info_subscriptions = Arc::clone(&self.slot_info_subscriptions);
+        let metrics = self.metrics.clone();
+        let subscriptions = slot_info_subscriptions.fetch_add(1, Ordering::Relaxed) + 1;
+        if let Some(metrics) = &metrics {
+            metrics.set_slot_info_subscriptions(subscriptions);
+        }
+
         let fut = async move {
             sink.pipe_from_stream(stream).await;
+
+            let subscriptions = slot_info_subscriptions.fetch_sub(1, Ordering::Relaxed) - 1;
+            if let Some(metrics) = &metrics {
+                metrics.set_slot_info_subscriptions(subscriptions);
+            }
         };
 
         self.executor
@@ -472,73 +634,81 @@ where
         Ok(())
     }
 
-    fn subscribe_archived_segment_header(&self, mut sink: SubscriptionSink) -> SubscriptionResult {
-        let archived_segment_acknowledgement_senders =
-            self.archived_segment_acknowledgement_senders.clone();
-
+    fn subscribe_archived_segment_header(&self, sink: SubscriptionSink) -> SubscriptionResult {
         let subscription_id = self.next_subscription_id.fetch_add(1, Ordering::Relaxed);
 
-        let stream = self
-            .archived_segment_notification_stream
-            .subscribe()
-            .filter_map(move |archived_segment_notification| {
-                let ArchivedSegmentNotification {
-                    archived_segment,
-                    acknowledgement_sender,
-                } = archived_segment_notification;
+        let stream = self.archived_segment_header_stream(subscription_id, |_segment_index| false);
+        self.spawn_archived_segment_header_subscription(sink, stream, subscription_id);
 
-                let segment_index = archived_segment.segment_header.segment_index();
+        Ok(())
+    }
 
-                // Store acknowledgment sender so that we can retrieve it when acknowledgement
-                // comes from the farmer
-                {
-                    let mut archived_segment_acknowledgement_senders =
-                        archived_segment_acknowledgement_senders.lock();
+    fn resubscribe_archived_segments(
+        &self,
+        mut sink: SubscriptionSink,
+        from_segment_index: SegmentIndex,
+    ) -> SubscriptionResult {
+        if let Err(error) = self.deny_unsafe.check_if_safe() {
+            let _ = sink.reject(JsonRpseeError::from(error));
+            return Ok(());
+        }
 
-                    if archived_segment_acknowledgement_senders.segment_index != segment_index {
-                        archived_segment_acknowledgement_senders.segment_index = segment_index;
-                        archived_segment_acknowledgement_senders.senders.clear();
-                    }
+        let subscription_id = self.next_subscription_id.fetch_add(1, Ordering::Relaxed);
+        let last_replayed_segment_index = Arc::new(Mutex::new(None::<SegmentIndex>));
+
+        // Subscribe before replaying historical segment headers, such that no segments are missed
+        // in between, segments that were already replayed are skipped
+        let new_segment_headers = self.archived_segment_header_stream(subscription_id, {
+            let last_replayed_segment_index = Arc::clone(&last_replayed_segment_index);
+
+            move |segment_index| {
+                last_replayed_segment_index
+                    .lock()
+                    .map_or(false, |last_replayed_segment_index| {
+                        segment_index <= last_replayed_segment_index
+                    })
+            }
+        });
 
-                    let maybe_archived_segment_header =
-                        match archived_segment_acknowledgement_senders
-                            .senders
-                            .entry(subscription_id)
-                        {
-                            Entry::Occupied(_) => {
-                                // No need to do anything, farmer is processing request
-                                None
-                            }
-                            Entry::Vacant(entry) => {
-                                entry.insert(acknowledgement_sender);
+        let max_segment_index = self.segment_header_provider.max_segment_index();
 
-                                // This will be sent to the farmer
-                                Some(archived_segment.segment_header)
+        debug!(
+            %from_segment_index,
+            %max_segment_index,
+            "Replaying archived segment headers."
+        );
+
+        // Historical segment headers are read one by one as subscriber consumes them
+        let segment_header_provider = self.segment_header_provider.clone();
+        let historical_segment_headers =
+            stream::iter(u64::from(from_segment_index)..=u64::from(max_segment_index))
+                .map(SegmentIndex::from)
+                .scan((), move |(), segment_index| {
+                    let maybe_segment_header =
+                        match segment_header_provider.get_segment_header(segment_index) {
+                            Ok(maybe_segment_header) => Some(maybe_segment_header),
+                            Err(error) => {
+                                error!(
+                                    %segment_index,
+                                    %error,
+                                    "Failed to get segment header for replay."
+                                );
+                                None
                             }
                         };
 
-                    Box::pin(async move { maybe_archived_segment_header })
-                }
-            });
-
-        let archived_segment_acknowledgement_senders =
-            self.archived_segment_acknowledgement_senders.clone();
-        let fut = async move {
-            sink.pipe_from_stream(stream).await;
-
-            let mut archived_segment_acknowledgement_senders =
-                archived_segment_acknowledgement_senders.lock();
-
-            archived_segment_acknowledgement_senders
-                .senders
-                .remove(&subscription_id);
-        };
+                    future::ready(maybe_segment_header)
+                })
+                // Segment header was pruned or segment was not archived yet
+                .filter_map(future::ready)
+                .inspect(move |segment_header| {
+                    last_replayed_segment_index
+                        .lock()
+                        .replace(segment_header.segment_index());
+                });
 
-        self.executor.spawn(
-            "subspace-archived-segment-header-subscription",
-            Some("rpc"),
-            fut.boxed(),
-        );
+        let stream = historical_segment_headers.chain(new_segment_headers);
+        self.spawn_archived_segment_header_subscription(sink, stream, subscription_id);
 
         Ok(())
     }
@@ -689,4 +859,57 @@ where
             ))
         }
     }
+
+    fn declare_farmer(&self, farmer_declaration: FarmerDeclaration) -> RpcResult<()> {
+        let FarmerDeclaration {
+            public_key,
+            plotted_space,
+        } = farmer_declaration;
+
+        let mut declared_farms = self.declared_farms.lock();
+        declared_farms.retain(|_public_key, declaration| {
+            declaration.declared_at.elapsed() < FARM_DECLARATION_TIMEOUT
+        });
+
+        if declared_farms.len() >= MAX_DECLARED_FARMS && !declared_farms.contains_key(&public_key) {
+            debug!(%public_key, "Too many declared farms, ignoring declaration");
+            return Ok(());
+        }
+
+        declared_farms.insert(
+            public_key,
+            FarmDeclaration {
+                plotted_space,
+                declared_at: Instant::now(),
+            },
+        );
+
+        Ok(())
+    }
+
+    fn connected_farmers(&self) -> RpcResult<ConnectedFarmers> {
+        let mut declared_farms = self.declared_farms.lock();
+        declared_farms.retain(|_public_key, declaration| {
+            declaration.declared_at.elapsed() < FARM_DECLARATION_TIMEOUT
+        });
+
+        let declared_farms = declared_farms
+            .iter()
+            .map(|(public_key, declaration)| DeclaredFarm {
+                public_key: *public_key,
+                plotted_space: declaration.plotted_space,
+                last_declared_secs_ago: declaration.declared_at.elapsed().as_secs(),
+            })
+            .collect::<Vec<_>>();
+
+        Ok(ConnectedFarmers {
+            rpc_subscriptions: self.slot_info_subscriptions.load(Ordering::Relaxed) as u64,
+            dsn_connected_peers: self.dsn_connected_peers.load(Ordering::Relaxed) as u64,
+            declared_plotted_space: declared_farms
+                .iter()
+                .map(|declared_farm| declared_farm.plotted_space)
+                .fold(0u64, u64::saturating_add),
+            declared_farms,
+        })
+    }
 }
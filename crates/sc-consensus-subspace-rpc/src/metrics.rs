@@ -0,0 +1,26 @@
+use substrate_prometheus_endpoint::{register, Gauge, PrometheusError, Registry, U64};
+
+/// Metrics of the Subspace RPC
+#[derive(Debug, Clone)]
+pub struct SubspaceRpcMetrics {
+    slot_info_subscriptions: Gauge<U64>,
+}
+
+impl SubspaceRpcMetrics {
+    /// Create and register metrics in provided registry
+    pub fn new(registry: &Registry) -> Result<Self, PrometheusError> {
+        Ok(Self {
+            slot_info_subscriptions: register(
+                Gauge::new(
+                    "subspace_rpc_slot_info_subscriptions",
+                    "Number of active slot info subscriptions",
+                )?,
+                registry,
+            )?,
+        })
+    }
+
+    pub(crate) fn set_slot_info_subscriptions(&self, subscriptions: usize) {
+        self.slot_info_subscriptions.set(subscriptions as u64);
+    }
+}
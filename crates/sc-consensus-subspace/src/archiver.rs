@@ -14,13 +14,19 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
+mod metrics;
+
+use crate::archiver::metrics::ArchiverMetrics;
 use crate::{
     get_chain_constants, ArchivedSegmentNotification, BlockImportingNotification, SubspaceLink,
     SubspaceNotificationSender,
 };
 use codec::Encode;
-use futures::StreamExt;
+use futures::channel::{mpsc, oneshot};
+use futures::{executor, stream, SinkExt, StreamExt};
 use log::{debug, error, info, warn};
+use prometheus_endpoint::Registry;
+use rayon::ThreadPoolBuilder;
 use sc_client_api::{AuxStore, Backend as BackendT, BlockBackend, Finalizer, LockImportRun};
 use sc_telemetry::{telemetry, TelemetryHandle, CONSENSUS_INFO};
 use sc_utils::mpsc::tracing_unbounded;
@@ -29,10 +35,13 @@ use sp_blockchain::HeaderBackend;
 use sp_consensus_subspace::{FarmerPublicKey, SubspaceApi};
 use sp_objects::ObjectsApi;
 use sp_runtime::generic::SignedBlock;
-use sp_runtime::traits::{Block as BlockT, CheckedSub, Header, NumberFor, One, Zero};
+use sp_runtime::traits::{Block as BlockT, CheckedSub, Header, NumberFor, One, Saturating, Zero};
+use sp_runtime::SaturatedConversion;
 use std::future::Future;
+use std::num::NonZeroUsize;
 use std::sync::Arc;
-use subspace_archiving::archiver::{Archiver, NewArchivedSegment};
+use std::time::Instant;
+use subspace_archiving::archiver::{ArchivedSegmentsStream, Archiver, NewArchivedSegment};
 use subspace_core_primitives::crypto::kzg::Kzg;
 use subspace_core_primitives::objects::BlockObjectMapping;
 use subspace_core_primitives::{BlockNumber, SegmentHeader};
@@ -191,9 +200,138 @@ where
     best_archived_block: (Block::Hash, NumberFor<Block>),
 }
 
+/// Create archiver from the last segment header found on chain starting from
+/// `last_archived_search_from` (or from genesis if there is none), returns archiver and last
+/// archived block (`None` if archiving starts from genesis).
+fn create_archiver<Block, Client>(
+    client: &Client,
+    last_archived_search_from: Block::Hash,
+    kzg: Kzg,
+) -> (Archiver, Option<(Block::Hash, NumberFor<Block>)>)
+where
+    Block: BlockT,
+    Client: ProvideRuntimeApi<Block> + BlockBackend<Block> + HeaderBackend<Block>,
+    Client::Api: SubspaceApi<Block, FarmerPublicKey> + ObjectsApi<Block>,
+{
+    if let Some((last_segment_header, last_archived_block, block_object_mappings)) =
+        find_last_archived_block(client, last_archived_search_from)
+    {
+        // Continuing from existing initial state
+        let last_archived_block_number = last_segment_header.last_archived_block().number;
+        info!(
+            target: "subspace",
+            "Last archived block {}",
+            last_archived_block_number,
+        );
+
+        let archiver = Archiver::with_initial_state(
+            kzg,
+            last_segment_header,
+            &last_archived_block.encode(),
+            block_object_mappings,
+        )
+        .expect("Incorrect parameters for archiver");
+
+        (
+            archiver,
+            Some((
+                last_archived_block.block.hash(),
+                *last_archived_block.block.header().number(),
+            )),
+        )
+    } else {
+        info!(target: "subspace", "Starting archiving from genesis");
+
+        (
+            Archiver::new(kzg).expect("Incorrect parameters for archiver"),
+            None,
+        )
+    }
+}
+
+/// Range of blocks (inclusive) that were produced, but not archived by `archiver` yet, up to the
+/// best block minus confirmation depth.
+fn blocks_to_archive<Block>(
+    archiver: &Archiver,
+    best_block_number: NumberFor<Block>,
+    confirmation_depth_k: BlockNumber,
+    have_last_segment_header: bool,
+) -> Option<(BlockNumber, BlockNumber)>
+where
+    Block: BlockT,
+{
+    let blocks_to_archive_from = archiver
+        .last_archived_block_number()
+        .map(|n| n + 1)
+        .unwrap_or_default();
+    let blocks_to_archive_to = TryInto::<BlockNumber>::try_into(best_block_number)
+        .unwrap_or_else(|_| {
+            panic!("Best block number {best_block_number} can't be converted into BlockNumber");
+        })
+        .checked_sub(confirmation_depth_k)
+        .or({
+            if have_last_segment_header {
+                None
+            } else {
+                // If not continuation, archive genesis block
+                Some(0)
+            }
+        })?;
+
+    Some((blocks_to_archive_from, blocks_to_archive_to))
+}
+
+/// Encoded block that was already produced, with its object mappings.
+fn encode_block_to_archive<Block, Client>(
+    client: &Client,
+    block_hash_to_archive: Block::Hash,
+) -> (NumberFor<Block>, Vec<u8>, BlockObjectMapping)
+where
+    Block: BlockT,
+    Client: ProvideRuntimeApi<Block> + BlockBackend<Block>,
+    Client::Api: ObjectsApi<Block>,
+{
+    let block = client
+        .block(block_hash_to_archive)
+        .expect("Older block by number must always exist")
+        .expect("Older block by number must always exist");
+    let block_number_to_archive = *block.block.header().number();
+
+    let block_object_mappings = client
+        .runtime_api()
+        .validated_object_call_hashes(block_hash_to_archive)
+        .and_then(|calls| {
+            client.runtime_api().extract_block_object_mapping(
+                *block.block.header().parent_hash(),
+                block.block.clone(),
+                calls,
+            )
+        })
+        .unwrap_or_default();
+
+    let encoded_block = block.encode();
+    debug!(
+        target: "subspace",
+        "Encoded block {} has size of {:.2} kiB",
+        block_number_to_archive,
+        encoded_block.len() as f32 / 1024.0
+    );
+
+    (
+        block_number_to_archive,
+        encoded_block,
+        block_object_mappings,
+    )
+}
+
+/// Initialize archiver from the last segment header found on chain.
+///
+/// Last archived block is searched starting from `last_archived_search_from` (normally best
+/// block), blocks after it are archived up to the best block minus confirmation depth.
 fn initialize_archiver<Block, Client>(
     best_block_hash: Block::Hash,
     best_block_number: NumberFor<Block>,
+    last_archived_search_from: Block::Hash,
     subspace_link: &SubspaceLink<Block>,
     client: &Client,
     kzg: Kzg,
@@ -207,68 +345,22 @@ where
         .expect("Must always be able to get chain constants")
         .confirmation_depth_k();
 
-    let maybe_last_archived_block = find_last_archived_block(client, best_block_hash);
-    let have_last_segment_header = maybe_last_archived_block.is_some();
-    let mut best_archived_block = None;
-
-    let mut archiver =
-        if let Some((last_segment_header, last_archived_block, block_object_mappings)) =
-            maybe_last_archived_block
-        {
-            // Continuing from existing initial state
-            let last_archived_block_number = last_segment_header.last_archived_block().number;
-            info!(
-                target: "subspace",
-                "Last archived block {}",
-                last_archived_block_number,
-            );
-
-            // Set initial value, this is needed in case only genesis block was archived and there
-            // is nothing else available
-            best_archived_block.replace((
-                last_archived_block.block.hash(),
-                *last_archived_block.block.header().number(),
-            ));
-
-            Archiver::with_initial_state(
-                kzg,
-                last_segment_header,
-                &last_archived_block.encode(),
-                block_object_mappings,
-            )
-            .expect("Incorrect parameters for archiver")
-        } else {
-            info!(target: "subspace", "Starting archiving from genesis");
-
-            Archiver::new(kzg).expect("Incorrect parameters for archiver")
-        };
+    // Best archived block is set initially in case only genesis block was archived and there is
+    // nothing else available
+    let (mut archiver, mut best_archived_block) =
+        create_archiver(client, last_archived_search_from, kzg);
+    let have_last_segment_header = best_archived_block.is_some();
 
     let mut older_archived_segments = Vec::new();
 
     // Process blocks since last fully archived block (or genesis) up to the current head minus K
     {
-        let blocks_to_archive_from = archiver
-            .last_archived_block_number()
-            .map(|n| n + 1)
-            .unwrap_or_default();
-        let blocks_to_archive_to =
-            TryInto::<BlockNumber>::try_into(best_block_number)
-                .unwrap_or_else(|_| {
-                    panic!(
-                        "Best block number {best_block_number} can't be converted into BlockNumber",
-                    );
-                })
-                .checked_sub(confirmation_depth_k)
-                .or({
-                    if have_last_segment_header {
-                        None
-                    } else {
-                        // If not continuation, archive genesis block
-                        Some(0)
-                    }
-                });
-
-        if let Some(blocks_to_archive_to) = blocks_to_archive_to {
+        if let Some((blocks_to_archive_from, blocks_to_archive_to)) = blocks_to_archive::<Block>(
+            &archiver,
+            best_block_number,
+            confirmation_depth_k,
+            have_last_segment_header,
+        ) {
             info!(
                 target: "subspace",
                 "Archiving already produced blocks {}..={}",
@@ -286,31 +378,8 @@ where
             let block_hashes_to_archive = block_hashes_to_archive.block_hashes;
 
             for block_hash_to_archive in block_hashes_to_archive.into_iter().rev() {
-                let block = client
-                    .block(block_hash_to_archive)
-                    .expect("Older block by number must always exist")
-                    .expect("Older block by number must always exist");
-                let block_number_to_archive = *block.block.header().number();
-
-                let block_object_mappings = client
-                    .runtime_api()
-                    .validated_object_call_hashes(block_hash_to_archive)
-                    .and_then(|calls| {
-                        client.runtime_api().extract_block_object_mapping(
-                            *block.block.header().parent_hash(),
-                            block.block.clone(),
-                            calls,
-                        )
-                    })
-                    .unwrap_or_default();
-
-                let encoded_block = block.encode();
-                debug!(
-                    target: "subspace",
-                    "Encoded block {} has size of {:.2} kiB",
-                    block_number_to_archive,
-                    encoded_block.len() as f32 / 1024.0
-                );
+                let (block_number_to_archive, encoded_block, block_object_mappings) =
+                    encode_block_to_archive(client, block_hash_to_archive);
 
                 let archived_segments = archiver.add_block(encoded_block, block_object_mappings);
                 let new_segment_headers: Vec<SegmentHeader> = archived_segments
@@ -390,12 +459,18 @@ fn finalize_block<Block, Backend, Client>(
 /// producing pieces and segment headers (segment headers are then added back to the blockchain as
 /// `store_segment_header` extrinsic).
 ///
+/// Erasure coding and commitments computation of archived segments are parallelized across a
+/// dedicated thread pool with `archiver_threads` threads (number of logical CPUs by default), such
+/// that archiving of large blocks doesn't slow down block import.
+///
 /// NOTE: Archiver is doing blocking operations and must run in a dedicated task.
 pub fn create_subspace_archiver<Block, Backend, Client>(
     subspace_link: &SubspaceLink<Block>,
     client: Arc<Client>,
     telemetry: Option<TelemetryHandle>,
-) -> impl Future<Output = ()> + Send + 'static
+    archiver_threads: Option<NonZeroUsize>,
+    registry: Option<&Registry>,
+) -> sp_blockchain::Result<impl Future<Output = ()> + Send + 'static>
 where
     Block: BlockT,
     Backend: BackendT<Block>,
@@ -414,18 +489,38 @@ where
     let best_block_hash = client_info.best_hash;
     let best_block_number = client_info.best_number;
 
+    let thread_pool = ThreadPoolBuilder::new()
+        .thread_name(|thread_index| format!("subspace-archiver-{thread_index}"))
+        .num_threads(archiver_threads.map(NonZeroUsize::get).unwrap_or_default())
+        .build()
+        .map_err(|error| {
+            sp_blockchain::Error::Application(
+                format!("Failed to create archiver thread pool: {error}").into(),
+            )
+        })?;
+    let metrics = registry.and_then(|registry| match ArchiverMetrics::new(registry) {
+        Ok(metrics) => Some(metrics),
+        Err(error) => {
+            error!(target: "subspace", "Failed to initialize archiver metrics: {error:?}");
+            None
+        }
+    });
+
     let InitializedArchiver {
         confirmation_depth_k,
         mut archiver,
         older_archived_segments,
         best_archived_block: (mut best_archived_block_hash, mut best_archived_block_number),
-    } = initialize_archiver(
-        best_block_hash,
-        best_block_number,
-        subspace_link,
-        client.as_ref(),
-        subspace_link.kzg.clone(),
-    );
+    } = thread_pool.install(|| {
+        initialize_archiver(
+            best_block_hash,
+            best_block_number,
+            best_block_hash,
+            subspace_link,
+            client.as_ref(),
+            subspace_link.kzg.clone(),
+        )
+    });
 
     let mut block_importing_notification_stream = subspace_link
         .block_importing_notification_stream
@@ -434,7 +529,7 @@ where
         subspace_link.archived_segment_notification_sender.clone();
     let segment_headers = Arc::clone(&subspace_link.segment_headers);
 
-    async move {
+    Ok(async move {
         // Farmers may have not received all previous segments, send them now.
         for archived_segment in older_archived_segments {
             send_archived_segment_notification(
@@ -466,6 +561,7 @@ where
             }
 
             best_archived_block_number = block_number_to_archive;
+            let archiving_started = Instant::now();
 
             let block = client
                 .block(
@@ -528,8 +624,27 @@ where
                 encoded_block.len() as f32 / 1024.0
             );
 
+            let archived_segments =
+                thread_pool.install(|| archiver.add_block(encoded_block, block_object_mappings));
+
+            if let Some(metrics) = &metrics {
+                metrics.on_block_archived(
+                    block_number_to_archive.saturated_into(),
+                    archiving_started.elapsed(),
+                );
+                // Block that triggered archiving might not be the best block yet, whichever is
+                // higher is the tip archiver is trying to keep up with
+                let best_block_number = client.info().best_number.max(block_number);
+                metrics.set_lag(
+                    best_block_number
+                        .saturating_sub(confirmation_depth_k.into())
+                        .saturating_sub(block_number_to_archive)
+                        .saturated_into(),
+                );
+            }
+
             let mut new_segment_headers = Vec::new();
-            for archived_segment in archiver.add_block(encoded_block, block_object_mappings) {
+            for archived_segment in archived_segments {
                 let segment_header = archived_segment.segment_header;
 
                 send_archived_segment_notification(
@@ -568,7 +683,140 @@ where
                 }
             }
         }
+    })
+}
+
+/// Re-run archiving starting from the last segment header included in the chain at or before
+/// `from_block`, regenerating all segments since then up to the best block minus confirmation
+/// depth.
+///
+/// Archived segment notifications are sent for every regenerated segment as soon as it is produced
+/// and acknowledgements are awaited the same way as during regular archiving, which allows
+/// subscribers to rebuild data derived from archived segments (segment headers, object mappings,
+/// etc.). Returns number of regenerated segments.
+///
+/// Blocks are archived one by one on a dedicated thread pool with `archiver_threads` threads
+/// (number of logical CPUs by default), such that only a few segments are held in memory at any
+/// time.
+///
+/// NOTE: This is not supposed to run concurrently with regular archiver.
+pub async fn rearchive_from_block<Block, Client>(
+    subspace_link: &SubspaceLink<Block>,
+    client: Arc<Client>,
+    from_block: NumberFor<Block>,
+    archiver_threads: Option<NonZeroUsize>,
+) -> Result<usize, sp_blockchain::Error>
+where
+    Block: BlockT,
+    Client: ProvideRuntimeApi<Block>
+        + BlockBackend<Block>
+        + HeaderBackend<Block>
+        + AuxStore
+        + Send
+        + Sync
+        + 'static,
+    Client::Api: SubspaceApi<Block, FarmerPublicKey> + ObjectsApi<Block>,
+{
+    let client_info = client.info();
+    if from_block > client_info.best_number {
+        return Err(sp_blockchain::Error::Application(
+            format!(
+                "Can't re-archive from block {from_block}, best block is {}",
+                client_info.best_number
+            )
+            .into(),
+        ));
+    }
+    let from_block_hash = client.hash(from_block)?.ok_or_else(|| {
+        sp_blockchain::Error::UnknownBlock(format!("Block {from_block} is not found"))
+    })?;
+    let confirmation_depth_k = get_chain_constants(client.as_ref())
+        .map_err(|error| {
+            sp_blockchain::Error::Application(
+                format!("Failed to get chain constants: {error}").into(),
+            )
+        })?
+        .confirmation_depth_k();
+
+    let thread_pool = ThreadPoolBuilder::new()
+        .thread_name(|thread_index| format!("subspace-rearchiver-{thread_index}"))
+        .num_threads(archiver_threads.map(NonZeroUsize::get).unwrap_or_default())
+        .build()
+        .map_err(|error| {
+            sp_blockchain::Error::Application(
+                format!("Failed to create archiver thread pool: {error}").into(),
+            )
+        })?;
+
+    // No buffering, such that archiving doesn't get ahead of subscribers by more than a segment
+    let (mut archived_segments_sender, mut archived_segments_receiver) =
+        mpsc::channel::<NewArchivedSegment>(0);
+    let (archiving_result_sender, archiving_result_receiver) = oneshot::channel();
+    let kzg = subspace_link.kzg.clone();
+
+    thread_pool.spawn(move || {
+        let (archiver, best_archived_block) =
+            create_archiver(client.as_ref(), from_block_hash, kzg);
+
+        let Some((blocks_to_archive_from, blocks_to_archive_to)) = blocks_to_archive::<Block>(
+            &archiver,
+            client_info.best_number,
+            confirmation_depth_k,
+            best_archived_block.is_some(),
+        ) else {
+            let _ = archiving_result_sender.send(0);
+            return;
+        };
+
+        info!(
+            target: "subspace",
+            "Re-archiving blocks {}..={}",
+            blocks_to_archive_from,
+            blocks_to_archive_to,
+        );
+
+        let block_hashes_to_archive = block_hashes_to_archive(
+            client.as_ref(),
+            client_info.best_hash,
+            blocks_to_archive_from.into(),
+            blocks_to_archive_to.into(),
+        )
+        .block_hashes;
+
+        // Blocks are only read and encoded once previously produced segments were consumed
+        let blocks =
+            stream::iter(block_hashes_to_archive.into_iter().rev()).map(|block_hash_to_archive| {
+                let (_block_number, encoded_block, block_object_mappings) =
+                    encode_block_to_archive(client.as_ref(), block_hash_to_archive);
+
+                (encoded_block, block_object_mappings)
+            });
+
+        let mut regenerated_segments = 0;
+        for archived_segment in
+            executor::block_on_stream(ArchivedSegmentsStream::new(archiver, blocks))
+        {
+            if executor::block_on(archived_segments_sender.send(archived_segment)).is_err() {
+                // Receiver is gone, nobody is interested in further segments
+                return;
+            }
+            regenerated_segments += 1;
+        }
+
+        let _ = archiving_result_sender.send(regenerated_segments);
+    });
+
+    while let Some(archived_segment) = archived_segments_receiver.next().await {
+        send_archived_segment_notification(
+            &subspace_link.archived_segment_notification_sender,
+            archived_segment,
+        )
+        .await;
     }
+
+    archiving_result_receiver.await.map_err(|_error| {
+        sp_blockchain::Error::Application("Re-archiving has stopped unexpectedly".into())
+    })
 }
 
 async fn send_archived_segment_notification(
@@ -0,0 +1,55 @@
+use prometheus_endpoint::{
+    exponential_buckets, register, Gauge, Histogram, HistogramOpts, PrometheusError, Registry, U64,
+};
+use std::time::Duration;
+
+/// Metrics of the archiver
+#[derive(Debug, Clone)]
+pub(super) struct ArchiverMetrics {
+    last_archived_block: Gauge<U64>,
+    lag: Gauge<U64>,
+    block_archiving_duration: Histogram,
+}
+
+impl ArchiverMetrics {
+    /// Create and register metrics in provided registry
+    pub(super) fn new(registry: &Registry) -> Result<Self, PrometheusError> {
+        Ok(Self {
+            last_archived_block: register(
+                Gauge::new(
+                    "subspace_archiver_last_archived_block",
+                    "Number of the last block archived by the node",
+                )?,
+                registry,
+            )?,
+            lag: register(
+                Gauge::new(
+                    "subspace_archiver_lag_blocks",
+                    "Number of blocks beyond confirmation depth that are waiting to be archived",
+                )?,
+                registry,
+            )?,
+            block_archiving_duration: register(
+                Histogram::with_opts(
+                    HistogramOpts::new(
+                        "subspace_archiver_block_archiving_duration_seconds",
+                        "Time spent archiving a block, including segment encoding when block \
+                        completes a segment",
+                    )
+                    .buckets(exponential_buckets(0.001, 2.0, 16)?),
+                )?,
+                registry,
+            )?,
+        })
+    }
+
+    pub(super) fn set_lag(&self, blocks: u64) {
+        self.lag.set(blocks);
+    }
+
+    pub(super) fn on_block_archived(&self, block_number: u64, duration: Duration) {
+        self.last_archived_block.set(block_number);
+        self.block_archiving_duration
+            .observe(duration.as_secs_f64());
+    }
+}
@@ -30,7 +30,7 @@ mod tests;
 use crate::archiver::FINALIZATION_DEPTH_IN_SEGMENTS;
 use crate::notification::{SubspaceNotificationSender, SubspaceNotificationStream};
 use crate::slot_worker::{SlotWorkerSyncOracle, SubspaceSlotWorker};
-pub use archiver::create_subspace_archiver;
+pub use archiver::{create_subspace_archiver, rearchive_from_block};
 use codec::Encode;
 use futures::channel::mpsc;
 use futures::StreamExt;
@@ -31,6 +31,7 @@ use crate::digests::{CompatibleDigestItem, PreDigest};
 use alloc::borrow::Cow;
 use alloc::string::String;
 use codec::{Decode, Encode, MaxEncodedLen};
+use core::mem;
 use scale_info::TypeInfo;
 use schnorrkel::context::SigningContext;
 use sp_api::{BlockT, HeaderT};
@@ -43,9 +44,10 @@ use sp_runtime_interface::pass_by::PassBy;
 use sp_runtime_interface::{pass_by, runtime_interface};
 use sp_std::vec::Vec;
 use subspace_core_primitives::crypto::kzg::Kzg;
+use subspace_core_primitives::crypto::Scalar;
 use subspace_core_primitives::{
-    BlockNumber, HistorySize, PublicKey, Randomness, RewardSignature, SegmentCommitment,
-    SegmentHeader, SegmentIndex, Solution, SolutionRange, PUBLIC_KEY_LENGTH,
+    BlockNumber, HistorySize, Piece, PublicKey, Randomness, Record, RewardSignature,
+    SegmentCommitment, SegmentHeader, SegmentIndex, Solution, SolutionRange, PUBLIC_KEY_LENGTH,
     REWARD_SIGNATURE_LENGTH,
 };
 #[cfg(feature = "std")]
@@ -303,6 +305,27 @@ impl Default for SolutionRanges {
     }
 }
 
+/// Estimate total space pledged to the network in bytes from solution range and slot probability.
+///
+/// Used by the runtime for transaction fees and by the node for reporting, such that both always
+/// agree on the estimate.
+pub fn total_space_pledged(solution_range: SolutionRange, slot_probability: (u64, u64)) -> u128 {
+    // Operations reordered to avoid data loss, but essentially are:
+    // SolutionRange::MAX * SlotProbability
+    //     / solution_range * Piece::SIZE
+    //     / Record::NUM_S_BUCKETS * Record::NUM_CHUNKS
+    //     / SolutionRange::SIZE * Scalar::FULL_BYTES
+    u128::from(u64::MAX)
+        .saturating_mul(Piece::SIZE as u128)
+        .saturating_mul(u128::from(slot_probability.0))
+        / Record::NUM_S_BUCKETS as u128
+        * Record::NUM_CHUNKS as u128
+        / u128::from(solution_range.max(1))
+        / u128::from(slot_probability.1.max(1))
+        * Scalar::FULL_BYTES as u128
+        / mem::size_of::<SolutionRange>() as u128
+}
+
 // TODO: Likely add more stuff here
 /// Subspace blockchain constants.
 #[derive(Debug, Encode, Decode, MaxEncodedLen, PartialEq, Eq, Clone, Copy, TypeInfo)]
@@ -0,0 +1,78 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rand::{thread_rng, Rng};
+use rayon::ThreadPoolBuilder;
+use subspace_archiving::archiver::Archiver;
+use subspace_archiving::piece_reconstructor::PiecesReconstructor;
+use subspace_archiving::reconstructor::Reconstructor;
+use subspace_core_primitives::crypto::kzg;
+use subspace_core_primitives::crypto::kzg::Kzg;
+use subspace_core_primitives::{Piece, RecordedHistorySegment};
+
+fn criterion_benchmark(c: &mut Criterion) {
+    let mut input = vec![0u8; RecordedHistorySegment::SIZE];
+    thread_rng().fill(input.as_mut_slice());
+    let kzg = Kzg::new(kzg::embedded_kzg_settings());
+    let mut archiver = Archiver::new(kzg.clone()).unwrap();
+    let archived_segment = archiver
+        .add_block(input, Default::default())
+        .into_iter()
+        .next()
+        .unwrap();
+
+    // Only parity pieces are available, so all source pieces need to be recovered with erasure
+    // coding
+    let segment_pieces = archived_segment
+        .pieces
+        .iter()
+        .enumerate()
+        .map(|(position, piece)| (position % 2 == 1).then(|| Piece::from(piece)))
+        .collect::<Vec<_>>();
+
+    let reconstructor = Reconstructor::new().unwrap();
+    let pieces_reconstructor = PiecesReconstructor::new(kzg).unwrap();
+
+    let available_parallelism = std::thread::available_parallelism()
+        .map(|threads| threads.get())
+        .unwrap_or(1);
+
+    for threads in [1, available_parallelism] {
+        let thread_pool = ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .unwrap();
+
+        c.bench_function(&format!("segment-recovery/{threads}-threads"), |b| {
+            b.iter(|| {
+                thread_pool
+                    .install(|| reconstructor.recover_segment(black_box(&segment_pieces)))
+                    .unwrap();
+            })
+        });
+
+        c.bench_function(
+            &format!("segment-pieces-reconstruction/{threads}-threads"),
+            |b| {
+                b.iter(|| {
+                    thread_pool
+                        .install(|| {
+                            pieces_reconstructor.reconstruct_segment(black_box(&segment_pieces))
+                        })
+                        .unwrap();
+                })
+            },
+        );
+
+        c.bench_function(&format!("piece-reconstruction/{threads}-threads"), |b| {
+            b.iter(|| {
+                thread_pool
+                    .install(|| {
+                        pieces_reconstructor.reconstruct_piece(black_box(&segment_pieces), 0)
+                    })
+                    .unwrap();
+            })
+        });
+    }
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -14,6 +14,8 @@
 // limitations under the License.
 
 mod incremental_record_commitments;
+#[cfg(feature = "std")]
+mod stream;
 
 extern crate alloc;
 
@@ -25,6 +27,7 @@ use alloc::string::String;
 use alloc::vec;
 use alloc::vec::Vec;
 use core::cmp::Ordering;
+use core::convert::Infallible;
 use core::num::NonZeroUsize;
 use parity_scale_codec::{Compact, CompactLen, Decode, Encode, Input, Output};
 #[cfg(feature = "parallel")]
@@ -40,6 +43,13 @@ use subspace_core_primitives::{
 };
 use subspace_erasure_coding::ErasureCoding;
 
+#[cfg(feature = "std")]
+pub use stream::ArchivedSegmentsStream;
+
+/// Number of record offsets processed in parallel at once.
+#[cfg(feature = "parallel")]
+const RECORD_OFFSETS_PER_BATCH: usize = 1024;
+
 const INITIAL_LAST_ARCHIVED_BLOCK: LastArchivedBlock = LastArchivedBlock {
     number: 0,
     // Special case for the genesis block.
@@ -673,48 +683,47 @@ impl Archiver {
 
             let mut pieces = ArchivedHistorySegment::default();
 
-            // Scratch buffer to avoid re-allocation
-            let mut tmp_source_shards_scalars =
-                Vec::<Scalar>::with_capacity(RecordedHistorySegment::NUM_RAW_RECORDS);
-            // Iterate over the chunks of `Scalar::SAFE_BYTES` bytes of all records
-            for record_offset in 0..RawRecord::SIZE / Scalar::SAFE_BYTES {
-                // Collect chunks of each record at the same offset
-                raw_record_shards
-                    .array_chunks::<{ RawRecord::SIZE }>()
-                    .map(|record_bytes| {
-                        record_bytes
-                            .array_chunks::<{ Scalar::SAFE_BYTES }>()
-                            .nth(record_offset)
-                            .expect("Statically known to exist in a record; qed")
-                    })
-                    .map(Scalar::from)
-                    .collect_into(&mut tmp_source_shards_scalars);
-
-                // Extend to obtain corresponding parity shards
-                let parity_shards = self
-                    .erasure_coding
-                    .extend(&tmp_source_shards_scalars)
-                    .expect(
+            process_record_offsets(
+                || Vec::<Scalar>::with_capacity(RecordedHistorySegment::NUM_RAW_RECORDS),
+                // Collect chunks of `Scalar::SAFE_BYTES` bytes of all records at the same offset
+                // into `source_shards_scalars` and extend them to obtain corresponding parity shards
+                |record_offset, source_shards_scalars| {
+                    raw_record_shards
+                        .array_chunks::<{ RawRecord::SIZE }>()
+                        .map(|record_bytes| {
+                            record_bytes
+                                .array_chunks::<{ Scalar::SAFE_BYTES }>()
+                                .nth(record_offset)
+                                .expect("Statically known to exist in a record; qed")
+                        })
+                        .map(Scalar::from)
+                        .collect_into(source_shards_scalars);
+
+                    Ok::<_, Infallible>(self.erasure_coding.extend(source_shards_scalars).expect(
                         "Erasure coding instance is deliberately configured to support this \
                         input; qed",
-                    );
-
-                let interleaved_input_chunks = tmp_source_shards_scalars
-                    .drain(..)
-                    .zip(parity_shards)
-                    .flat_map(|(a, b)| [a, b]);
-                let output_chunks = pieces.iter_mut().map(|piece| {
-                    piece
-                        .record_mut()
-                        .iter_mut()
-                        .nth(record_offset)
-                        .expect("Statically known to exist in a record; qed")
-                });
+                    ))
+                },
+                // Write source and parity shards interleaved into pieces
+                |record_offset, source_shards_scalars, parity_shards| {
+                    let interleaved_input_chunks = source_shards_scalars
+                        .drain(..)
+                        .zip(parity_shards)
+                        .flat_map(|(a, b)| [a, b]);
+                    let output_chunks = pieces.iter_mut().map(|piece| {
+                        piece
+                            .record_mut()
+                            .iter_mut()
+                            .nth(record_offset)
+                            .expect("Statically known to exist in a record; qed")
+                    });
 
-                interleaved_input_chunks
-                    .zip(output_chunks)
-                    .for_each(|(input, output)| output.copy_from_slice(&input.to_bytes()));
-            }
+                    interleaved_input_chunks
+                        .zip(output_chunks)
+                        .for_each(|(input, output)| output.copy_from_slice(&input.to_bytes()));
+                },
+            )
+            .unwrap_or_else(|error| match error {});
 
             pieces
         };
@@ -833,6 +842,59 @@ impl Archiver {
     }
 }
 
+/// Process every record offset of a segment with `process` and pass results to `write` in order of
+/// record offsets, scratch space created with `new_scratch` is passed to both to avoid
+/// re-allocation.
+///
+/// With `parallel` feature enabled record offsets are processed in parallel in batches, such that
+/// results for the whole segment are not held in memory at once.
+pub(crate) fn process_record_offsets<Scratch, Output, Error, NewScratch, Process, Write>(
+    new_scratch: NewScratch,
+    process: Process,
+    mut write: Write,
+) -> Result<(), Error>
+where
+    Scratch: Send,
+    Output: Send,
+    Error: Send,
+    NewScratch: Fn() -> Scratch,
+    Process: Fn(usize, &mut Scratch) -> Result<Output, Error> + Sync,
+    Write: FnMut(usize, &mut Scratch, Output),
+{
+    let record_offsets = 0..RawRecord::SIZE / Scalar::SAFE_BYTES;
+
+    #[cfg(not(feature = "parallel"))]
+    {
+        let mut scratch = new_scratch();
+        for record_offset in record_offsets {
+            let output = process(record_offset, &mut scratch)?;
+            write(record_offset, &mut scratch, output);
+        }
+    }
+    #[cfg(feature = "parallel")]
+    {
+        let mut scratches = (0..RECORD_OFFSETS_PER_BATCH)
+            .map(|_| new_scratch())
+            .collect::<Vec<_>>();
+        for batch_start in record_offsets.clone().step_by(RECORD_OFFSETS_PER_BATCH) {
+            let batch =
+                batch_start..(batch_start + RECORD_OFFSETS_PER_BATCH).min(record_offsets.end);
+            let outputs = batch
+                .clone()
+                .into_par_iter()
+                .zip(scratches.par_iter_mut())
+                .map(|(record_offset, scratch)| process(record_offset, scratch))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            for ((record_offset, scratch), output) in batch.zip(scratches.iter_mut()).zip(outputs) {
+                write(record_offset, scratch, output);
+            }
+        }
+    }
+
+    Ok(())
+}
+
 /// Validate witness embedded within a piece produced by archiver
 pub fn is_piece_valid(
     kzg: &Kzg,
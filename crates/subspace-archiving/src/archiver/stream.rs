@@ -0,0 +1,79 @@
+use crate::archiver::{Archiver, NewArchivedSegment};
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+use futures::{Stream, StreamExt};
+use subspace_core_primitives::objects::BlockObjectMapping;
+use subspace_core_primitives::BlockNumber;
+
+/// Stream of archived segments produced from a stream of encoded blocks (with corresponding object
+/// mappings).
+///
+/// Blocks are pulled from the underlying stream only once all previously produced segments were
+/// consumed, so memory usage is bounded by archiver's buffer (less than one segment worth of
+/// blocks) and segments produced by a single block, regardless of the number of blocks archived.
+///
+/// NOTE: Archiving is CPU-intensive and happens while polling, so this stream should be polled
+/// from a context where blocking is acceptable (dedicated thread, for instance).
+#[derive(Debug)]
+pub struct ArchivedSegmentsStream<Blocks> {
+    archiver: Archiver,
+    blocks: Blocks,
+    pending_segments: VecDeque<NewArchivedSegment>,
+}
+
+impl<Blocks> Stream for ArchivedSegmentsStream<Blocks>
+where
+    Blocks: Stream<Item = (Vec<u8>, BlockObjectMapping)> + Unpin,
+{
+    type Item = NewArchivedSegment;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+
+        loop {
+            if let Some(archived_segment) = this.pending_segments.pop_front() {
+                return Poll::Ready(Some(archived_segment));
+            }
+
+            match this.blocks.poll_next_unpin(cx) {
+                Poll::Ready(Some((bytes, object_mapping))) => {
+                    this.pending_segments
+                        .extend(this.archiver.add_block(bytes, object_mapping));
+                }
+                Poll::Ready(None) => {
+                    return Poll::Ready(None);
+                }
+                Poll::Pending => {
+                    return Poll::Pending;
+                }
+            }
+        }
+    }
+}
+
+impl<Blocks> ArchivedSegmentsStream<Blocks>
+where
+    Blocks: Stream<Item = (Vec<u8>, BlockObjectMapping)> + Unpin,
+{
+    /// Create new stream that will archive blocks with provided archiver.
+    pub fn new(archiver: Archiver, blocks: Blocks) -> Self {
+        Self {
+            archiver,
+            blocks,
+            pending_segments: VecDeque::new(),
+        }
+    }
+
+    /// Get last archived block if there was any
+    pub fn last_archived_block_number(&self) -> Option<BlockNumber> {
+        self.archiver.last_archived_block_number()
+    }
+
+    /// Returns archiver (for instance to continue archiving once stream has ended), archived
+    /// segments that were not yet consumed are discarded.
+    pub fn into_archiver(self) -> Archiver {
+        self.archiver
+    }
+}
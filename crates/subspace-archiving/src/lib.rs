@@ -14,6 +14,9 @@
 // limitations under the License.
 
 //! Collection of modules used for dealing with archived state of Subspace Network.
+//!
+//! With `parallel` feature enabled erasure coding, commitments and reconstruction are parallelized
+//! with [rayon](https://docs.rs/rayon).
 #![cfg_attr(not(feature = "std"), no_std)]
 #![feature(array_chunks, drain_filter, iter_collect_into, slice_flatten)]
 
@@ -1,5 +1,6 @@
 extern crate alloc;
 
+use crate::archiver::process_record_offsets;
 use alloc::string::String;
 use alloc::vec::Vec;
 use core::num::NonZeroUsize;
@@ -7,7 +8,7 @@ use core::num::NonZeroUsize;
 use rayon::prelude::*;
 use subspace_core_primitives::crypto::kzg::{Commitment, Kzg, Polynomial};
 use subspace_core_primitives::crypto::{blake2b_256_254_hash_to_scalar, Scalar};
-use subspace_core_primitives::{ArchivedHistorySegment, Piece, RawRecord};
+use subspace_core_primitives::{ArchivedHistorySegment, Piece};
 use subspace_erasure_coding::ErasureCoding;
 
 /// Reconstructor-related instantiation error.
@@ -104,46 +105,51 @@ impl PiecesReconstructor {
             // If not all data pieces are available, need to reconstruct data shards using erasure
             // coding.
 
-            // Scratch buffer to avoid re-allocation
-            let mut tmp_shards_scalars =
-                Vec::<Option<Scalar>>::with_capacity(ArchivedHistorySegment::NUM_PIECES);
-            // Iterate over the chunks of `Scalar::SAFE_BYTES` bytes of all records
-            for record_offset in 0..RawRecord::SIZE / Scalar::SAFE_BYTES {
-                // Collect chunks of each record at the same offset
-                for maybe_piece in input_pieces.iter() {
-                    let maybe_scalar = maybe_piece
-                        .as_ref()
-                        .map(|piece| {
+            process_record_offsets(
+                || Vec::<Option<Scalar>>::with_capacity(ArchivedHistorySegment::NUM_PIECES),
+                // Recover chunks of `Scalar::SAFE_BYTES` bytes of all records at the same offset
+                |record_offset, shards_scalars| {
+                    // Collect chunks of each record at the same offset
+                    for maybe_piece in input_pieces.iter() {
+                        let maybe_scalar = maybe_piece
+                            .as_ref()
+                            .map(|piece| {
+                                piece
+                                    .record()
+                                    .iter()
+                                    .nth(record_offset)
+                                    .expect("Statically guaranteed to exist in a piece; qed")
+                            })
+                            .map(Scalar::try_from)
+                            .transpose()
+                            .map_err(ReconstructorError::DataShardsReconstruction)?;
+
+                        shards_scalars.push(maybe_scalar);
+                    }
+
+                    let result = self
+                        .erasure_coding
+                        .recover(shards_scalars)
+                        .map_err(ReconstructorError::DataShardsReconstruction);
+                    shards_scalars.clear();
+
+                    result
+                },
+                |record_offset, _shards_scalars, scalars| {
+                    scalars
+                        .into_iter()
+                        .zip(reconstructed_pieces.iter_mut().map(|piece| {
                             piece
-                                .record()
-                                .iter()
+                                .record_mut()
+                                .iter_mut()
                                 .nth(record_offset)
                                 .expect("Statically guaranteed to exist in a piece; qed")
-                        })
-                        .map(Scalar::try_from)
-                        .transpose()
-                        .map_err(ReconstructorError::DataShardsReconstruction)?;
-
-                    tmp_shards_scalars.push(maybe_scalar);
-                }
-
-                self.erasure_coding
-                    .recover(&tmp_shards_scalars)
-                    .map_err(ReconstructorError::DataShardsReconstruction)?
-                    .into_iter()
-                    .zip(reconstructed_pieces.iter_mut().map(|piece| {
-                        piece
-                            .record_mut()
-                            .iter_mut()
-                            .nth(record_offset)
-                            .expect("Statically guaranteed to exist in a piece; qed")
-                    }))
-                    .for_each(|(source_scalar, segment_data)| {
-                        segment_data.copy_from_slice(&source_scalar.to_bytes());
-                    });
-
-                tmp_shards_scalars.clear();
-            }
+                        }))
+                        .for_each(|(source_scalar, segment_data)| {
+                            segment_data.copy_from_slice(&source_scalar.to_bytes());
+                        });
+                },
+            )?;
         }
 
         let source_record_commitments = {
@@ -257,26 +263,58 @@ impl PiecesReconstructor {
         segment_pieces: &[Option<Piece>],
         piece_position: usize,
     ) -> Result<Piece, ReconstructorError> {
-        let (reconstructed_records, polynomial) = self.reconstruct_shards(segment_pieces)?;
+        let mut pieces = self.reconstruct_pieces(segment_pieces, &[piece_position])?;
 
-        if piece_position >= ArchivedHistorySegment::NUM_PIECES {
+        Ok(pieces.remove(0))
+    }
+
+    /// Returns pieces at specified positions for a segment using given set of pieces of a segment
+    /// of the archived history (any half of all pieces are required to be present).
+    ///
+    /// This is more efficient than calling [`Self::reconstruct_piece`] for each piece, since
+    /// erasure coding and commitments are only done once, and cheaper than
+    /// [`Self::reconstruct_segment`] when only some pieces are needed, since witnesses are only
+    /// created for requested pieces. Original blocks can be retrieved from the pieces with
+    /// [`Reconstructor`](crate::reconstructor::Reconstructor).
+    pub fn reconstruct_pieces(
+        &self,
+        segment_pieces: &[Option<Piece>],
+        piece_positions: &[usize],
+    ) -> Result<Vec<Piece>, ReconstructorError> {
+        if piece_positions
+            .iter()
+            .any(|&piece_position| piece_position >= ArchivedHistorySegment::NUM_PIECES)
+        {
             return Err(ReconstructorError::IncorrectPiecePosition);
         }
 
-        let mut piece = Piece::from(&reconstructed_records[piece_position]);
-
-        piece.witness_mut().copy_from_slice(
-            &self
-                .kzg
-                .create_witness(
-                    &polynomial,
-                    ArchivedHistorySegment::NUM_PIECES,
-                    piece_position as u32,
-                )
-                .expect("Position is verified to be valid above; qed")
-                .to_bytes(),
-        );
-
-        Ok(piece)
+        let (reconstructed_records, polynomial) = self.reconstruct_shards(segment_pieces)?;
+
+        #[cfg(not(feature = "parallel"))]
+        let iter = piece_positions.iter();
+        #[cfg(feature = "parallel")]
+        let iter = piece_positions.par_iter();
+
+        let pieces = iter
+            .map(|&piece_position| {
+                let mut piece = Piece::from(&reconstructed_records[piece_position]);
+
+                piece.witness_mut().copy_from_slice(
+                    &self
+                        .kzg
+                        .create_witness(
+                            &polynomial,
+                            ArchivedHistorySegment::NUM_PIECES,
+                            piece_position as u32,
+                        )
+                        .expect("Position is verified to be valid above; qed")
+                        .to_bytes(),
+                );
+
+                piece
+            })
+            .collect();
+
+        Ok(pieces)
     }
 }
@@ -1,6 +1,6 @@
 extern crate alloc;
 
-use crate::archiver::{Segment, SegmentItem};
+use crate::archiver::{process_record_offsets, Segment, SegmentItem};
 use alloc::string::String;
 use alloc::vec::Vec;
 use core::mem;
@@ -9,7 +9,7 @@ use parity_scale_codec::Decode;
 use subspace_core_primitives::crypto::Scalar;
 use subspace_core_primitives::{
     ArchivedBlockProgress, ArchivedHistorySegment, BlockNumber, LastArchivedBlock, Piece,
-    RawRecord, RecordedHistorySegment, SegmentHeader, SegmentIndex,
+    RecordedHistorySegment, SegmentHeader, SegmentIndex,
 };
 use subspace_erasure_coding::ErasureCoding;
 
@@ -100,6 +100,21 @@ impl Reconstructor {
         &mut self,
         segment_pieces: &[Option<Piece>],
     ) -> Result<ReconstructedContents, ReconstructorError> {
+        let segment = self.recover_segment(segment_pieces)?;
+
+        self.add_recovered_segment(segment)
+    }
+
+    /// Given a set of pieces of a segment of the archived history (any half of all pieces are
+    /// required to be present), recovers and decodes the segment.
+    ///
+    /// This is the computationally expensive part of [`Self::add_segment`] that doesn't depend on
+    /// previously added segments, so it can be done for multiple segments in parallel, with
+    /// results passed to [`Self::add_recovered_segment`] in order afterwards.
+    pub fn recover_segment(
+        &self,
+        segment_pieces: &[Option<Piece>],
+    ) -> Result<Segment, ReconstructorError> {
         let mut segment_data = RecordedHistorySegment::new_boxed();
 
         if !segment_pieces
@@ -123,55 +138,71 @@ impl Reconstructor {
             // If not all data pieces are available, need to reconstruct data shards using erasure
             // coding.
 
-            // Scratch buffer to avoid re-allocation
-            let mut tmp_shards_scalars =
-                Vec::<Option<Scalar>>::with_capacity(ArchivedHistorySegment::NUM_PIECES);
-            // Iterate over the chunks of `Scalar::SAFE_BYTES` bytes of all records
-            for record_offset in 0..RawRecord::SIZE / Scalar::SAFE_BYTES {
-                // Collect chunks of each record at the same offset
-                for maybe_piece in segment_pieces.iter() {
-                    let maybe_scalar = maybe_piece
-                        .as_ref()
-                        .map(|piece| {
-                            piece
-                                .record()
-                                .iter()
+            process_record_offsets(
+                || Vec::<Option<Scalar>>::with_capacity(ArchivedHistorySegment::NUM_PIECES),
+                // Recover chunks of `Scalar::SAFE_BYTES` bytes of all records at the same offset
+                |record_offset, shards_scalars| {
+                    // Collect chunks of each record at the same offset
+                    for maybe_piece in segment_pieces.iter() {
+                        let maybe_scalar = maybe_piece
+                            .as_ref()
+                            .map(|piece| {
+                                piece
+                                    .record()
+                                    .iter()
+                                    .nth(record_offset)
+                                    .expect("Statically guaranteed to exist in a piece; qed")
+                            })
+                            .map(Scalar::try_from)
+                            .transpose()
+                            .map_err(ReconstructorError::DataShardsReconstruction)?;
+
+                        shards_scalars.push(maybe_scalar);
+                    }
+
+                    let result = self
+                        .erasure_coding
+                        .recover(shards_scalars)
+                        .map_err(ReconstructorError::DataShardsReconstruction);
+                    shards_scalars.clear();
+
+                    result
+                },
+                |record_offset, _shards_scalars, scalars| {
+                    scalars
+                        .into_iter()
+                        // Take each source shards here
+                        .step_by(2)
+                        .zip(segment_data.iter_mut().map(|raw_record| {
+                            raw_record
+                                .iter_mut()
                                 .nth(record_offset)
                                 .expect("Statically guaranteed to exist in a piece; qed")
-                        })
-                        .map(Scalar::try_from)
-                        .transpose()
-                        .map_err(ReconstructorError::DataShardsReconstruction)?;
-
-                    tmp_shards_scalars.push(maybe_scalar);
-                }
-
-                self.erasure_coding
-                    .recover(&tmp_shards_scalars)
-                    .map_err(ReconstructorError::DataShardsReconstruction)?
-                    .into_iter()
-                    // Take each source shards here
-                    .step_by(2)
-                    .zip(segment_data.iter_mut().map(|raw_record| {
-                        raw_record
-                            .iter_mut()
-                            .nth(record_offset)
-                            .expect("Statically guaranteed to exist in a piece; qed")
-                    }))
-                    .for_each(|(source_scalar, segment_data)| {
-                        // Source scalar only contains payload data within first
-                        // [`Scalar::SAFE_BYTES`]
-                        segment_data
-                            .copy_from_slice(&source_scalar.to_bytes()[..Scalar::SAFE_BYTES]);
-                    });
-
-                tmp_shards_scalars.clear();
-            }
+                        }))
+                        .for_each(|(source_scalar, segment_data)| {
+                            // Source scalar only contains payload data within first
+                            // [`Scalar::SAFE_BYTES`]
+                            segment_data
+                                .copy_from_slice(&source_scalar.to_bytes()[..Scalar::SAFE_BYTES]);
+                        });
+                },
+            )?;
         }
 
-        let Segment::V0 { items } =
-            Segment::decode(&mut AsRef::<[u8]>::as_ref(segment_data.as_ref()))
-                .map_err(ReconstructorError::SegmentDecoding)?;
+        Segment::decode(&mut AsRef::<[u8]>::as_ref(segment_data.as_ref()))
+            .map_err(ReconstructorError::SegmentDecoding)
+    }
+
+    /// Reconstructs segment header and a list of encoded blocks with corresponding block numbers
+    /// from segment recovered with [`Self::recover_segment`].
+    ///
+    /// It is possible to start with any segment, but when next segment is pushed, it needs to
+    /// follow the previous one or else error will be returned.
+    pub fn add_recovered_segment(
+        &mut self,
+        segment: Segment,
+    ) -> Result<ReconstructedContents, ReconstructorError> {
+        let Segment::V0 { items } = segment;
 
         let mut reconstructed_contents = ReconstructedContents::default();
         let mut next_block_number = 0;
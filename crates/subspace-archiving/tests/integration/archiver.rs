@@ -1,3 +1,5 @@
+use futures::executor::block_on;
+use futures::{stream, StreamExt};
 use parity_scale_codec::{Compact, CompactLen, Decode, Encode};
 use rand::{thread_rng, Rng};
 #[cfg(feature = "parallel")]
@@ -6,7 +8,9 @@ use std::assert_matches::assert_matches;
 use std::io::Write;
 use std::iter;
 use subspace_archiving::archiver;
-use subspace_archiving::archiver::{Archiver, ArchiverInstantiationError, SegmentItem};
+use subspace_archiving::archiver::{
+    ArchivedSegmentsStream, Archiver, ArchiverInstantiationError, SegmentItem,
+};
 use subspace_core_primitives::crypto::kzg::{embedded_kzg_settings, Commitment, Kzg};
 use subspace_core_primitives::crypto::Scalar;
 use subspace_core_primitives::objects::{BlockObject, BlockObjectMapping, PieceObject};
@@ -649,3 +653,39 @@ fn object_on_the_edge_of_segment() {
         mapped_bytes
     );
 }
+
+#[test]
+fn archived_segments_stream() {
+    let kzg = Kzg::new(embedded_kzg_settings());
+
+    let blocks = (0..3)
+        .map(|_| {
+            let mut block = vec![0u8; RecordedHistorySegment::SIZE / 2];
+            thread_rng().fill(block.as_mut_slice());
+            block
+        })
+        .collect::<Vec<_>>();
+
+    let mut archiver = Archiver::new(kzg.clone()).unwrap();
+    let expected_archived_segments = blocks
+        .iter()
+        .flat_map(|block| archiver.add_block(block.clone(), BlockObjectMapping::default()))
+        .collect::<Vec<_>>();
+    assert_eq!(expected_archived_segments.len(), 1);
+
+    let mut archived_segments_stream = ArchivedSegmentsStream::new(
+        Archiver::new(kzg).unwrap(),
+        stream::iter(
+            blocks
+                .into_iter()
+                .map(|block| (block, BlockObjectMapping::default())),
+        ),
+    );
+    let archived_segments = block_on(archived_segments_stream.by_ref().collect::<Vec<_>>());
+
+    assert_eq!(archived_segments, expected_archived_segments);
+    assert_eq!(
+        archived_segments_stream.last_archived_block_number(),
+        archiver.last_archived_block_number()
+    );
+}
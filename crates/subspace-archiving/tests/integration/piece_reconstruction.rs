@@ -104,6 +104,56 @@ fn piece_reconstruction_works() {
     }
 }
 
+#[test]
+fn multiple_pieces_reconstruction_works() {
+    let kzg = Kzg::new(embedded_kzg_settings());
+    let mut archiver = Archiver::new(kzg.clone()).unwrap();
+    // Block that fits into the segment fully
+    let block = get_random_block();
+
+    let archived_segments = archiver.add_block(block, BlockObjectMapping::default());
+
+    assert_eq!(archived_segments.len(), 1);
+
+    let mut maybe_pieces = pieces_to_option_of_pieces(&archived_segments.first().unwrap().pieces);
+
+    // Remove some pieces from the vector
+    let (missing_piece_positions, missing_pieces) = maybe_pieces
+        .iter_mut()
+        .enumerate()
+        .skip(50)
+        .step_by(3)
+        .take(40)
+        .map(|(piece_position, piece)| (piece_position, piece.take().unwrap()))
+        .unzip::<_, _, Vec<_>, Vec<_>>();
+
+    let reconstructor = PiecesReconstructor::new(kzg).unwrap();
+
+    let reconstructed_pieces = reconstructor
+        .reconstruct_pieces(&maybe_pieces, &missing_piece_positions)
+        .unwrap();
+
+    assert_eq!(reconstructed_pieces, missing_pieces);
+
+    // Pieces that are present can be requested too
+    let reconstructed_pieces = reconstructor
+        .reconstruct_pieces(&maybe_pieces, &[0, 1])
+        .unwrap();
+
+    assert_eq!(
+        reconstructed_pieces,
+        maybe_pieces[..2]
+            .iter()
+            .map(|piece| piece.clone().unwrap())
+            .collect::<Vec<_>>()
+    );
+
+    assert_eq!(
+        reconstructor.reconstruct_pieces(&maybe_pieces, &[0, ArchivedHistorySegment::NUM_PIECES]),
+        Err(ReconstructorError::IncorrectPiecePosition)
+    );
+}
+
 #[test]
 fn segment_reconstruction_fails() {
     let kzg = Kzg::new(embedded_kzg_settings());
@@ -146,7 +146,8 @@ pub const REWARD_SIGNATURE_LENGTH: usize = 64;
 
 /// Proof of space seed.
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Deref)]
-pub struct PosSeed([u8; Self::SIZE]);
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PosSeed(#[cfg_attr(feature = "serde", serde(with = "hex::serde"))] [u8; Self::SIZE]);
 
 impl From<[u8; PosSeed::SIZE]> for PosSeed {
     #[inline]
@@ -169,7 +170,10 @@ impl PosSeed {
 
 /// Proof of space quality.
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Deref)]
-pub struct PosQualityBytes([u8; Self::SIZE]);
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PosQualityBytes(
+    #[cfg_attr(feature = "serde", serde(with = "hex::serde"))] [u8; Self::SIZE],
+);
 
 impl From<[u8; PosQualityBytes::SIZE]> for PosQualityBytes {
     #[inline]
@@ -224,19 +228,24 @@ impl PosProof {
 
 /// Proof of time key(input to the encryption).
 #[derive(Debug, Copy, Clone, From, Into, Encode, Decode)]
-pub struct PotKey(PotBytes);
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PotKey(#[cfg_attr(feature = "serde", serde(with = "hex::serde"))] PotBytes);
 
 /// Proof of time seed (input to the encryption).
 #[derive(Debug, Copy, Clone, From, Into, Encode, Decode)]
-pub struct PotSeed(PotBytes);
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PotSeed(#[cfg_attr(feature = "serde", serde(with = "hex::serde"))] PotBytes);
 
 /// Proof of time ciphertext (output from the encryption).
 #[derive(Debug, Copy, Clone, From, Into, Encode, Decode)]
-pub struct PotCheckpoint(PotBytes);
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PotCheckpoint(#[cfg_attr(feature = "serde", serde(with = "hex::serde"))] PotBytes);
 
 /// Proof of time.
 /// TODO: versioning.
 #[derive(Debug, Clone, Encode, Decode)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
 pub struct PotProof {
     /// Slot the proof was evaluated for.
     pub slot_number: SlotNumber,
@@ -251,6 +260,7 @@ pub struct PotProof {
     pub checkpoints: Vec<PotCheckpoint>,
 
     /// Hash of last block at injection point.
+    #[cfg_attr(feature = "serde", serde(with = "hex::serde"))]
     pub injected_block_hash: BlockHash,
 }
 
@@ -830,7 +840,10 @@ impl Default for U256 {
 
 /// Challenge used for a particular sector for particular slot
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Deref)]
-pub struct SectorSlotChallenge(Blake2b256Hash);
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SectorSlotChallenge(
+    #[cfg_attr(feature = "serde", serde(with = "hex::serde"))] Blake2b256Hash,
+);
 
 impl SectorSlotChallenge {
     /// Index of s-bucket within sector to be audited
@@ -453,8 +453,11 @@ impl Record {
     TypeInfo,
     MaxEncodedLen,
 )]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[repr(transparent)]
-pub struct RecordCommitment([u8; RecordCommitment::SIZE]);
+pub struct RecordCommitment(
+    #[cfg_attr(feature = "serde", serde(with = "serde_arrays"))] [u8; RecordCommitment::SIZE],
+);
 
 impl Default for RecordCommitment {
     #[inline]
@@ -507,8 +510,11 @@ impl RecordCommitment {
     TypeInfo,
     MaxEncodedLen,
 )]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[repr(transparent)]
-pub struct RecordWitness([u8; RecordWitness::SIZE]);
+pub struct RecordWitness(
+    #[cfg_attr(feature = "serde", serde(with = "serde_arrays"))] [u8; RecordWitness::SIZE],
+);
 
 impl Default for RecordWitness {
     #[inline]
@@ -0,0 +1,9 @@
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Only server is needed by the farmer, clients can be generated from the same file in any
+    // language
+    tonic_build::configure()
+        .build_client(false)
+        .compile(&["proto/management.proto"], &["proto"])?;
+
+    Ok(())
+}
@@ -1,6 +1,18 @@
+mod benchmark;
+mod check_upgrade;
+mod diagnostics;
 mod farm;
 mod info;
+mod service;
 mod shared;
+mod wipe;
 
+pub(crate) use benchmark::benchmark;
+pub(crate) use check_upgrade::check_upgrade;
+pub(crate) use diagnostics::diagnostics;
 pub(crate) use farm::farm_multi_disk;
 pub(crate) use info::info;
+pub(crate) use service::service;
+#[cfg(windows)]
+pub(crate) use service::EventLogLayer;
+pub(crate) use wipe::wipe;
@@ -0,0 +1,147 @@
+use crate::{BenchmarkArgs, DiskBenchmarkArgs};
+use anyhow::anyhow;
+use rand::prelude::*;
+use std::fs::OpenOptions;
+use std::time::{Duration, Instant};
+use subspace_core_primitives::crypto::Scalar;
+use subspace_core_primitives::{Piece, Record};
+use subspace_farmer_components::file_ext::FileExt;
+use subspace_farmer_components::sector::sector_size;
+
+const BENCHMARK_FILE: &str = "benchmark.bin";
+/// Expected slot duration, every plotted sector is audited once per slot
+const SLOT_DURATION: Duration = Duration::from_secs(1);
+/// How much of the slot can be spent on auditing, the rest is reserved for proving and network
+/// round-trips
+const AUDIT_SLOT_FRACTION: f64 = 0.5;
+/// Size of the small random read
+const SMALL_READ_SIZE: usize = 4 * 1024;
+
+pub(crate) fn benchmark(benchmark_args: BenchmarkArgs) -> anyhow::Result<()> {
+    match benchmark_args {
+        BenchmarkArgs::Disk(disk_benchmark_args) => benchmark_disk(disk_benchmark_args),
+    }
+}
+
+fn benchmark_disk(disk_benchmark_args: DiskBenchmarkArgs) -> anyhow::Result<()> {
+    let DiskBenchmarkArgs {
+        directory,
+        file_size,
+        duration,
+        plot_size,
+        pieces_in_sector,
+    } = disk_benchmark_args;
+
+    let duration = Duration::from_secs(duration);
+    let sector_size = sector_size(pieces_in_sector);
+    // Sectors are written sequentially during plotting
+    let write_size = Record::SIZE;
+    // One s-bucket is read from every sector during audit, on average every record contributes
+    // half of its chunks to each s-bucket
+    let audit_read_size = usize::from(pieces_in_sector) * Scalar::FULL_BYTES / 2;
+    let file_size = file_size.as_u64() / write_size as u64 * write_size as u64;
+    if file_size < Piece::SIZE as u64 {
+        return Err(anyhow!(
+            "File size must be at least one piece ({} bytes)",
+            Piece::SIZE
+        ));
+    }
+
+    let benchmark_file_path = directory.join(BENCHMARK_FILE);
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&benchmark_file_path)?;
+
+    let result = (|| -> anyhow::Result<()> {
+        println!("Benchmarking disk at {}", directory.display());
+        println!("  File size: {}", bytesize::to_string(file_size, true));
+        println!("  NOTE: file size should exceed amount of RAM to avoid page cache effects");
+        println!();
+
+        file.preallocate(file_size)?;
+
+        let write_throughput = {
+            let mut buffer = vec![0u8; write_size];
+            let mut rng = thread_rng();
+            let start = Instant::now();
+            for offset in (0..file_size).step_by(write_size) {
+                rng.fill_bytes(&mut buffer);
+                file.write_all_at(&buffer, offset)?;
+            }
+            file.sync_all()?;
+
+            file_size as f64 / start.elapsed().as_secs_f64()
+        };
+        println!(
+            "Sequential write: {}/s",
+            bytesize::to_string(write_throughput as u64, true)
+        );
+
+        file.advise_random_access()?;
+
+        let small_read_iops = random_read_iops(&file, file_size, SMALL_READ_SIZE, duration)?;
+        println!("Random {SMALL_READ_SIZE} bytes reads: {small_read_iops:.0} IOPS");
+        let audit_read_iops = random_read_iops(&file, file_size, audit_read_size, duration)?;
+        println!("Random {audit_read_size} bytes (audit) reads: {audit_read_iops:.0} IOPS");
+        let piece_read_iops = random_read_iops(&file, file_size, Piece::SIZE, duration)?;
+        println!(
+            "Random {} bytes (piece) reads: {piece_read_iops:.0} IOPS",
+            Piece::SIZE
+        );
+
+        if let Some(plot_size) = plot_size {
+            let sector_count = plot_size.as_u64() / sector_size as u64;
+            let audit_time = sector_count as f64 / audit_read_iops;
+            let audit_time_limit = SLOT_DURATION.as_secs_f64() * AUDIT_SLOT_FRACTION;
+
+            println!();
+            println!(
+                "Plot of {} contains {sector_count} sectors, auditing it takes ~{audit_time:.3}s \
+                per slot (limit is {audit_time_limit:.3}s)",
+                bytesize::to_string(plot_size.as_u64(), true)
+            );
+            if audit_time <= audit_time_limit {
+                println!("Disk can sustain audits for this plot size");
+            } else {
+                println!(
+                    "Disk can NOT sustain audits for this plot size, consider plot of at most {}",
+                    bytesize::to_string(
+                        (audit_time_limit * audit_read_iops) as u64 * sector_size as u64,
+                        true
+                    )
+                );
+            }
+        }
+
+        Ok(())
+    })();
+
+    drop(file);
+    std::fs::remove_file(&benchmark_file_path)?;
+
+    result
+}
+
+fn random_read_iops(
+    file: &std::fs::File,
+    file_size: u64,
+    read_size: usize,
+    duration: Duration,
+) -> std::io::Result<f64> {
+    let mut buffer = vec![0u8; read_size];
+    let max_offset = file_size - read_size as u64;
+    let mut rng = thread_rng();
+    let mut reads = 0_u64;
+
+    let start = Instant::now();
+    while start.elapsed() < duration {
+        let offset = rng.gen_range(0..=max_offset);
+        file.read_exact_at(&mut buffer, offset)?;
+        reads += 1;
+    }
+
+    Ok(reads as f64 / start.elapsed().as_secs_f64())
+}
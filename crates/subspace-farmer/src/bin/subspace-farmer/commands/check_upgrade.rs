@@ -0,0 +1,42 @@
+use crate::DiskFarm;
+use anyhow::anyhow;
+use subspace_farmer::single_disk_plot::{PlotCompatibility, SingleDiskPlot};
+
+/// Check whether farms created by previous versions of the farmer can be used by this binary
+pub(crate) fn check_upgrade(disk_farms: Vec<DiskFarm>) -> anyhow::Result<()> {
+    let mut incompatible_farms = 0;
+    let mut unknown_farms = 0;
+
+    for (disk_farm_index, disk_farm) in disk_farms.into_iter().enumerate() {
+        let compatibility = SingleDiskPlot::check_compatibility(&disk_farm.directory);
+        match &compatibility {
+            PlotCompatibility::NotFound | PlotCompatibility::Compatible => {}
+            PlotCompatibility::RequiresReplotting { .. }
+            | PlotCompatibility::RequiresNewerFarmer { .. } => {
+                incompatible_farms += 1;
+            }
+            PlotCompatibility::Unknown { .. } => {
+                unknown_farms += 1;
+            }
+        }
+
+        println!(
+            "Farm {disk_farm_index} ({}): {compatibility}",
+            disk_farm.directory.display()
+        );
+    }
+
+    if incompatible_farms > 0 {
+        return Err(anyhow!(
+            "{incompatible_farms} farm(s) can't be used by this version of the farmer"
+        ));
+    }
+    if unknown_farms > 0 {
+        return Err(anyhow!(
+            "Compatibility of {unknown_farms} farm(s) could not be determined, check permissions \
+            and disk health"
+        ));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,256 @@
+use crate::commands::shared::SHARED_DATABASES;
+use crate::utils::{default_base_path, directory_size};
+use crate::{DiagnosticsArgs, DiskFarm};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use regex::{Captures, Regex};
+use std::fmt::Write;
+use std::fs;
+use std::fs::File;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use subspace_farmer::single_disk_plot::{SingleDiskPlot, SingleDiskPlotSummary};
+
+/// Log files written when farmer runs as launchd agent
+const SERVICE_LOG_FILES: &[&str] = &["farmer.log", "farmer.err.log"];
+const REDACTED: &str = "<redacted>";
+/// libp2p peer IDs (both Ed25519 identity and SHA2-256 multihash forms)
+const PEER_ID_PATTERN: &str = r"\b(?:12D3KooW|Qm)[1-9A-HJ-NP-Za-km-z]{40,}\b";
+/// SS58 addresses, such as reward addresses
+const SS58_ADDRESS_PATTERN: &str = r"\b[1-9A-HJ-NP-Za-km-z]{46,48}\b";
+const IPV4_PATTERN: &str = r"\b(?:\d{1,3}\.){3}\d{1,3}\b";
+/// IPv6 addresses in multiaddrs and socket addresses
+const IPV6_PATTERN: &str = r"/ip6/([0-9a-fA-F:.]+)|\[([0-9a-fA-F.]*:[0-9a-fA-F:.]*)\]";
+
+/// Removes information that identifies user or their machine from diagnostics: public keys,
+/// reward addresses, peer IDs, IP addresses and home directory
+struct Redactor {
+    secrets: Vec<String>,
+    home_dir: Option<String>,
+    peer_id: Regex,
+    ss58_address: Regex,
+    ipv4: Regex,
+    ipv6: Regex,
+}
+
+impl Redactor {
+    fn new() -> Self {
+        Self {
+            secrets: Vec::new(),
+            home_dir: dirs::home_dir().map(|home_dir| home_dir.display().to_string()),
+            peer_id: Regex::new(PEER_ID_PATTERN).expect("Pattern is valid; qed"),
+            ss58_address: Regex::new(SS58_ADDRESS_PATTERN).expect("Pattern is valid; qed"),
+            ipv4: Regex::new(IPV4_PATTERN).expect("Pattern is valid; qed"),
+            ipv6: Regex::new(IPV6_PATTERN).expect("Pattern is valid; qed"),
+        }
+    }
+
+    fn add_secret(&mut self, secret: String) {
+        self.secrets.push(secret);
+    }
+
+    fn redact(&self, text: &str) -> String {
+        let mut text = text.to_string();
+        if let Some(home_dir) = &self.home_dir {
+            text = text.replace(home_dir.as_str(), "~");
+        }
+        for secret in &self.secrets {
+            text = text.replace(secret.as_str(), REDACTED);
+        }
+
+        let text = self.peer_id.replace_all(&text, REDACTED);
+        let text = self.ss58_address.replace_all(&text, REDACTED);
+        let text = self.ipv4.replace_all(&text, |captures: &Captures<'_>| {
+            redact_ip_address(&captures[0]).to_string()
+        });
+        let text = self.ipv6.replace_all(&text, |captures: &Captures<'_>| {
+            let whole = &captures[0];
+            match captures.get(1).or_else(|| captures.get(2)) {
+                Some(address) => {
+                    whole.replace(address.as_str(), redact_ip_address(address.as_str()))
+                }
+                None => whole.to_string(),
+            }
+        });
+
+        text.into_owned()
+    }
+}
+
+/// Loopback and unspecified addresses don't identify the machine and help to understand
+/// configuration, everything else is redacted
+fn redact_ip_address(address: &str) -> &str {
+    match address.parse::<IpAddr>() {
+        Ok(ip_address) if ip_address.is_loopback() || ip_address.is_unspecified() => address,
+        _ => REDACTED,
+    }
+}
+
+/// Collect diagnostic information about farms, disks and networking databases along with recent
+/// logs into a single `.tar.gz` archive that can be attached to bug reports.
+///
+/// Public keys, reward addresses, peer IDs, IP addresses and home directory are redacted.
+pub(crate) fn diagnostics(
+    base_path: &Path,
+    disk_farms: Vec<DiskFarm>,
+    diagnostics_args: DiagnosticsArgs,
+) -> anyhow::Result<()> {
+    let DiagnosticsArgs {
+        output,
+        log_files,
+        log_lines,
+    } = diagnostics_args;
+
+    let mut redactor = Redactor::new();
+    let mut summary = String::new();
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let _ = writeln!(summary, "# Subspace farmer diagnostics");
+    let _ = writeln!(summary, "Version: {}", env!("CARGO_PKG_VERSION"));
+    let _ = writeln!(
+        summary,
+        "OS: {} ({})",
+        std::env::consts::OS,
+        std::env::consts::ARCH
+    );
+    let _ = writeln!(summary, "Timestamp: {timestamp}");
+
+    for (disk_farm_index, disk_farm) in disk_farms.into_iter().enumerate() {
+        let directory = disk_farm.directory;
+
+        let _ = writeln!(summary);
+        let _ = writeln!(summary, "## Farm {disk_farm_index}");
+        let _ = writeln!(summary, "Directory: {}", directory.display());
+
+        match SingleDiskPlot::collect_summary(directory.clone()) {
+            SingleDiskPlotSummary::Found { info, .. } => {
+                redactor.add_secret(hex::encode(info.public_key()));
+                let _ = writeln!(summary, "ID: {}", info.id());
+                let _ = writeln!(
+                    summary,
+                    "Genesis hash: 0x{}",
+                    hex::encode(info.genesis_hash())
+                );
+                let _ = writeln!(summary, "Pieces in sector: {}", info.pieces_in_sector());
+                let _ = writeln!(
+                    summary,
+                    "Allocated space: {}",
+                    bytesize::to_string(info.allocated_space(), true)
+                );
+            }
+            SingleDiskPlotSummary::NotFound { .. } => {
+                let _ = writeln!(summary, "No farm found");
+            }
+            SingleDiskPlotSummary::Error { error, .. } => {
+                let _ = writeln!(summary, "Failed to open farm info: {error}");
+            }
+        }
+
+        match SingleDiskPlot::read_metadata(&directory) {
+            Ok(metadata) => {
+                let _ = writeln!(summary, "Format version: {}", metadata.version);
+                let _ = writeln!(
+                    summary,
+                    "Plotted sectors: {}",
+                    metadata.sectors_metadata.len()
+                );
+                let _ = writeln!(
+                    summary,
+                    "Plot file size: {}",
+                    bytesize::to_string(metadata.plot_file_size, true)
+                );
+                let _ = writeln!(
+                    summary,
+                    "Metadata file size: {}",
+                    bytesize::to_string(metadata.metadata_file_size, true)
+                );
+            }
+            Err(error) => {
+                let _ = writeln!(summary, "Failed to read plot metadata: {error}");
+            }
+        }
+
+        match (
+            fs2::total_space(&directory),
+            fs2::available_space(&directory),
+        ) {
+            (Ok(total_space), Ok(available_space)) => {
+                let _ = writeln!(
+                    summary,
+                    "Disk space: {} available of {}",
+                    bytesize::to_string(available_space, true),
+                    bytesize::to_string(total_space, true)
+                );
+            }
+            (Err(error), _) | (_, Err(error)) => {
+                let _ = writeln!(summary, "Failed to get disk space: {error}");
+            }
+        }
+    }
+
+    let _ = writeln!(summary);
+    let _ = writeln!(summary, "## Networking");
+    for database in SHARED_DATABASES {
+        match directory_size(&base_path.join(database)) {
+            Ok(size) => {
+                let _ = writeln!(summary, "{database}: {}", bytesize::to_string(size, true));
+            }
+            Err(error) => {
+                let _ = writeln!(summary, "{database}: unavailable ({error})");
+            }
+        }
+    }
+
+    let output = output.unwrap_or_else(|| PathBuf::from(format!("diagnostics-{timestamp}.tar.gz")));
+    let mut archive = tar::Builder::new(GzEncoder::new(
+        File::create(&output)?,
+        Compression::default(),
+    ));
+    let mut append_file = |path: &str, contents: &str| {
+        let contents = redactor.redact(contents);
+        let mut header = tar::Header::new_gnu();
+        header.set_size(contents.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(timestamp);
+        archive.append_data(&mut header, path, contents.as_bytes())
+    };
+
+    append_file("summary.txt", &summary)?;
+
+    let service_log_directory = default_base_path();
+    let log_files = log_files.into_iter().chain(
+        SERVICE_LOG_FILES
+            .iter()
+            .map(|log_file| service_log_directory.join(log_file))
+            .filter(|log_file| log_file.exists()),
+    );
+    for (log_file_index, log_file) in log_files.enumerate() {
+        let file_name = log_file
+            .file_name()
+            .map(|file_name| file_name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "log".to_string());
+        let contents = match fs::read_to_string(&log_file) {
+            Ok(contents) => {
+                let lines = contents.lines().collect::<Vec<_>>();
+                let mut tail = lines[lines.len().saturating_sub(log_lines)..].join("\n");
+                tail.push('\n');
+                tail
+            }
+            Err(error) => {
+                format!("Failed to read log file {}: {error}\n", log_file.display())
+            }
+        };
+
+        // Index avoids collisions between log files with the same name in different directories
+        append_file(&format!("logs/{log_file_index}-{file_name}"), &contents)?;
+    }
+
+    archive.into_inner()?.finish()?;
+    println!("Diagnostics written to {}", output.display());
+
+    Ok(())
+}
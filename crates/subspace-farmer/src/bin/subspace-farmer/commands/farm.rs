@@ -1,13 +1,29 @@
+mod alerts;
+mod dashboard;
+mod declarations;
 mod dsn;
-
+mod farms;
+mod health;
+mod management;
+mod metrics_push;
+mod preflight;
+mod telemetry;
+
+use crate::commands::farm::alerts::{run_alert_monitor, Alert, AlertNotifier};
+use crate::commands::farm::dashboard::run_dashboard;
+use crate::commands::farm::declarations::run_farm_declarations;
 use crate::commands::farm::dsn::configure_dsn;
-use crate::commands::shared::print_disk_farm_info;
-use crate::utils::{get_required_plot_space_with_overhead, shutdown_signal};
+use crate::commands::farm::farms::{add_plotted_sectors, run_farms, FarmPublicKeys, FarmStarter};
+use crate::commands::farm::health::start_health_check_server;
+use crate::commands::farm::management::start_management_server;
+use crate::commands::farm::metrics_push::run_metrics_push;
+use crate::commands::farm::preflight::validate_disk_farms;
+use crate::commands::farm::telemetry::run_telemetry;
+use crate::utils::LogTail;
 use crate::{DiskFarm, FarmingArgs};
 use anyhow::{anyhow, Context, Result};
-use futures::future::{select, Either};
-use futures::stream::FuturesUnordered;
-use futures::{FutureExt, StreamExt};
+use futures::channel::mpsc;
+use futures::{Future, FutureExt, StreamExt};
 use lru::LruCache;
 use parking_lot::Mutex;
 use std::num::NonZeroUsize;
@@ -15,14 +31,10 @@ use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Duration;
 use subspace_core_primitives::crypto::kzg::{embedded_kzg_settings, Kzg};
-use subspace_core_primitives::{
-    ArchivedHistorySegment, Piece, PieceIndex, Record, SectorIndex, SegmentIndex,
-};
+use subspace_core_primitives::{ArchivedHistorySegment, Piece, PieceIndex, Record, SegmentIndex};
 use subspace_erasure_coding::ErasureCoding;
-use subspace_farmer::single_disk_plot::{
-    SingleDiskPlot, SingleDiskPlotError, SingleDiskPlotOptions,
-};
 use subspace_farmer::utils::archival_storage_pieces::ArchivalStoragePieces;
+use subspace_farmer::utils::farmer_health::{FarmerHealth, HealthThresholds};
 use subspace_farmer::utils::farmer_piece_cache::FarmerPieceCache;
 use subspace_farmer::utils::farmer_piece_getter::FarmerPieceGetter;
 use subspace_farmer::utils::node_piece_getter::NodePieceGetter;
@@ -31,15 +43,13 @@ use subspace_farmer::utils::piece_validator::SegmentCommitmentPieceValidator;
 use subspace_farmer::utils::readers_and_pieces::ReadersAndPieces;
 use subspace_farmer::utils::run_future_in_dedicated_thread;
 use subspace_farmer::{Identity, NodeClient, NodeRpcClient};
-use subspace_farmer_components::plotting::{PieceGetter, PieceGetterRetryPolicy, PlottedSector};
+use subspace_farmer_components::plotting::{PieceGetter, PieceGetterRetryPolicy};
 use subspace_networking::libp2p::identity::{ed25519, Keypair};
 use subspace_networking::utils::multihash::ToMultihash;
-use subspace_networking::utils::piece_announcement::announce_single_piece_index_hash_with_backoff;
 use subspace_networking::utils::piece_provider::PieceProvider;
 use subspace_proof_of_space::Table;
-use tokio::sync::{broadcast, OwnedSemaphorePermit};
 use tokio::time::sleep;
-use tracing::{debug, error, info, info_span, trace, warn, Instrument};
+use tracing::{debug, error, info, trace, warn};
 use zeroize::Zeroizing;
 
 const RECORDS_ROOTS_CACHE_SIZE: NonZeroUsize = NonZeroUsize::new(1_000_000).expect("Not zero; qed");
@@ -48,10 +58,14 @@ const GET_PIECE_DELAY_IN_SECS: u64 = 3;
 
 /// Start farming by using multiple replica plot in specified path and connecting to WebSocket
 /// server at specified address.
+///
+/// Farmer shuts down gracefully once `shutdown` future resolves.
 pub(crate) async fn farm_multi_disk<PosTable>(
     base_path: PathBuf,
     disk_farms: Vec<DiskFarm>,
     farming_args: FarmingArgs,
+    log_tail: Option<LogTail>,
+    shutdown: impl Future<Output = ()>,
 ) -> Result<(), anyhow::Error>
 where
     PosTable: Table,
@@ -60,8 +74,6 @@ where
         return Err(anyhow!("There must be at least one disk farm provided"));
     }
 
-    let signal = shutdown_signal();
-
     // TODO: Use variables and remove this suppression
     #[allow(unused_variables)]
     let FarmingArgs {
@@ -74,8 +86,21 @@ where
         mut dsn,
         max_concurrent_plots,
         no_info: _,
+        tui: _,
+        health_check_listen_on,
+        telemetry_endpoint,
+        telemetry_interval,
+        management_listen_on,
+        management_token,
+        alert_webhook,
+        alert_node_rpc_disconnection_threshold,
+        metrics_push_endpoint,
+        metrics_push_interval,
+        metrics_push_label,
     } = farming_args;
 
+    validate_disk_farms(&disk_farms, reward_address.is_some())?;
+
     let readers_and_pieces = Arc::new(Mutex::new(None));
 
     info!(url = %node_rpc_url, "Connecting to node RPC");
@@ -121,11 +146,30 @@ where
             &readers_and_pieces,
             node_client.clone(),
             archival_storage_pieces.clone(),
+            metrics_push_endpoint.is_some(),
         )?
     };
 
     let piece_cache = Arc::new(tokio::sync::Mutex::new(piece_cache));
 
+    let farmer_health = FarmerHealth::new(HealthThresholds::default());
+    node.on_num_established_peer_connections_change(Arc::new({
+        let farmer_health = farmer_health.clone();
+
+        move |&connected_peers| {
+            farmer_health.set_connected_peers(connected_peers);
+        }
+    }))
+    .detach();
+
+    let _health_check_server = match health_check_listen_on {
+        Some(listen_on) => Some(
+            start_health_check_server(listen_on, farmer_health.clone(), node_client.clone())
+                .await?,
+        ),
+        None => None,
+    };
+
     let kzg = Kzg::new(embedded_kzg_settings());
     let erasure_coding = ErasureCoding::new(
         NonZeroUsize::new(Record::NUM_S_BUCKETS.next_power_of_two().ilog2() as usize).unwrap(),
@@ -164,13 +208,13 @@ where
         Box::pin({
             let piece_cache = piece_cache.clone();
             let node_client = node_client.clone();
+            let farmer_health = farmer_health.clone();
 
-            fill_piece_cache_from_archived_segments(node_client, piece_cache)
+            fill_piece_cache_from_archived_segments(node_client, piece_cache, farmer_health)
         }),
         "pieces-cache-maintainer".to_string(),
     )?;
 
-    let mut single_disk_plots = Vec::with_capacity(disk_farms.len());
     let max_pieces_in_sector = match max_pieces_in_sector {
         Some(max_pieces_in_sector) => {
             if max_pieces_in_sector > farmer_app_info.protocol_info.max_pieces_in_sector {
@@ -189,201 +233,81 @@ where
         None => farmer_app_info.protocol_info.max_pieces_in_sector,
     };
 
-    // TODO: Check plot and metadata sizes to ensure there is enough space for farmer to not
-    //  fail later
-    for (disk_farm_index, disk_farm) in disk_farms.into_iter().enumerate() {
-        debug!(url = %node_rpc_url, %disk_farm_index, "Connecting to node RPC");
-        let node_client = NodeRpcClient::new(&node_rpc_url).await?;
-
-        let single_disk_plot_fut = SingleDiskPlot::new::<_, _, PosTable>(
-            SingleDiskPlotOptions {
-                directory: disk_farm.directory.clone(),
-                farmer_app_info: farmer_app_info.clone(),
-                allocated_space: disk_farm.allocated_plotting_space,
-                max_pieces_in_sector,
-                node_client,
-                reward_address,
-                kzg: kzg.clone(),
-                erasure_coding: erasure_coding.clone(),
-                piece_getter: piece_getter.clone(),
-                concurrent_plotting_semaphore: Arc::clone(&concurrent_plotting_semaphore),
-            },
-            disk_farm_index,
-        );
-
-        let single_disk_plot = match single_disk_plot_fut.await {
-            Ok(single_disk_plot) => single_disk_plot,
-            Err(SingleDiskPlotError::InsufficientAllocatedSpace {
-                min_size,
-                allocated_space,
-            }) => {
-                let minimum_plot_size = get_required_plot_space_with_overhead(min_size as u64);
-                let allocated_plotting_space_with_overhead =
-                    get_required_plot_space_with_overhead(allocated_space);
-
-                return Err(anyhow::anyhow!(
-                    "Plot size is too low ({} bytes). Minimum is {}",
-                    allocated_plotting_space_with_overhead,
-                    minimum_plot_size
-                ));
-            }
-            Err(error) => {
-                return Err(error.into());
-            }
-        };
-
-        if !farming_args.no_info {
-            print_disk_farm_info(disk_farm.directory, disk_farm_index);
-        }
+    let farm_public_keys = FarmPublicKeys::default();
+    let farm_starter = FarmStarter {
+        node_rpc_url,
+        farmer_app_info,
+        max_pieces_in_sector,
+        reward_address,
+        kzg,
+        erasure_coding,
+        piece_getter,
+        concurrent_plotting_semaphore: Arc::clone(&concurrent_plotting_semaphore),
+        readers_and_pieces: Arc::clone(&readers_and_pieces),
+        node: node.clone(),
+        farmer_health: farmer_health.clone(),
+        farm_public_keys: Arc::clone(&farm_public_keys),
+        no_info: farming_args.no_info,
+    };
 
-        single_disk_plots.push(single_disk_plot);
+    let mut single_disk_plots = Vec::with_capacity(disk_farms.len());
+    for (disk_farm_index, disk_farm) in disk_farms.into_iter().enumerate() {
+        let disk_farm_index = disk_farm_index.try_into().map_err(|_error| {
+            anyhow!(
+                "More than 256 plots are not supported, consider running multiple farmer \
+                instances"
+            )
+        })?;
+        let directory = disk_farm.directory.clone();
+        let single_disk_plot = farm_starter
+            .create_farm::<PosTable>(disk_farm, disk_farm_index)
+            .await?;
+
+        single_disk_plots.push((directory, single_disk_plot));
     }
 
-    // Store piece readers so we can reference them later
-    let piece_readers = single_disk_plots
-        .iter()
-        .map(|single_disk_plot| single_disk_plot.piece_reader())
-        .collect::<Vec<_>>();
-
     info!("Collecting already plotted pieces (this will take some time)...");
 
     // Collect already plotted pieces
     {
         let mut readers_and_pieces = readers_and_pieces.lock();
-        let readers_and_pieces = readers_and_pieces.insert(ReadersAndPieces::new(
-            piece_readers,
-            archival_storage_pieces,
-        ));
-
-        single_disk_plots.iter().enumerate().try_for_each(
-            |(disk_farm_index, single_disk_plot)| {
-                let disk_farm_index = disk_farm_index.try_into().map_err(|_error| {
-                    anyhow!(
-                        "More than 256 plots are not supported, consider running multiple farmer \
-                        instances"
-                    )
-                })?;
-
-                (0 as SectorIndex..)
-                    .zip(single_disk_plot.plotted_sectors())
-                    .for_each(
-                        |(sector_index, plotted_sector_result)| match plotted_sector_result {
-                            Ok(plotted_sector) => {
-                                readers_and_pieces.add_sector(disk_farm_index, &plotted_sector);
-                            }
-                            Err(error) => {
-                                error!(
-                                    %error,
-                                    %disk_farm_index,
-                                    %sector_index,
-                                    "Failed reading plotted sector on startup, skipping"
-                                );
-                            }
-                        },
-                    );
+        let readers_and_pieces =
+            readers_and_pieces.insert(ReadersAndPieces::new(Vec::new(), archival_storage_pieces));
 
-                Ok::<_, anyhow::Error>(())
-            },
-        )?;
+        for (disk_farm_index, (_directory, single_disk_plot)) in (0u8..).zip(&single_disk_plots) {
+            add_plotted_sectors(readers_and_pieces, disk_farm_index, single_disk_plot);
+        }
     }
 
     info!("Finished collecting already plotted pieces successfully");
 
-    let mut single_disk_plots_stream = single_disk_plots
-        .into_iter()
-        .enumerate()
-        .map(|(disk_farm_index, single_disk_plot)| {
-            let disk_farm_index = disk_farm_index.try_into().expect(
-                "More than 256 plots are not supported, this is checked above already; qed",
-            );
-            let readers_and_pieces = Arc::clone(&readers_and_pieces);
-            let node = node.clone();
-            let span = info_span!("farm", %disk_farm_index);
-
-            // We are not going to send anything here, but dropping of sender on dropping of
-            // corresponding `SingleDiskPlot` will allow us to stop background tasks.
-            let (dropped_sender, _dropped_receiver) = broadcast::channel::<()>(1);
-
-            // Collect newly plotted pieces
-            let on_plotted_sector_callback = move |(
-                plotted_sector,
-                maybe_old_plotted_sector,
-                plotting_permit,
-            ): &(
-                PlottedSector,
-                Option<PlottedSector>,
-                Arc<OwnedSemaphorePermit>,
-            )| {
-                let _span_guard = span.enter();
-                let plotting_permit = Arc::clone(plotting_permit);
-                let node = node.clone();
-                let sector_index = plotted_sector.sector_index;
-
-                let mut dropped_receiver = dropped_sender.subscribe();
-
-                {
-                    let mut readers_and_pieces = readers_and_pieces.lock();
-                    let readers_and_pieces = readers_and_pieces
-                        .as_mut()
-                        .expect("Initial value was populated above; qed");
-
-                    if let Some(old_plotted_sector) = maybe_old_plotted_sector {
-                        readers_and_pieces.delete_sector(disk_farm_index, old_plotted_sector);
-                    }
-                    readers_and_pieces.add_sector(disk_farm_index, plotted_sector);
-                }
-
-                let piece_indexes = plotted_sector.piece_indexes.clone();
-                // TODO: Remove when we no longer need announcements
-                let publish_fut = async move {
-                    let mut pieces_publishing_futures = piece_indexes
-                        .iter()
-                        .map(|piece_index| {
-                            announce_single_piece_index_hash_with_backoff(piece_index.hash(), &node)
-                        })
-                        .collect::<FuturesUnordered<_>>();
-
-                    while pieces_publishing_futures.next().await.is_some() {
-                        // Nothing is needed here, just driving all futures to completion
-                    }
-
-                    info!(?sector_index, "Sector publishing was successful.");
-
-                    // Release only after publishing is finished
-                    drop(plotting_permit);
-                }
-                .in_current_span();
-
-                tokio::spawn(async move {
-                    let result =
-                        select(Box::pin(publish_fut), Box::pin(dropped_receiver.recv())).await;
-                    if matches!(result, Either::Right(_)) {
-                        debug!("Piece publishing was cancelled due to shutdown.");
-                    }
-                });
-            };
-
-            single_disk_plot
-                .on_sector_plotted(Arc::new(on_plotted_sector_callback))
-                .detach();
+    // Drop original instance such that the only remaining instances are in farm starter and
+    // `SingleDiskPlot` event handlers
+    drop(readers_and_pieces);
 
-            single_disk_plot.run()
-        })
-        .collect::<FuturesUnordered<_>>();
+    let (farm_commands_sender, farm_commands_receiver) = mpsc::channel(1);
 
-    // Drop original instance such that the only remaining instances are in `SingleDiskPlot`
-    // event handlers
-    drop(readers_and_pieces);
+    let _management_server = match management_listen_on {
+        Some(listen_on) => Some(start_management_server(
+            listen_on,
+            management_token.ok_or_else(|| {
+                anyhow!("`--management-token` is required when management API is enabled")
+            })?,
+            farmer_health.clone(),
+            node_client.clone(),
+            Arc::clone(&concurrent_plotting_semaphore),
+            u32::try_from(max_concurrent_plots.get())?,
+            farm_commands_sender,
+        )?),
+        None => None,
+    };
 
     let farm_fut = run_future_in_dedicated_thread(
-        Box::pin(async move {
-            while let Some(result) = single_disk_plots_stream.next().await {
-                result?;
-
-                info!("Farm exited successfully");
-            }
-            anyhow::Ok(())
-        }),
+        Box::pin(run_farms::<PosTable, _>(
+            farm_starter,
+            single_disk_plots,
+            farm_commands_receiver,
+        )),
         "farmer-farm".to_string(),
     )?;
     let mut farm_fut = Box::pin(farm_fut).fuse();
@@ -394,13 +318,90 @@ where
     )?;
     let mut networking_fut = Box::pin(networking_fut).fuse();
 
+    let alert_notifier = (!alert_webhook.is_empty()).then(|| AlertNotifier::new(alert_webhook));
+
+    let mut alerts_fut = match &alert_notifier {
+        Some(alert_notifier) => run_alert_monitor(
+            alert_notifier.clone(),
+            farmer_health.clone(),
+            node_client.clone(),
+            Duration::from_secs(alert_node_rpc_disconnection_threshold * 60),
+        )
+        .boxed(),
+        None => futures::future::pending().boxed(),
+    }
+    .fuse();
+
+    let mut declarations_fut = Box::pin(run_farm_declarations(
+        node_client.clone(),
+        farm_public_keys,
+        farmer_health.clone(),
+    ))
+    .fuse();
+
+    let mut dashboard_fut = match log_tail {
+        Some(log_tail) => run_dashboard(farmer_health.clone(), node_client, log_tail).boxed(),
+        None => futures::future::pending().boxed(),
+    }
+    .fuse();
+
+    let mut telemetry_fut = match telemetry_endpoint {
+        Some(telemetry_endpoint) => run_telemetry(
+            telemetry_endpoint,
+            Duration::from_secs(telemetry_interval),
+            farmer_health.clone(),
+        )
+        .boxed(),
+        None => futures::future::pending().boxed(),
+    }
+    .fuse();
+
+    let mut metrics_push_fut = match metrics_push_endpoint {
+        Some(metrics_push_endpoint) => run_metrics_push(
+            metrics_push_endpoint,
+            Duration::from_secs(metrics_push_interval),
+            metrics_push_label,
+            farmer_health.clone(),
+            node.metrics().cloned(),
+        )
+        .boxed(),
+        None => futures::future::pending().boxed(),
+    }
+    .fuse();
+
     futures::select!(
-        // Signal future
-        _ = signal.fuse() => {},
+        // Shutdown future
+        _ = shutdown.fuse() => {},
+
+        // Dashboard future, never finishes
+        _ = dashboard_fut => {},
+
+        // Telemetry future, never finishes
+        _ = telemetry_fut => {},
+
+        // Alerts future, never finishes
+        _ = alerts_fut => {},
+
+        // Farm declarations future, never finishes
+        _ = declarations_fut => {},
+
+        // Metrics push future, only finishes on invalid configuration
+        result = metrics_push_fut => {
+            result?;
+        },
 
         // Farm future
         result = farm_fut => {
-            result??;
+            let result = result?;
+            if let (Err(error), Some(alert_notifier)) = (&result, &alert_notifier) {
+                alert_notifier
+                    .notify(Alert::new(
+                        "farmError",
+                        format!("Farm exited with error: {error}"),
+                    ))
+                    .await;
+            }
+            result?;
         },
 
         // Node runner future
@@ -484,6 +485,7 @@ async fn populate_pieces_cache<PG, PC>(
 async fn fill_piece_cache_from_archived_segments(
     node_client: NodeRpcClient,
     piece_cache: Arc<tokio::sync::Mutex<FarmerPieceCache>>,
+    farmer_health: FarmerHealth,
 ) {
     let segment_headers_notifications = node_client
         .subscribe_archived_segment_headers()
@@ -495,6 +497,7 @@ async fn fill_piece_cache_from_archived_segments(
         Ok(mut segment_headers_notifications) => {
             while let Some(segment_header) = segment_headers_notifications.next().await {
                 let segment_index = segment_header.segment_index();
+                farmer_health.on_archived_segment();
 
                 debug!(%segment_index, "Starting to process archived segment....");
 
@@ -0,0 +1,155 @@
+use crate::commands::farm::health::is_node_rpc_available;
+use crate::utils::{http_client, http_post, HttpClient};
+use hyper::Uri;
+use serde::Serialize;
+use std::time::{Duration, Instant};
+use subspace_farmer::utils::farmer_health::FarmerHealth;
+use subspace_farmer::NodeRpcClient;
+use tokio::time::sleep;
+use tracing::{debug, warn};
+
+/// How often conditions that trigger alerts are checked
+const ALERT_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+/// Audit happens every slot, not having audits for this long means challenges are being missed
+const MAX_AUDIT_AGE: Duration = Duration::from_secs(60);
+
+/// Alert sent to webhooks as JSON
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(super) struct Alert {
+    /// Machine-readable event name
+    event: &'static str,
+    /// Human-readable description of the event
+    message: String,
+    /// Farmer version
+    version: &'static str,
+}
+
+impl Alert {
+    pub(super) fn new(event: &'static str, message: String) -> Self {
+        Self {
+            event,
+            message,
+            version: env!("CARGO_PKG_VERSION"),
+        }
+    }
+}
+
+/// Sends alerts to configured webhooks
+#[derive(Clone)]
+pub(super) struct AlertNotifier {
+    webhooks: Vec<Uri>,
+    http_client: HttpClient,
+}
+
+impl AlertNotifier {
+    pub(super) fn new(webhooks: Vec<Uri>) -> Self {
+        Self {
+            webhooks,
+            http_client: http_client(),
+        }
+    }
+
+    /// Send alert to all webhooks, failures are logged and otherwise ignored
+    pub(super) async fn notify(&self, alert: Alert) {
+        warn!(event = %alert.event, "{}", alert.message);
+
+        let body = serde_json::to_vec(&alert).expect("Alert serialization never fails; qed");
+
+        for webhook in &self.webhooks {
+            match http_post(
+                &self.http_client,
+                webhook.clone(),
+                "application/json",
+                body.clone(),
+            )
+            .await
+            {
+                Ok(()) => {
+                    debug!(%webhook, event = %alert.event, "Alert sent");
+                }
+                Err(error) => {
+                    warn!(%webhook, %error, "Failed to send alert");
+                }
+            }
+        }
+    }
+}
+
+/// Watch for node RPC disconnections and missed challenges, send alerts when they start and when
+/// they are resolved
+pub(super) async fn run_alert_monitor(
+    alert_notifier: AlertNotifier,
+    farmer_health: FarmerHealth,
+    node_client: NodeRpcClient,
+    node_rpc_disconnection_threshold: Duration,
+) {
+    let started_at = Instant::now();
+    let mut node_rpc_disconnected_since = None::<Instant>;
+    let mut node_rpc_disconnection_alerted = false;
+    let mut missed_audits_alerted = false;
+
+    loop {
+        sleep(ALERT_CHECK_INTERVAL).await;
+
+        if is_node_rpc_available(&node_client).await {
+            node_rpc_disconnected_since.take();
+            if node_rpc_disconnection_alerted {
+                node_rpc_disconnection_alerted = false;
+                alert_notifier
+                    .notify(Alert::new(
+                        "nodeRpcReconnected",
+                        "Node RPC is available again".to_string(),
+                    ))
+                    .await;
+            }
+        } else {
+            let disconnected_for = node_rpc_disconnected_since
+                .get_or_insert_with(Instant::now)
+                .elapsed();
+            if !node_rpc_disconnection_alerted
+                && disconnected_for >= node_rpc_disconnection_threshold
+            {
+                node_rpc_disconnection_alerted = true;
+                alert_notifier
+                    .notify(Alert::new(
+                        "nodeRpcDisconnected",
+                        format!(
+                            "Node RPC is not available for {}s",
+                            disconnected_for.as_secs()
+                        ),
+                    ))
+                    .await;
+            }
+        }
+
+        // Before first audit measure age from start
+        let audit_age = farmer_health
+            .check(true)
+            .last_audit_secs_ago
+            .map(Duration::from_secs)
+            .unwrap_or_else(|| started_at.elapsed());
+        if audit_age > MAX_AUDIT_AGE {
+            if !missed_audits_alerted {
+                missed_audits_alerted = true;
+                alert_notifier
+                    .notify(Alert::new(
+                        "missedChallenges",
+                        format!(
+                            "No audits for {}s, challenges are being missed",
+                            audit_age.as_secs()
+                        ),
+                    ))
+                    .await;
+            }
+        } else if missed_audits_alerted {
+            missed_audits_alerted = false;
+            alert_notifier
+                .notify(Alert::new(
+                    "challengesResumed",
+                    "Audits resumed".to_string(),
+                ))
+                .await;
+        }
+    }
+}
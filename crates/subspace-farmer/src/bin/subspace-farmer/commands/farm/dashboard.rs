@@ -0,0 +1,124 @@
+use crate::commands::farm::health::is_node_rpc_available;
+use crate::utils::{format_eta, LogTail};
+use futures::future;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use subspace_farmer::utils::farmer_health::{FarmerHealth, HealthState};
+use subspace_farmer::NodeRpcClient;
+use tokio::time::sleep;
+
+const REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+/// How often node RPC availability is checked, dashboard uses last known result in between
+const NODE_RPC_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+const PROGRESS_BAR_WIDTH: usize = 30;
+/// Clear screen and move cursor to the top left corner
+const CLEAR_SCREEN: &str = "\x1b[2J\x1b[H";
+
+/// Periodically render terminal dashboard with status of the farmer and recent logs
+pub(super) async fn run_dashboard(
+    farmer_health: FarmerHealth,
+    node_client: NodeRpcClient,
+    log_tail: LogTail,
+) {
+    let node_rpc_available = AtomicBool::new(false);
+
+    // Node RPC check can take up to its timeout when node is down, so it runs concurrently with
+    // rendering instead of delaying every refresh
+    let check_node_rpc_fut = async {
+        loop {
+            node_rpc_available.store(is_node_rpc_available(&node_client).await, Ordering::Relaxed);
+
+            sleep(NODE_RPC_CHECK_INTERVAL).await;
+        }
+    };
+
+    let render_fut = async {
+        loop {
+            render(
+                &farmer_health,
+                node_rpc_available.load(Ordering::Relaxed),
+                &log_tail,
+            );
+
+            sleep(REFRESH_INTERVAL).await;
+        }
+    };
+
+    future::join(check_node_rpc_fut, render_fut).await;
+}
+
+fn render(farmer_health: &FarmerHealth, node_rpc_available: bool, log_tail: &LogTail) {
+    let report = farmer_health.check(node_rpc_available);
+
+    let mut output = String::from(CLEAR_SCREEN);
+    let state = match report.state {
+        HealthState::Healthy => "healthy",
+        HealthState::Degraded => "degraded",
+        HealthState::Unhealthy => "unhealthy",
+    };
+    let _ = writeln!(output, "Subspace farmer ({state})");
+    let _ = writeln!(
+        output,
+        "Node RPC: {}, DSN peers: {}, last audit: {}, last archived segment: {}",
+        if report.node_rpc_available {
+            "available"
+        } else {
+            "unavailable"
+        },
+        report.connected_peers,
+        format_secs_ago(report.last_audit_secs_ago),
+        format_secs_ago(report.last_archived_segment_secs_ago),
+    );
+    let _ = writeln!(output);
+
+    for farm in &report.farms {
+        let progress = if farm.total_sectors == 0 {
+            1.0
+        } else {
+            (farm.plotted_sectors as f64 / farm.total_sectors as f64).min(1.0)
+        };
+        let filled = (progress * PROGRESS_BAR_WIDTH as f64) as usize;
+        let _ = writeln!(
+            output,
+            "Farm {}: [{}{}] {:5.1}% {}/{} sectors, {}, ETA: {}, solutions: {}",
+            farm.farm_index,
+            "#".repeat(filled),
+            ".".repeat(PROGRESS_BAR_WIDTH - filled),
+            progress * 100.0,
+            farm.plotted_sectors,
+            farm.total_sectors,
+            match farm.sectors_per_hour {
+                Some(sectors_per_hour) => format!("{sectors_per_hour:.1} sectors/h"),
+                None => "rate unknown".to_string(),
+            },
+            match farm.eta_secs {
+                Some(eta_secs) => format_eta(eta_secs),
+                None => "unknown".to_string(),
+            },
+            farm.solutions,
+        );
+    }
+
+    if !report.issues.is_empty() {
+        let _ = writeln!(output);
+        for issue in &report.issues {
+            let _ = writeln!(output, "! {issue}");
+        }
+    }
+
+    let _ = writeln!(output);
+    let _ = writeln!(output, "Recent logs:");
+    for line in log_tail.lines() {
+        let _ = writeln!(output, "  {line}");
+    }
+
+    print!("{output}");
+}
+
+fn format_secs_ago(secs_ago: Option<u64>) -> String {
+    match secs_ago {
+        Some(secs_ago) => format!("{secs_ago}s ago"),
+        None => "never".to_string(),
+    }
+}
@@ -0,0 +1,39 @@
+use crate::commands::farm::farms::FarmPublicKeys;
+use std::time::Duration;
+use subspace_farmer::utils::farmer_health::FarmerHealth;
+use subspace_farmer::{NodeClient, NodeRpcClient};
+use subspace_rpc_primitives::FarmerDeclaration;
+use tokio::time::sleep;
+use tracing::{debug, warn};
+
+/// How often farms are declared to the node, must be well below declaration timeout on the node
+const FARM_DECLARATION_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Periodically declare farms to the node, such that node operator can see attached farmers
+pub(super) async fn run_farm_declarations(
+    node_client: NodeRpcClient,
+    farm_public_keys: FarmPublicKeys,
+    farmer_health: FarmerHealth,
+) {
+    loop {
+        // Farms might be added or removed through management API in the meantime
+        let farm_public_keys = farm_public_keys.lock().clone();
+        for (disk_farm_index, public_key) in farm_public_keys {
+            let Some(farm) = farmer_health.farm(usize::from(disk_farm_index)) else {
+                continue;
+            };
+            let farmer_declaration = FarmerDeclaration {
+                public_key,
+                plotted_space: farm.plotted_sectors * farm.sector_size,
+            };
+
+            if let Err(error) = node_client.declare_farmer(farmer_declaration).await {
+                warn!(%error, %public_key, "Failed to declare farm to the node");
+            } else {
+                debug!(?farmer_declaration, "Farm declared to the node");
+            }
+        }
+
+        sleep(FARM_DECLARATION_INTERVAL).await;
+    }
+}
@@ -5,7 +5,7 @@ use parking_lot::Mutex;
 use std::path::PathBuf;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use subspace_core_primitives::SegmentIndex;
 use subspace_farmer::utils::archival_storage_pieces::ArchivalStoragePieces;
 use subspace_farmer::utils::farmer_piece_cache::FarmerPieceCache;
@@ -18,11 +18,11 @@ use subspace_networking::libp2p::kad::ProviderRecord;
 use subspace_networking::libp2p::multiaddr::Protocol;
 use subspace_networking::utils::multihash::ToMultihash;
 use subspace_networking::{
-    create, peer_id, Config, NetworkingParametersManager, Node, NodeRunner,
-    ParityDbProviderStorage, PeerInfoProvider, PieceAnnouncementRequestHandler,
+    create, peer_id, Config, ConnectionLimitsPreset, KeepAlivePolicy, NetworkingParametersManager,
+    Node, NodeRunner, ParityDbProviderStorage, PeerInfoProvider, PieceAnnouncementRequestHandler,
     PieceAnnouncementResponse, PieceByHashRequest, PieceByHashRequestHandler, PieceByHashResponse,
     ProviderStorage, SegmentHeaderBySegmentIndexesRequestHandler, SegmentHeaderRequest,
-    SegmentHeaderResponse, KADEMLIA_PROVIDER_TTL_IN_SECS,
+    SegmentHeaderResponse, KADEMLIA_PROVIDER_TTL_IN_SECS, SEGMENT_INDEX_RANGE_LIMIT,
 };
 use tracing::{debug, error, info, trace, Instrument};
 
@@ -39,16 +39,21 @@ pub(super) fn configure_dsn(
         piece_cache_size,
         provided_keys_limit,
         disable_private_ips,
+        disable_quic,
+        prefer_quic,
         reserved_peers,
         in_connections,
         out_connections,
         pending_in_connections,
         pending_out_connections,
         target_connections,
+        connection_idle_timeout,
+        keep_alive_all_connections,
     }: DsnArgs,
     readers_and_pieces: &Arc<Mutex<Option<ReadersAndPieces>>>,
     node_client: NodeRpcClient,
     archival_storage_pieces: ArchivalStoragePieces,
+    enable_metrics: bool,
 ) -> Result<
     (
         Node,
@@ -146,11 +151,21 @@ pub(super) fn configure_dsn(
         keypair,
         farmer_provider_storage.clone(),
         PeerInfoProvider::new_farmer(Box::new(archival_storage_pieces)),
-    );
+    )
+    .with_connection_limits_preset(ConnectionLimitsPreset::Farmer);
     let config = Config {
         reserved_peers,
+        connection_idle_timeout: Duration::from_secs(connection_idle_timeout),
+        keep_alive_policy: if keep_alive_all_connections {
+            KeepAlivePolicy::AllConnections
+        } else {
+            KeepAlivePolicy::ReservedPeers
+        },
         listen_on,
         allow_non_global_addresses_in_dht: !disable_private_ips,
+        enable_quic: !disable_quic,
+        prefer_quic_for_dials: prefer_quic,
+        enable_metrics,
         networking_parameters_registry,
         request_response_protocols: vec![
             PieceAnnouncementRequestHandler::create({
@@ -262,6 +277,9 @@ pub(super) fn configure_dsn(
                                 .take(segment_header_number as usize)
                                 .collect::<Vec<_>>()
                         }
+                        SegmentHeaderRequest::SegmentIndexRange { start, end } => (start..=end)
+                            .take(SEGMENT_INDEX_RANGE_LIMIT as usize)
+                            .collect::<Vec<_>>(),
                     };
 
                     debug!(
@@ -0,0 +1,444 @@
+use crate::commands::farm::management::FarmCommand;
+use crate::commands::farm::preflight::validate_disk_farms;
+use crate::commands::shared::print_disk_farm_info;
+use crate::utils::{format_eta, get_required_plot_space_with_overhead};
+use crate::DiskFarm;
+use anyhow::anyhow;
+use futures::channel::{mpsc, oneshot};
+use futures::future::{select, Either};
+use futures::stream::FuturesUnordered;
+use futures::{Future, FutureExt, StreamExt};
+use parking_lot::Mutex;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::slice;
+use std::sync::Arc;
+use subspace_core_primitives::crypto::kzg::Kzg;
+use subspace_core_primitives::{PublicKey, SectorIndex};
+use subspace_erasure_coding::ErasureCoding;
+use subspace_farmer::single_disk_plot::scrubbing::PlotScrubber;
+use subspace_farmer::single_disk_plot::{
+    SingleDiskPlot, SingleDiskPlotError, SingleDiskPlotOptions,
+};
+use subspace_farmer::utils::farmer_health::FarmerHealth;
+use subspace_farmer::utils::readers_and_pieces::ReadersAndPieces;
+use subspace_farmer::NodeRpcClient;
+use subspace_farmer_components::plotting::{PieceGetter, PlottedSector};
+use subspace_networking::utils::piece_announcement::announce_single_piece_index_hash_with_backoff;
+use subspace_networking::Node;
+use subspace_proof_of_space::Table;
+use subspace_rpc_primitives::FarmerAppInfo;
+use tokio::sync::{broadcast, OwnedSemaphorePermit, Semaphore};
+use tracing::{debug, error, info, info_span, Instrument};
+
+/// Public keys of running farms by disk farm index, used for farm declarations
+pub(super) type FarmPublicKeys = Arc<Mutex<BTreeMap<u8, PublicKey>>>;
+
+/// Resolves with disk farm index and farm result once farm exits, `None` result means farm was
+/// removed
+type FarmFuture = Pin<Box<dyn Future<Output = (u8, Option<anyhow::Result<()>>)> + Send>>;
+
+/// Running farm
+struct FarmHandle {
+    directory: PathBuf,
+    scrubber: PlotScrubber,
+    /// Stops the farm when sent or dropped
+    remove_sender: oneshot::Sender<()>,
+}
+
+/// Everything that is needed to start farms, both on startup and when farm is added through
+/// management API
+pub(super) struct FarmStarter<PG> {
+    pub(super) node_rpc_url: String,
+    pub(super) farmer_app_info: FarmerAppInfo,
+    pub(super) max_pieces_in_sector: u16,
+    pub(super) reward_address: Option<PublicKey>,
+    pub(super) kzg: Kzg,
+    pub(super) erasure_coding: ErasureCoding,
+    pub(super) piece_getter: PG,
+    pub(super) concurrent_plotting_semaphore: Arc<Semaphore>,
+    pub(super) readers_and_pieces: Arc<Mutex<Option<ReadersAndPieces>>>,
+    pub(super) node: Node,
+    pub(super) farmer_health: FarmerHealth,
+    pub(super) farm_public_keys: FarmPublicKeys,
+    pub(super) no_info: bool,
+}
+
+impl<PG> FarmStarter<PG>
+where
+    PG: PieceGetter + Clone + Send + Sync + 'static,
+{
+    /// Create or open farm, farm doesn't do anything until started with [`Self::start_farm()`]
+    pub(super) async fn create_farm<PosTable>(
+        &self,
+        disk_farm: DiskFarm,
+        disk_farm_index: u8,
+    ) -> anyhow::Result<SingleDiskPlot>
+    where
+        PosTable: Table,
+    {
+        debug!(url = %self.node_rpc_url, %disk_farm_index, "Connecting to node RPC");
+        let node_client = NodeRpcClient::new(&self.node_rpc_url).await?;
+
+        let single_disk_plot_fut = SingleDiskPlot::new::<_, _, PosTable>(
+            SingleDiskPlotOptions {
+                directory: disk_farm.directory.clone(),
+                farmer_app_info: self.farmer_app_info.clone(),
+                allocated_space: disk_farm.allocated_plotting_space,
+                max_pieces_in_sector: self.max_pieces_in_sector,
+                node_client,
+                reward_address: disk_farm
+                    .reward_address
+                    .or(self.reward_address)
+                    .expect("Presence of reward address was validated above; qed"),
+                kzg: self.kzg.clone(),
+                erasure_coding: self.erasure_coding.clone(),
+                piece_getter: self.piece_getter.clone(),
+                concurrent_plotting_semaphore: Arc::clone(&self.concurrent_plotting_semaphore),
+            },
+            usize::from(disk_farm_index),
+        );
+
+        let single_disk_plot = match single_disk_plot_fut.await {
+            Ok(single_disk_plot) => single_disk_plot,
+            Err(SingleDiskPlotError::InsufficientAllocatedSpace {
+                min_size,
+                allocated_space,
+            }) => {
+                let minimum_plot_size = get_required_plot_space_with_overhead(min_size as u64);
+                let allocated_plotting_space_with_overhead =
+                    get_required_plot_space_with_overhead(allocated_space);
+
+                return Err(anyhow::anyhow!(
+                    "Plot size is too low ({} bytes). Minimum is {}",
+                    allocated_plotting_space_with_overhead,
+                    minimum_plot_size
+                ));
+            }
+            Err(error) => {
+                return Err(error.into());
+            }
+        };
+
+        if !self.no_info {
+            print_disk_farm_info(disk_farm.directory, usize::from(disk_farm_index));
+        }
+
+        Ok(single_disk_plot)
+    }
+
+    /// Start farming and plotting, farm's piece reader and already plotted sectors must be added
+    /// to [`ReadersAndPieces`] before this
+    fn start_farm(
+        &self,
+        directory: PathBuf,
+        single_disk_plot: SingleDiskPlot,
+        disk_farm_index: u8,
+    ) -> (FarmHandle, FarmFuture) {
+        let readers_and_pieces = Arc::clone(&self.readers_and_pieces);
+        let node = self.node.clone();
+        let farmer_health = self.farmer_health.clone();
+        let span = info_span!("farm", %disk_farm_index);
+        let farm_index = usize::from(disk_farm_index);
+        farmer_health.add_farm(
+            farm_index,
+            single_disk_plot.sector_size() as u64,
+            u64::from(single_disk_plot.total_sectors_count()),
+            single_disk_plot.plotted_sectors_count() as u64,
+        );
+        self.farm_public_keys
+            .lock()
+            .insert(disk_farm_index, *single_disk_plot.public_key());
+
+        // Solution response is sent after every audit, even if there are no solutions
+        single_disk_plot
+            .on_solution(Arc::new({
+                let farmer_health = farmer_health.clone();
+
+                move |solution_response| {
+                    farmer_health.on_audit(farm_index, solution_response.solutions.len());
+                }
+            }))
+            .detach();
+
+        // We are not going to send anything here, but dropping of sender on dropping of
+        // corresponding `SingleDiskPlot` will allow us to stop background tasks.
+        let (dropped_sender, _dropped_receiver) = broadcast::channel::<()>(1);
+
+        // Collect newly plotted pieces
+        let on_plotted_sector_callback =
+            move |(plotted_sector, maybe_old_plotted_sector, plotting_permit): &(
+                PlottedSector,
+                Option<PlottedSector>,
+                Arc<OwnedSemaphorePermit>,
+            )| {
+                let _span_guard = span.enter();
+                farmer_health.on_sector_plotted(farm_index, maybe_old_plotted_sector.is_some());
+                // Replotting doesn't change plotting progress
+                if maybe_old_plotted_sector.is_none() {
+                    if let Some(farm_status) = farmer_health.farm(farm_index) {
+                        let progress = if farm_status.total_sectors == 0 {
+                            100.0
+                        } else {
+                            farm_status.plotted_sectors as f64 * 100.0
+                                / farm_status.total_sectors as f64
+                        };
+                        let eta = farm_status
+                            .eta_secs
+                            .map(format_eta)
+                            .unwrap_or_else(|| "unknown".to_string());
+                        info!(
+                            plotted_sectors = %farm_status.plotted_sectors,
+                            total_sectors = %farm_status.total_sectors,
+                            %eta,
+                            "Plotting progress {progress:.2}%",
+                        );
+                    }
+                }
+                let plotting_permit = Arc::clone(plotting_permit);
+                let node = node.clone();
+                let sector_index = plotted_sector.sector_index;
+
+                let mut dropped_receiver = dropped_sender.subscribe();
+
+                {
+                    let mut readers_and_pieces = readers_and_pieces.lock();
+                    let readers_and_pieces = readers_and_pieces
+                        .as_mut()
+                        .expect("Initial value was populated before starting farms; qed");
+
+                    if let Some(old_plotted_sector) = maybe_old_plotted_sector {
+                        readers_and_pieces.delete_sector(disk_farm_index, old_plotted_sector);
+                    }
+                    readers_and_pieces.add_sector(disk_farm_index, plotted_sector);
+                }
+
+                let piece_indexes = plotted_sector.piece_indexes.clone();
+                // TODO: Remove when we no longer need announcements
+                let publish_fut = async move {
+                    let mut pieces_publishing_futures = piece_indexes
+                        .iter()
+                        .map(|piece_index| {
+                            announce_single_piece_index_hash_with_backoff(piece_index.hash(), &node)
+                        })
+                        .collect::<FuturesUnordered<_>>();
+
+                    while pieces_publishing_futures.next().await.is_some() {
+                        // Nothing is needed here, just driving all futures to completion
+                    }
+
+                    info!(?sector_index, "Sector publishing was successful.");
+
+                    // Release only after publishing is finished
+                    drop(plotting_permit);
+                }
+                .in_current_span();
+
+                tokio::spawn(async move {
+                    let result =
+                        select(Box::pin(publish_fut), Box::pin(dropped_receiver.recv())).await;
+                    if matches!(result, Either::Right(_)) {
+                        debug!("Piece publishing was cancelled due to shutdown.");
+                    }
+                });
+            };
+
+        single_disk_plot
+            .on_sector_plotted(Arc::new(on_plotted_sector_callback))
+            .detach();
+
+        let scrubber = single_disk_plot.scrubber();
+        let (remove_sender, remove_receiver) = oneshot::channel();
+
+        let farm_fut = async move {
+            // Farm is dropped together with run future when removed
+            match select(Box::pin(single_disk_plot.run()), remove_receiver).await {
+                Either::Left((result, _remove_receiver)) => (disk_farm_index, Some(result)),
+                Either::Right((_, _run_fut)) => (disk_farm_index, None),
+            }
+        };
+
+        let farm_handle = FarmHandle {
+            directory,
+            scrubber,
+            remove_sender,
+        };
+
+        (farm_handle, farm_fut.boxed())
+    }
+
+    /// Forget about farm that was removed
+    fn remove_farm(&self, disk_farm_index: u8) {
+        if let Some(readers_and_pieces) = self.readers_and_pieces.lock().as_mut() {
+            readers_and_pieces.remove_farm(disk_farm_index);
+        }
+        self.farmer_health.remove_farm(usize::from(disk_farm_index));
+        self.farm_public_keys.lock().remove(&disk_farm_index);
+    }
+}
+
+/// Add piece reader and already plotted sectors of the farm to [`ReadersAndPieces`]
+pub(super) fn add_plotted_sectors(
+    readers_and_pieces: &mut ReadersAndPieces,
+    disk_farm_index: u8,
+    single_disk_plot: &SingleDiskPlot,
+) {
+    readers_and_pieces.add_reader(disk_farm_index, single_disk_plot.piece_reader());
+
+    (0 as SectorIndex..)
+        .zip(single_disk_plot.plotted_sectors())
+        .for_each(
+            |(sector_index, plotted_sector_result)| match plotted_sector_result {
+                Ok(plotted_sector) => {
+                    readers_and_pieces.add_sector(disk_farm_index, &plotted_sector);
+                }
+                Err(error) => {
+                    error!(
+                        %error,
+                        %disk_farm_index,
+                        %sector_index,
+                        "Failed reading plotted sector on startup, skipping"
+                    );
+                }
+            },
+        );
+}
+
+/// Run farms until one of them fails or all of them exit, adding, removing and scrubbing farms on
+/// request from management API.
+///
+/// `single_disk_plots` are farms created on startup with their directories, their piece readers
+/// and plotted sectors must be already added to [`ReadersAndPieces`].
+pub(super) async fn run_farms<PosTable, PG>(
+    farm_starter: FarmStarter<PG>,
+    single_disk_plots: Vec<(PathBuf, SingleDiskPlot)>,
+    mut farm_commands: mpsc::Receiver<FarmCommand>,
+) -> anyhow::Result<()>
+where
+    PosTable: Table,
+    PG: PieceGetter + Clone + Send + Sync + 'static,
+{
+    let mut next_disk_farm_index = single_disk_plots.len();
+    let mut farms = HashMap::<u8, FarmHandle>::with_capacity(single_disk_plots.len());
+    let mut pending_removals = HashMap::<u8, oneshot::Sender<anyhow::Result<()>>>::new();
+    let mut farms_stream = FuturesUnordered::<FarmFuture>::new();
+
+    for (disk_farm_index, (directory, single_disk_plot)) in (0u8..).zip(single_disk_plots) {
+        let (farm_handle, farm_fut) =
+            farm_starter.start_farm(directory, single_disk_plot, disk_farm_index);
+        farms.insert(disk_farm_index, farm_handle);
+        farms_stream.push(farm_fut);
+    }
+
+    loop {
+        futures::select! {
+            maybe_farm_exit = farms_stream.next() => {
+                let Some((disk_farm_index, maybe_result)) = maybe_farm_exit else {
+                    break;
+                };
+
+                match maybe_result {
+                    Some(result) => {
+                        farms.remove(&disk_farm_index);
+                        result?;
+
+                        info!(%disk_farm_index, "Farm exited successfully");
+                    }
+                    None => {
+                        farm_starter.remove_farm(disk_farm_index);
+
+                        info!(%disk_farm_index, "Farm removed");
+
+                        if let Some(result_sender) = pending_removals.remove(&disk_farm_index) {
+                            let _ = result_sender.send(Ok(()));
+                        }
+                    }
+                }
+            }
+            farm_command = farm_commands.select_next_some() => match farm_command {
+                FarmCommand::Add { disk_farm, result_sender } => {
+                    let result = async {
+                        if farms.values().any(|farm| farm.directory == disk_farm.directory) {
+                            return Err(anyhow!(
+                                "Farm at {} is already running",
+                                disk_farm.directory.display()
+                            ));
+                        }
+                        let disk_farm_index = u8::try_from(next_disk_farm_index).map_err(|_| {
+                            anyhow!(
+                                "More than 256 plots are not supported, consider running \
+                                multiple farmer instances"
+                            )
+                        })?;
+                        validate_disk_farms(
+                            slice::from_ref(&disk_farm),
+                            farm_starter.reward_address.is_some(),
+                        )?;
+
+                        let directory = disk_farm.directory.clone();
+                        let single_disk_plot = farm_starter
+                            .create_farm::<PosTable>(disk_farm, disk_farm_index)
+                            .await?;
+                        next_disk_farm_index += 1;
+
+                        if let Some(readers_and_pieces) =
+                            farm_starter.readers_and_pieces.lock().as_mut()
+                        {
+                            add_plotted_sectors(
+                                readers_and_pieces,
+                                disk_farm_index,
+                                &single_disk_plot,
+                            );
+                        }
+
+                        let (farm_handle, farm_fut) =
+                            farm_starter.start_farm(directory, single_disk_plot, disk_farm_index);
+                        farms.insert(disk_farm_index, farm_handle);
+                        farms_stream.push(farm_fut);
+
+                        info!(%disk_farm_index, "Farm added");
+
+                        Ok(disk_farm_index)
+                    }
+                    .await;
+
+                    let _ = result_sender.send(result);
+                }
+                FarmCommand::Remove { disk_farm_index, result_sender } => {
+                    if !farms.contains_key(&disk_farm_index) {
+                        let _ = result_sender
+                            .send(Err(anyhow!("Farm {disk_farm_index} doesn't exist")));
+                    } else if farms.len() == 1 {
+                        let _ = result_sender.send(Err(anyhow!(
+                            "Farm {disk_farm_index} is the last farm and can't be removed"
+                        )));
+                    } else if let Some(farm_handle) = farms.remove(&disk_farm_index) {
+                        // Response is sent once farm is actually stopped
+                        let _ = farm_handle.remove_sender.send(());
+                        pending_removals.insert(disk_farm_index, result_sender);
+                    }
+                }
+                FarmCommand::Scrub { disk_farm_index, result_sender } => {
+                    match farms.get(&disk_farm_index) {
+                        Some(farm_handle) => {
+                            let scrubber = farm_handle.scrubber.clone();
+                            tokio::spawn(
+                                async move {
+                                    let _ = result_sender.send(Ok(scrubber.scrub().await));
+                                }
+                                .instrument(info_span!("farm", %disk_farm_index)),
+                            );
+                        }
+                        None => {
+                            let _ = result_sender
+                                .send(Err(anyhow!("Farm {disk_farm_index} doesn't exist")));
+                        }
+                    }
+                }
+            },
+        }
+    }
+
+    Ok(())
+}
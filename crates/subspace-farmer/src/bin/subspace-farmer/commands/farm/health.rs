@@ -0,0 +1,65 @@
+use anyhow::anyhow;
+use jsonrpsee::core::Error;
+use jsonrpsee::server::{ServerBuilder, ServerHandle};
+use jsonrpsee::types::error::{CallError, ErrorObject};
+use jsonrpsee::RpcModule;
+use std::net::SocketAddr;
+use std::time::Duration;
+use subspace_farmer::utils::farmer_health::{FarmerHealth, HealthState};
+use subspace_farmer::{NodeClient, NodeRpcClient};
+use tokio::time::timeout;
+use tracing::info;
+
+/// HTTP path of the health check endpoint
+const HEALTH_CHECK_PATH: &str = "/healthz";
+const HEALTH_CHECK_METHOD: &str = "farmer_health";
+/// How long to wait for node RPC to respond before considering it unavailable
+const NODE_RPC_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Check whether node responds to RPC requests in reasonable time
+pub(super) async fn is_node_rpc_available(node_client: &NodeRpcClient) -> bool {
+    matches!(
+        timeout(NODE_RPC_TIMEOUT, node_client.farmer_app_info()).await,
+        Ok(Ok(_))
+    )
+}
+
+/// Start HTTP server with health check endpoint.
+///
+/// Endpoint responds with `200 OK` and JSON health report when farmer is healthy or degraded and
+/// with `500 Internal Server Error` when farmer is unhealthy.
+pub(super) async fn start_health_check_server(
+    listen_on: SocketAddr,
+    farmer_health: FarmerHealth,
+    node_client: NodeRpcClient,
+) -> anyhow::Result<ServerHandle> {
+    let mut module = RpcModule::new((farmer_health, node_client));
+    module.register_async_method(HEALTH_CHECK_METHOD, |_params, context| async move {
+        let (farmer_health, node_client) = context.as_ref();
+
+        let report = farmer_health.check(is_node_rpc_available(node_client).await);
+
+        if report.state == HealthState::Unhealthy {
+            return Err(Error::Call(CallError::Custom(ErrorObject::owned(
+                1,
+                "Farmer is unhealthy",
+                Some(report),
+            ))));
+        }
+
+        Ok(report)
+    })?;
+
+    let server = ServerBuilder::default()
+        .health_api(HEALTH_CHECK_PATH, HEALTH_CHECK_METHOD)?
+        .build(listen_on)
+        .await
+        .map_err(|error| anyhow!("Failed to start health check server: {error}"))?;
+
+    info!(
+        "Health check endpoint is available at http://{}{HEALTH_CHECK_PATH}",
+        server.local_addr()?
+    );
+
+    Ok(server.start(module)?)
+}
@@ -0,0 +1,337 @@
+mod proto {
+    #![allow(clippy::derive_partial_eq_without_eq)]
+
+    tonic::include_proto!("subspace.farmer.management");
+}
+
+use crate::commands::farm::health::is_node_rpc_available;
+use crate::DiskFarm;
+use anyhow::anyhow;
+use futures::channel::{mpsc, oneshot};
+use futures::{FutureExt, SinkExt};
+use parking_lot::Mutex;
+use proto::farmer_management_server::{FarmerManagement, FarmerManagementServer};
+use proto::{
+    farm_status, status_response, AddFarmRequest, AddFarmResponse, FarmStatus, HealthState,
+    PausePlottingRequest, PausePlottingResponse, RemoveFarmRequest, RemoveFarmResponse,
+    ResumePlottingRequest, ResumePlottingResponse, ScrubRequest, ScrubResponse, StatusRequest,
+    StatusResponse,
+};
+use std::mem;
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::sync::Arc;
+use subspace_farmer::single_disk_plot::scrubbing::ScrubReport;
+use subspace_farmer::utils::farmer_health::{self, FarmerHealth};
+use subspace_farmer::NodeRpcClient;
+use subtle::ConstantTimeEq;
+use tokio::sync::{Notify, OwnedSemaphorePermit, Semaphore};
+use tonic::transport::server::TcpIncoming;
+use tonic::transport::Server;
+use tonic::{Request, Response, Status};
+use tracing::{error, info};
+
+/// Request to farm loop, result is sent back once request is processed
+pub(super) enum FarmCommand {
+    Add {
+        disk_farm: DiskFarm,
+        result_sender: oneshot::Sender<anyhow::Result<u8>>,
+    },
+    Remove {
+        disk_farm_index: u8,
+        result_sender: oneshot::Sender<anyhow::Result<()>>,
+    },
+    Scrub {
+        disk_farm_index: u8,
+        result_sender: oneshot::Sender<anyhow::Result<ScrubReport>>,
+    },
+}
+
+enum PlottingPause {
+    Running,
+    /// Waiting for sectors that are being plotted right now to finish
+    Pausing,
+    /// Holding all plotting permits pauses plotting
+    Paused(OwnedSemaphorePermit),
+}
+
+/// Returns plotting back to running state if pausing was cancelled before plotting was paused
+struct PausingGuard<'a>(&'a Mutex<PlottingPause>);
+
+impl Drop for PausingGuard<'_> {
+    fn drop(&mut self) {
+        let mut plotting_pause = self.0.lock();
+        if matches!(*plotting_pause, PlottingPause::Pausing) {
+            *plotting_pause = PlottingPause::Running;
+        }
+    }
+}
+
+struct FarmerManagementImpl {
+    farmer_health: FarmerHealth,
+    node_client: NodeRpcClient,
+    concurrent_plotting_semaphore: Arc<Semaphore>,
+    max_concurrent_plots: u32,
+    plotting_pause: Mutex<PlottingPause>,
+    plotting_resumed: Notify,
+    farm_commands_sender: mpsc::Sender<FarmCommand>,
+}
+
+impl FarmerManagementImpl {
+    async fn send_farm_command<T>(
+        &self,
+        create_command: impl FnOnce(oneshot::Sender<anyhow::Result<T>>) -> FarmCommand,
+    ) -> Result<T, Status> {
+        let (result_sender, result_receiver) = oneshot::channel();
+        self.farm_commands_sender
+            .clone()
+            .send(create_command(result_sender))
+            .await
+            .map_err(|_error| Status::unavailable("Farmer is shutting down"))?;
+
+        result_receiver
+            .await
+            .map_err(|_error| Status::unavailable("Farmer is shutting down"))?
+            .map_err(|error| Status::failed_precondition(error.to_string()))
+    }
+}
+
+fn parse_farm_index(farm_index: u32) -> Result<u8, Status> {
+    u8::try_from(farm_index)
+        .map_err(|_error| Status::not_found(format!("Farm {farm_index} doesn't exist")))
+}
+
+#[tonic::async_trait]
+impl FarmerManagement for FarmerManagementImpl {
+    async fn status(
+        &self,
+        _request: Request<StatusRequest>,
+    ) -> Result<Response<StatusResponse>, Status> {
+        let report = self
+            .farmer_health
+            .check(is_node_rpc_available(&self.node_client).await);
+        let plotting_paused = matches!(*self.plotting_pause.lock(), PlottingPause::Paused(_));
+
+        let state = match report.state {
+            farmer_health::HealthState::Healthy => HealthState::Healthy,
+            farmer_health::HealthState::Degraded => HealthState::Degraded,
+            farmer_health::HealthState::Unhealthy => HealthState::Unhealthy,
+        };
+
+        Ok(Response::new(StatusResponse {
+            state: state.into(),
+            issues: report.issues,
+            node_rpc_available: report.node_rpc_available,
+            last_audit: report
+                .last_audit_secs_ago
+                .map(status_response::LastAudit::LastAuditSecsAgo),
+            last_archived_segment: report
+                .last_archived_segment_secs_ago
+                .map(status_response::LastArchivedSegment::LastArchivedSegmentSecsAgo),
+            connected_peers: report.connected_peers as u64,
+            plotting_paused,
+            farms: report
+                .farms
+                .into_iter()
+                .map(|farm| FarmStatus {
+                    farm_index: farm.farm_index as u32,
+                    sector_size: farm.sector_size,
+                    total_sectors: farm.total_sectors,
+                    plotted_sectors: farm.plotted_sectors,
+                    plotting_rate: farm
+                        .sectors_per_hour
+                        .map(farm_status::PlottingRate::SectorsPerHour),
+                    plotting_eta: farm.eta_secs.map(farm_status::PlottingEta::EtaSecs),
+                    solutions: farm.solutions,
+                })
+                .collect(),
+        }))
+    }
+
+    async fn pause_plotting(
+        &self,
+        _request: Request<PausePlottingRequest>,
+    ) -> Result<Response<PausePlottingResponse>, Status> {
+        let plotting_resumed = self.plotting_resumed.notified();
+        tokio::pin!(plotting_resumed);
+
+        {
+            let mut plotting_pause = self.plotting_pause.lock();
+            if !matches!(*plotting_pause, PlottingPause::Running) {
+                // Already paused or being paused by another request
+                return Ok(Response::new(PausePlottingResponse {}));
+            }
+            *plotting_pause = PlottingPause::Pausing;
+            // Subscribe before lock is released to not miss resumption
+            plotting_resumed.as_mut().enable();
+        }
+
+        let _pausing_guard = PausingGuard(&self.plotting_pause);
+
+        // Lock is not held here, such that plotting can be resumed while waiting for permits
+        let permit = tokio::select! {
+            result = Arc::clone(&self.concurrent_plotting_semaphore)
+                .acquire_many_owned(self.max_concurrent_plots) => {
+                result.map_err(|error| Status::internal(error.to_string()))?
+            }
+            _ = plotting_resumed => {
+                return Err(Status::aborted("Plotting was resumed before it was paused"));
+            }
+        };
+
+        let mut plotting_pause = self.plotting_pause.lock();
+        if matches!(*plotting_pause, PlottingPause::Pausing) {
+            *plotting_pause = PlottingPause::Paused(permit);
+            info!("Plotting paused");
+        }
+
+        Ok(Response::new(PausePlottingResponse {}))
+    }
+
+    async fn resume_plotting(
+        &self,
+        _request: Request<ResumePlottingRequest>,
+    ) -> Result<Response<ResumePlottingResponse>, Status> {
+        let previous_plotting_pause =
+            mem::replace(&mut *self.plotting_pause.lock(), PlottingPause::Running);
+        self.plotting_resumed.notify_waiters();
+
+        if !matches!(previous_plotting_pause, PlottingPause::Running) {
+            info!("Plotting resumed");
+        }
+
+        Ok(Response::new(ResumePlottingResponse {}))
+    }
+
+    async fn add_farm(
+        &self,
+        request: Request<AddFarmRequest>,
+    ) -> Result<Response<AddFarmResponse>, Status> {
+        let disk_farm =
+            DiskFarm::from_str(&request.into_inner().farm).map_err(Status::invalid_argument)?;
+
+        let disk_farm_index = self
+            .send_farm_command(|result_sender| FarmCommand::Add {
+                disk_farm,
+                result_sender,
+            })
+            .await?;
+
+        Ok(Response::new(AddFarmResponse {
+            farm_index: u32::from(disk_farm_index),
+        }))
+    }
+
+    async fn remove_farm(
+        &self,
+        request: Request<RemoveFarmRequest>,
+    ) -> Result<Response<RemoveFarmResponse>, Status> {
+        let disk_farm_index = parse_farm_index(request.into_inner().farm_index)?;
+
+        self.send_farm_command(|result_sender| FarmCommand::Remove {
+            disk_farm_index,
+            result_sender,
+        })
+        .await?;
+
+        Ok(Response::new(RemoveFarmResponse {}))
+    }
+
+    async fn scrub(
+        &self,
+        request: Request<ScrubRequest>,
+    ) -> Result<Response<ScrubResponse>, Status> {
+        let disk_farm_index = parse_farm_index(request.into_inner().farm_index)?;
+
+        let scrub_report = self
+            .send_farm_command(|result_sender| FarmCommand::Scrub {
+                disk_farm_index,
+                result_sender,
+            })
+            .await?;
+
+        Ok(Response::new(ScrubResponse {
+            checked_sectors: scrub_report.checked_sectors as u64,
+            corrupted_sectors: scrub_report
+                .corrupted_sectors
+                .into_iter()
+                .map(u32::from)
+                .collect(),
+        }))
+    }
+}
+
+/// Management server runs until dropped
+pub(super) struct ManagementServer {
+    _stop_sender: oneshot::Sender<()>,
+}
+
+/// Start gRPC server with farmer management API, see `proto/management.proto` for details.
+///
+/// Clients must provide token with every call as `authorization: Bearer <token>` metadata.
+#[allow(clippy::too_many_arguments)]
+pub(super) fn start_management_server(
+    listen_on: SocketAddr,
+    token: String,
+    farmer_health: FarmerHealth,
+    node_client: NodeRpcClient,
+    concurrent_plotting_semaphore: Arc<Semaphore>,
+    max_concurrent_plots: u32,
+    farm_commands_sender: mpsc::Sender<FarmCommand>,
+) -> anyhow::Result<ManagementServer> {
+    let farmer_management = FarmerManagementImpl {
+        farmer_health,
+        node_client,
+        concurrent_plotting_semaphore,
+        max_concurrent_plots,
+        plotting_pause: Mutex::new(PlottingPause::Running),
+        plotting_resumed: Notify::new(),
+        farm_commands_sender,
+    };
+
+    let expected_authorization = format!("Bearer {token}");
+    let check_token = move |request: Request<()>| {
+        // Constant time comparison to not leak the token through response timing
+        let authorized = request
+            .metadata()
+            .get("authorization")
+            .map(|authorization| {
+                bool::from(
+                    authorization
+                        .as_bytes()
+                        .ct_eq(expected_authorization.as_bytes()),
+                )
+            })
+            .unwrap_or_default();
+
+        if authorized {
+            Ok(request)
+        } else {
+            Err(Status::unauthenticated("Invalid management token"))
+        }
+    };
+
+    let incoming = TcpIncoming::new(listen_on, true, None)
+        .map_err(|error| anyhow!("Failed to start management server: {error}"))?;
+    let (stop_sender, stop_receiver) = oneshot::channel::<()>();
+
+    tokio::spawn(async move {
+        let result = Server::builder()
+            .add_service(FarmerManagementServer::with_interceptor(
+                farmer_management,
+                check_token,
+            ))
+            .serve_with_incoming_shutdown(incoming, stop_receiver.map(|_| ()))
+            .await;
+
+        if let Err(error) = result {
+            error!(%error, "Management server failed");
+        }
+    });
+
+    info!("Management API is available at {listen_on}");
+
+    Ok(ManagementServer {
+        _stop_sender: stop_sender,
+    })
+}
@@ -0,0 +1,151 @@
+use crate::utils::{http_client, http_post};
+use hyper::Uri;
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use prometheus_client::encoding::text::encode;
+use prometheus_client::metrics::counter::Counter;
+use prometheus_client::metrics::family::Family;
+use prometheus_client::metrics::gauge::Gauge;
+use prometheus_client::registry::Registry;
+use std::time::Duration;
+use subspace_farmer::utils::farmer_health::FarmerHealth;
+use subspace_networking::NetworkingMetrics;
+use tokio::time::sleep;
+use tracing::{debug, warn};
+
+/// Job name used for grouping metrics in Pushgateway
+const PUSHGATEWAY_JOB: &str = "subspace_farmer";
+
+type FarmLabels = Vec<(String, String)>;
+
+/// Pushgateway URL for metrics group identified by job and additional labels
+fn pushgateway_uri(endpoint: &Uri, labels: &[(String, String)]) -> anyhow::Result<Uri> {
+    let mut uri = format!(
+        "{}/metrics/job/{PUSHGATEWAY_JOB}",
+        endpoint.to_string().trim_end_matches('/')
+    );
+    for (name, value) in labels {
+        if value.is_empty() {
+            // Empty path segments are not allowed, Pushgateway expects this special form instead
+            uri.push_str(&format!("/{name}@base64/="));
+        } else {
+            uri.push_str(&format!(
+                "/{name}/{}",
+                utf8_percent_encode(value, NON_ALPHANUMERIC)
+            ));
+        }
+    }
+
+    Ok(uri.parse()?)
+}
+
+/// Encode current farmer metrics in Prometheus text format
+fn encode_metrics(
+    farmer_health: &FarmerHealth,
+    networking_metrics: Option<&NetworkingMetrics>,
+) -> anyhow::Result<String> {
+    let mut registry = Registry::default();
+
+    let plotted_sectors = Family::<FarmLabels, Gauge>::default();
+    let total_sectors = Family::<FarmLabels, Gauge>::default();
+    let sector_size = Family::<FarmLabels, Gauge>::default();
+    let solutions = Family::<FarmLabels, Counter>::default();
+    for farm in farmer_health.farms() {
+        let labels = vec![("farm".to_string(), farm.farm_index.to_string())];
+        plotted_sectors
+            .get_or_create(&labels)
+            .set(farm.plotted_sectors as i64);
+        total_sectors
+            .get_or_create(&labels)
+            .set(farm.total_sectors as i64);
+        sector_size
+            .get_or_create(&labels)
+            .set(farm.sector_size as i64);
+        solutions.get_or_create(&labels).inc_by(farm.solutions);
+    }
+
+    let report = farmer_health.check(true);
+    let connected_peers = Gauge::<i64>::default();
+    connected_peers.set(report.connected_peers as i64);
+    let last_audit_secs_ago = Gauge::<i64>::default();
+    if let Some(secs) = report.last_audit_secs_ago {
+        last_audit_secs_ago.set(secs as i64);
+    }
+
+    registry.register(
+        "farmer_plotted_sectors",
+        "Number of sectors plotted so far",
+        plotted_sectors,
+    );
+    registry.register(
+        "farmer_total_sectors",
+        "Total number of sectors in the farm",
+        total_sectors,
+    );
+    registry.register(
+        "farmer_sector_size_bytes",
+        "Size of one sector in bytes",
+        sector_size,
+    );
+    registry.register(
+        "farmer_solutions",
+        "Number of solutions found since start",
+        solutions,
+    );
+    registry.register(
+        "farmer_connected_peers",
+        "Number of established DSN peer connections",
+        connected_peers,
+    );
+    registry.register(
+        "farmer_last_audit_seconds_ago",
+        "Seconds since last audit",
+        last_audit_secs_ago,
+    );
+
+    let mut encoded = String::new();
+    encode(&mut encoded, &registry)?;
+    if let Some(networking_metrics) = networking_metrics {
+        networking_metrics.merge_into(&mut encoded)?;
+    }
+
+    Ok(encoded)
+}
+
+/// Periodically push farmer metrics to Prometheus Pushgateway, useful when farmer can't be
+/// scraped directly (behind NAT for instance)
+pub(super) async fn run_metrics_push(
+    endpoint: Uri,
+    interval: Duration,
+    labels: Vec<(String, String)>,
+    farmer_health: FarmerHealth,
+    networking_metrics: Option<NetworkingMetrics>,
+) -> anyhow::Result<()> {
+    let uri = pushgateway_uri(&endpoint, &labels)?;
+    let http_client = http_client();
+
+    loop {
+        let result = match encode_metrics(&farmer_health, networking_metrics.as_ref()) {
+            Ok(encoded) => {
+                http_post(
+                    &http_client,
+                    uri.clone(),
+                    "text/plain; version=0.0.4",
+                    encoded.into_bytes(),
+                )
+                .await
+            }
+            Err(error) => Err(error),
+        };
+
+        match result {
+            Ok(()) => {
+                debug!(%uri, "Metrics pushed");
+            }
+            Err(error) => {
+                warn!(%uri, %error, "Failed to push metrics");
+            }
+        }
+
+        sleep(interval).await;
+    }
+}
@@ -0,0 +1,153 @@
+use crate::utils::get_required_plot_space_with_overhead;
+use crate::DiskFarm;
+use anyhow::anyhow;
+use std::collections::BTreeMap;
+use std::io;
+use std::path::Path;
+use subspace_farmer::single_disk_plot::{PlotCompatibility, SingleDiskPlot};
+use tempfile::NamedTempFile;
+use tracing::warn;
+
+/// Validate farms configuration before doing anything with them, such that issues are discovered
+/// early rather than in the middle of plotting.
+///
+/// Reward addresses are already well-formed after parsing, but each farm must have one. Existing
+/// plots must be compatible with this version of the farmer. Farms on the same device must fit
+/// into its free space together.
+pub(super) fn validate_disk_farms(
+    disk_farms: &[DiskFarm],
+    default_reward_address_present: bool,
+) -> anyhow::Result<()> {
+    let mut devices = BTreeMap::<Device, DeviceSpace<'_>>::new();
+
+    for (farm_index, disk_farm) in disk_farms.iter().enumerate() {
+        let directory = &disk_farm.directory;
+
+        if disk_farm.reward_address.is_none() && !default_reward_address_present {
+            return Err(anyhow!(
+                "Reward address is not specified for farm at {}, use `--reward-address` or \
+                `reward_address` component of `--farm`",
+                directory.display()
+            ));
+        }
+
+        match SingleDiskPlot::check_compatibility(directory) {
+            PlotCompatibility::NotFound | PlotCompatibility::Compatible => {}
+            PlotCompatibility::Unknown { error } => {
+                return Err(anyhow!(
+                    "Failed to check compatibility of farm at {}: {error}",
+                    directory.display()
+                ));
+            }
+            compatibility => {
+                return Err(anyhow!(
+                    "Farm at {} can't be used by this version of the farmer: {compatibility}",
+                    directory.display()
+                ));
+            }
+        }
+
+        if let Err(error) = NamedTempFile::new_in(directory) {
+            return Err(anyhow!(
+                "Directory {} is not writable, check permissions: {error}",
+                directory.display()
+            ));
+        }
+
+        // Space that was already allocated by previous runs can be reused
+        let already_allocated = SingleDiskPlot::read_metadata(directory)
+            .map(|metadata| metadata.plot_file_size + metadata.metadata_file_size)
+            .map_err(|error| {
+                anyhow!(
+                    "Failed to read metadata of farm at {}: {error}",
+                    directory.display()
+                )
+            })?;
+        let required_space =
+            get_required_plot_space_with_overhead(disk_farm.allocated_plotting_space);
+
+        let device = match device_id(directory)? {
+            Some(device_id) => Device::Id(device_id),
+            None => Device::Unknown(farm_index),
+        };
+        let device_space = devices.entry(device).or_insert(DeviceSpace {
+            directories: Vec::new(),
+            required_space: 0,
+            // Free space is shared by all farms on the device, so it is only counted once
+            available_space: fs2::available_space(directory)?,
+        });
+        if let Some(other_directory) = device_space.directories.last() {
+            // Multiple farms on the same device compete for I/O and rarely make sense
+            warn!(
+                "Farms at {} and {} are on the same device, consider using a single larger farm \
+                instead",
+                other_directory.display(),
+                directory.display(),
+            );
+        }
+        device_space.directories.push(directory);
+        device_space.required_space += required_space;
+        device_space.available_space += already_allocated;
+    }
+
+    for device_space in devices.values() {
+        let DeviceSpace {
+            directories,
+            required_space,
+            available_space,
+        } = device_space;
+
+        if required_space <= available_space {
+            continue;
+        }
+
+        let required_space = bytesize::to_string(*required_space, true);
+        let available_space = bytesize::to_string(*available_space, true);
+        return Err(match directories.as_slice() {
+            [directory] => anyhow!(
+                "Farm at {} requires {required_space} (including metadata), but only \
+                {available_space} is available, decrease farm size or free some space",
+                directory.display(),
+            ),
+            directories => anyhow!(
+                "Farms at {} are on the same device and together require {required_space} \
+                (including metadata), but only {available_space} is available, decrease farm \
+                sizes or free some space",
+                directories
+                    .iter()
+                    .map(|directory| directory.display().to_string())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            ),
+        });
+    }
+
+    Ok(())
+}
+
+/// Device farm directory is located on, farms with unknown device are checked independently
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
+enum Device {
+    Id(u64),
+    Unknown(usize),
+}
+
+/// Space requirements of all farms located on the same device
+struct DeviceSpace<'a> {
+    directories: Vec<&'a Path>,
+    required_space: u64,
+    /// Free space on the device plus space already allocated by farms on it
+    available_space: u64,
+}
+
+#[cfg(unix)]
+fn device_id(directory: &Path) -> io::Result<Option<u64>> {
+    use std::os::unix::fs::MetadataExt;
+
+    Ok(Some(std::fs::metadata(directory)?.dev()))
+}
+
+#[cfg(not(unix))]
+fn device_id(_directory: &Path) -> io::Result<Option<u64>> {
+    Ok(None)
+}
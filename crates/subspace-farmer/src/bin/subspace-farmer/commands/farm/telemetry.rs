@@ -0,0 +1,54 @@
+use crate::utils::{http_client, http_post};
+use hyper::Uri;
+use serde::Serialize;
+use std::time::Duration;
+use subspace_farmer::utils::farmer_health::FarmerHealth;
+use tokio::time::sleep;
+use tracing::{debug, warn};
+
+/// Anonymized farmer statistics, doesn't contain anything that can identify the farmer
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TelemetryReport {
+    version: &'static str,
+    os: &'static str,
+    arch: &'static str,
+    plot_count: usize,
+    allocated_space: u64,
+    plotted_space: u64,
+}
+
+/// Periodically submit anonymized farmer statistics to telemetry endpoint
+pub(super) async fn run_telemetry(endpoint: Uri, interval: Duration, farmer_health: FarmerHealth) {
+    let http_client = http_client();
+
+    loop {
+        let farms = farmer_health.farms();
+        let report = TelemetryReport {
+            version: env!("CARGO_PKG_VERSION"),
+            os: std::env::consts::OS,
+            arch: std::env::consts::ARCH,
+            plot_count: farms.len(),
+            allocated_space: farms
+                .iter()
+                .map(|farm| farm.total_sectors * farm.sector_size)
+                .sum(),
+            plotted_space: farms
+                .iter()
+                .map(|farm| farm.plotted_sectors * farm.sector_size)
+                .sum(),
+        };
+        let body = serde_json::to_vec(&report).expect("Report serialization never fails; qed");
+
+        match http_post(&http_client, endpoint.clone(), "application/json", body).await {
+            Ok(()) => {
+                debug!(?report, "Telemetry submitted");
+            }
+            Err(error) => {
+                warn!(%error, "Failed to submit telemetry");
+            }
+        }
+
+        sleep(interval).await;
+    }
+}
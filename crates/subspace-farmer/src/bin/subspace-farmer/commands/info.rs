@@ -1,7 +1,11 @@
-use crate::commands::shared::print_disk_farm_info;
+use crate::commands::shared::{print_disk_farm_info, SHARED_DATABASES};
+use crate::utils::directory_size;
 use crate::DiskFarm;
+use std::path::Path;
+use subspace_farmer::single_disk_plot::SingleDiskPlot;
+use subspace_farmer_components::sector::sector_size;
 
-pub(crate) fn info(disk_farms: Vec<DiskFarm>) {
+pub(crate) fn info(base_path: &Path, disk_farms: Vec<DiskFarm>) {
     for (disk_farm_index, disk_farm) in disk_farms.into_iter().enumerate() {
         if disk_farm_index > 0 {
             println!();
@@ -9,6 +13,74 @@ pub(crate) fn info(disk_farms: Vec<DiskFarm>) {
 
         let DiskFarm { directory, .. } = disk_farm;
 
-        print_disk_farm_info(directory, disk_farm_index);
+        print_disk_farm_info(directory.clone(), disk_farm_index);
+        print_disk_farm_contents(&directory);
     }
+
+    println!();
+    println!("Shared databases:");
+    for database in SHARED_DATABASES {
+        let path = base_path.join(database);
+        match directory_size(&path) {
+            Ok(size) => {
+                println!("  {database}: {}", bytesize::to_string(size, true));
+            }
+            Err(error) => {
+                println!("  {database}: unavailable ({error})");
+            }
+        }
+    }
+}
+
+fn print_disk_farm_contents(directory: &Path) {
+    let metadata = match SingleDiskPlot::read_metadata(directory) {
+        Ok(metadata) => metadata,
+        Err(error) => {
+            println!("  Failed to read plot metadata: {error}");
+            return;
+        }
+    };
+
+    let plotted_space = metadata
+        .sectors_metadata
+        .iter()
+        .map(|sector_metadata| sector_size(sector_metadata.pieces_in_sector) as u64)
+        .sum::<u64>();
+    let piece_count = metadata
+        .sectors_metadata
+        .iter()
+        .map(|sector_metadata| u64::from(sector_metadata.pieces_in_sector))
+        .sum::<u64>();
+
+    println!("  Format version: {}", metadata.version);
+    println!("  Plotted sectors: {}", metadata.sectors_metadata.len());
+    println!(
+        "  Plotted space: {} ({})",
+        bytesize::to_string(plotted_space, true),
+        bytesize::to_string(plotted_space, false)
+    );
+    println!("  Pieces: {piece_count}");
+
+    let segment_indexes = metadata
+        .sectors_metadata
+        .iter()
+        .map(|sector_metadata| sector_metadata.history_size.segment_index());
+    match (segment_indexes.clone().min(), segment_indexes.max()) {
+        (Some(first_segment_index), Some(last_segment_index)) => {
+            println!("  First segment plotted: {first_segment_index}");
+            println!("  Last segment plotted: {last_segment_index}");
+        }
+        _ => {
+            println!("  No segments plotted yet");
+        }
+    }
+
+    println!(
+        "  Plot file size: {}",
+        bytesize::to_string(metadata.plot_file_size, true)
+    );
+    println!(
+        "  Metadata file size: {}",
+        bytesize::to_string(metadata.metadata_file_size, true)
+    );
 }
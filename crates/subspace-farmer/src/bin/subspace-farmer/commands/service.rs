@@ -0,0 +1,83 @@
+#[cfg(target_os = "macos")]
+mod launchd;
+#[cfg(target_os = "linux")]
+mod systemd;
+#[cfg(windows)]
+mod windows;
+
+use crate::{Command, ServiceArgs, Subcommand};
+use anyhow::anyhow;
+use clap::Parser;
+#[cfg(target_os = "macos")]
+use launchd::{install, uninstall};
+use std::time::Duration;
+#[cfg(target_os = "linux")]
+use systemd::{install, uninstall};
+#[cfg(windows)]
+pub(crate) use windows::EventLogLayer;
+#[cfg(windows)]
+use windows::{install, run, uninstall};
+
+/// How long service manager waits before restarting farmer that exited with an error
+#[cfg_attr(
+    not(any(target_os = "linux", target_os = "macos", windows)),
+    allow(dead_code)
+)]
+const RESTART_DELAY: Duration = Duration::from_secs(60);
+/// How long service manager waits for farmer to shut down gracefully before killing it
+#[cfg_attr(
+    not(any(target_os = "linux", target_os = "macos", windows)),
+    allow(dead_code)
+)]
+const STOP_TIMEOUT: Duration = Duration::from_secs(300);
+
+pub(crate) fn service(service_args: ServiceArgs) -> anyhow::Result<()> {
+    match service_args {
+        ServiceArgs::Install { args } => {
+            // Catch invalid arguments right away instead of service manager restarting farmer
+            // that can never start
+            parse_args(&args)?;
+
+            install(args)
+        }
+        ServiceArgs::Uninstall => uninstall(),
+        ServiceArgs::Run { args } => run(args),
+    }
+}
+
+/// Parse farmer arguments the same way farmer does when started by service manager
+fn parse_args(args: &[String]) -> anyhow::Result<Command> {
+    let command = Command::try_parse_from(
+        std::iter::once("subspace-farmer").chain(args.iter().map(String::as_str)),
+    )?;
+
+    if !matches!(command.subcommand, Subcommand::Farm(_)) {
+        return Err(anyhow!("Only `farm` subcommand can run as a service"));
+    }
+    if command.tmp {
+        return Err(anyhow!("Temporary farmer can't run as a service"));
+    }
+
+    Ok(command)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+fn install(_args: Vec<String>) -> anyhow::Result<()> {
+    Err(anyhow!(
+        "Service installation is not supported on this platform"
+    ))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+fn uninstall() -> anyhow::Result<()> {
+    Err(anyhow!(
+        "Service installation is not supported on this platform"
+    ))
+}
+
+#[cfg(not(windows))]
+fn run(_args: Vec<String>) -> anyhow::Result<()> {
+    Err(anyhow!(
+        "Running under Service Control Manager is only supported on Windows"
+    ))
+}
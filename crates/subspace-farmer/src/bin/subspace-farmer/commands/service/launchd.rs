@@ -0,0 +1,121 @@
+use crate::commands::service::{RESTART_DELAY, STOP_TIMEOUT};
+use anyhow::anyhow;
+use std::fmt::Write;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Service label used by launchd
+const SERVICE_LABEL: &str = "network.subspace.farmer";
+
+fn launch_agent_path() -> anyhow::Result<PathBuf> {
+    Ok(dirs::home_dir()
+        .ok_or_else(|| anyhow!("Can't find home directory"))?
+        .join("Library")
+        .join("LaunchAgents")
+        .join(format!("{SERVICE_LABEL}.plist")))
+}
+
+/// Escape string for inclusion into XML document
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// Install launchd agent that starts farmer on login and restarts it if it exits with an error.
+///
+/// launchd stops farmer with SIGTERM, which is handled the same way as during interactive run.
+pub(super) fn install(args: Vec<String>) -> anyhow::Result<()> {
+    let executable = std::env::current_exe()?;
+    let log_directory = crate::utils::default_base_path();
+    fs::create_dir_all(&log_directory)?;
+
+    let mut program_arguments = String::new();
+    for argument in std::iter::once(executable.display().to_string()).chain(args) {
+        let _ = writeln!(
+            program_arguments,
+            "        <string>{}</string>",
+            escape_xml(&argument)
+        );
+    }
+
+    // `SuccessfulExit = false` only restarts farmer after failures, `ThrottleInterval` limits how
+    // often that happens if farmer keeps failing on start
+    let plist = format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{SERVICE_LABEL}</string>
+    <key>ProgramArguments</key>
+    <array>
+{program_arguments}    </array>
+    <key>RunAtLoad</key>
+    <true/>
+    <key>KeepAlive</key>
+    <dict>
+        <key>SuccessfulExit</key>
+        <false/>
+    </dict>
+    <key>ThrottleInterval</key>
+    <integer>{throttle_interval}</integer>
+    <key>ExitTimeOut</key>
+    <integer>{exit_timeout}</integer>
+    <key>StandardOutPath</key>
+    <string>{stdout}</string>
+    <key>StandardErrorPath</key>
+    <string>{stderr}</string>
+</dict>
+</plist>
+"#,
+        throttle_interval = RESTART_DELAY.as_secs(),
+        exit_timeout = STOP_TIMEOUT.as_secs(),
+        stdout = escape_xml(&log_directory.join("farmer.log").display().to_string()),
+        stderr = escape_xml(&log_directory.join("farmer.err.log").display().to_string()),
+    );
+
+    let launch_agent_path = launch_agent_path()?;
+    if let Some(parent) = launch_agent_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&launch_agent_path, plist)?;
+    println!("Launch agent written to {}", launch_agent_path.display());
+
+    let status = Command::new("launchctl")
+        .arg("load")
+        .arg("-w")
+        .arg(&launch_agent_path)
+        .status()?;
+    if !status.success() {
+        return Err(anyhow!("Failed to load launch agent: {status}"));
+    }
+
+    println!("Service {SERVICE_LABEL} installed and started");
+
+    Ok(())
+}
+
+pub(super) fn uninstall() -> anyhow::Result<()> {
+    let launch_agent_path = launch_agent_path()?;
+    if !launch_agent_path.exists() {
+        return Err(anyhow!("Service {SERVICE_LABEL} is not installed"));
+    }
+
+    let status = Command::new("launchctl")
+        .arg("unload")
+        .arg("-w")
+        .arg(&launch_agent_path)
+        .status()?;
+    if !status.success() {
+        return Err(anyhow!("Failed to unload launch agent: {status}"));
+    }
+
+    fs::remove_file(&launch_agent_path)?;
+    println!("Service {SERVICE_LABEL} uninstalled");
+
+    Ok(())
+}
@@ -0,0 +1,116 @@
+use crate::commands::service::{RESTART_DELAY, STOP_TIMEOUT};
+use anyhow::anyhow;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Name of systemd unit
+const UNIT_NAME: &str = "subspace-farmer.service";
+/// Number of failed starts within [`START_LIMIT_INTERVAL`] after which systemd gives up
+const START_LIMIT_BURST: u32 = 5;
+/// Interval in which [`START_LIMIT_BURST`] failed starts are counted
+const START_LIMIT_INTERVAL: std::time::Duration = std::time::Duration::from_secs(600);
+
+fn unit_path() -> anyhow::Result<PathBuf> {
+    Ok(dirs::config_dir()
+        .ok_or_else(|| anyhow!("Can't find config directory"))?
+        .join("systemd")
+        .join("user")
+        .join(UNIT_NAME))
+}
+
+/// Quote argument for `ExecStart=` such that systemd passes it to farmer as is
+fn quote_argument(argument: &str) -> String {
+    format!(
+        "\"{}\"",
+        argument
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('%', "%%")
+            .replace('$', "$$")
+    )
+}
+
+fn systemctl(args: &[&str]) -> anyhow::Result<()> {
+    let status = Command::new("systemctl")
+        .arg("--user")
+        .args(args)
+        .status()?;
+    if !status.success() {
+        return Err(anyhow!(
+            "`systemctl --user {}` failed: {status}",
+            args.join(" ")
+        ));
+    }
+
+    Ok(())
+}
+
+/// Install systemd user unit that starts farmer on login and restarts it if it exits with an
+/// error.
+///
+/// systemd stops farmer with SIGTERM, which is handled the same way as during interactive run.
+pub(super) fn install(args: Vec<String>) -> anyhow::Result<()> {
+    let executable = std::env::current_exe()?;
+
+    let exec_start = std::iter::once(executable.display().to_string())
+        .chain(args)
+        .map(|argument| quote_argument(&argument))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let unit = format!(
+        "[Unit]
+Description=Subspace farmer
+Wants=network-online.target
+After=network-online.target
+StartLimitIntervalSec={start_limit_interval}
+StartLimitBurst={START_LIMIT_BURST}
+
+[Service]
+ExecStart={exec_start}
+Restart=on-failure
+RestartSec={restart_sec}
+TimeoutStopSec={timeout_stop_sec}
+
+[Install]
+WantedBy=default.target
+",
+        start_limit_interval = START_LIMIT_INTERVAL.as_secs(),
+        restart_sec = RESTART_DELAY.as_secs(),
+        timeout_stop_sec = STOP_TIMEOUT.as_secs(),
+    );
+
+    let unit_path = unit_path()?;
+    if let Some(parent) = unit_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&unit_path, unit)?;
+    println!("Systemd unit written to {}", unit_path.display());
+
+    systemctl(&["daemon-reload"])?;
+    systemctl(&["enable", "--now", UNIT_NAME])?;
+
+    println!(
+        "Service {UNIT_NAME} installed and started, logs are available with \
+        `journalctl --user -u {UNIT_NAME}`, run `loginctl enable-linger` to keep it running \
+        after logout"
+    );
+
+    Ok(())
+}
+
+pub(super) fn uninstall() -> anyhow::Result<()> {
+    let unit_path = unit_path()?;
+    if !unit_path.exists() {
+        return Err(anyhow!("Service {UNIT_NAME} is not installed"));
+    }
+
+    systemctl(&["disable", "--now", UNIT_NAME])?;
+    fs::remove_file(&unit_path)?;
+    systemctl(&["daemon-reload"])?;
+
+    println!("Service {UNIT_NAME} uninstalled");
+
+    Ok(())
+}
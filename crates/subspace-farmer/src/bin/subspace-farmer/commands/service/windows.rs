@@ -0,0 +1,333 @@
+use crate::commands::farm_multi_disk;
+use crate::commands::service::{parse_args, RESTART_DELAY, STOP_TIMEOUT};
+use crate::{farming_disk_farms, PosTable, Subcommand};
+use anyhow::anyhow;
+use futures::channel::oneshot;
+use parking_lot::Mutex;
+use std::ffi::{OsStr, OsString};
+use std::fmt::Write;
+use std::os::windows::ffi::OsStrExt;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+use std::{iter, ptr, thread};
+use tracing::field::{Field, Visit};
+use tracing::{error, info, Event, Level, Subscriber};
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::Layer;
+use windows_service::service::{
+    ServiceAccess, ServiceAction, ServiceActionType, ServiceControl, ServiceControlAccept,
+    ServiceErrorControl, ServiceExitCode, ServiceFailureActions, ServiceFailureResetPeriod,
+    ServiceInfo, ServiceStartType, ServiceState, ServiceStatus, ServiceType,
+};
+use windows_service::service_control_handler::{ServiceControlHandlerResult, ServiceStatusHandle};
+use windows_service::service_manager::{ServiceManager, ServiceManagerAccess};
+use windows_service::{define_windows_service, service_control_handler, service_dispatcher};
+use windows_sys::Win32::System::EventLog::{
+    DeregisterEventSource, RegisterEventSourceW, ReportEventW, EVENTLOG_ERROR_TYPE,
+    EVENTLOG_INFORMATION_TYPE, EVENTLOG_WARNING_TYPE,
+};
+
+/// Name of Windows service, also used as event log source
+const SERVICE_NAME: &str = "subspace-farmer";
+const SERVICE_DISPLAY_NAME: &str = "Subspace Farmer";
+const SERVICE_DESCRIPTION: &str = "Farmer for the Subspace Network Blockchain";
+/// Period without failures after which Service Control Manager resets failure counter
+const FAILURE_RESET_PERIOD: Duration = Duration::from_secs(24 * 60 * 60);
+/// Number of times service is restarted after consecutive failures before Service Control
+/// Manager gives up
+const MAX_RESTARTS: usize = 3;
+/// How long to wait for service to stop during uninstallation
+const UNINSTALL_STOP_TIMEOUT: Duration = Duration::from_secs(330);
+
+/// Farmer arguments of `service run`, `service_main` is called by Service Control Manager without
+/// access to them otherwise
+static SERVICE_ARGS: OnceLock<Vec<String>> = OnceLock::new();
+
+define_windows_service!(ffi_service_main, service_main);
+
+fn encode_wide(s: impl AsRef<OsStr>) -> Vec<u16> {
+    s.as_ref().encode_wide().chain(iter::once(0)).collect()
+}
+
+/// Tracing layer that writes events to Windows event log, used when farmer runs as a service and
+/// has no console to write logs to
+pub(crate) struct EventLogLayer {
+    event_source: windows_sys::Win32::System::EventLog::EventSourceHandle,
+}
+
+impl Drop for EventLogLayer {
+    fn drop(&mut self) {
+        // SAFETY: Handle was returned by successful `RegisterEventSourceW` call
+        unsafe {
+            DeregisterEventSource(self.event_source);
+        }
+    }
+}
+
+impl EventLogLayer {
+    /// Returns `None` if event source can't be registered
+    pub(crate) fn new() -> Option<Self> {
+        let source_name = encode_wide(SERVICE_NAME);
+        // SAFETY: Source name is a valid NUL-terminated wide string
+        let event_source = unsafe { RegisterEventSourceW(ptr::null(), source_name.as_ptr()) };
+
+        (event_source != 0).then_some(Self { event_source })
+    }
+}
+
+/// Formats event message followed by its fields
+#[derive(Default)]
+struct EventMessage(String);
+
+impl Visit for EventMessage {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.0, "{value:?}");
+        } else {
+            let _ = write!(self.0, " {}={value:?}", field.name());
+        }
+    }
+}
+
+impl<S> Layer<S> for EventLogLayer
+where
+    S: Subscriber,
+{
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let event_type = match *event.metadata().level() {
+            Level::ERROR => EVENTLOG_ERROR_TYPE,
+            Level::WARN => EVENTLOG_WARNING_TYPE,
+            _ => EVENTLOG_INFORMATION_TYPE,
+        };
+        let mut message = EventMessage::default();
+        event.record(&mut message);
+        let message = encode_wide(format!("{}: {}", event.metadata().target(), message.0));
+        let strings = [message.as_ptr()];
+
+        // SAFETY: Event source handle is valid while layer exists, strings array contains one
+        // valid NUL-terminated wide string
+        unsafe {
+            ReportEventW(
+                self.event_source,
+                event_type,
+                0,
+                0,
+                ptr::null_mut(),
+                strings.len() as u16,
+                0,
+                strings.as_ptr(),
+                ptr::null(),
+            );
+        }
+    }
+}
+
+/// Install Windows service that starts farmer on boot and restarts it (up to [`MAX_RESTARTS`]
+/// times in a row) if it fails.
+///
+/// Service stop is handled the same way as Ctrl+C during interactive run.
+pub(super) fn install(args: Vec<String>) -> anyhow::Result<()> {
+    let manager = ServiceManager::local_computer(
+        None::<&str>,
+        ServiceManagerAccess::CONNECT | ServiceManagerAccess::CREATE_SERVICE,
+    )?;
+
+    let service_info = ServiceInfo {
+        name: SERVICE_NAME.into(),
+        display_name: SERVICE_DISPLAY_NAME.into(),
+        service_type: ServiceType::OWN_PROCESS,
+        start_type: ServiceStartType::AutoStart,
+        error_control: ServiceErrorControl::Normal,
+        executable_path: std::env::current_exe()?,
+        launch_arguments: ["service", "run", "--"]
+            .into_iter()
+            .map(OsString::from)
+            .chain(args.into_iter().map(OsString::from))
+            .collect(),
+        dependencies: Vec::new(),
+        account_name: None,
+        account_password: None,
+    };
+    let service = manager.create_service(
+        &service_info,
+        ServiceAccess::CHANGE_CONFIG | ServiceAccess::START,
+    )?;
+    service.set_description(SERVICE_DESCRIPTION)?;
+
+    // The last action is repeated for all subsequent failures, so it must not be a restart
+    let mut actions = vec![
+        ServiceAction {
+            action_type: ServiceActionType::Restart,
+            delay: RESTART_DELAY,
+        };
+        MAX_RESTARTS
+    ];
+    actions.push(ServiceAction {
+        action_type: ServiceActionType::None,
+        delay: Duration::ZERO,
+    });
+    service.update_failure_actions(ServiceFailureActions {
+        reset_period: ServiceFailureResetPeriod::After(FAILURE_RESET_PERIOD),
+        reboot_msg: None,
+        command: None,
+        actions: Some(actions),
+    })?;
+    // Farmer exits with an error rather than crashing, which is also considered a failure
+    service.set_failure_actions_on_non_crash_failures(true)?;
+
+    service.start::<&str>(&[])?;
+
+    println!(
+        "Service {SERVICE_NAME} installed and started, logs are available in Windows event log"
+    );
+
+    Ok(())
+}
+
+pub(super) fn uninstall() -> anyhow::Result<()> {
+    let manager = ServiceManager::local_computer(None::<&str>, ServiceManagerAccess::CONNECT)?;
+    let service = manager.open_service(
+        SERVICE_NAME,
+        ServiceAccess::QUERY_STATUS | ServiceAccess::STOP | ServiceAccess::DELETE,
+    )?;
+
+    if service.query_status()?.current_state != ServiceState::Stopped {
+        service.stop()?;
+
+        let started_at = Instant::now();
+        while service.query_status()?.current_state != ServiceState::Stopped {
+            if started_at.elapsed() > UNINSTALL_STOP_TIMEOUT {
+                return Err(anyhow!("Service {SERVICE_NAME} didn't stop in time"));
+            }
+            thread::sleep(Duration::from_secs(1));
+        }
+    }
+
+    service.delete()?;
+    println!("Service {SERVICE_NAME} uninstalled");
+
+    Ok(())
+}
+
+/// Run farmer under Service Control Manager, blocks until service stops
+pub(super) fn run(args: Vec<String>) -> anyhow::Result<()> {
+    SERVICE_ARGS
+        .set(args)
+        .map_err(|_args| anyhow!("Service is already running"))?;
+
+    service_dispatcher::start(SERVICE_NAME, ffi_service_main)?;
+
+    Ok(())
+}
+
+fn service_main(_arguments: Vec<OsString>) {
+    if let Err(error) = run_service() {
+        error!(%error, "Farmer service failed");
+    }
+}
+
+fn set_status(
+    status_handle: ServiceStatusHandle,
+    current_state: ServiceState,
+    exit_code: ServiceExitCode,
+) -> windows_service::Result<()> {
+    let (controls_accepted, checkpoint, wait_hint) = match current_state {
+        ServiceState::Running => (
+            ServiceControlAccept::STOP | ServiceControlAccept::SHUTDOWN,
+            0,
+            Duration::ZERO,
+        ),
+        ServiceState::StopPending => (ServiceControlAccept::empty(), 1, STOP_TIMEOUT),
+        _ => (ServiceControlAccept::empty(), 0, Duration::ZERO),
+    };
+
+    status_handle.set_service_status(ServiceStatus {
+        service_type: ServiceType::OWN_PROCESS,
+        current_state,
+        controls_accepted,
+        exit_code,
+        checkpoint,
+        wait_hint,
+        process_id: None,
+    })
+}
+
+fn run_service() -> anyhow::Result<()> {
+    let (stop_sender, stop_receiver) = oneshot::channel::<()>();
+    let stop_sender = Mutex::new(Some(stop_sender));
+
+    let status_handle =
+        service_control_handler::register(
+            SERVICE_NAME,
+            move |control_event| match control_event {
+                ServiceControl::Stop | ServiceControl::Shutdown => {
+                    if let Some(stop_sender) = stop_sender.lock().take() {
+                        let _ = stop_sender.send(());
+                    }
+                    ServiceControlHandlerResult::NoError
+                }
+                ServiceControl::Interrogate => ServiceControlHandlerResult::NoError,
+                _ => ServiceControlHandlerResult::NotImplemented,
+            },
+        )?;
+
+    set_status(
+        status_handle,
+        ServiceState::Running,
+        ServiceExitCode::Win32(0),
+    )?;
+
+    let result = run_farmer(stop_receiver, status_handle);
+
+    let exit_code = match &result {
+        Ok(()) => ServiceExitCode::Win32(0),
+        // Non-zero exit code makes Service Control Manager apply failure actions
+        Err(_error) => ServiceExitCode::ServiceSpecific(1),
+    };
+    set_status(status_handle, ServiceState::Stopped, exit_code)?;
+
+    result
+}
+
+fn run_farmer(
+    stop_receiver: oneshot::Receiver<()>,
+    status_handle: ServiceStatusHandle,
+) -> anyhow::Result<()> {
+    let args = SERVICE_ARGS
+        .get()
+        .ok_or_else(|| anyhow!("Service arguments are not set"))?;
+    let command = parse_args(args)?;
+    let Subcommand::Farm(farming_args) = command.subcommand else {
+        unreachable!("Checked by `parse_args`; qed");
+    };
+
+    let base_path = command.base_path;
+    let disk_farms = farming_disk_farms(&base_path, command.farm, farming_args.plot_size);
+
+    let shutdown = async move {
+        // Sender is only dropped together with control handler, which lives as long as process
+        let _ = stop_receiver.await;
+
+        info!("Received service stop request, shutting down farmer...");
+
+        // Plots are flushed on shutdown, which might take a while
+        if let Err(error) = set_status(
+            status_handle,
+            ServiceState::StopPending,
+            ServiceExitCode::Win32(0),
+        ) {
+            error!(%error, "Failed to report service stop pending status");
+        }
+    };
+
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()?
+        .block_on(farm_multi_disk::<PosTable>(
+            base_path,
+            disk_farms,
+            farming_args,
+            None,
+            shutdown,
+        ))
+}
@@ -1,6 +1,10 @@
 use std::path::PathBuf;
 use subspace_farmer::single_disk_plot::{SingleDiskPlot, SingleDiskPlotSummary};
 
+/// Databases shared by all farms that are stored in base path
+pub(crate) const SHARED_DATABASES: &[&str] =
+    &["known_addresses_db", "piece_cache_db", "providers_db"];
+
 pub(crate) fn print_disk_farm_info(directory: PathBuf, disk_farm_index: usize) {
     println!("Single disk farm {disk_farm_index}:");
     match SingleDiskPlot::collect_summary(directory) {
@@ -0,0 +1,82 @@
+use crate::{DiskFarm, WipeArgs};
+use anyhow::anyhow;
+use std::fs;
+use std::path::Path;
+use subspace_farmer::single_disk_plot::SingleDiskPlot;
+use tracing::info;
+
+/// Networking databases shared by all farms that are stored in base path
+const NETWORKING_DATABASES: &[&str] = &["known_addresses_db", "providers_db"];
+/// Piece cache database shared by all farms that is stored in base path
+const PIECE_CACHE_DATABASE: &str = "piece_cache_db";
+
+pub(crate) fn wipe(
+    base_path: &Path,
+    disk_farms: Vec<DiskFarm>,
+    wipe_args: WipeArgs,
+) -> anyhow::Result<()> {
+    let WipeArgs {
+        plot,
+        metadata,
+        identity,
+        piece_cache,
+        networking,
+        farm_index,
+    } = wipe_args;
+
+    // Without any selectors everything that belongs to the farm is wiped, like before
+    let wipe_all = !(plot || metadata || identity || piece_cache || networking);
+
+    if let Some(&disk_farm_index) = farm_index
+        .iter()
+        .find(|&&disk_farm_index| disk_farm_index >= disk_farms.len())
+    {
+        return Err(anyhow!(
+            "Farm index {disk_farm_index} is out of range, there are {} farms",
+            disk_farms.len()
+        ));
+    }
+
+    for (disk_farm_index, disk_farm) in disk_farms.into_iter().enumerate() {
+        if !farm_index.is_empty() && !farm_index.contains(&disk_farm_index) {
+            continue;
+        }
+
+        let directory = disk_farm.directory;
+
+        if wipe_all || identity {
+            // Plot is bound to identity and is useless without it, so it is wiped as well
+            info!(%disk_farm_index, "Wiping farm");
+            SingleDiskPlot::wipe(&directory)?;
+        } else if plot {
+            info!(%disk_farm_index, "Wiping plot");
+            SingleDiskPlot::wipe_plot(&directory)?;
+        } else if metadata {
+            info!(%disk_farm_index, "Wiping plot metadata");
+            SingleDiskPlot::wipe_metadata(&directory)?;
+        }
+    }
+
+    if piece_cache {
+        wipe_database(&base_path.join(PIECE_CACHE_DATABASE))?;
+    }
+
+    if networking {
+        for database in NETWORKING_DATABASES {
+            wipe_database(&base_path.join(database))?;
+        }
+    }
+
+    info!("Done");
+
+    Ok(())
+}
+
+fn wipe_database(path: &Path) -> anyhow::Result<()> {
+    if path.exists() {
+        info!("Deleting database at {}", path.display());
+        fs::remove_dir_all(path)?;
+    }
+
+    Ok(())
+}
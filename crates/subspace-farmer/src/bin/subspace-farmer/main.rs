@@ -4,24 +4,25 @@ mod commands;
 mod ss58;
 mod utils;
 
-use crate::utils::get_usable_plot_space;
+use crate::utils::{get_usable_plot_space, parse_metrics_label, LogTail};
 use anyhow::Result;
 use bytesize::ByteSize;
 use clap::{Parser, ValueEnum, ValueHint};
+use hyper::Uri;
 use ss58::parse_ss58_reward_address;
 use std::fs;
+use std::net::SocketAddr;
 use std::num::{NonZeroU16, NonZeroUsize};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use subspace_core_primitives::PublicKey;
-use subspace_farmer::single_disk_plot::SingleDiskPlot;
 use subspace_networking::libp2p::Multiaddr;
 use subspace_proof_of_space::chia::ChiaTable;
 use tempfile::TempDir;
 use tracing::info;
 use tracing_subscriber::filter::LevelFilter;
 use tracing_subscriber::prelude::*;
-use tracing_subscriber::{fmt, EnvFilter};
+use tracing_subscriber::{fmt, reload, EnvFilter};
 
 type PosTable = ChiaTable;
 
@@ -38,71 +39,307 @@ static GLOBAL: jemallocator::Jemalloc = jemallocator::Jemalloc;
 #[derive(Debug, Parser)]
 struct FarmingArgs {
     /// WebSocket RPC URL of the Subspace node to connect to
-    #[arg(long, value_hint = ValueHint::Url, default_value = "ws://127.0.0.1:9944")]
+    #[arg(
+        long,
+        env = "SUBSPACE_FARMER_NODE_RPC_URL",
+        value_hint = ValueHint::Url,
+        default_value = "ws://127.0.0.1:9944",
+    )]
     node_rpc_url: String,
-    /// Address for farming rewards
-    #[arg(long, value_parser = parse_ss58_reward_address)]
-    reward_address: PublicKey,
+    /// Address for farming rewards, used for farms that don't specify their own reward address
+    #[arg(long, env = "SUBSPACE_FARMER_REWARD_ADDRESS", value_parser = parse_ss58_reward_address)]
+    reward_address: Option<PublicKey>,
     /// Maximum plot size in human readable format (e.g. 10GB, 2TiB) or just bytes (e.g. 4096).
-    #[arg(long, default_value_t)]
+    #[arg(long, env = "SUBSPACE_FARMER_PLOT_SIZE", default_value_t)]
     plot_size: ByteSize,
     /// Maximum number of pieces in sector (can override protocol value to something lower).
-    #[arg(long)]
+    #[arg(long, env = "SUBSPACE_FARMER_MAX_PIECES_IN_SECTOR")]
     max_pieces_in_sector: Option<u16>,
     /// Number of major concurrent operations to allow for disk
-    #[arg(long, default_value = "2")]
+    #[arg(long, env = "SUBSPACE_FARMER_DISK_CONCURRENCY", default_value = "2")]
     disk_concurrency: NonZeroU16,
     /// Disable farming
-    #[arg(long)]
+    #[arg(long, env = "SUBSPACE_FARMER_DISABLE_FARMING")]
     disable_farming: bool,
     /// DSN parameters
     #[clap(flatten)]
     dsn: DsnArgs,
     /// Number of plots that can be plotted concurrently, impacts RAM usage.
-    #[arg(long, default_value = "10")]
+    #[arg(
+        long,
+        env = "SUBSPACE_FARMER_MAX_CONCURRENT_PLOTS",
+        default_value = "10"
+    )]
     max_concurrent_plots: NonZeroUsize,
     /// Do not print info about configured farms on startup.
-    #[arg(long)]
+    #[arg(long, env = "SUBSPACE_FARMER_NO_INFO")]
     no_info: bool,
+    /// Render terminal dashboard with farms status and recent logs instead of printing logs
+    #[arg(long, env = "SUBSPACE_FARMER_TUI")]
+    tui: bool,
+    /// Address to listen on for HTTP health check endpoint (`/healthz`), for instance
+    /// `127.0.0.1:9616`, disabled by default.
+    #[arg(long, env = "SUBSPACE_FARMER_HEALTH_CHECK_LISTEN_ON")]
+    health_check_listen_on: Option<SocketAddr>,
+    /// Opt-in submission of anonymized farmer statistics (version, OS, number of plots, allocated
+    /// and plotted space) to specified HTTP(S) endpoint, disabled by default.
+    #[arg(long, env = "SUBSPACE_FARMER_TELEMETRY_ENDPOINT")]
+    telemetry_endpoint: Option<Uri>,
+    /// Interval in seconds between telemetry submissions.
+    #[arg(
+        long,
+        env = "SUBSPACE_FARMER_TELEMETRY_INTERVAL",
+        default_value_t = 3600
+    )]
+    telemetry_interval: u64,
+    /// Address to listen on for gRPC management API (status, pause/resume plotting, add/remove
+    /// farm and scrubbing), for instance `127.0.0.1:9617`, disabled by default.
+    #[arg(
+        long,
+        env = "SUBSPACE_FARMER_MANAGEMENT_LISTEN_ON",
+        requires = "management_token"
+    )]
+    management_listen_on: Option<SocketAddr>,
+    /// Token that management API clients must provide with every request as
+    /// `authorization: Bearer <token>` metadata.
+    #[arg(long, env = "SUBSPACE_FARMER_MANAGEMENT_TOKEN")]
+    management_token: Option<String>,
+    /// Webhook URLs that alerts are sent to as JSON `POST` requests (farm errors, node RPC
+    /// disconnection, missed challenges), multiple are supported.
+    #[arg(long, env = "SUBSPACE_FARMER_ALERT_WEBHOOK", value_delimiter = ',')]
+    alert_webhook: Vec<Uri>,
+    /// Send alert when node RPC is not available for this many minutes.
+    #[arg(
+        long,
+        env = "SUBSPACE_FARMER_ALERT_NODE_RPC_DISCONNECTION_THRESHOLD",
+        default_value_t = 5
+    )]
+    alert_node_rpc_disconnection_threshold: u64,
+    /// Prometheus Pushgateway URL to periodically push farmer and networking metrics to, for farms
+    /// that can't be scraped directly (behind NAT for instance), disabled by default.
+    #[arg(long, env = "SUBSPACE_FARMER_METRICS_PUSH_ENDPOINT")]
+    metrics_push_endpoint: Option<Uri>,
+    /// Interval in seconds between metrics pushes.
+    #[arg(
+        long,
+        env = "SUBSPACE_FARMER_METRICS_PUSH_INTERVAL",
+        default_value_t = 60
+    )]
+    metrics_push_interval: u64,
+    /// Additional labels in `name=value` format that identify this farmer in Pushgateway,
+    /// multiple are supported.
+    #[arg(
+        long,
+        env = "SUBSPACE_FARMER_METRICS_PUSH_LABEL",
+        value_delimiter = ',',
+        value_parser = parse_metrics_label
+    )]
+    metrics_push_label: Vec<(String, String)>,
 }
 
 /// Arguments for DSN
 #[derive(Debug, Parser)]
 struct DsnArgs {
-    /// Multiaddrs of bootstrap nodes to connect to on startup, multiple are supported
-    #[arg(long)]
+    /// Multiaddrs of bootstrap nodes to connect to on startup, multiple are supported.
+    ///
+    /// `/dnsaddr/<domain>` entries are supported and periodically resolved from DNS TXT records.
+    #[arg(long, env = "SUBSPACE_FARMER_BOOTSTRAP_NODES")]
     bootstrap_nodes: Vec<Multiaddr>,
     /// Multiaddr to listen on for subspace networking, for instance `/ip4/0.0.0.0/tcp/0`,
     /// multiple are supported.
-    #[arg(long, default_value = "/ip4/0.0.0.0/tcp/30533")]
+    #[arg(
+        long,
+        env = "SUBSPACE_FARMER_LISTEN_ON",
+        default_value = "/ip4/0.0.0.0/tcp/30533"
+    )]
     listen_on: Vec<Multiaddr>,
     /// Piece cache size in pieces.
-    #[arg(long, default_value = "1000")]
+    #[arg(long, env = "SUBSPACE_FARMER_PIECE_CACHE_SIZE", default_value = "1000")]
     piece_cache_size: NonZeroUsize,
     /// Number of provided keys (by other peers) that will be stored.
-    #[arg(long, default_value = "655360")]
+    #[arg(
+        long,
+        env = "SUBSPACE_FARMER_PROVIDED_KEYS_LIMIT",
+        default_value = "655360"
+    )]
     provided_keys_limit: NonZeroUsize,
     /// Determines whether we allow keeping non-global (private, shared, loopback..) addresses in Kademlia DHT.
-    #[arg(long, default_value_t = false)]
+    #[arg(
+        long,
+        env = "SUBSPACE_FARMER_DISABLE_PRIVATE_IPS",
+        default_value_t = false
+    )]
     disable_private_ips: bool,
+    /// Disable QUIC transport, only TCP and WebSocket will be used.
+    #[arg(long, env = "SUBSPACE_FARMER_DISABLE_QUIC", default_value_t = false)]
+    disable_quic: bool,
+    /// Dial QUIC addresses of peers first and only fall back to their other addresses if QUIC dial
+    /// failed, QUIC listen address like `/ip4/0.0.0.0/udp/30533/quic-v1` needs to be added to
+    /// `--listen-on` for other peers to be able to dial this farmer over QUIC.
+    #[arg(
+        long,
+        env = "SUBSPACE_FARMER_PREFER_QUIC",
+        default_value_t = false,
+        conflicts_with = "disable_quic"
+    )]
+    prefer_quic: bool,
     /// Multiaddrs of reserved nodes to maintain a connection to, multiple are supported
-    #[arg(long)]
+    #[arg(long, env = "SUBSPACE_FARMER_RESERVED_PEERS")]
     reserved_peers: Vec<Multiaddr>,
     /// Defines max established incoming connection limit.
-    #[arg(long, default_value_t = 50)]
+    #[arg(long, env = "SUBSPACE_FARMER_IN_CONNECTIONS", default_value_t = 50)]
     in_connections: u32,
     /// Defines max established outgoing swarm connection limit.
-    #[arg(long, default_value_t = 50)]
+    #[arg(long, env = "SUBSPACE_FARMER_OUT_CONNECTIONS", default_value_t = 50)]
     out_connections: u32,
     /// Defines max pending incoming connection limit.
-    #[arg(long, default_value_t = 50)]
+    #[arg(
+        long,
+        env = "SUBSPACE_FARMER_PENDING_IN_CONNECTIONS",
+        default_value_t = 50
+    )]
     pending_in_connections: u32,
     /// Defines max pending outgoing swarm connection limit.
-    #[arg(long, default_value_t = 50)]
+    #[arg(
+        long,
+        env = "SUBSPACE_FARMER_PENDING_OUT_CONNECTIONS",
+        default_value_t = 50
+    )]
     pending_out_connections: u32,
     /// Defines target total (in and out) connection number that should be maintained.
-    #[arg(long, default_value_t = 50)]
+    #[arg(long, env = "SUBSPACE_FARMER_TARGET_CONNECTIONS", default_value_t = 50)]
     target_connections: u32,
+    /// Number of seconds idle connections are kept alive before being closed.
+    #[arg(
+        long,
+        env = "SUBSPACE_FARMER_CONNECTION_IDLE_TIMEOUT",
+        default_value_t = 10
+    )]
+    connection_idle_timeout: u64,
+    /// Keep all connections alive instead of closing them once idle, such that frequently used
+    /// peers are not re-dialed all the time. Connections to reserved peers are always kept alive.
+    #[arg(
+        long,
+        env = "SUBSPACE_FARMER_KEEP_ALIVE_ALL_CONNECTIONS",
+        default_value_t = false
+    )]
+    keep_alive_all_connections: bool,
+}
+
+/// Arguments for wipe, without any selectors everything that belongs to farm is wiped
+#[derive(Debug, Parser)]
+struct WipeArgs {
+    /// Wipe plot and its metadata, identity is preserved and the same farm will be plotted again
+    /// from scratch on next start
+    #[arg(long, env = "SUBSPACE_FARMER_WIPE_PLOT")]
+    plot: bool,
+    /// Wipe plot metadata only, plotting will start from the first sector on next start
+    #[arg(long, env = "SUBSPACE_FARMER_WIPE_METADATA")]
+    metadata: bool,
+    /// Wipe identity, plot is bound to identity, so it will be wiped as well
+    #[arg(long, env = "SUBSPACE_FARMER_WIPE_IDENTITY")]
+    identity: bool,
+    /// Wipe piece cache database shared by all farms
+    #[arg(long, env = "SUBSPACE_FARMER_WIPE_PIECE_CACHE")]
+    piece_cache: bool,
+    /// Wipe networking databases (known addresses and providers) shared by all farms
+    #[arg(long, env = "SUBSPACE_FARMER_WIPE_NETWORKING")]
+    networking: bool,
+    /// Only wipe farms with specified indexes (in the order `--farm` was specified), multiple are
+    /// supported, all farms are wiped by default
+    #[arg(long, env = "SUBSPACE_FARMER_WIPE_FARM_INDEX")]
+    farm_index: Vec<usize>,
+}
+
+/// Arguments for benchmark
+#[derive(Debug, clap::Subcommand)]
+enum BenchmarkArgs {
+    /// Benchmark disk with the same I/O pattern as plotting and farming use
+    Disk(DiskBenchmarkArgs),
+}
+
+/// Arguments for disk benchmark
+#[derive(Debug, Parser)]
+struct DiskBenchmarkArgs {
+    /// Directory on the disk to benchmark, temporary file will be created there and removed
+    /// afterwards
+    #[arg(env = "SUBSPACE_FARMER_BENCHMARK_DISK_DIRECTORY", value_hint = ValueHint::DirPath)]
+    directory: PathBuf,
+    /// Size of the temporary file in human readable format (e.g. 10GB, 2TiB) or just bytes, should
+    /// exceed amount of RAM to avoid page cache effects
+    #[arg(
+        long,
+        env = "SUBSPACE_FARMER_BENCHMARK_DISK_FILE_SIZE",
+        default_value = "4GiB"
+    )]
+    file_size: ByteSize,
+    /// Duration of each random read test in seconds
+    #[arg(
+        long,
+        env = "SUBSPACE_FARMER_BENCHMARK_DISK_DURATION",
+        default_value_t = 10
+    )]
+    duration: u64,
+    /// Plot size in human readable format (e.g. 10GB, 2TiB) or just bytes to check whether disk
+    /// can sustain audits for it
+    #[arg(long, env = "SUBSPACE_FARMER_BENCHMARK_DISK_PLOT_SIZE")]
+    plot_size: Option<ByteSize>,
+    /// Number of pieces in sector, should match protocol value
+    #[arg(
+        long,
+        env = "SUBSPACE_FARMER_BENCHMARK_DISK_PIECES_IN_SECTOR",
+        default_value_t = 1000
+    )]
+    pieces_in_sector: u16,
+}
+
+/// Arguments for diagnostics
+#[derive(Debug, Parser)]
+struct DiagnosticsArgs {
+    /// Path to the `.tar.gz` archive where diagnostics will be written,
+    /// `diagnostics-<timestamp>.tar.gz` in current directory by default
+    #[arg(long, env = "SUBSPACE_FARMER_DIAGNOSTICS_OUTPUT", value_hint = ValueHint::FilePath)]
+    output: Option<PathBuf>,
+    /// Log files to include (logs of farmer running as launchd agent on macOS are included
+    /// automatically), multiple are supported
+    #[arg(
+        long = "log-file",
+        env = "SUBSPACE_FARMER_DIAGNOSTICS_LOG_FILE",
+        value_hint = ValueHint::FilePath
+    )]
+    log_files: Vec<PathBuf>,
+    /// Number of most recent lines to include from each log file
+    #[arg(
+        long,
+        env = "SUBSPACE_FARMER_DIAGNOSTICS_LOG_LINES",
+        default_value_t = 1000
+    )]
+    log_lines: usize,
+}
+
+/// Arguments for service management
+#[derive(Debug, clap::Subcommand)]
+enum ServiceArgs {
+    /// Install farmer as a service that starts automatically and is restarted if it fails
+    /// (systemd user unit on Linux, launchd agent on macOS, Windows service on Windows).
+    ///
+    /// Windows service runs as LocalSystem account, so `--farm` or `--base-path` should be
+    /// specified explicitly.
+    Install {
+        /// Arguments to run farmer with, for instance
+        /// `-- --farm path=/path/to/farm,size=1T farm --reward-address st...`
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
+    },
+    /// Stop and uninstall previously installed service
+    Uninstall,
+    /// Run farmer under Windows Service Control Manager, used by installed Windows service
+    #[clap(hide = true)]
+    Run {
+        /// Arguments to run farmer with
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
+    },
 }
 
 #[derive(Debug, Clone, Copy, ValueEnum)]
@@ -120,12 +357,24 @@ impl Default for WriteToDisk {
 
 #[derive(Debug, clap::Subcommand)]
 enum Subcommand {
-    /// Wipes plot and identity
-    Wipe,
+    /// Wipes plot and identity, or only selected parts of the farm
+    Wipe(WipeArgs),
     /// Start a farmer using previously created plot
     Farm(FarmingArgs),
-    /// Print information about farm and its content
+    /// Print information about farm and its content, works offline without connecting to the node
     Info,
+    /// Check whether existing farms can be used by this version of the farmer, need migration or
+    /// require replotting, works offline without connecting to the node
+    CheckUpgrade,
+    /// Benchmark farmer components
+    #[clap(subcommand)]
+    Benchmark(BenchmarkArgs),
+    /// Collect diagnostic information with redacted public keys, addresses and peer IDs into a
+    /// single archive that can be attached to bug reports
+    Diagnostics(DiagnosticsArgs),
+    /// Manage farmer running as a service
+    #[clap(subcommand)]
+    Service(ServiceArgs),
 }
 
 #[derive(Debug, Clone)]
@@ -134,6 +383,8 @@ struct DiskFarm {
     directory: PathBuf,
     /// How much space in bytes can farm use for plots (metadata space is not included)
     allocated_plotting_space: u64,
+    /// Address for farming rewards of this farm, overrides `--reward-address`
+    reward_address: Option<PublicKey>,
 }
 
 impl FromStr for DiskFarm {
@@ -141,12 +392,13 @@ impl FromStr for DiskFarm {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let parts = s.split(',').collect::<Vec<_>>();
-        if parts.len() != 2 {
-            return Err("Must contain 2 coma-separated components".to_string());
+        if !(2..=3).contains(&parts.len()) {
+            return Err("Must contain 2 or 3 coma-separated components".to_string());
         }
 
         let mut plot_directory = None;
         let mut allocated_plotting_space = None;
+        let mut reward_address = None;
 
         for part in parts {
             let part = part.splitn(2, '=').collect::<Vec<_>>();
@@ -175,9 +427,14 @@ impl FromStr for DiskFarm {
                             .as_u64(),
                     );
                 }
+                "reward_address" => {
+                    reward_address.replace(parse_ss58_reward_address(value).map_err(|error| {
+                        format!("Failed to parse `reward_address` \"{value}\": {error}")
+                    })?);
+                }
                 key => {
                     return Err(format!(
-                        "Key \"{key}\" is not supported, only `path` or `size`"
+                        "Key \"{key}\" is not supported, only `path`, `size` or `reward_address`"
                     ));
                 }
             }
@@ -190,18 +447,30 @@ impl FromStr for DiskFarm {
             allocated_plotting_space: allocated_plotting_space.ok_or({
                 "`size` key is required with path to directory where plots will be stored"
             })?,
+            reward_address,
         })
     }
 }
 
 #[derive(Debug, Parser)]
-#[clap(about, version)]
+#[clap(
+    about,
+    version,
+    after_help = "Global options and options of `farm` subcommand can also be set with \
+    `SUBSPACE_FARMER_*` environment variables named after the option, for instance \
+    `SUBSPACE_FARMER_REWARD_ADDRESS` for `--reward-address`. Options of other subcommands are \
+    prefixed with subcommand name, for instance `SUBSPACE_FARMER_WIPE_PLOT` for `wipe --plot`. \
+    Options that can be specified multiple times take a single value from environment variable. \
+    Command line arguments take precedence over environment variables, which take precedence \
+    over defaults."
+)]
 struct Command {
     #[clap(subcommand)]
     subcommand: Subcommand,
     /// Base path for data storage.
     #[arg(
         long,
+        env = "SUBSPACE_FARMER_BASE_PATH",
         default_value_os_t = utils::default_base_path(),
         value_hint = ValueHint::FilePath,
     )]
@@ -214,32 +483,98 @@ struct Command {
     ///   path=/path/to/directory,size=5T
     ///
     /// `size` is max plot size in human readable format (e.g. 10GB, 2TiB) or just bytes.
+    ///
+    /// Optional `reward_address=st...` component allows farms to use different reward addresses,
+    /// `--reward-address` of `farm` subcommand is used for farms without it.
     /// TODO: Update overhead number here or account for it automatically
     /// Note that `size` is how much data will be plotted, you also need to account for metadata,
     /// which right now occupies up to 8% of the disk space.
-    #[arg(long)]
+    #[arg(long, env = "SUBSPACE_FARMER_FARM")]
     farm: Vec<DiskFarm>,
     /// Run temporary farmer, this will create a temporary directory for storing farmer data that
     /// will be delete at the end of the process
-    #[arg(long, conflicts_with = "base_path", conflicts_with = "farm")]
+    #[arg(
+        long,
+        env = "SUBSPACE_FARMER_TMP",
+        conflicts_with = "base_path",
+        conflicts_with = "farm"
+    )]
     tmp: bool,
+    /// Path to file with log filter directives (same format as `RUST_LOG`, for instance
+    /// `info,subspace_farmer::single_disk_plot=trace`), file is re-read on SIGHUP, which allows to
+    /// change log level without restarting the farmer.
+    #[arg(long, env = "SUBSPACE_FARMER_LOG_FILTER_FILE", value_hint = ValueHint::FilePath)]
+    log_filter_file: Option<PathBuf>,
+}
+
+/// Farms to use for `farm` subcommand, farm in `base_path` with `plot_size` is used if no farms were
+/// specified explicitly
+fn farming_disk_farms(
+    base_path: &Path,
+    farms: Vec<DiskFarm>,
+    plot_size: ByteSize,
+) -> Vec<DiskFarm> {
+    if farms.is_empty() {
+        if !base_path.exists() {
+            fs::create_dir_all(base_path).unwrap_or_else(|error| {
+                panic!("Failed to create data directory {base_path:?}: {error:?}")
+            });
+        }
+
+        vec![DiskFarm {
+            directory: base_path.to_path_buf(),
+            allocated_plotting_space: get_usable_plot_space(plot_size.as_u64()),
+            reward_address: None,
+        }]
+    } else {
+        for farm in &farms {
+            if !farm.directory.exists() {
+                panic!("Directory {} doesn't exist", farm.directory.display());
+            }
+        }
+
+        farms
+    }
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    tracing_subscriber::registry()
+    let command = Command::parse();
+
+    let (log_filter, log_filter_reload_handle) = reload::Layer::new(
+        EnvFilter::builder()
+            .with_default_directive(LevelFilter::INFO.into())
+            .from_env_lossy(),
+    );
+    let log_tail = matches!(
+        &command.subcommand,
+        Subcommand::Farm(farming_args) if farming_args.tui
+    )
+    .then(LogTail::default);
+    let subscriber = tracing_subscriber::registry()
+        .with(log_filter)
         .with(
-            fmt::layer().with_filter(
-                EnvFilter::builder()
-                    .with_default_directive(LevelFilter::INFO.into())
-                    .from_env_lossy(),
-            ),
+            log_tail
+                .clone()
+                .map(|log_tail| fmt::layer().with_ansi(false).with_writer(log_tail)),
+        )
+        .with(log_tail.is_none().then(fmt::layer));
+    // Windows service has no console, so logs are written to Windows event log instead
+    #[cfg(windows)]
+    let subscriber = subscriber.with(
+        matches!(
+            &command.subcommand,
+            Subcommand::Service(ServiceArgs::Run { .. })
         )
-        .init();
+        .then(commands::EventLogLayer::new)
+        .flatten(),
+    );
+    subscriber.init();
+    if let Some(log_filter_file) = command.log_filter_file.clone() {
+        utils::reload_log_filter_on_signal(log_filter_file, log_filter_reload_handle);
+    }
     utils::raise_fd_limit();
 
-    let command = Command::parse();
-
     let (base_path, _tmp_directory) = if command.tmp {
         let tmp_directory = TempDir::new()?;
         (tmp_directory.as_ref().to_path_buf(), Some(tmp_directory))
@@ -248,7 +583,7 @@ async fn main() -> Result<()> {
     };
 
     match command.subcommand {
-        Subcommand::Wipe => {
+        Subcommand::Wipe(wipe_args) => {
             let disk_farms = if command.farm.is_empty() {
                 if !base_path.exists() {
                     info!("Done");
@@ -259,8 +594,9 @@ async fn main() -> Result<()> {
                 // TODO: Support wiping of old disk plots for backwards compatibility
 
                 vec![DiskFarm {
-                    directory: base_path,
+                    directory: base_path.clone(),
                     allocated_plotting_space: get_usable_plot_space(0),
+                    reward_address: None,
                 }]
             } else {
                 for farm in &command.farm {
@@ -272,49 +608,64 @@ async fn main() -> Result<()> {
                 command.farm
             };
 
-            for farm in &disk_farms {
-                SingleDiskPlot::wipe(&farm.directory)?;
-            }
-
-            info!("Done");
+            commands::wipe(&base_path, disk_farms, wipe_args)?;
         }
         Subcommand::Farm(farming_args) => {
+            let disk_farms = farming_disk_farms(&base_path, command.farm, farming_args.plot_size);
+
+            commands::farm_multi_disk::<PosTable>(
+                base_path,
+                disk_farms,
+                farming_args,
+                log_tail,
+                utils::shutdown_signal(),
+            )
+            .await?;
+        }
+        Subcommand::Info => {
             let disk_farms = if command.farm.is_empty() {
-                if !base_path.exists() {
-                    fs::create_dir_all(&base_path).unwrap_or_else(|error| {
-                        panic!("Failed to create data directory {base_path:?}: {error:?}")
-                    });
-                }
-
                 vec![DiskFarm {
                     directory: base_path.clone(),
-                    allocated_plotting_space: get_usable_plot_space(
-                        farming_args.plot_size.as_u64(),
-                    ),
+                    allocated_plotting_space: get_usable_plot_space(0),
+                    reward_address: None,
                 }]
             } else {
-                for farm in &command.farm {
-                    if !farm.directory.exists() {
-                        panic!("Directory {} doesn't exist", farm.directory.display());
-                    }
-                }
+                command.farm
+            };
 
+            commands::info(&base_path, disk_farms);
+        }
+        Subcommand::CheckUpgrade => {
+            let disk_farms = if command.farm.is_empty() {
+                vec![DiskFarm {
+                    directory: base_path.clone(),
+                    allocated_plotting_space: get_usable_plot_space(0),
+                    reward_address: None,
+                }]
+            } else {
                 command.farm
             };
 
-            commands::farm_multi_disk::<PosTable>(base_path, disk_farms, farming_args).await?;
+            commands::check_upgrade(disk_farms)?;
         }
-        Subcommand::Info => {
+        Subcommand::Benchmark(benchmark_args) => {
+            commands::benchmark(benchmark_args)?;
+        }
+        Subcommand::Diagnostics(diagnostics_args) => {
             let disk_farms = if command.farm.is_empty() {
                 vec![DiskFarm {
-                    directory: base_path,
+                    directory: base_path.clone(),
                     allocated_plotting_space: get_usable_plot_space(0),
+                    reward_address: None,
                 }]
             } else {
                 command.farm
             };
 
-            commands::info(disk_farms);
+            commands::diagnostics(&base_path, disk_farms, diagnostics_args)?;
+        }
+        Subcommand::Service(service_args) => {
+            commands::service(service_args)?;
         }
     }
     Ok(())
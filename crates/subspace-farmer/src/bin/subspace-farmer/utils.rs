@@ -1,5 +1,19 @@
-use std::path::PathBuf;
+use anyhow::anyhow;
+use hyper::client::HttpConnector;
+use hyper::header::CONTENT_TYPE;
+use hyper::{Body, Client, Request, Uri};
+use hyper_rustls::{HttpsConnector, HttpsConnectorBuilder};
+use parking_lot::Mutex;
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::{fs, io};
 use tokio::signal;
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::{reload, EnvFilter, Registry};
+
+/// Number of recent log lines kept in [`LogTail`]
+const LOG_TAIL_LINES: usize = 20;
 
 pub(crate) fn default_base_path() -> PathBuf {
     dirs::data_local_dir()
@@ -26,6 +40,45 @@ pub(crate) fn raise_fd_limit() {
     }
 }
 
+/// Format duration in seconds as human-readable string like `1d 2h 3m`
+pub(crate) fn format_eta(secs: u64) -> String {
+    let days = secs / 86400;
+    let hours = secs % 86400 / 3600;
+    let minutes = secs % 3600 / 60;
+
+    if days > 0 {
+        format!("{days}d {hours}h {minutes}m")
+    } else if hours > 0 {
+        format!("{hours}h {minutes}m")
+    } else {
+        format!("{minutes}m")
+    }
+}
+
+/// Total size of all files in directory (recursively), file that doesn't exist has size of zero
+pub(crate) fn directory_size(path: &Path) -> io::Result<u64> {
+    let metadata = match fs::metadata(path) {
+        Ok(metadata) => metadata,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => {
+            return Ok(0);
+        }
+        Err(error) => {
+            return Err(error);
+        }
+    };
+
+    if !metadata.is_dir() {
+        return Ok(metadata.len());
+    }
+
+    let mut size = 0;
+    for entry in fs::read_dir(path)? {
+        size += directory_size(&entry?.path())?;
+    }
+
+    Ok(size)
+}
+
 pub(crate) const DB_OVERHEAD_PERCENT: u64 = 92;
 
 pub(crate) fn get_usable_plot_space(allocated_space: u64) -> u64 {
@@ -73,3 +126,161 @@ pub(crate) async fn shutdown_signal() {
 
     tracing::info!("Received Ctrl+C, shutting down farmer...");
 }
+
+/// Read log filter directives from file and apply them
+fn reload_log_filter(path: &Path, reload_handle: &reload::Handle<EnvFilter, Registry>) {
+    let directives = match fs::read_to_string(path) {
+        Ok(directives) => directives,
+        Err(error) => {
+            tracing::warn!(%error, "Failed to read log filter file {}", path.display());
+            return;
+        }
+    };
+    let directives = directives.trim();
+
+    match EnvFilter::try_new(directives) {
+        Ok(log_filter) => match reload_handle.reload(log_filter) {
+            Ok(()) => {
+                tracing::info!(%directives, "Log filter updated");
+            }
+            Err(error) => {
+                tracing::warn!(%error, "Failed to update log filter");
+            }
+        },
+        Err(error) => {
+            tracing::warn!(%error, %directives, "Invalid log filter directives");
+        }
+    }
+}
+
+/// Apply log filter from file (if it exists) and re-apply it every time SIGHUP is received
+#[cfg(unix)]
+pub(crate) fn reload_log_filter_on_signal(
+    path: PathBuf,
+    reload_handle: reload::Handle<EnvFilter, Registry>,
+) {
+    if path.exists() {
+        reload_log_filter(&path, &reload_handle);
+    }
+
+    let mut hangup = signal::unix::signal(signal::unix::SignalKind::hangup())
+        .expect("Setting signal handlers must never fail");
+
+    tokio::spawn(async move {
+        while hangup.recv().await.is_some() {
+            tracing::info!("Received SIGHUP, reloading log filter...");
+            reload_log_filter(&path, &reload_handle);
+        }
+    });
+}
+
+/// Apply log filter from file (if it exists), there is no SIGHUP on this platform, so it is only
+/// applied once on start
+#[cfg(not(unix))]
+pub(crate) fn reload_log_filter_on_signal(
+    path: PathBuf,
+    reload_handle: reload::Handle<EnvFilter, Registry>,
+) {
+    if path.exists() {
+        reload_log_filter(&path, &reload_handle);
+    }
+}
+
+/// Keeps last few log lines in memory instead of printing them, used when dashboard occupies the
+/// terminal
+#[derive(Debug, Clone, Default)]
+pub(crate) struct LogTail {
+    lines: Arc<Mutex<VecDeque<String>>>,
+}
+
+impl LogTail {
+    /// Recent log lines, oldest first
+    pub(crate) fn lines(&self) -> Vec<String> {
+        self.lines.lock().iter().cloned().collect()
+    }
+}
+
+impl<'a> MakeWriter<'a> for LogTail {
+    type Writer = LogTailWriter;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        LogTailWriter {
+            log_tail: self.clone(),
+            buffer: Vec::new(),
+        }
+    }
+}
+
+/// Writer that collects one log event and appends it to [`LogTail`] on drop
+pub(crate) struct LogTailWriter {
+    log_tail: LogTail,
+    buffer: Vec<u8>,
+}
+
+impl io::Write for LogTailWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for LogTailWriter {
+    fn drop(&mut self) {
+        let mut lines = self.log_tail.lines.lock();
+        for line in String::from_utf8_lossy(&self.buffer).lines() {
+            if lines.len() == LOG_TAIL_LINES {
+                lines.pop_front();
+            }
+            lines.push_back(line.to_string());
+        }
+    }
+}
+
+/// HTTP client that supports both HTTP and HTTPS
+pub(crate) type HttpClient = Client<HttpsConnector<HttpConnector>>;
+
+pub(crate) fn http_client() -> HttpClient {
+    Client::builder().build(
+        HttpsConnectorBuilder::new()
+            .with_native_roots()
+            .https_or_http()
+            .enable_http1()
+            .build(),
+    )
+}
+
+/// Send POST request with specified body, non-successful response status is an error
+pub(crate) async fn http_post(
+    http_client: &HttpClient,
+    uri: Uri,
+    content_type: &str,
+    body: Vec<u8>,
+) -> anyhow::Result<()> {
+    let request = Request::post(uri)
+        .header(CONTENT_TYPE, content_type)
+        .body(Body::from(body))?;
+    let response = http_client.request(request).await?;
+
+    if !response.status().is_success() {
+        return Err(anyhow!("Unexpected response status {}", response.status()));
+    }
+
+    Ok(())
+}
+
+/// Parse metrics label in `name=value` format
+pub(crate) fn parse_metrics_label(s: &str) -> Result<(String, String), String> {
+    let (name, value) = s
+        .split_once('=')
+        .ok_or_else(|| format!("Label `{s}` must be in `name=value` format"))?;
+
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Err(format!("Invalid label name `{name}`"));
+    }
+
+    Ok((name.to_string(), value.to_string()))
+}
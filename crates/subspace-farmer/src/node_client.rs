@@ -5,7 +5,8 @@ use futures::Stream;
 use std::pin::Pin;
 use subspace_core_primitives::{Piece, PieceIndex, SegmentCommitment, SegmentHeader, SegmentIndex};
 use subspace_rpc_primitives::{
-    FarmerAppInfo, RewardSignatureResponse, RewardSigningInfo, SlotInfo, SolutionResponse,
+    FarmerAppInfo, FarmerDeclaration, RewardSignatureResponse, RewardSigningInfo, SlotInfo,
+    SolutionResponse,
 };
 
 /// To become error type agnostic
@@ -64,4 +65,7 @@ pub trait NodeClient: Clone + Send + Sync + 'static {
         &self,
         segment_index: SegmentIndex,
     ) -> Result<(), Error>;
+
+    /// Declare farm to the node, so that node operator can see attached farmers.
+    async fn declare_farmer(&self, farmer_declaration: FarmerDeclaration) -> Result<(), Error>;
 }
@@ -9,7 +9,8 @@ use std::pin::Pin;
 use std::sync::Arc;
 use subspace_core_primitives::{Piece, PieceIndex, SegmentCommitment, SegmentHeader, SegmentIndex};
 use subspace_rpc_primitives::{
-    FarmerAppInfo, RewardSignatureResponse, RewardSigningInfo, SlotInfo, SolutionResponse,
+    FarmerAppInfo, FarmerDeclaration, RewardSignatureResponse, RewardSigningInfo, SlotInfo,
+    SolutionResponse,
 };
 
 // Defines max_concurrent_requests constant in the node rpc client.
@@ -171,4 +172,11 @@ impl NodeClient for NodeRpcClient {
             )
             .await?)
     }
+
+    async fn declare_farmer(&self, farmer_declaration: FarmerDeclaration) -> Result<(), Error> {
+        Ok(self
+            .client
+            .request("subspace_declareFarmer", rpc_params![&farmer_declaration])
+            .await?)
+    }
 }
@@ -1,6 +1,7 @@
 mod farming;
 pub mod piece_reader;
 mod plotting;
+pub mod scrubbing;
 
 use crate::identity::Identity;
 use crate::node_client::NodeClient;
@@ -10,6 +11,7 @@ pub use crate::single_disk_plot::farming::FarmingError;
 use crate::single_disk_plot::piece_reader::PieceReader;
 use crate::single_disk_plot::plotting::plotting;
 pub use crate::single_disk_plot::plotting::PlottingError;
+use crate::single_disk_plot::scrubbing::PlotScrubber;
 use crate::utils::JoinOnDrop;
 use bytesize::ByteSize;
 use derive_more::{Display, From};
@@ -226,6 +228,68 @@ pub enum SingleDiskPlotSummary {
     },
 }
 
+/// Contents of plot metadata file, read without opening the plot for farming
+#[derive(Debug)]
+pub struct SingleDiskPlotMetadata {
+    /// Version of the plot format
+    pub version: u8,
+    /// Metadata of all sectors plotted so far
+    pub sectors_metadata: Vec<SectorMetadata>,
+    /// Size of the metadata file on disk in bytes
+    pub metadata_file_size: u64,
+    /// Size of the plot file on disk in bytes
+    pub plot_file_size: u64,
+}
+
+/// Whether existing plot can be used by this version of the farmer
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum PlotCompatibility {
+    /// There is no plot in the directory yet, it will be created
+    NotFound,
+    /// Plot can be opened as is
+    Compatible,
+    /// Plot can't be used by this version of the farmer and needs to be wiped and replotted
+    RequiresReplotting {
+        /// Why plot can't be used
+        reason: String,
+    },
+    /// Plot was created by a newer version of the farmer, farmer needs to be upgraded
+    RequiresNewerFarmer {
+        /// Version of the plot format found on disk
+        version: u8,
+    },
+    /// Plot files can't be read, so compatibility could not be determined
+    Unknown {
+        /// I/O error that prevented the check
+        error: String,
+    },
+}
+
+impl fmt::Display for PlotCompatibility {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFound => write!(f, "no plot found, it will be created"),
+            Self::Compatible => write!(f, "compatible"),
+            Self::RequiresReplotting { reason } => {
+                write!(
+                    f,
+                    "requires replotting ({reason}), wipe the farm and plot again"
+                )
+            }
+            Self::RequiresNewerFarmer { version } => {
+                write!(
+                    f,
+                    "plot format version {version} was created by a newer farmer, upgrade the \
+                    farmer or wipe the farm and plot again"
+                )
+            }
+            Self::Unknown { error } => {
+                write!(f, "could not determine compatibility ({error})")
+            }
+        }
+    }
+}
+
 #[derive(Debug, Encode, Decode)]
 struct PlotMetadataHeader {
     version: u8,
@@ -400,10 +464,13 @@ pub struct SingleDiskPlot {
     /// Metadata of all sectors plotted so far
     sectors_metadata: Arc<RwLock<Vec<SectorMetadata>>>,
     pieces_in_sector: u16,
+    sector_size: usize,
+    total_sectors_count: SectorIndex,
     span: Span,
     tasks: FuturesUnordered<BackgroundTask>,
     handlers: Arc<Handlers>,
     piece_reader: PieceReader,
+    kzg: Kzg,
     _plotting_join_handle: JoinOnDrop,
     _farming_join_handle: JoinOnDrop,
     _reading_join_handle: JoinOnDrop,
@@ -760,6 +827,7 @@ impl SingleDiskPlot {
                 }
 
                 let handle = handle.clone();
+                let kzg = kzg.clone();
                 let erasure_coding = erasure_coding.clone();
                 let handlers = Arc::clone(&handlers);
                 let modifying_sector_index = Arc::clone(&modifying_sector_index);
@@ -847,10 +915,13 @@ impl SingleDiskPlot {
             single_disk_plot_info,
             sectors_metadata,
             pieces_in_sector,
+            sector_size,
+            total_sectors_count: target_sector_count,
             span,
             tasks,
             handlers,
             piece_reader,
+            kzg,
             _plotting_join_handle: JoinOnDrop::new(plotting_join_handle),
             _farming_join_handle: JoinOnDrop::new(farming_join_handle),
             _reading_join_handle: JoinOnDrop::new(reading_join_handle),
@@ -879,16 +950,171 @@ impl SingleDiskPlot {
         }
     }
 
+    /// Read plot metadata from disk without opening the plot, for offline inspection.
+    ///
+    /// Metadata file that doesn't exist yet is treated as plot without any sectors plotted.
+    pub fn read_metadata(directory: &Path) -> Result<SingleDiskPlotMetadata, SingleDiskPlotError> {
+        let plot_file_size = match fs::metadata(directory.join(Self::PLOT_FILE)) {
+            Ok(metadata) => metadata.len(),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => 0,
+            Err(error) => {
+                return Err(error.into());
+            }
+        };
+
+        let metadata_file = match OpenOptions::new()
+            .read(true)
+            .open(directory.join(Self::METADATA_FILE))
+        {
+            Ok(metadata_file) => metadata_file,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {
+                return Ok(SingleDiskPlotMetadata {
+                    version: Self::SUPPORTED_PLOT_VERSION,
+                    sectors_metadata: Vec::new(),
+                    metadata_file_size: 0,
+                    plot_file_size,
+                });
+            }
+            Err(error) => {
+                return Err(error.into());
+            }
+        };
+        let metadata_file_size = metadata_file.metadata()?.len();
+
+        let mut metadata_header_bytes = vec![0; PlotMetadataHeader::encoded_size()];
+        metadata_file.read_exact_at(&mut metadata_header_bytes, 0)?;
+        let metadata_header = PlotMetadataHeader::decode(&mut metadata_header_bytes.as_slice())
+            .map_err(SingleDiskPlotError::FailedToDecodeMetadataHeader)?;
+
+        if metadata_header.version != Self::SUPPORTED_PLOT_VERSION {
+            return Err(SingleDiskPlotError::UnexpectedMetadataVersion(
+                metadata_header.version,
+            ));
+        }
+
+        let sector_metadata_size = SectorMetadata::encoded_size();
+        let mut sectors_metadata_bytes =
+            vec![0; sector_metadata_size * usize::from(metadata_header.sector_count)];
+        metadata_file.read_exact_at(&mut sectors_metadata_bytes, RESERVED_PLOT_METADATA)?;
+
+        let sectors_metadata = sectors_metadata_bytes
+            .chunks_exact(sector_metadata_size)
+            .map(|mut sector_metadata_bytes| {
+                SectorMetadata::decode(&mut sector_metadata_bytes)
+                    .map_err(SingleDiskPlotError::FailedToDecodeSectorMetadata)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(SingleDiskPlotMetadata {
+            version: metadata_header.version,
+            sectors_metadata,
+            metadata_file_size,
+            plot_file_size,
+        })
+    }
+
+    /// Check whether plot in specified directory can be opened by this version of the farmer
+    /// without actually opening it.
+    pub fn check_compatibility(directory: &Path) -> PlotCompatibility {
+        match SingleDiskPlotInfo::load_from(directory) {
+            Ok(Some(SingleDiskPlotInfo::V0 { .. })) => {
+                // Supported info version
+            }
+            Ok(None) => {
+                return PlotCompatibility::NotFound;
+            }
+            Err(error) if error.kind() == io::ErrorKind::InvalidData => {
+                return PlotCompatibility::RequiresReplotting {
+                    reason: format!(
+                        "failed to decode {}: {error}",
+                        SingleDiskPlotInfo::FILE_NAME
+                    ),
+                };
+            }
+            Err(error) => {
+                return PlotCompatibility::Unknown {
+                    error: format!("failed to read {}: {error}", SingleDiskPlotInfo::FILE_NAME),
+                };
+            }
+        }
+
+        let metadata_file = match OpenOptions::new()
+            .read(true)
+            .open(directory.join(Self::METADATA_FILE))
+        {
+            Ok(metadata_file) => metadata_file,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {
+                // Metadata file will be created on start
+                return PlotCompatibility::Compatible;
+            }
+            Err(error) => {
+                return PlotCompatibility::Unknown {
+                    error: format!("failed to open {}: {error}", Self::METADATA_FILE),
+                };
+            }
+        };
+
+        let mut metadata_header_bytes = vec![0; PlotMetadataHeader::encoded_size()];
+        match metadata_file.read_exact_at(&mut metadata_header_bytes, 0) {
+            Ok(()) => {}
+            Err(error) if error.kind() == io::ErrorKind::UnexpectedEof => {
+                return PlotCompatibility::RequiresReplotting {
+                    reason: format!("{} is truncated", Self::METADATA_FILE),
+                };
+            }
+            Err(error) => {
+                return PlotCompatibility::Unknown {
+                    error: format!("failed to read {} header: {error}", Self::METADATA_FILE),
+                };
+            }
+        }
+        let metadata_header =
+            match PlotMetadataHeader::decode(&mut metadata_header_bytes.as_slice()) {
+                Ok(metadata_header) => metadata_header,
+                Err(error) => {
+                    return PlotCompatibility::RequiresReplotting {
+                        reason: format!("failed to decode {} header: {error}", Self::METADATA_FILE),
+                    };
+                }
+            };
+
+        let version = metadata_header.version;
+        if version == Self::SUPPORTED_PLOT_VERSION {
+            PlotCompatibility::Compatible
+        } else if version > Self::SUPPORTED_PLOT_VERSION {
+            PlotCompatibility::RequiresNewerFarmer { version }
+        } else {
+            PlotCompatibility::RequiresReplotting {
+                reason: format!("plot format version {version} is no longer supported"),
+            }
+        }
+    }
+
     /// ID of this farm
     pub fn id(&self) -> &SingleDiskPlotId {
         self.single_disk_plot_info.id()
     }
 
+    /// Public key of this farm
+    pub fn public_key(&self) -> &PublicKey {
+        self.single_disk_plot_info.public_key()
+    }
+
     /// Number of sectors successfully plotted so far
     pub fn plotted_sectors_count(&self) -> usize {
         self.sectors_metadata.read().len()
     }
 
+    /// Size of one sector in bytes
+    pub fn sector_size(&self) -> usize {
+        self.sector_size
+    }
+
+    /// Total number of sectors that will be plotted once plotting is finished
+    pub fn total_sectors_count(&self) -> SectorIndex {
+        self.total_sectors_count
+    }
+
     /// Read information about sectors plotted so far
     pub fn plotted_sectors(
         &self,
@@ -928,6 +1154,16 @@ impl SingleDiskPlot {
         self.piece_reader.clone()
     }
 
+    /// Get scrubber to check plotted sectors for corruption later
+    pub fn scrubber(&self) -> PlotScrubber {
+        PlotScrubber {
+            piece_reader: self.piece_reader.clone(),
+            sectors_metadata: Arc::clone(&self.sectors_metadata),
+            pieces_in_sector: self.pieces_in_sector,
+            kzg: self.kzg.clone(),
+        }
+    }
+
     /// Subscribe to sector plotting notification
     ///
     /// Plotting permit is given such that it can be dropped later by the implementation is
@@ -983,16 +1219,7 @@ impl SingleDiskPlot {
             }
         }
 
-        {
-            let plot = directory.join(Self::PLOT_FILE);
-            info!("Deleting plot file at {}", plot.display());
-            fs::remove_file(plot)?;
-        }
-        {
-            let metadata = directory.join(Self::METADATA_FILE);
-            info!("Deleting metadata file at {}", metadata.display());
-            fs::remove_file(metadata)?;
-        }
+        Self::wipe_plot(directory)?;
         // TODO: Identity should be able to wipe itself instead of assuming a specific file name
         //  here
         {
@@ -1007,4 +1234,35 @@ impl SingleDiskPlot {
         );
         fs::remove_file(single_disk_plot_info_path)
     }
+
+    /// Wipe plot and its metadata, identity and plot info are preserved, such that plot will be
+    /// re-created from scratch with the same identity on next start
+    pub fn wipe_plot(directory: &Path) -> io::Result<()> {
+        {
+            let plot = directory.join(Self::PLOT_FILE);
+            info!("Deleting plot file at {}", plot.display());
+            remove_file_if_exists(&plot)?;
+        }
+
+        Self::wipe_metadata(directory)
+    }
+
+    /// Wipe plot metadata only, plot file is preserved, but since information about plotted
+    /// sectors is lost, plotting will start from the first sector on next start
+    pub fn wipe_metadata(directory: &Path) -> io::Result<()> {
+        let metadata = directory.join(Self::METADATA_FILE);
+        info!("Deleting metadata file at {}", metadata.display());
+        remove_file_if_exists(&metadata)
+    }
+}
+
+fn remove_file_if_exists(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Ok(()) => Ok(()),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => {
+            debug!("File {} doesn't exist, nothing to delete", path.display());
+            Ok(())
+        }
+        Err(error) => Err(error),
+    }
 }
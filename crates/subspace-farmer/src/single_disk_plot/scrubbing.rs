@@ -0,0 +1,105 @@
+use crate::single_disk_plot::piece_reader::PieceReader;
+use parking_lot::RwLock;
+use std::sync::Arc;
+use subspace_core_primitives::crypto::kzg::Kzg;
+use subspace_core_primitives::crypto::Scalar;
+use subspace_core_primitives::{Piece, PieceOffset, SectorIndex};
+use subspace_farmer_components::sector::SectorMetadata;
+use tracing::{info, warn};
+
+/// Result of plot scrubbing
+#[derive(Debug, Default, Clone)]
+pub struct ScrubReport {
+    /// Number of sectors that were checked
+    pub checked_sectors: usize,
+    /// Sectors with pieces that can't be read back or don't match their record commitments
+    pub corrupted_sectors: Vec<SectorIndex>,
+}
+
+/// Reads plotted sectors back and checks that every piece matches its record commitment, such that
+/// plots affected by disk corruption can be discovered before they cost farming rewards.
+#[derive(Debug, Clone)]
+pub struct PlotScrubber {
+    pub(super) piece_reader: PieceReader,
+    pub(super) sectors_metadata: Arc<RwLock<Vec<SectorMetadata>>>,
+    pub(super) pieces_in_sector: u16,
+    pub(super) kzg: Kzg,
+}
+
+impl PlotScrubber {
+    /// Check all sectors plotted so far.
+    ///
+    /// NOTE: Sector that is being replotted during scrubbing can't be read and will be reported as
+    /// corrupted.
+    pub async fn scrub(&self) -> ScrubReport {
+        let sector_count = self.sectors_metadata.read().len() as SectorIndex;
+        let mut piece_reader = self.piece_reader.clone();
+        let mut report = ScrubReport::default();
+
+        info!(%sector_count, "Scrubbing started");
+
+        for sector_index in 0..sector_count {
+            for piece_offset in (PieceOffset::ZERO..).take(usize::from(self.pieces_in_sector)) {
+                let valid = match piece_reader.read_piece(sector_index, piece_offset).await {
+                    Some(piece) => {
+                        let kzg = self.kzg.clone();
+                        // Commitment creation is CPU-intensive
+                        tokio::task::spawn_blocking(move || is_record_valid(&kzg, &piece))
+                            .await
+                            .unwrap_or_default()
+                    }
+                    None => false,
+                };
+
+                if !valid {
+                    warn!(%sector_index, %piece_offset, "Corrupted piece found during scrubbing");
+                    report.corrupted_sectors.push(sector_index);
+                    break;
+                }
+            }
+
+            report.checked_sectors += 1;
+        }
+
+        info!(
+            checked_sectors = %report.checked_sectors,
+            corrupted_sectors = %report.corrupted_sectors.len(),
+            "Scrubbing finished"
+        );
+
+        report
+    }
+}
+
+/// Check that record stored in the piece matches record commitment stored alongside
+fn is_record_valid(kzg: &Kzg, piece: &Piece) -> bool {
+    let (record, commitment, _witness) = piece.split();
+
+    let mut scalars = Vec::with_capacity(record.len().next_power_of_two());
+
+    for record_chunk in record.iter() {
+        match Scalar::try_from(record_chunk) {
+            Ok(scalar) => {
+                scalars.push(scalar);
+            }
+            _ => {
+                return false;
+            }
+        }
+    }
+
+    // Number of scalars for KZG must be a power of two elements
+    scalars.resize(scalars.capacity(), Scalar::default());
+
+    let polynomial = match kzg.poly(&scalars) {
+        Ok(polynomial) => polynomial,
+        _ => {
+            return false;
+        }
+    };
+
+    kzg.commit(&polynomial)
+        .map(|commitment| commitment.to_bytes())
+        .as_ref()
+        == Ok(commitment)
+}
@@ -0,0 +1,321 @@
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Thresholds used to decide whether farmer is healthy
+#[derive(Debug, Copy, Clone)]
+pub struct HealthThresholds {
+    /// Farmer is unhealthy if no audit was done for this long (audit happens every slot)
+    pub max_audit_age: Duration,
+    /// Farmer is degraded if no segment was archived for this long
+    pub max_archived_segment_age: Duration,
+    /// Farmer is degraded if it is connected to fewer DSN peers than this
+    pub min_connected_peers: usize,
+}
+
+impl Default for HealthThresholds {
+    fn default() -> Self {
+        Self {
+            max_audit_age: Duration::from_secs(60),
+            max_archived_segment_age: Duration::from_secs(3600),
+            min_connected_peers: 1,
+        }
+    }
+}
+
+/// Overall health state of the farmer, ordered from best to worst
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum HealthState {
+    /// Everything works as expected
+    Healthy,
+    /// Farmer works, but some of the non-critical subsystems have issues
+    Degraded,
+    /// Farmer doesn't work properly and likely needs to be restarted
+    Unhealthy,
+}
+
+/// Number of recently plotted sectors used to estimate plotting rate
+const PLOTTING_RATE_WINDOW: usize = 10;
+
+/// Plotting and farming status of a single farm
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FarmStatus {
+    /// Index of the farm, the same as `disk_farm_index` in logs
+    pub farm_index: usize,
+    /// Size of one sector in bytes
+    pub sector_size: u64,
+    /// Total number of sectors in the farm
+    pub total_sectors: u64,
+    /// Number of sectors plotted so far
+    pub plotted_sectors: u64,
+    /// Plotting rate in sectors per hour based on recently plotted sectors, `None` if there is not
+    /// enough data yet
+    pub sectors_per_hour: Option<f64>,
+    /// Estimated time to finish plotting in seconds based on plotting rate, `None` if plotting
+    /// rate is not known yet
+    pub eta_secs: Option<u64>,
+    /// Number of solutions found since start
+    pub solutions: u64,
+}
+
+#[derive(Debug)]
+struct FarmState {
+    sector_size: u64,
+    total_sectors: u64,
+    plotted_sectors: u64,
+    solutions: u64,
+    recently_plotted: VecDeque<Instant>,
+}
+
+impl FarmState {
+    fn status(&self, farm_index: usize) -> FarmStatus {
+        let sectors_per_hour = match (self.recently_plotted.front(), self.recently_plotted.back()) {
+            (Some(first), Some(last)) if first != last => {
+                let sectors = (self.recently_plotted.len() - 1) as f64;
+                Some(sectors * 3600.0 / last.duration_since(*first).as_secs_f64())
+            }
+            _ => None,
+        };
+
+        let remaining_sectors = self.total_sectors.saturating_sub(self.plotted_sectors);
+        let eta_secs = if remaining_sectors == 0 {
+            Some(0)
+        } else {
+            sectors_per_hour.map(|sectors_per_hour| {
+                (remaining_sectors as f64 * 3600.0 / sectors_per_hour) as u64
+            })
+        };
+
+        FarmStatus {
+            farm_index,
+            sector_size: self.sector_size,
+            total_sectors: self.total_sectors,
+            plotted_sectors: self.plotted_sectors,
+            sectors_per_hour,
+            eta_secs,
+            solutions: self.solutions,
+        }
+    }
+}
+
+/// Health report of the farmer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthReport {
+    /// Overall health state
+    pub state: HealthState,
+    /// Human-readable descriptions of detected issues
+    pub issues: Vec<String>,
+    /// Whether node RPC is reachable
+    pub node_rpc_available: bool,
+    /// Seconds since last audit, `None` if there were no audits yet
+    pub last_audit_secs_ago: Option<u64>,
+    /// Seconds since last archived segment notification, `None` if there were none yet
+    pub last_archived_segment_secs_ago: Option<u64>,
+    /// Number of established DSN peer connections
+    pub connected_peers: usize,
+    /// Status of individual farms in the order of their indices
+    pub farms: Vec<FarmStatus>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    thresholds: HealthThresholds,
+    started_at: Instant,
+    last_audit: Mutex<Option<Instant>>,
+    last_archived_segment: Mutex<Option<Instant>>,
+    connected_peers: AtomicUsize,
+    farms: Mutex<BTreeMap<usize, FarmState>>,
+}
+
+/// Tracks events that are used to determine farmer health
+#[derive(Debug, Clone)]
+pub struct FarmerHealth {
+    inner: Arc<Inner>,
+}
+
+impl FarmerHealth {
+    /// Create new instance
+    pub fn new(thresholds: HealthThresholds) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                thresholds,
+                started_at: Instant::now(),
+                last_audit: Mutex::default(),
+                last_archived_segment: Mutex::default(),
+                connected_peers: AtomicUsize::default(),
+                farms: Mutex::default(),
+            }),
+        }
+    }
+
+    /// Add farm to track, `farm_index` is used for farm-specific events afterwards
+    pub fn add_farm(
+        &self,
+        farm_index: usize,
+        sector_size: u64,
+        total_sectors: u64,
+        plotted_sectors: u64,
+    ) {
+        self.inner.farms.lock().insert(
+            farm_index,
+            FarmState {
+                sector_size,
+                total_sectors,
+                plotted_sectors,
+                solutions: 0,
+                recently_plotted: VecDeque::with_capacity(PLOTTING_RATE_WINDOW),
+            },
+        );
+    }
+
+    /// Stop tracking farm that is no longer used
+    pub fn remove_farm(&self, farm_index: usize) {
+        self.inner.farms.lock().remove(&farm_index);
+    }
+
+    /// Record plotted sector, `replotted` is `true` when sector replaced previously plotted sector
+    pub fn on_sector_plotted(&self, farm_index: usize, replotted: bool) {
+        self.on_sector_plotted_at(farm_index, replotted, Instant::now());
+    }
+
+    pub(crate) fn on_sector_plotted_at(&self, farm_index: usize, replotted: bool, now: Instant) {
+        if let Some(farm) = self.inner.farms.lock().get_mut(&farm_index) {
+            if !replotted {
+                farm.plotted_sectors += 1;
+            }
+            if farm.recently_plotted.len() == PLOTTING_RATE_WINDOW {
+                farm.recently_plotted.pop_front();
+            }
+            farm.recently_plotted.push_back(now);
+        }
+    }
+
+    /// Record successful audit of the farm and number of solutions found
+    pub fn on_audit(&self, farm_index: usize, solutions: usize) {
+        self.on_audit_at(farm_index, solutions, Instant::now());
+    }
+
+    pub(crate) fn on_audit_at(&self, farm_index: usize, solutions: usize, now: Instant) {
+        self.inner.last_audit.lock().replace(now);
+        if let Some(farm) = self.inner.farms.lock().get_mut(&farm_index) {
+            farm.solutions += solutions as u64;
+        }
+    }
+
+    /// Record archived segment notification
+    pub fn on_archived_segment(&self) {
+        self.on_archived_segment_at(Instant::now());
+    }
+
+    pub(crate) fn on_archived_segment_at(&self, now: Instant) {
+        self.inner.last_archived_segment.lock().replace(now);
+    }
+
+    /// Update number of established DSN peer connections
+    pub fn set_connected_peers(&self, connected_peers: usize) {
+        self.inner
+            .connected_peers
+            .store(connected_peers, Ordering::Relaxed);
+    }
+
+    /// Status of the farm with specified index
+    pub fn farm(&self, farm_index: usize) -> Option<FarmStatus> {
+        self.inner
+            .farms
+            .lock()
+            .get(&farm_index)
+            .map(|farm| farm.status(farm_index))
+    }
+
+    /// Status of individual farms in the order of their indices
+    pub fn farms(&self) -> Vec<FarmStatus> {
+        self.inner
+            .farms
+            .lock()
+            .iter()
+            .map(|(&farm_index, farm)| farm.status(farm_index))
+            .collect()
+    }
+
+    /// Check health of the farmer, node RPC availability is checked by the caller
+    pub fn check(&self, node_rpc_available: bool) -> HealthReport {
+        self.check_at(node_rpc_available, Instant::now())
+    }
+
+    /// Same as [`Self::check()`], but with ages of events measured relatively to `now`
+    pub(crate) fn check_at(&self, node_rpc_available: bool, now: Instant) -> HealthReport {
+        let Inner {
+            thresholds,
+            started_at,
+            last_audit,
+            last_archived_segment,
+            connected_peers,
+            farms,
+        } = self.inner.as_ref();
+
+        let farms = farms
+            .lock()
+            .iter()
+            .map(|(&farm_index, farm)| farm.status(farm_index))
+            .collect();
+        let last_audit = *last_audit.lock();
+        let last_archived_segment = *last_archived_segment.lock();
+        let connected_peers = connected_peers.load(Ordering::Relaxed);
+
+        let mut state = HealthState::Healthy;
+        let mut issues = Vec::new();
+
+        if !node_rpc_available {
+            state = HealthState::Unhealthy;
+            issues.push("Node RPC is not available".to_string());
+        }
+
+        // Before first event happened measure age from start
+        let audit_age = now.saturating_duration_since(last_audit.unwrap_or(*started_at));
+        if audit_age > thresholds.max_audit_age {
+            state = HealthState::Unhealthy;
+            issues.push(format!(
+                "No audits for {}s, expected at least every {}s",
+                audit_age.as_secs(),
+                thresholds.max_audit_age.as_secs()
+            ));
+        }
+
+        let archived_segment_age =
+            now.saturating_duration_since(last_archived_segment.unwrap_or(*started_at));
+        if archived_segment_age > thresholds.max_archived_segment_age {
+            state = state.max(HealthState::Degraded);
+            issues.push(format!(
+                "No archived segments for {}s, expected at least every {}s",
+                archived_segment_age.as_secs(),
+                thresholds.max_archived_segment_age.as_secs()
+            ));
+        }
+
+        if connected_peers < thresholds.min_connected_peers {
+            state = state.max(HealthState::Degraded);
+            issues.push(format!(
+                "Connected to {connected_peers} DSN peers, expected at least {}",
+                thresholds.min_connected_peers
+            ));
+        }
+
+        HealthReport {
+            state,
+            issues,
+            node_rpc_available,
+            last_audit_secs_ago: last_audit
+                .map(|instant| now.saturating_duration_since(instant).as_secs()),
+            last_archived_segment_secs_ago: last_archived_segment
+                .map(|instant| now.saturating_duration_since(instant).as_secs()),
+            connected_peers,
+            farms,
+        }
+    }
+}
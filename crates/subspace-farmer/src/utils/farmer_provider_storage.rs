@@ -10,7 +10,7 @@ use subspace_networking::libp2p::kad::ProviderRecord;
 use subspace_networking::libp2p::multihash::Multihash;
 use subspace_networking::libp2p::PeerId;
 use subspace_networking::utils::multihash::{MultihashCode, ToMultihash};
-use subspace_networking::ProviderStorage;
+use subspace_networking::{ProviderStorage, ProviderStorageStats};
 use tracing::trace;
 
 #[derive(Clone)]
@@ -163,4 +163,9 @@ where
         self.persistent_provider_storage
             .remove_provider(key, peer_id);
     }
+
+    fn stats(&self) -> ProviderStorageStats {
+        // Implicit provider records are not stored
+        self.persistent_provider_storage.stats()
+    }
 }
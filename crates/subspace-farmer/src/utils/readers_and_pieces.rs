@@ -3,12 +3,13 @@ use crate::utils::archival_storage_pieces::ArchivalStoragePieces;
 use std::collections::hash_map::Entry;
 use std::collections::HashMap;
 use std::future::Future;
-use subspace_core_primitives::{Piece, PieceIndexHash, PieceOffset, SectorIndex};
+use subspace_core_primitives::{Piece, PieceIndex, PieceIndexHash, PieceOffset, SectorIndex};
 use subspace_farmer_components::plotting::PlottedSector;
 use tracing::{trace, warn};
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 struct PieceDetails {
+    piece_index: PieceIndex,
     disk_farm_index: u8,
     sector_index: SectorIndex,
     piece_offset: PieceOffset,
@@ -18,7 +19,8 @@ struct PieceDetails {
 /// it also maintains filter in given [`ArchivalStoragePieces`].
 #[derive(Debug)]
 pub struct ReadersAndPieces {
-    readers: Vec<PieceReader>,
+    /// Readers indexed by disk farm index, `None` for farms that were removed
+    readers: Vec<Option<PieceReader>>,
     pieces: HashMap<PieceIndexHash, Vec<PieceDetails>>,
     archival_storage_pieces: ArchivalStoragePieces,
 }
@@ -26,7 +28,7 @@ pub struct ReadersAndPieces {
 impl ReadersAndPieces {
     pub fn new(readers: Vec<PieceReader>, archival_storage_pieces: ArchivalStoragePieces) -> Self {
         Self {
-            readers,
+            readers: readers.into_iter().map(Some).collect(),
             pieces: HashMap::new(),
             archival_storage_pieces,
         }
@@ -58,7 +60,11 @@ impl ReadersAndPieces {
                 return None;
             }
         };
-        let mut reader = match self.readers.get(usize::from(piece_details.disk_farm_index)) {
+        let mut reader = match self
+            .readers
+            .get(usize::from(piece_details.disk_farm_index))
+            .and_then(Option::as_ref)
+        {
             Some(reader) => reader.clone(),
             None => {
                 warn!(?piece_index_hash, ?piece_details, "Plot offset is invalid");
@@ -80,6 +86,7 @@ impl ReadersAndPieces {
             (PieceOffset::ZERO..).zip(plotted_sector.piece_indexes.iter())
         {
             let piece_details = PieceDetails {
+                piece_index,
                 disk_farm_index,
                 sector_index: plotted_sector.sector_index,
                 piece_offset,
@@ -108,6 +115,7 @@ impl ReadersAndPieces {
             (PieceOffset::ZERO..).zip(plotted_sector.piece_indexes.iter())
         {
             let searching_piece_details = PieceDetails {
+                piece_index,
                 disk_farm_index,
                 sector_index: plotted_sector.sector_index,
                 piece_offset,
@@ -140,6 +148,45 @@ impl ReadersAndPieces {
         }
     }
 
+    /// Add reader of the farm that was started after this instance was created, its sectors are
+    /// added with [`Self::add_sector()`] afterwards
+    pub fn add_reader(&mut self, disk_farm_index: u8, reader: PieceReader) {
+        let disk_farm_index = usize::from(disk_farm_index);
+        if self.readers.len() <= disk_farm_index {
+            self.readers.resize_with(disk_farm_index + 1, || None);
+        }
+        self.readers[disk_farm_index].replace(reader);
+    }
+
+    /// Remove reader and all pieces of the farm, used when farm is stopped
+    pub fn remove_farm(&mut self, disk_farm_index: u8) {
+        if let Some(reader) = self.readers.get_mut(usize::from(disk_farm_index)) {
+            reader.take();
+        }
+
+        let mut deleted_piece_indices = Vec::new();
+
+        self.pieces.retain(|_piece_index_hash, piece_details| {
+            let maybe_piece_index = piece_details
+                .first()
+                .map(|piece_details| piece_details.piece_index);
+            piece_details.retain(|piece_details| piece_details.disk_farm_index != disk_farm_index);
+
+            // We do not store empty lists
+            if piece_details.is_empty() {
+                deleted_piece_indices.extend(maybe_piece_index);
+                false
+            } else {
+                true
+            }
+        });
+
+        if !deleted_piece_indices.is_empty() {
+            self.archival_storage_pieces
+                .delete_pieces(&deleted_piece_indices);
+        }
+    }
+
     pub fn piece_index_hashes(&self) -> impl Iterator<Item = &PieceIndexHash> {
         self.pieces.keys()
     }
@@ -1,5 +1,7 @@
+use crate::utils::farmer_health::{FarmerHealth, HealthState, HealthThresholds};
 use crate::utils::run_future_in_dedicated_thread;
 use std::future;
+use std::time::{Duration, Instant};
 
 #[tokio::test]
 async fn run_future_in_dedicated_thread_ready() {
@@ -25,3 +27,111 @@ async fn run_future_in_dedicated_thread_cancellation() {
         .unwrap(),
     );
 }
+
+#[test]
+fn farmer_health_states() {
+    let farmer_health = FarmerHealth::new(HealthThresholds {
+        max_audit_age: Duration::from_secs(60),
+        max_archived_segment_age: Duration::from_secs(60),
+        min_connected_peers: 2,
+    });
+
+    let farm_index = 0;
+    farmer_health.add_farm(farm_index, 1024, 10, 0);
+    farmer_health.on_audit(farm_index, 0);
+    farmer_health.on_archived_segment();
+    farmer_health.set_connected_peers(1);
+    // Not enough peers
+    let report = farmer_health.check(true);
+    assert_eq!(report.state, HealthState::Degraded);
+    assert_eq!(report.issues.len(), 1);
+
+    let report = farmer_health.check(false);
+    assert_eq!(report.state, HealthState::Unhealthy);
+    assert!(!report.node_rpc_available);
+}
+
+#[test]
+fn farmer_health_healthy() {
+    let farmer_health = FarmerHealth::new(HealthThresholds::default());
+
+    let farm_index = 0;
+    farmer_health.add_farm(farm_index, 1024, 10, 2);
+    farmer_health.on_audit(farm_index, 1);
+    farmer_health.on_sector_plotted(farm_index, false);
+    farmer_health.on_sector_plotted(farm_index, true);
+    farmer_health.on_archived_segment();
+    farmer_health.set_connected_peers(10);
+
+    let report = farmer_health.check(true);
+    assert_eq!(report.state, HealthState::Healthy);
+    assert!(report.issues.is_empty());
+    assert_eq!(report.connected_peers, 10);
+    assert_eq!(report.farms.len(), 1);
+    assert_eq!(report.farms[0].total_sectors, 10);
+    // Replotted sector doesn't increase number of plotted sectors
+    assert_eq!(report.farms[0].plotted_sectors, 3);
+    assert_eq!(report.farms[0].solutions, 1);
+    assert!(report.farms[0].sectors_per_hour.is_some());
+    assert!(report.farms[0].eta_secs.is_some());
+}
+
+#[test]
+fn farmer_health_event_age() {
+    let farmer_health = FarmerHealth::new(HealthThresholds {
+        max_audit_age: Duration::from_secs(60),
+        max_archived_segment_age: Duration::from_secs(3600),
+        min_connected_peers: 0,
+    });
+    let start = Instant::now();
+
+    let farm_index = 0;
+    farmer_health.add_farm(farm_index, 1024, 10, 0);
+    farmer_health.on_audit_at(farm_index, 0, start);
+    farmer_health.on_archived_segment_at(start);
+
+    let report = farmer_health.check_at(true, start + Duration::from_secs(30));
+    assert_eq!(report.state, HealthState::Healthy);
+    assert_eq!(report.last_audit_secs_ago, Some(30));
+
+    // Audits stopped, but segments are not expected yet
+    let report = farmer_health.check_at(true, start + Duration::from_secs(61));
+    assert_eq!(report.state, HealthState::Unhealthy);
+    assert_eq!(report.issues.len(), 1);
+
+    farmer_health.on_audit_at(farm_index, 0, start + Duration::from_secs(3601));
+    let report = farmer_health.check_at(true, start + Duration::from_secs(3601));
+    assert_eq!(report.state, HealthState::Degraded);
+    assert_eq!(report.last_archived_segment_secs_ago, Some(3601));
+}
+
+#[test]
+fn farmer_health_plotting_eta() {
+    let farmer_health = FarmerHealth::new(HealthThresholds::default());
+    let start = Instant::now();
+
+    let farm_index = 0;
+    farmer_health.add_farm(farm_index, 1024, 10, 0);
+    farmer_health.on_sector_plotted_at(farm_index, false, start);
+    // Rate is unknown until at least two sectors are plotted
+    let farm_status = farmer_health.farm(farm_index).unwrap();
+    assert_eq!(farm_status.sectors_per_hour, None);
+    assert_eq!(farm_status.eta_secs, None);
+
+    farmer_health.on_sector_plotted_at(farm_index, false, start + Duration::from_secs(1800));
+    farmer_health.on_sector_plotted_at(farm_index, false, start + Duration::from_secs(3600));
+    let farm_status = farmer_health.farm(farm_index).unwrap();
+    assert_eq!(farm_status.plotted_sectors, 3);
+    assert_eq!(farm_status.sectors_per_hour, Some(2.0));
+    // 7 remaining sectors at 2 sectors per hour
+    assert_eq!(farm_status.eta_secs, Some(7 * 1800));
+
+    farmer_health.add_farm(1, 1024, 0, 0);
+    let farm_status = farmer_health.farm(1).unwrap();
+    assert_eq!(farm_status.eta_secs, Some(0));
+
+    farmer_health.remove_farm(farm_index);
+    let farms = farmer_health.farms();
+    assert_eq!(farms.len(), 1);
+    assert_eq!(farms[0].farm_index, 1);
+}
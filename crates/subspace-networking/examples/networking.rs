@@ -2,14 +2,30 @@
 
 use futures::channel::oneshot;
 use futures::StreamExt;
-use libp2p::gossipsub::Sha256Topic;
+use libp2p::gossipsub::MessageAcceptance;
 use libp2p::multiaddr::Protocol;
+use libp2p::PeerId;
+use parity_scale_codec::{Decode, Encode};
 use parking_lot::Mutex;
 use std::sync::Arc;
 use std::time::Duration;
-use subspace_networking::{BootstrappedNetworkingParameters, Config};
+use subspace_networking::{BootstrappedNetworkingParameters, Config, GossipTopic};
 
-const TOPIC: &str = "Foo";
+#[derive(Debug, Encode, Decode)]
+struct Greeting(String);
+
+impl GossipTopic for Greeting {
+    const TOPIC_NAME: &'static str = "Foo";
+    const LOG_TARGET: &'static str = "greeting";
+}
+
+fn validate_greeting(_peer_id: PeerId, greeting: &Greeting) -> MessageAcceptance {
+    if greeting.0.is_empty() {
+        MessageAcceptance::Reject
+    } else {
+        MessageAcceptance::Accept
+    }
+}
 
 #[tokio::main]
 async fn main() {
@@ -19,7 +35,8 @@ async fn main() {
         listen_on: vec!["/ip4/0.0.0.0/tcp/0".parse().unwrap()],
         allow_non_global_addresses_in_dht: true,
         ..Config::default()
-    };
+    }
+    .with_gossip_topic(validate_greeting);
     let (node_1, mut node_runner_1) = subspace_networking::create(config_1).unwrap();
 
     println!("Node 1 ID is {}", node_1.id());
@@ -45,7 +62,7 @@ async fn main() {
     let node_1_addr = node_1_address_receiver.await.unwrap();
     drop(on_new_listener_handler);
 
-    let mut subscription = node_1.subscribe(Sha256Topic::new(TOPIC)).await.unwrap();
+    let mut subscription = node_1.subscribe_to_topic::<Greeting>().await.unwrap();
 
     let config_2 = Config {
         networking_parameters_registry: BootstrappedNetworkingParameters::new(vec![
@@ -55,7 +72,8 @@ async fn main() {
         listen_on: vec!["/ip4/0.0.0.0/tcp/0".parse().unwrap()],
         allow_non_global_addresses_in_dht: true,
         ..Config::default()
-    };
+    }
+    .with_gossip_topic(validate_greeting);
 
     let (node_2, mut node_runner_2) = subspace_networking::create(config_2).unwrap();
 
@@ -69,7 +87,7 @@ async fn main() {
 
     tokio::spawn(async move {
         node_2
-            .publish(Sha256Topic::new(TOPIC), "hello".to_string().into_bytes())
+            .publish_to_topic(&Greeting("hello".to_string()))
             .await
             .unwrap();
     });
@@ -77,7 +95,7 @@ async fn main() {
     tokio::time::sleep(Duration::from_secs(1)).await;
 
     let message = subscription.next().await.unwrap();
-    println!("Got message: {}", String::from_utf8_lossy(&message));
+    println!("Got message: {}", message.0);
 
     tokio::time::sleep(Duration::from_secs(5)).await;
 }
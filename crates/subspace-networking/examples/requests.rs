@@ -1,9 +1,7 @@
 use futures::channel::oneshot;
-use libp2p::metrics::Metrics;
 use libp2p::multiaddr::Protocol;
 use parity_scale_codec::{Decode, Encode};
 use parking_lot::Mutex;
-use prometheus_client::registry::Registry;
 use std::sync::Arc;
 use std::time::Duration;
 use subspace_networking::{
@@ -29,30 +27,26 @@ struct ExampleResponse;
 async fn main() {
     tracing_subscriber::fmt::init();
 
-    let mut metric_registry = Registry::default();
-    let metrics = Metrics::new(&mut metric_registry);
-
     let config_1 = Config {
         listen_on: vec!["/ip4/0.0.0.0/tcp/0".parse().unwrap()],
         allow_non_global_addresses_in_dht: true,
-        request_response_protocols: vec![GenericRequestHandler::create(
-            |_, &ExampleRequest| async {
-                sleep(Duration::from_secs(2)).await;
-
-                println!("Request handler for request");
-                Some(ExampleResponse)
-            },
-        )],
-        metrics: Some(metrics),
+        enable_metrics: true,
         ..Config::default()
-    };
+    }
+    .with_request_response_protocol(|_, &ExampleRequest| async {
+        sleep(Duration::from_secs(2)).await;
+
+        println!("Request handler for request");
+        Some(ExampleResponse)
+    });
     let (node_1, mut node_runner_1) = subspace_networking::create(config_1).unwrap();
 
     // Init prometheus
     let prometheus_metrics_server_address = "127.0.0.1:63000".parse().unwrap();
+    let networking_metrics = node_1.metrics().cloned().expect("Metrics are enabled; qed");
     tokio::task::spawn(async move {
         if let Err(err) =
-            start_prometheus_metrics_server(prometheus_metrics_server_address, metric_registry)
+            start_prometheus_metrics_server(prometheus_metrics_server_address, networking_metrics)
                 .await
         {
             error!(
@@ -0,0 +1,382 @@
+//! Bandwidth accounting per protocol and per peer.
+//!
+//! Every connection muxer is wrapped in [`BandwidthMuxer`], which counts bytes read from and
+//! written to substreams of the connection. Substreams are attributed to the protocol negotiated
+//! with multistream-select by inspecting the bytes written by the listener side of the substream
+//! (the header followed by either `na` or the accepted protocol name), such that all protocols
+//! (Kademlia, request-response protocols, gossipsub, etc.) are accounted for without any
+//! cooperation from the protocols themselves.
+
+#[cfg(test)]
+mod tests;
+
+use futures::{AsyncRead, AsyncWrite};
+use libp2p::core::muxing::{StreamMuxer, StreamMuxerBox, StreamMuxerEvent, SubstreamBox};
+use libp2p::PeerId;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::io;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+/// Protocol name substreams are attributed to when negotiated protocol is not known.
+const UNKNOWN_PROTOCOL: &str = "unknown";
+/// Upper bound on the number of bytes inspected to find out negotiated protocol.
+const MAX_NEGOTIATION_BYTES: usize = 1024;
+/// Upper bound on the number of distinct protocols tracked, protocol name of outbound substreams
+/// comes from remote peer, so this prevents malicious peers from bloating the collection.
+const MAX_TRACKED_PROTOCOLS: usize = 64;
+const MULTISTREAM_HEADER: &[u8] = b"/multistream/1.0.0";
+const MULTISTREAM_NOT_AVAILABLE: &[u8] = b"na";
+
+/// Number of bytes received and sent.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub struct BandwidthUsage {
+    /// Number of bytes received.
+    pub inbound_bytes: u64,
+    /// Number of bytes sent.
+    pub outbound_bytes: u64,
+}
+
+#[derive(Debug, Default)]
+struct BandwidthCounters {
+    inbound_bytes: AtomicU64,
+    outbound_bytes: AtomicU64,
+}
+
+impl BandwidthCounters {
+    fn add(&self, inbound_bytes: u64, outbound_bytes: u64) {
+        self.inbound_bytes
+            .fetch_add(inbound_bytes, Ordering::Relaxed);
+        self.outbound_bytes
+            .fetch_add(outbound_bytes, Ordering::Relaxed);
+    }
+
+    fn usage(&self) -> BandwidthUsage {
+        BandwidthUsage {
+            inbound_bytes: self.inbound_bytes.load(Ordering::Relaxed),
+            outbound_bytes: self.outbound_bytes.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct PeerBandwidth {
+    counters: BandwidthCounters,
+    /// Number of established connections with the peer
+    connections: AtomicUsize,
+}
+
+/// Tracks bandwidth usage of all connections per protocol and per peer.
+#[derive(Debug, Default)]
+pub(crate) struct BandwidthTracker {
+    protocols: Mutex<HashMap<String, Arc<BandwidthCounters>>>,
+    peers: Mutex<HashMap<PeerId, Arc<PeerBandwidth>>>,
+}
+
+impl BandwidthTracker {
+    /// Bandwidth usage of each protocol since the start of the node.
+    pub(crate) fn protocols(&self) -> HashMap<String, BandwidthUsage> {
+        self.protocols
+            .lock()
+            .iter()
+            .map(|(protocol_name, counters)| (protocol_name.clone(), counters.usage()))
+            .collect()
+    }
+
+    /// Bandwidth usage of each currently connected peer since connection establishment.
+    pub(crate) fn peers(&self) -> HashMap<PeerId, BandwidthUsage> {
+        self.peers
+            .lock()
+            .iter()
+            .map(|(peer_id, peer_bandwidth)| (*peer_id, peer_bandwidth.counters.usage()))
+            .collect()
+    }
+
+    fn protocol_counters(&self, protocol_name: &str) -> Arc<BandwidthCounters> {
+        let mut protocols = self.protocols.lock();
+
+        if let Some(counters) = protocols.get(protocol_name) {
+            return Arc::clone(counters);
+        }
+
+        let protocol_name = if protocols.len() < MAX_TRACKED_PROTOCOLS {
+            protocol_name
+        } else {
+            UNKNOWN_PROTOCOL
+        };
+
+        Arc::clone(protocols.entry(protocol_name.to_string()).or_default())
+    }
+
+    fn connection_established(&self, peer_id: PeerId) -> Arc<PeerBandwidth> {
+        let mut peers = self.peers.lock();
+        let peer_bandwidth = peers.entry(peer_id).or_default();
+        peer_bandwidth.connections.fetch_add(1, Ordering::Relaxed);
+
+        Arc::clone(peer_bandwidth)
+    }
+
+    fn connection_closed(&self, peer_id: &PeerId) {
+        let mut peers = self.peers.lock();
+
+        if let Some(peer_bandwidth) = peers.get(peer_id) {
+            if peer_bandwidth.connections.fetch_sub(1, Ordering::Relaxed) == 1 {
+                peers.remove(peer_id);
+            }
+        }
+    }
+}
+
+/// Result of inspecting bytes written by the listener side of multistream-select negotiation.
+#[derive(Debug, Eq, PartialEq)]
+pub(crate) enum NegotiatedProtocol {
+    /// More bytes are needed to find out the protocol.
+    Incomplete,
+    /// Protocol was accepted by the listener.
+    Accepted(String),
+    /// Bytes do not look like multistream-select negotiation.
+    Invalid,
+}
+
+/// Find protocol accepted by the listener from the bytes it has written so far.
+pub(crate) fn negotiated_protocol(mut listener_bytes: &[u8]) -> NegotiatedProtocol {
+    loop {
+        let (length, remaining) = match unsigned_varint::decode::usize(listener_bytes) {
+            Ok(result) => result,
+            Err(unsigned_varint::decode::Error::Insufficient) => {
+                return NegotiatedProtocol::Incomplete;
+            }
+            Err(_) => {
+                return NegotiatedProtocol::Invalid;
+            }
+        };
+
+        if length > MAX_NEGOTIATION_BYTES {
+            return NegotiatedProtocol::Invalid;
+        }
+        if remaining.len() < length {
+            return NegotiatedProtocol::Incomplete;
+        }
+
+        let (message, remaining) = remaining.split_at(length);
+        let Some(message) = message.strip_suffix(b"\n") else {
+            return NegotiatedProtocol::Invalid;
+        };
+
+        if message != MULTISTREAM_HEADER && message != MULTISTREAM_NOT_AVAILABLE {
+            return match String::from_utf8(message.to_vec()) {
+                Ok(protocol_name) => NegotiatedProtocol::Accepted(protocol_name),
+                Err(_) => NegotiatedProtocol::Invalid,
+            };
+        }
+
+        listener_bytes = remaining;
+    }
+}
+
+/// Muxer wrapper that accounts for bandwidth used by substreams of the connection.
+pub(crate) struct BandwidthMuxer {
+    inner: StreamMuxerBox,
+    peer_id: PeerId,
+    peer_bandwidth: Arc<PeerBandwidth>,
+    tracker: Arc<BandwidthTracker>,
+}
+
+impl Drop for BandwidthMuxer {
+    fn drop(&mut self) {
+        self.tracker.connection_closed(&self.peer_id);
+    }
+}
+
+impl BandwidthMuxer {
+    pub(crate) fn new(
+        inner: StreamMuxerBox,
+        peer_id: PeerId,
+        tracker: Arc<BandwidthTracker>,
+    ) -> Self {
+        Self {
+            inner,
+            peer_id,
+            peer_bandwidth: tracker.connection_established(peer_id),
+            tracker,
+        }
+    }
+
+    fn wrap_substream(&self, inner: SubstreamBox, inbound: bool) -> BandwidthSubstream {
+        BandwidthSubstream {
+            inner,
+            inbound,
+            peer_bandwidth: Arc::clone(&self.peer_bandwidth),
+            tracker: Arc::clone(&self.tracker),
+            protocol: SubstreamProtocol::Negotiating {
+                listener_bytes: Vec::new(),
+                inbound_bytes: 0,
+                outbound_bytes: 0,
+            },
+        }
+    }
+}
+
+impl StreamMuxer for BandwidthMuxer {
+    type Substream = BandwidthSubstream;
+    type Error = io::Error;
+
+    fn poll_inbound(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Self::Substream, Self::Error>> {
+        let this = self.get_mut();
+        let substream = futures::ready!(Pin::new(&mut this.inner).poll_inbound(cx))?;
+
+        Poll::Ready(Ok(this.wrap_substream(substream, true)))
+    }
+
+    fn poll_outbound(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Self::Substream, Self::Error>> {
+        let this = self.get_mut();
+        let substream = futures::ready!(Pin::new(&mut this.inner).poll_outbound(cx))?;
+
+        Poll::Ready(Ok(this.wrap_substream(substream, false)))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.get_mut().inner).poll_close(cx)
+    }
+
+    fn poll(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<StreamMuxerEvent, Self::Error>> {
+        Pin::new(&mut self.get_mut().inner).poll(cx)
+    }
+}
+
+enum SubstreamProtocol {
+    /// Protocol is not negotiated yet, bytes are accounted for once it is known.
+    Negotiating {
+        listener_bytes: Vec<u8>,
+        inbound_bytes: u64,
+        outbound_bytes: u64,
+    },
+    Known(Arc<BandwidthCounters>),
+}
+
+/// Substream wrapper that accounts for bandwidth used by the substream.
+pub(crate) struct BandwidthSubstream {
+    inner: SubstreamBox,
+    /// Whether substream was opened by remote peer, in which case local peer is the listener in
+    /// multistream-select negotiation.
+    inbound: bool,
+    peer_bandwidth: Arc<PeerBandwidth>,
+    tracker: Arc<BandwidthTracker>,
+    protocol: SubstreamProtocol,
+}
+
+impl Drop for BandwidthSubstream {
+    fn drop(&mut self) {
+        if matches!(self.protocol, SubstreamProtocol::Negotiating { .. }) {
+            self.resolve_protocol(UNKNOWN_PROTOCOL);
+        }
+    }
+}
+
+impl BandwidthSubstream {
+    fn record(&mut self, bytes: &[u8], read: bool) {
+        let length = bytes.len() as u64;
+        let (inbound, outbound) = if read { (length, 0) } else { (0, length) };
+
+        self.peer_bandwidth.counters.add(inbound, outbound);
+
+        let negotiated = match &mut self.protocol {
+            SubstreamProtocol::Known(counters) => {
+                counters.add(inbound, outbound);
+                return;
+            }
+            SubstreamProtocol::Negotiating {
+                listener_bytes,
+                inbound_bytes,
+                outbound_bytes,
+            } => {
+                *inbound_bytes += inbound;
+                *outbound_bytes += outbound;
+
+                // Local peer writes as the listener of inbound substreams and reads what remote
+                // listener has written for outbound substreams
+                if read == self.inbound {
+                    return;
+                }
+
+                let remaining = MAX_NEGOTIATION_BYTES.saturating_sub(listener_bytes.len());
+                listener_bytes.extend_from_slice(&bytes[..bytes.len().min(remaining)]);
+
+                negotiated_protocol(listener_bytes)
+            }
+        };
+
+        match negotiated {
+            NegotiatedProtocol::Incomplete => {}
+            NegotiatedProtocol::Accepted(protocol_name) => {
+                self.resolve_protocol(&protocol_name);
+            }
+            NegotiatedProtocol::Invalid => {
+                self.resolve_protocol(UNKNOWN_PROTOCOL);
+            }
+        }
+    }
+
+    fn resolve_protocol(&mut self, protocol_name: &str) {
+        let counters = self.tracker.protocol_counters(protocol_name);
+
+        if let SubstreamProtocol::Negotiating {
+            inbound_bytes,
+            outbound_bytes,
+            ..
+        } = &self.protocol
+        {
+            counters.add(*inbound_bytes, *outbound_bytes);
+        }
+
+        self.protocol = SubstreamProtocol::Known(counters);
+    }
+}
+
+impl AsyncRead for BandwidthSubstream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        let read = futures::ready!(Pin::new(&mut this.inner).poll_read(cx, buf))?;
+        this.record(&buf[..read], true);
+
+        Poll::Ready(Ok(read))
+    }
+}
+
+impl AsyncWrite for BandwidthSubstream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        let written = futures::ready!(Pin::new(&mut this.inner).poll_write(cx, buf))?;
+        this.record(&buf[..written], false);
+
+        Poll::Ready(Ok(written))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_flush(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_close(cx)
+    }
+}
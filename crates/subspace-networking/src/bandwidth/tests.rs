@@ -0,0 +1,61 @@
+use super::{negotiated_protocol, BandwidthTracker, NegotiatedProtocol};
+use libp2p::PeerId;
+
+fn message(content: &[u8]) -> Vec<u8> {
+    let mut buffer = unsigned_varint::encode::usize_buffer();
+    let mut message = unsigned_varint::encode::usize(content.len() + 1, &mut buffer).to_vec();
+    message.extend_from_slice(content);
+    message.push(b'\n');
+    message
+}
+
+#[test]
+fn negotiated_protocol_parsing() {
+    let header = message(b"/multistream/1.0.0");
+    let not_available = message(b"na");
+    let protocol = message(b"/subspace/kad/0.1.0");
+
+    assert_eq!(negotiated_protocol(&[]), NegotiatedProtocol::Incomplete);
+    assert_eq!(negotiated_protocol(&header), NegotiatedProtocol::Incomplete);
+
+    let mut listener_bytes = header.clone();
+    listener_bytes.extend_from_slice(&protocol[..protocol.len() - 1]);
+    assert_eq!(
+        negotiated_protocol(&listener_bytes),
+        NegotiatedProtocol::Incomplete
+    );
+
+    let mut listener_bytes = [header.clone(), not_available, protocol].concat();
+    // Payload of the protocol itself follows negotiation
+    listener_bytes.extend_from_slice(b"payload");
+    assert_eq!(
+        negotiated_protocol(&listener_bytes),
+        NegotiatedProtocol::Accepted("/subspace/kad/0.1.0".to_string())
+    );
+
+    assert_eq!(
+        negotiated_protocol(b"\x05hello"),
+        NegotiatedProtocol::Invalid
+    );
+}
+
+#[test]
+fn bandwidth_tracker_peers() {
+    let tracker = BandwidthTracker::default();
+    let peer_id = PeerId::random();
+
+    let peer_bandwidth = tracker.connection_established(peer_id);
+    tracker.connection_established(peer_id);
+    peer_bandwidth.counters.add(10, 20);
+
+    let peers = tracker.peers();
+    assert_eq!(peers.len(), 1);
+    assert_eq!(peers[&peer_id].inbound_bytes, 10);
+    assert_eq!(peers[&peer_id].outbound_bytes, 20);
+
+    // Peer is tracked until last connection is closed
+    tracker.connection_closed(&peer_id);
+    assert_eq!(tracker.peers().len(), 1);
+    tracker.connection_closed(&peer_id);
+    assert!(tracker.peers().is_empty());
+}
@@ -7,7 +7,7 @@ use crate::peer_info::{
     Behaviour as PeerInfoBehaviour, Config as PeerInfoConfig, Event as PeerInfoEvent,
 };
 use crate::request_responses::{
-    Event as RequestResponseEvent, RequestHandler, RequestResponsesBehaviour,
+    Event as RequestResponseEvent, RegisterError, RequestHandler, RequestResponsesBehaviour,
 };
 use crate::reserved_peers::{
     Behaviour as ReservedPeersBehaviour, Config as ReservedPeersConfig, Event as ReservedPeersEvent,
@@ -15,6 +15,7 @@ use crate::reserved_peers::{
 use crate::PeerInfoProvider;
 use derive_more::From;
 use libp2p::allow_block_list::{Behaviour as AllowBlockListBehaviour, BlockedPeers};
+use libp2p::autonat::{Behaviour as Autonat, Config as AutonatConfig, Event as AutonatEvent};
 use libp2p::connection_limits::{Behaviour as ConnectionLimitsBehaviour, ConnectionLimits};
 use libp2p::gossipsub::{
     Behaviour as Gossipsub, Config as GossipsubConfig, Event as GossipsubEvent, MessageAuthenticity,
@@ -22,9 +23,15 @@ use libp2p::gossipsub::{
 use libp2p::identify::{Behaviour as Identify, Config as IdentifyConfig, Event as IdentifyEvent};
 use libp2p::kad::{Kademlia, KademliaConfig, KademliaEvent};
 use libp2p::ping::{Behaviour as Ping, Event as PingEvent};
+use libp2p::relay::{
+    Behaviour as RelayServer, Config as RelayServerConfig, Event as RelayServerEvent,
+};
 use libp2p::swarm::behaviour::toggle::Toggle;
+use libp2p::swarm::keep_alive::Behaviour as KeepAliveBehaviour;
 use libp2p::swarm::NetworkBehaviour;
 use libp2p::PeerId;
+use std::collections::HashMap;
+use std::time::Duration;
 use void::Void as VoidEvent;
 
 type BlockListBehaviour = AllowBlockListBehaviour<BlockedPeers>;
@@ -38,10 +45,20 @@ pub(crate) struct BehaviorConfig<RecordStore> {
     pub(crate) kademlia: KademliaConfig,
     /// The configuration for the [`Gossipsub`] behaviour.
     pub(crate) gossipsub: Option<GossipsubConfig>,
+    /// The configuration for the [`Autonat`] behaviour.
+    pub(crate) autonat: Option<AutonatConfig>,
+    /// The configuration for the [`RelayServer`] behaviour.
+    pub(crate) relay_server: Option<RelayServerConfig>,
     /// Externally provided implementation of the custom record store for Kademlia DHT,
     pub(crate) record_store: RecordStore,
     /// The configuration for the [`RequestResponsesBehaviour`] protocol.
     pub(crate) request_response_protocols: Vec<Box<dyn RequestHandler>>,
+    /// Timeouts overriding default timeouts of request-response protocols by protocol name.
+    pub(crate) request_timeouts: HashMap<&'static str, Duration>,
+    /// How long connections are kept alive by [`RequestResponsesBehaviour`] once idle.
+    pub(crate) connection_idle_timeout: Duration,
+    /// Whether all connections should be kept alive regardless of whether they are used.
+    pub(crate) keep_alive_all_connections: bool,
     /// Connection limits for the swarm.
     pub(crate) connection_limits: ConnectionLimits,
     /// The configuration for the [`ReservedPeersBehaviour`].
@@ -59,19 +76,22 @@ pub(crate) struct Behavior<RecordStore> {
     pub(crate) identify: Identify,
     pub(crate) kademlia: Kademlia<RecordStore>,
     pub(crate) gossipsub: Toggle<Gossipsub>,
+    pub(crate) autonat: Toggle<Autonat>,
+    pub(crate) relay_server: Toggle<RelayServer>,
     pub(crate) ping: Ping,
     pub(crate) request_response: RequestResponsesBehaviour,
     pub(crate) connection_limits: ConnectionLimitsBehaviour,
     pub(crate) block_list: BlockListBehaviour,
     pub(crate) reserved_peers: ReservedPeersBehaviour,
     pub(crate) peer_info: PeerInfoBehaviour,
+    pub(crate) keep_alive: Toggle<KeepAliveBehaviour>,
 }
 
 impl<RecordStore> Behavior<RecordStore>
 where
     RecordStore: Send + Sync + libp2p::kad::store::RecordStore + 'static,
 {
-    pub(crate) fn new(config: BehaviorConfig<RecordStore>) -> Self {
+    pub(crate) fn new(config: BehaviorConfig<RecordStore>) -> Result<Self, RegisterError> {
         let kademlia = Kademlia::<RecordStore>::with_config(
             config.peer_id,
             config.record_store,
@@ -90,21 +110,37 @@ where
             })
             .into();
 
-        Self {
+        let autonat = config
+            .autonat
+            .map(|autonat_config| Autonat::new(config.peer_id, autonat_config))
+            .into();
+
+        let relay_server = config
+            .relay_server
+            .map(|relay_server_config| RelayServer::new(config.peer_id, relay_server_config))
+            .into();
+
+        Ok(Self {
             identify: Identify::new(config.identify),
             kademlia,
             gossipsub,
+            autonat,
+            relay_server,
             ping: Ping::default(),
             request_response: RequestResponsesBehaviour::new(
                 config.request_response_protocols.into_iter(),
-            )
-            //TODO: Convert to an error.
-            .expect("RequestResponse protocols registration failed."),
+                &config.request_timeouts,
+                config.connection_idle_timeout,
+            )?,
             connection_limits: ConnectionLimitsBehaviour::new(config.connection_limits),
             block_list: BlockListBehaviour::default(),
             reserved_peers: ReservedPeersBehaviour::new(config.reserved_peers),
             peer_info: PeerInfoBehaviour::new(config.peer_info_config, config.peer_info_provider),
-        }
+            keep_alive: config
+                .keep_alive_all_connections
+                .then(KeepAliveBehaviour::default)
+                .into(),
+        })
     }
 }
 
@@ -113,6 +149,8 @@ pub(crate) enum Event {
     Identify(IdentifyEvent),
     Kademlia(KademliaEvent),
     Gossipsub(GossipsubEvent),
+    Autonat(AutonatEvent),
+    RelayServer(RelayServerEvent),
     Ping(PingEvent),
     RequestResponse(RequestResponseEvent),
     /// Event stub for connection limits and block list behaviours. We won't receive such events.
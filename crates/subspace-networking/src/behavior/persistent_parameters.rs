@@ -1,4 +1,5 @@
-use crate::utils::{convert_multiaddresses, CollectionBatcher, PeerAddress};
+use crate::utils::dnsaddr::BootstrapAddresses;
+use crate::utils::{CollectionBatcher, PeerAddress};
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use futures::future::Fuse;
@@ -54,6 +55,16 @@ pub trait NetworkingParametersRegistry: Send + Sync {
     /// It removes p2p-protocol suffix.
     async fn next_known_addresses_batch(&mut self) -> Vec<PeerAddress>;
 
+    /// Replaces persisted snapshot of Kademlia routing table with provided peers and their
+    /// addresses.
+    async fn save_routing_table(&mut self, _peers: HashMap<PeerId, Vec<Multiaddr>>) {}
+
+    /// Returns previously persisted snapshot of Kademlia routing table, used to restore routing
+    /// table on startup without relying on bootstrap nodes.
+    async fn routing_table(&self) -> HashMap<PeerId, Vec<Multiaddr>> {
+        HashMap::new()
+    }
+
     /// Reset the batching process to the initial state.
     fn start_over_address_batching(&mut self) {}
 
@@ -73,21 +84,17 @@ impl Clone for Box<dyn NetworkingParametersRegistry> {
 /// Networking manager implementation with bootstrapped addresses. All other operations muted.
 #[derive(Clone, Default)]
 pub struct BootstrappedNetworkingParameters {
-    bootstrap_addresses: Vec<Multiaddr>,
+    bootstrap_addresses: BootstrapAddresses,
 }
 
 impl BootstrappedNetworkingParameters {
     /// Creates a new instance of `BootstrappedNetworkingParameters`.
     pub fn new(bootstrap_addresses: Vec<Multiaddr>) -> Self {
         Self {
-            bootstrap_addresses,
+            bootstrap_addresses: BootstrapAddresses::new(bootstrap_addresses),
         }
     }
 
-    fn bootstrap_addresses(&self) -> Vec<PeerAddress> {
-        convert_multiaddresses(self.bootstrap_addresses.clone())
-    }
-
     /// Returns an instance of `BootstrappedNetworkingParameters` as the `Box` reference.
     pub fn boxed(self) -> Box<dyn NetworkingParametersRegistry> {
         Box::new(self)
@@ -103,7 +110,7 @@ impl NetworkingParametersRegistry for BootstrappedNetworkingParameters {
     async fn remove_all_known_peer_addresses(&mut self, _peer_id: PeerId) {}
 
     async fn next_known_addresses_batch(&mut self) -> Vec<PeerAddress> {
-        self.bootstrap_addresses()
+        self.bootstrap_addresses.peer_addresses().await
     }
 
     async fn run(&mut self) {
@@ -133,6 +140,8 @@ pub struct NetworkingParametersManager {
     cache_need_saving: bool,
     // LRU cache for the known peers and their addresses
     known_peers: LruCache<PeerId, LruCache<Multiaddr, FailureTime>>,
+    // Last snapshot of Kademlia routing table
+    routing_table: HashMap<PeerId, Vec<Multiaddr>>,
     // Period between networking parameters saves.
     networking_parameters_save_delay: Pin<Box<Fuse<Sleep>>>,
     // Parity DB instance
@@ -141,8 +150,8 @@ pub struct NetworkingParametersManager {
     column_id: u8,
     // Key to persistent parameters
     object_id: &'static [u8],
-    // Bootstrap addresses provided on creation
-    bootstrap_addresses: Vec<Multiaddr>,
+    // Bootstrap addresses provided on creation, `/dnsaddr` entries are resolved periodically
+    bootstrap_addresses: BootstrapAddresses,
     // Provides batching capabilities for the address collection (it stores the last batch index)
     collection_batcher: CollectionBatcher<PeerAddress>,
 }
@@ -162,12 +171,11 @@ impl NetworkingParametersManager {
         let column_id = 0u8;
         let object_id = b"global_networking_parameters_key";
 
-        // load known peers cache.
-        let cache = db
+        // load known peers cache and routing table.
+        let networking_parameters = db
             .get(column_id, object_id)?
             .map(|data| {
-                let result = serde_json::from_slice::<NetworkingParameters>(&data)
-                    .map(|data| data.to_cache());
+                let result = serde_json::from_slice::<NetworkingParameters>(&data);
 
                 if result.is_ok() {
                     debug!("Networking parameters loaded from DB");
@@ -175,7 +183,8 @@ impl NetworkingParametersManager {
 
                 result
             })
-            .unwrap_or_else(|| Ok(LruCache::new(PEER_CACHE_SIZE)))?;
+            .unwrap_or_else(|| Ok(NetworkingParameters::default()))?;
+        let cache = networking_parameters.to_cache();
 
         Ok(Self {
             cache_need_saving: false,
@@ -183,8 +192,9 @@ impl NetworkingParametersManager {
             column_id,
             object_id,
             known_peers: cache,
+            routing_table: networking_parameters.routing_table,
             networking_parameters_save_delay: Self::default_delay(),
-            bootstrap_addresses,
+            bootstrap_addresses: BootstrapAddresses::new(bootstrap_addresses),
             collection_batcher: CollectionBatcher::new(
                 NonZeroUsize::new(PEERS_ADDRESSES_BATCH_SIZE)
                     .expect("Manual non-zero initialization failed."),
@@ -202,12 +212,6 @@ impl NetworkingParametersManager {
             .collect()
     }
 
-    // Returns boostrap addresses from networking parameters initialization.
-    // It removes p2p-protocol suffix.
-    fn bootstrap_addresses(&self) -> Vec<PeerAddress> {
-        convert_multiaddresses(self.bootstrap_addresses.clone())
-    }
-
     // Helps create a copy of the internal LruCache
     fn clone_known_peers(&self) -> LruCache<PeerId, LruCache<Multiaddr, FailureTime>> {
         let mut known_peers = LruCache::new(self.known_peers.cap());
@@ -308,7 +312,7 @@ impl NetworkingParametersRegistry for NetworkingParametersManager {
             .known_addresses()
             .await
             .into_iter()
-            .chain(self.bootstrap_addresses().into_iter())
+            .chain(self.bootstrap_addresses.peer_addresses().await.into_iter())
             .collect::<Vec<_>>();
 
         trace!(
@@ -323,13 +327,29 @@ impl NetworkingParametersRegistry for NetworkingParametersManager {
         self.collection_batcher.reset();
     }
 
+    async fn save_routing_table(&mut self, peers: HashMap<PeerId, Vec<Multiaddr>>) {
+        if self.routing_table != peers {
+            trace!(peers = peers.len(), "Routing table snapshot updated");
+
+            self.routing_table = peers;
+            self.cache_need_saving = true;
+        }
+    }
+
+    async fn routing_table(&self) -> HashMap<PeerId, Vec<Multiaddr>> {
+        self.routing_table.clone()
+    }
+
     async fn run(&mut self) {
         loop {
             (&mut self.networking_parameters_save_delay).await;
 
             if self.cache_need_saving {
                 // save accumulated cache to DB
-                let dto = NetworkingParameters::from_cache(self.clone_known_peers());
+                let dto = NetworkingParameters::from_cache(
+                    self.clone_known_peers(),
+                    self.routing_table.clone(),
+                );
                 let save_result = serde_json::to_vec(&dto)
                     .map_err(NetworkParametersPersistenceError::from)
                     .and_then(|data| {
@@ -355,6 +375,7 @@ impl NetworkingParametersRegistry for NetworkingParametersManager {
         Self {
             cache_need_saving: self.cache_need_saving,
             known_peers: self.clone_known_peers(),
+            routing_table: self.routing_table.clone(),
             networking_parameters_save_delay: Self::default_delay(),
             db: self.db.clone(),
             column_id: self.column_id,
@@ -368,12 +389,18 @@ impl NetworkingParametersRegistry for NetworkingParametersManager {
 
 // Helper struct for NetworkingPersistence implementations (data transfer object).
 #[derive(Default, Debug, Serialize, Deserialize)]
-struct NetworkingParameters {
+pub(super) struct NetworkingParameters {
     pub known_peers: HashMap<PeerId, HashMap<Multiaddr, FailureTime>>,
+    // Absent in parameters persisted by older versions
+    #[serde(default)]
+    pub routing_table: HashMap<PeerId, Vec<Multiaddr>>,
 }
 
 impl NetworkingParameters {
-    fn from_cache(cache: LruCache<PeerId, LruCache<Multiaddr, FailureTime>>) -> Self {
+    fn from_cache(
+        cache: LruCache<PeerId, LruCache<Multiaddr, FailureTime>>,
+        routing_table: HashMap<PeerId, Vec<Multiaddr>>,
+    ) -> Self {
         Self {
             known_peers: cache
                 .into_iter()
@@ -381,6 +408,7 @@ impl NetworkingParameters {
                     (peer_id, addresses.into_iter().collect::<HashMap<_, _>>())
                 })
                 .collect::<HashMap<_, _>>(),
+            routing_table,
         }
     }
 
@@ -8,6 +8,16 @@ pub(crate) use providers::{instant_to_micros, micros_to_instant};
 pub use providers::{MemoryProviderStorage, ParityDbProviderStorage, VoidProviderStorage};
 use std::borrow::Cow;
 
+/// Statistics of provider records storage, used for metrics.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct ProviderStorageStats {
+    /// Number of keys provider records are currently stored for.
+    pub stored_keys: usize,
+    /// Number of keys whose provider records were evicted due to storage size limit since
+    /// storage creation.
+    pub evicted_keys: u64,
+}
+
 /// A trait for providers storages - wrapper around `provider` functions of the libp2p RecordStore.
 pub trait ProviderStorage {
     /// Provider record iterator.
@@ -31,4 +41,9 @@ pub trait ProviderStorage {
 
     /// Removes a provider record from the store.
     fn remove_provider(&self, k: &Key, p: &PeerId);
+
+    /// Statistics of the storage, storages that don't track it return empty statistics.
+    fn stats(&self) -> ProviderStorageStats {
+        ProviderStorageStats::default()
+    }
 }
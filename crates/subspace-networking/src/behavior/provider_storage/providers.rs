@@ -1,28 +1,30 @@
 #[cfg(test)]
 mod tests;
 
-use super::ProviderStorage;
+use super::{ProviderStorage, ProviderStorageStats};
 use crate::utils::unique_record_binary_heap::UniqueRecordBinaryHeap;
 use either::Either;
 use libp2p::kad::record::Key;
-use libp2p::kad::store::{MemoryStoreConfig, RecordStore};
 use libp2p::kad::{store, ProviderRecord, K_VALUE};
 use libp2p::{Multiaddr, PeerId};
+use lru::LruCache;
 use parity_db::{ColumnOptions, Db, Options};
 use parity_scale_codec::{Decode, Encode};
 use parking_lot::Mutex;
 use std::borrow::{Borrow, Cow};
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
 use std::iter;
 use std::num::NonZeroUsize;
 use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant, SystemTime};
 use std::vec::IntoIter;
 use tracing::{debug, error, trace, warn};
 
-// Defines max provider records number. Each provider record is expected to be less than 1KB.
-const MEMORY_STORE_PROVIDED_KEY_LIMIT: usize = 100000; // ~100 MB
+// Defines max number of keys in memory store (~100 MB). Each provider record is expected to be
+// less than 1KB.
+const MEMORY_STORE_KEY_LIMIT: NonZeroUsize = NonZeroUsize::new(100000).expect("Not zero; qed");
 
 const PARITY_DB_ALL_PROVIDERS_COLUMN_NAME: u8 = 0;
 const PARITY_DB_LOCAL_PROVIDER_COLUMN_NAME: u8 = 1;
@@ -49,25 +51,48 @@ impl ProviderStorage for VoidProviderStorage {
     fn remove_provider(&self, _: &Key, _: &PeerId) {}
 }
 
-/// Memory based provider records storage.
+struct MemoryProviderStorageInner {
+    local_peer_id: PeerId,
+    /// Provider records of remote providers, least recently used keys are evicted first
+    providers: LruCache<Key, Vec<ProviderRecord>>,
+    /// Provider records of the local peer, never evicted
+    provided: HashMap<Key, ProviderRecord>,
+    max_keys: usize,
+    max_providers_per_key: usize,
+    evicted_keys: u64,
+}
+
+/// Memory based provider records storage with limited number of keys.
 #[derive(Clone)]
 pub struct MemoryProviderStorage {
-    inner: Arc<Mutex<store::MemoryStore>>,
+    inner: Arc<Mutex<MemoryProviderStorageInner>>,
 }
 
 impl MemoryProviderStorage {
-    /// Create new memory based provider records storage.
+    /// Create new memory based provider records storage with default limits.
     pub fn new(peer_id: PeerId) -> Self {
+        Self::with_limits(peer_id, MEMORY_STORE_KEY_LIMIT, K_VALUE)
+    }
+
+    /// Create new memory based provider records storage with specified limits.
+    ///
+    /// Once `max_keys` is reached, records of remote providers for least recently used key are
+    /// evicted, records of local provider are never evicted and are rejected instead. Once
+    /// `max_providers_per_key` is reached for a key, the oldest provider record is evicted.
+    pub fn with_limits(
+        peer_id: PeerId,
+        max_keys: NonZeroUsize,
+        max_providers_per_key: NonZeroUsize,
+    ) -> Self {
         Self {
-            inner: Arc::new(Mutex::new(store::MemoryStore::with_config(
-                peer_id,
-                MemoryStoreConfig {
-                    max_records: 0,
-                    max_value_bytes: 0,
-                    max_providers_per_key: K_VALUE.get(),
-                    max_provided_keys: MEMORY_STORE_PROVIDED_KEY_LIMIT,
-                },
-            ))),
+            inner: Arc::new(Mutex::new(MemoryProviderStorageInner {
+                local_peer_id: peer_id,
+                providers: LruCache::unbounded(),
+                provided: HashMap::new(),
+                max_keys: max_keys.get(),
+                max_providers_per_key: max_providers_per_key.get(),
+                evicted_keys: 0,
+            })),
         }
     }
 }
@@ -81,11 +106,65 @@ impl ProviderStorage for MemoryProviderStorage {
     fn add_provider(&self, record: ProviderRecord) -> store::Result<()> {
         trace!("New provider record added: {:?}", record);
 
-        self.inner.lock().add_provider(record)
+        let mut inner = self.inner.lock();
+        let inner = &mut *inner;
+
+        let is_local = record.provider == inner.local_peer_id;
+        let new_key = if is_local {
+            !inner.provided.contains_key(&record.key)
+        } else {
+            !inner.providers.contains(&record.key)
+        };
+        if new_key && inner.provided.len() + inner.providers.len() >= inner.max_keys {
+            // Only records of remote providers are evicted
+            match inner.providers.pop_lru() {
+                Some((key, _records)) => {
+                    trace!(?key, "Provider records evicted from memory store.");
+                    inner.evicted_keys += 1;
+                }
+                None => {
+                    return Err(store::Error::MaxProvidedKeys);
+                }
+            }
+        }
+
+        if is_local {
+            inner.provided.insert(record.key.clone(), record);
+            return Ok(());
+        }
+
+        if !inner.providers.contains(&record.key) {
+            inner.providers.put(record.key.clone(), Vec::new());
+        }
+        let records = inner
+            .providers
+            .get_mut(&record.key)
+            .expect("Inserted above if didn't exist; qed");
+
+        if let Some(existing_record) = records
+            .iter_mut()
+            .find(|existing_record| existing_record.provider == record.provider)
+        {
+            *existing_record = record;
+        } else {
+            if records.len() >= inner.max_providers_per_key {
+                records.remove(0);
+            }
+            records.push(record);
+        }
+
+        Ok(())
     }
 
     fn providers(&self, key: &Key) -> Vec<ProviderRecord> {
-        self.inner.lock().providers(key)
+        let mut inner = self.inner.lock();
+
+        let mut records = inner.providers.get(key).cloned().unwrap_or_default();
+        if let Some(record) = inner.provided.get(key) {
+            records.push(record.clone());
+        }
+
+        records
     }
 
     fn provided(&self) -> Self::ProvidedIter<'_> {
@@ -93,8 +172,9 @@ impl ProviderStorage for MemoryProviderStorage {
         let records = {
             self.inner
                 .lock()
-                .provided()
-                .map(|item| item.into_owned())
+                .provided
+                .values()
+                .cloned()
                 .collect::<Vec<_>>()
         };
 
@@ -104,7 +184,29 @@ impl ProviderStorage for MemoryProviderStorage {
     fn remove_provider(&self, key: &Key, provider: &PeerId) {
         trace!(?key, ?provider, "Provider record removed.");
 
-        self.inner.lock().remove_provider(key, provider)
+        let mut inner = self.inner.lock();
+
+        if *provider == inner.local_peer_id {
+            inner.provided.remove(key);
+            return;
+        }
+
+        if let Some(records) = inner.providers.peek_mut(key) {
+            records.retain(|record| record.provider != *provider);
+
+            if records.is_empty() {
+                inner.providers.pop(key);
+            }
+        }
+    }
+
+    fn stats(&self) -> ProviderStorageStats {
+        let inner = self.inner.lock();
+
+        ProviderStorageStats {
+            stored_keys: inner.provided.len() + inner.providers.len(),
+            evicted_keys: inner.evicted_keys,
+        }
     }
 }
 
@@ -225,6 +327,8 @@ pub struct ParityDbProviderStorage {
     db: Arc<Db>,
     /// Maintains a heap to limit total item number.
     heap: Arc<Mutex<UniqueRecordBinaryHeap>>,
+    /// Number of keys evicted due to heap size limit
+    evicted_keys: Arc<AtomicU64>,
     /// Local provider PeerID
     local_peer_id: PeerId,
 }
@@ -290,6 +394,7 @@ impl ParityDbProviderStorage {
         Ok(Self {
             db: Arc::new(db),
             heap: Arc::new(Mutex::new(heap)),
+            evicted_keys: Arc::default(),
             local_peer_id,
         })
     }
@@ -426,6 +531,7 @@ impl ProviderStorage for ParityDbProviderStorage {
         if let Some(key) = evicted_key {
             trace!(?key, "Record evicted from cache.");
 
+            self.evicted_keys.fetch_add(1, Ordering::Relaxed);
             self.remove_local_provider_to_db(&key);
             self.remove_providers_from_db(&key);
         }
@@ -470,6 +576,13 @@ impl ProviderStorage for ParityDbProviderStorage {
             .map(Into::into)
             .collect()
     }
+
+    fn stats(&self) -> ProviderStorageStats {
+        ProviderStorageStats {
+            stored_keys: self.size(),
+            evicted_keys: self.evicted_keys.load(Ordering::Relaxed),
+        }
+    }
 }
 
 /// Parity DB BTree iterator wrapper.
@@ -564,6 +677,13 @@ where
             Either::Right(inner) => inner.remove_provider(key, peer_id),
         }
     }
+
+    fn stats(&self) -> ProviderStorageStats {
+        match self {
+            Either::Left(inner) => inner.stats(),
+            Either::Right(inner) => inner.stats(),
+        }
+    }
 }
 
 struct EitherProviderStorageIterator<'a, L, R>
@@ -1,9 +1,11 @@
 use super::MemoryProviderStorage;
+use crate::behavior::provider_storage::ProviderStorageStats;
 use crate::ProviderStorage;
 use libp2p::kad::record::Key;
 use libp2p::kad::ProviderRecord;
 use libp2p::PeerId;
 use std::collections::HashSet;
+use std::num::NonZeroUsize;
 
 #[allow(clippy::mutable_key_type)] // we use hash set for sorting to compare collections
 #[test]
@@ -72,3 +74,59 @@ fn memory_storage_provider() {
         provided_collection
     );
 }
+
+#[test]
+fn memory_storage_provider_eviction() {
+    let local_peer_id = PeerId::random();
+    let store = MemoryProviderStorage::with_limits(
+        local_peer_id,
+        NonZeroUsize::new(2).unwrap(),
+        NonZeroUsize::new(1).unwrap(),
+    );
+
+    let record = |key: &[u8], provider| ProviderRecord {
+        provider,
+        key: key.to_vec().into(),
+        expires: None,
+        addresses: Vec::new(),
+    };
+
+    let provider1 = PeerId::random();
+    let provider2 = PeerId::random();
+
+    store.add_provider(record(b"key1", provider1)).unwrap();
+    store.add_provider(record(b"key2", provider1)).unwrap();
+    // Access first key, such that second key becomes least recently used
+    assert_eq!(store.providers(&b"key1".to_vec().into()).len(), 1);
+
+    // Second key is evicted
+    store.add_provider(record(b"key3", provider1)).unwrap();
+    assert!(store.providers(&b"key2".to_vec().into()).is_empty());
+    assert_eq!(
+        store.stats(),
+        ProviderStorageStats {
+            stored_keys: 2,
+            evicted_keys: 1,
+        }
+    );
+
+    // Older provider is replaced for the same key
+    store.add_provider(record(b"key1", provider2)).unwrap();
+    assert_eq!(
+        store.providers(&b"key1".to_vec().into()),
+        vec![record(b"key1", provider2)]
+    );
+
+    // Local records are never evicted, remote are evicted in favor of them instead
+    store.add_provider(record(b"key4", local_peer_id)).unwrap();
+    store.add_provider(record(b"key5", local_peer_id)).unwrap();
+    assert!(store.add_provider(record(b"key6", local_peer_id)).is_err());
+    assert_eq!(store.provided().count(), 2);
+    assert_eq!(
+        store.stats(),
+        ProviderStorageStats {
+            stored_keys: 2,
+            evicted_keys: 3,
+        }
+    );
+}
@@ -1,4 +1,4 @@
-use super::persistent_parameters::remove_known_peer_addresses_internal;
+use super::persistent_parameters::{remove_known_peer_addresses_internal, NetworkingParameters};
 use crate::behavior::provider_storage::{instant_to_micros, micros_to_instant};
 use crate::{BootstrappedNetworkingParameters, Config, GenericRequest, GenericRequestHandler};
 use futures::channel::oneshot;
@@ -66,6 +66,30 @@ async fn test_address_timed_removal_from_known_peers_cache() {
     assert_eq!(peers_cache.len(), 0);
 }
 
+#[test]
+fn networking_parameters_routing_table_serialization() {
+    let peer_id = PeerId::random();
+    let address = Multiaddr::empty().with(Protocol::Memory(0));
+
+    // Parameters persisted before routing table was introduced
+    let networking_parameters =
+        serde_json::from_str::<NetworkingParameters>(r#"{"known_peers":{}}"#).unwrap();
+    assert!(networking_parameters.routing_table.is_empty());
+
+    let networking_parameters = NetworkingParameters {
+        known_peers: Default::default(),
+        routing_table: [(peer_id, vec![address.clone()])].into_iter().collect(),
+    };
+    let networking_parameters = serde_json::from_slice::<NetworkingParameters>(
+        &serde_json::to_vec(&networking_parameters).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        networking_parameters.routing_table.get(&peer_id),
+        Some(&vec![address])
+    );
+}
+
 #[test]
 fn instant_conversion() {
     let inst1 = Instant::now();
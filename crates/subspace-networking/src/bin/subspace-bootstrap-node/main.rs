@@ -15,8 +15,8 @@ use std::path::PathBuf;
 use std::sync::Arc;
 use subspace_networking::libp2p::multiaddr::Protocol;
 use subspace_networking::{
-    peer_id, BootstrappedNetworkingParameters, Config, NetworkingParametersManager,
-    ParityDbProviderStorage, PeerInfoProvider, VoidProviderStorage,
+    peer_id, BootstrappedNetworkingParameters, Config, ConnectionLimitsPreset,
+    NetworkingParametersManager, ParityDbProviderStorage, PeerInfoProvider, VoidProviderStorage,
 };
 use tracing::{debug, info, Level};
 use tracing_subscriber::fmt::Subscriber;
@@ -28,7 +28,8 @@ use tracing_subscriber::EnvFilter;
 enum Command {
     /// Start bootstrap node
     Start {
-        /// Multiaddresses of bootstrap nodes to connect to on startup, multiple are supported
+        /// Multiaddresses of bootstrap nodes to connect to on startup, multiple are supported,
+        /// `/dnsaddr/<domain>` entries are periodically resolved from DNS TXT records
         #[arg(long, alias = "bootstrap-node")]
         bootstrap_nodes: Vec<Multiaddr>,
         /// Keypair for node identity, can be obtained with `generate-keypair` command
@@ -186,6 +187,7 @@ async fn main() -> anyhow::Result<()> {
                     provider_storage,
                     PeerInfoProvider::new_bootstrap_node(),
                 )
+                .with_connection_limits_preset(ConnectionLimitsPreset::BootstrapNode)
             };
             let (node, mut node_runner) =
                 subspace_networking::create(config).expect("Networking stack creation failed.");
@@ -1,27 +1,34 @@
 pub(crate) mod temporary_bans;
 mod transport;
 
+use crate::bandwidth::{BandwidthMuxer, BandwidthTracker};
 use crate::behavior::persistent_parameters::{
     BootstrappedNetworkingParameters, NetworkingParametersRegistry,
 };
-use crate::behavior::provider_storage::MemoryProviderStorage;
+use crate::behavior::provider_storage::{MemoryProviderStorage, ProviderStorageStats};
 use crate::behavior::{provider_storage, Behavior, BehaviorConfig};
 use crate::create::temporary_bans::TemporaryBans;
 use crate::create::transport::build_transport;
-use crate::node::Node;
-use crate::node_runner::{NodeRunner, NodeRunnerConfig};
+use crate::gossip::{GossipTopic, GossipTopicValidator};
+use crate::metrics::NetworkingMetrics;
+use crate::node::{KademliaQueryOptions, Node};
+use crate::node_runner::provider_republication::ProviderRepublicationScheduler;
+use crate::node_runner::{NodeRunner, NodeRunnerConfig, KADEMLIA_PROVIDER_TTL_IN_SECS};
 use crate::peer_info::PeerInfoProvider;
-use crate::request_responses::RequestHandler;
+use crate::request_handlers::generic_request_handler::{GenericRequest, GenericRequestHandler};
+use crate::request_responses::{RegisterError, RequestHandler};
 use crate::reserved_peers::Config as ReservedPeersConfig;
 use crate::shared::Shared;
 use crate::utils::{convert_multiaddresses, ResizableSemaphore};
 use crate::PeerInfoConfig;
 use backoff::{ExponentialBackoff, SystemClock};
 use futures::channel::mpsc;
+use libp2p::autonat::Config as AutonatConfig;
 use libp2p::connection_limits::ConnectionLimits;
+use libp2p::core::muxing::StreamMuxerBox;
 use libp2p::gossipsub::{
     Config as GossipsubConfig, ConfigBuilder as GossipsubConfigBuilder,
-    Message as GossipsubMessage, MessageId, ValidationMode,
+    Message as GossipsubMessage, MessageAcceptance, MessageId, ValidationMode,
 };
 use libp2p::identify::Config as IdentifyConfig;
 use libp2p::kad::record::Key;
@@ -29,22 +36,25 @@ use libp2p::kad::store::RecordStore;
 use libp2p::kad::{
     store, KademliaBucketInserts, KademliaConfig, KademliaStoreInserts, ProviderRecord, Record,
 };
-use libp2p::metrics::Metrics;
 use libp2p::multiaddr::Protocol;
+use libp2p::relay::Config as RelayServerConfig;
 use libp2p::swarm::SwarmBuilder;
 use libp2p::yamux::Config as YamuxConfig;
-use libp2p::{identity, Multiaddr, PeerId, TransportError};
+use libp2p::{identity, Multiaddr, PeerId, Transport, TransportError, TransportExt};
 use parking_lot::Mutex;
 use std::borrow::Cow;
+use std::collections::HashMap;
+use std::future::Future;
 use std::iter::Empty;
-use std::num::NonZeroUsize;
+use std::num::{NonZeroU8, NonZeroUsize};
 use std::string::ToString;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use std::{fmt, io, iter};
 use subspace_core_primitives::{crypto, Piece};
 use thiserror::Error;
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, trace};
 
 const DEFAULT_NETWORK_PROTOCOL_VERSION: &str = "dev";
 const KADEMLIA_PROTOCOL: &[u8] = b"/subspace/kad/0.1.0";
@@ -65,16 +75,21 @@ const SWARM_MAX_PENDING_INCOMING_CONNECTIONS: u32 = 50;
 const SWARM_MAX_PENDING_OUTGOING_CONNECTIONS: u32 = 50;
 // The default maximum connection number to be maintained for the swarm.
 const SWARM_TARGET_CONNECTION_NUMBER: u32 = 50;
-// Defines an expiration interval for item providers in Kademlia network.
-const KADEMLIA_PROVIDER_TTL_IN_SECS: Option<Duration> = Some(Duration::from_secs(86400)); /* 1 day */
+/// The default number of addresses of the same peer dialed concurrently.
+const SWARM_DIAL_CONCURRENCY_FACTOR: NonZeroU8 = NonZeroU8::new(8).expect("Not zero; qed");
 // Defines a republication interval for item providers in Kademlia network.
 const KADEMLIA_PROVIDER_REPUBLICATION_INTERVAL_IN_SECS: Option<Duration> =
     Some(Duration::from_secs(3600)); /* 1 hour */
+/// Defines how often batches of provider records are republished, republication of all records is
+/// spread over republication interval in such batches.
+const KADEMLIA_PROVIDER_REPUBLICATION_BATCH_INTERVAL: Duration = Duration::from_secs(10);
 // Defines a replication factor for Kademlia on get_record operation.
 // "Good citizen" supports the network health.
 const YAMUX_MAX_STREAMS: usize = 256;
 const KADEMLIA_QUERY_TIMEOUT: Duration = Duration::from_secs(40);
-const SWARM_MAX_ESTABLISHED_CONNECTIONS_PER_PEER: Option<u32> = Some(2);
+/// Default timeout after which idle connections are closed.
+const CONNECTION_IDLE_TIMEOUT: Duration = Duration::from_secs(10);
+const SWARM_MAX_ESTABLISHED_CONNECTIONS_PER_PEER: u32 = 2;
 // TODO: Consider moving this constant to configuration or removing `Toggle` wrapper when we find a
 // use-case for gossipsub protocol.
 const ENABLE_GOSSIP_PROTOCOL: bool = false;
@@ -111,11 +126,29 @@ const TEMPORARY_BANS_DEFAULT_MAX_INTERVAL: Duration = Duration::from_secs(30 * 6
 /// Record store that can't be created, only
 pub(crate) struct ProviderOnlyRecordStore<ProviderStorage> {
     provider_storage: ProviderStorage,
+    local_peer_id: PeerId,
+    /// Number of expired provider records of remote providers that were removed from storage
+    expired_records: AtomicU64,
 }
 
-impl<ProviderStorage> ProviderOnlyRecordStore<ProviderStorage> {
-    fn new(provider_storage: ProviderStorage) -> Self {
-        Self { provider_storage }
+impl<ProviderStorage> ProviderOnlyRecordStore<ProviderStorage>
+where
+    ProviderStorage: provider_storage::ProviderStorage,
+{
+    fn new(provider_storage: ProviderStorage, local_peer_id: PeerId) -> Self {
+        Self {
+            provider_storage,
+            local_peer_id,
+            expired_records: AtomicU64::new(0),
+        }
+    }
+
+    /// Statistics of the provider storage and number of expired records removed from it.
+    pub(crate) fn stats(&self) -> (ProviderStorageStats, u64) {
+        (
+            self.provider_storage.stats(),
+            self.expired_records.load(Ordering::Relaxed),
+        )
     }
 }
 
@@ -150,7 +183,23 @@ where
     }
 
     fn providers(&self, key: &Key) -> Vec<ProviderRecord> {
-        self.provider_storage.providers(key)
+        let now = Instant::now();
+        let (expired, providers): (Vec<_>, Vec<_>) = self
+            .provider_storage
+            .providers(key)
+            .into_iter()
+            // Records of local provider are republished and should not expire
+            .partition(|record| record.provider != self.local_peer_id && record.is_expired(now));
+
+        for record in &expired {
+            trace!(?key, provider = %record.provider, "Removing expired provider record");
+
+            self.provider_storage.remove_provider(key, &record.provider);
+        }
+        self.expired_records
+            .fetch_add(expired.len() as u64, Ordering::Relaxed);
+
+        providers
     }
 
     fn provided(&self) -> Self::ProvidedIter<'_> {
@@ -183,6 +232,30 @@ impl RelayMode {
     }
 }
 
+/// Connection limits for typical use cases, see [`Config::with_connection_limits_preset`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ConnectionLimitsPreset {
+    /// Farmer, typically running on consumer hardware and home network where too many connections
+    /// waste resources and may overwhelm the router.
+    Farmer,
+    /// Regular node.
+    Node,
+    /// Bootstrap node or other large DSN node many peers connect to, which is supposed to run on
+    /// the server with raised OS limits for file descriptors.
+    BootstrapNode,
+}
+
+/// Defines which connections are kept alive when they are not used by any protocol.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum KeepAlivePolicy {
+    /// Connections are closed after being idle for [`Config::connection_idle_timeout`], except
+    /// connections to reserved peers, which are kept alive and re-established when lost.
+    ReservedPeers,
+    /// All connections are kept alive until closed by remote peer or due to connection limits,
+    /// avoids repeated re-dialing of frequently used peers at the cost of idle connections.
+    AllConnections,
+}
+
 /// [`Node`] configuration.
 pub struct Config<ProviderStorage> {
     /// Identity keypair of a node used for authenticated connections.
@@ -194,24 +267,57 @@ pub struct Config<ProviderStorage> {
     /// Adds a timeout to the setup and protocol upgrade process for all inbound and outbound
     /// connections established through the transport.
     pub timeout: Duration,
+    /// How long connections are kept alive by Kademlia and request-response protocols after
+    /// becoming idle.
+    pub connection_idle_timeout: Duration,
+    /// Defines which connections are kept alive regardless of idle timeout.
+    pub keep_alive_policy: KeepAlivePolicy,
     /// The configuration for the Identify behaviour.
     pub identify: IdentifyConfig,
     /// The configuration for the Kademlia behaviour.
     pub kademlia: KademliaConfig,
+    /// Default options of `get_value`/`put_value`/`get_providers` Kademlia queries issued by
+    /// [`Node`].
+    pub kademlia_query_options: KademliaQueryOptions,
     /// The configuration for the Gossip behaviour.
     pub gossipsub: Option<GossipsubConfig>,
+    /// Validators of incoming messages for registered gossip topics, see
+    /// [`Config::with_gossip_topic()`].
+    pub gossip_topic_validators: Vec<GossipTopicValidator>,
+    /// The configuration for the AutoNAT behaviour that detects whether node is publicly
+    /// reachable, `None` disables reachability detection.
+    pub autonat: Option<AutonatConfig>,
+    /// The configuration for circuit relay v2 server that allows peers that are not publicly
+    /// reachable to be reached through this node, `None` disables relay server.
+    pub relay_server: Option<RelayServerConfig>,
     /// Externally provided implementation of the custom provider storage for Kademlia DHT,
     pub provider_storage: ProviderStorage,
     /// Yamux multiplexing configuration.
     pub yamux_config: YamuxConfig,
-    /// Should non-global addresses be added to the DHT?
+    /// Should non-global (private, loopback, link-local, etc.) addresses be added to the DHT,
+    /// dialed and advertised to other peers? Only makes sense for private deployments and local
+    /// development, on public network such addresses result in wasted dials and routing table
+    /// pollution.
     pub allow_non_global_addresses_in_dht: bool,
+    /// Enables in-memory transport (`/memory/<port>` addresses) in addition to regular ones, only
+    /// useful for local development and tests where all peers run within the same process.
+    pub enable_memory_transport: bool,
+    /// Enables QUIC transport (`/udp/<port>/quic-v1` addresses) in addition to TCP and WebSocket.
+    pub enable_quic: bool,
+    /// Dial QUIC addresses of a peer first and only fall back to its other addresses if QUIC
+    /// dial failed, has no effect unless QUIC is enabled.
+    pub prefer_quic_for_dials: bool,
     /// How frequently should random queries be done using Kademlia DHT to populate routing table.
     pub initial_random_query_interval: Duration,
     /// A reference to the `NetworkingParametersRegistry` implementation.
     pub networking_parameters_registry: Box<dyn NetworkingParametersRegistry>,
     /// The configuration for the `RequestResponsesBehaviour` protocol.
     pub request_response_protocols: Vec<Box<dyn RequestHandler>>,
+    /// Timeouts of outgoing requests of request-response protocols by protocol name, overriding
+    /// default timeouts of these protocols, see [`Config::with_request_timeout()`].
+    ///
+    /// Kademlia DHT queries are bounded by query timeout in [`Config::kademlia`] instead.
+    pub request_timeouts: HashMap<&'static str, Duration>,
     /// Defines set of peers with a permanent connection (and reconnection if necessary).
     pub reserved_peers: Vec<Multiaddr>,
     /// Established incoming swarm connection limit.
@@ -224,12 +330,24 @@ pub struct Config<ProviderStorage> {
     pub max_pending_outgoing_connections: u32,
     /// Defines target total (in and out) connection number that should be maintained.
     pub target_connections: u32,
+    /// Established swarm connection limit per peer, `None` means no limit.
+    pub max_established_connections_per_peer: Option<u32>,
+    /// Number of addresses of the same peer that are dialed concurrently.
+    pub dial_concurrency_factor: NonZeroU8,
+    /// Expiration interval of provider records, `None` means records never expire.
+    pub provider_record_ttl: Option<Duration>,
+    /// How often locally provided records are republished, `None` disables republication.
+    pub provider_republication_interval: Option<Duration>,
+    /// Republication of all locally provided records is spread evenly over republication interval
+    /// in batches republished this often, such that large farms don't announce millions of
+    /// records in a single burst.
+    pub provider_republication_batch_interval: Duration,
     /// How many temporarily banned unreachable peers to keep in memory.
     pub temporary_bans_cache_size: NonZeroUsize,
     /// Backoff policy for temporary banning of unreachable peers.
     pub temporary_ban_backoff: ExponentialBackoff,
-    /// Optional external prometheus metrics. None will disable metrics gathering.
-    pub metrics: Option<Metrics>,
+    /// Whether to collect networking metrics, available through [`Node::metrics`] afterwards.
+    pub enable_metrics: bool,
     /// Defines protocol version for the network peers. Affects network partition.
     pub protocol_version: String,
     /// Specifies a source for peer information.
@@ -277,10 +395,6 @@ where
             .set_max_packet_size(2 * Piece::SIZE)
             .set_kbucket_inserts(KademliaBucketInserts::Manual)
             .set_record_filtering(KademliaStoreInserts::FilterBoth)
-            // Providers' settings
-            .set_provider_record_ttl(KADEMLIA_PROVIDER_TTL_IN_SECS)
-            // TODO: remove republication after removing all pieces` announcements
-            .set_provider_publication_interval(KADEMLIA_PROVIDER_REPUBLICATION_INTERVAL_IN_SECS)
             // Our records don't expire.
             .set_record_ttl(None)
             .set_replication_interval(None);
@@ -288,19 +402,7 @@ where
         let mut yamux_config = YamuxConfig::default();
         yamux_config.set_max_num_streams(YAMUX_MAX_STREAMS);
 
-        let gossipsub = ENABLE_GOSSIP_PROTOCOL.then(|| {
-            GossipsubConfigBuilder::default()
-                .protocol_id_prefix(GOSSIPSUB_PROTOCOL_PREFIX)
-                // TODO: Do we want message signing?
-                .validation_mode(ValidationMode::None)
-                // To content-address message, we can take the hash of message and use it as an ID.
-                .message_id_fn(|message: &GossipsubMessage| {
-                    MessageId::from(crypto::blake2b_256_hash(&message.data))
-                })
-                .max_transmit_size(2 * 1024 * 1024) // 2MB
-                .build()
-                .expect("Default config for gossipsub is always correct; qed")
-        });
+        let gossipsub = ENABLE_GOSSIP_PROTOCOL.then(default_gossipsub_config);
 
         let protocol_version = format!("/subspace/{}", protocol_version);
         let identify = IdentifyConfig::new(protocol_version.clone(), keypair.public());
@@ -321,14 +423,24 @@ where
             listen_on: vec![],
             listen_on_fallback_to_random_port: true,
             timeout: Duration::from_secs(10),
+            connection_idle_timeout: CONNECTION_IDLE_TIMEOUT,
+            keep_alive_policy: KeepAlivePolicy::ReservedPeers,
             identify,
             kademlia,
+            kademlia_query_options: KademliaQueryOptions::default(),
             gossipsub,
+            gossip_topic_validators: Vec::new(),
+            autonat: Some(AutonatConfig::default()),
+            relay_server: None,
             provider_storage,
             allow_non_global_addresses_in_dht: false,
+            enable_memory_transport: false,
+            enable_quic: true,
+            prefer_quic_for_dials: false,
             initial_random_query_interval: Duration::from_secs(1),
             networking_parameters_registry: BootstrappedNetworkingParameters::default().boxed(),
             request_response_protocols: Vec::new(),
+            request_timeouts: HashMap::new(),
             yamux_config,
             reserved_peers: Vec::new(),
             max_established_incoming_connections: SWARM_MAX_ESTABLISHED_INCOMING_CONNECTIONS,
@@ -336,13 +448,99 @@ where
             max_pending_incoming_connections: SWARM_MAX_PENDING_INCOMING_CONNECTIONS,
             max_pending_outgoing_connections: SWARM_MAX_PENDING_OUTGOING_CONNECTIONS,
             target_connections: SWARM_TARGET_CONNECTION_NUMBER,
+            max_established_connections_per_peer: Some(SWARM_MAX_ESTABLISHED_CONNECTIONS_PER_PEER),
+            dial_concurrency_factor: SWARM_DIAL_CONCURRENCY_FACTOR,
+            provider_record_ttl: KADEMLIA_PROVIDER_TTL_IN_SECS,
+            // TODO: remove republication after removing all pieces` announcements
+            provider_republication_interval: KADEMLIA_PROVIDER_REPUBLICATION_INTERVAL_IN_SECS,
+            provider_republication_batch_interval: KADEMLIA_PROVIDER_REPUBLICATION_BATCH_INTERVAL,
             temporary_bans_cache_size: TEMPORARY_BANS_CACHE_SIZE,
             temporary_ban_backoff,
-            metrics: None,
+            enable_metrics: false,
             protocol_version,
             peer_info_provider,
         }
     }
+
+    /// Replace connection limits and dial concurrency with preset for specified use case.
+    pub fn with_connection_limits_preset(mut self, preset: ConnectionLimitsPreset) -> Self {
+        let (established_connections, pending_connections, dial_concurrency_factor) = match preset {
+            ConnectionLimitsPreset::Farmer => (50, 50, 4),
+            ConnectionLimitsPreset::Node => (100, 100, 8),
+            ConnectionLimitsPreset::BootstrapNode => (300, 300, 8),
+        };
+
+        self.max_established_incoming_connections = established_connections;
+        self.max_established_outgoing_connections = established_connections;
+        self.max_pending_incoming_connections = pending_connections;
+        self.max_pending_outgoing_connections = pending_connections;
+        self.dial_concurrency_factor =
+            NonZeroU8::new(dial_concurrency_factor).expect("Not zero; qed");
+
+        self
+    }
+
+    /// Register additional request-response protocol handled by provided handler.
+    ///
+    /// Protocol names must be unique, otherwise [`create`] will fail with
+    /// [`CreationError::RequestResponseProtocolRegistration`].
+    pub fn with_request_response_protocol<Request, RH, Fut>(mut self, request_handler: RH) -> Self
+    where
+        Request: GenericRequest,
+        RH: (Fn(PeerId, &Request) -> Fut) + Send + Sync + 'static,
+        Fut: Future<Output = Option<Request::Response>> + Send + 'static,
+    {
+        self.request_response_protocols
+            .push(GenericRequestHandler::<Request>::create(request_handler));
+
+        self
+    }
+
+    /// Override timeout of outgoing requests of request-response protocol, useful since requests
+    /// with large responses (like pieces) need much more time than small ones.
+    pub fn with_request_timeout<Request>(mut self, timeout: Duration) -> Self
+    where
+        Request: GenericRequest,
+    {
+        self.request_timeouts
+            .insert(Request::PROTOCOL_NAME, timeout);
+
+        self
+    }
+
+    /// Register gossip topic with validator of incoming messages, only accepted messages are
+    /// propagated further and delivered to subscribers. Enables gossipsub with default
+    /// configuration unless it was already enabled.
+    ///
+    /// Topic names must be unique, otherwise [`create`] will fail with
+    /// [`CreationError::DuplicateGossipTopic`].
+    pub fn with_gossip_topic<Topic, V>(mut self, validator: V) -> Self
+    where
+        Topic: GossipTopic,
+        V: (Fn(PeerId, &Topic) -> MessageAcceptance) + Send + Sync + 'static,
+    {
+        self.gossipsub.get_or_insert_with(default_gossipsub_config);
+        self.gossip_topic_validators
+            .push(GossipTopicValidator::new(validator));
+
+        self
+    }
+}
+
+fn default_gossipsub_config() -> GossipsubConfig {
+    GossipsubConfigBuilder::default()
+        .protocol_id_prefix(GOSSIPSUB_PROTOCOL_PREFIX)
+        // TODO: Do we want message signing?
+        .validation_mode(ValidationMode::None)
+        // Messages are only propagated after validation by topic validator (if any)
+        .validate_messages()
+        // To content-address message, we can take the hash of message and use it as an ID.
+        .message_id_fn(|message: &GossipsubMessage| {
+            MessageId::from(crypto::blake2b_256_hash(&message.data))
+        })
+        .max_transmit_size(2 * 1024 * 1024) // 2MB
+        .build()
+        .expect("Default config for gossipsub is always correct; qed")
 }
 
 /// Errors that might happen during network creation.
@@ -360,6 +558,12 @@ pub enum CreationError {
     /// ParityDb storage error
     #[error("ParityDb storage error: {0}")]
     ParityDbStorageError(#[from] parity_db::Error),
+    /// Request-response protocol registration error.
+    #[error("Request-response protocol registration error: {0}")]
+    RequestResponseProtocolRegistration(#[from] RegisterError),
+    /// Gossip topic was registered more than once.
+    #[error("Gossip topic {0} was registered more than once")]
+    DuplicateGossipTopic(&'static str),
 }
 
 /// Converts public key from keypair to PeerId.
@@ -380,24 +584,39 @@ where
         listen_on,
         listen_on_fallback_to_random_port,
         timeout,
+        connection_idle_timeout,
+        keep_alive_policy,
         identify,
-        kademlia,
+        mut kademlia,
+        kademlia_query_options,
         gossipsub,
+        gossip_topic_validators,
+        autonat,
+        relay_server,
         provider_storage,
         yamux_config,
         allow_non_global_addresses_in_dht,
+        enable_memory_transport,
+        enable_quic,
+        prefer_quic_for_dials,
         initial_random_query_interval,
         networking_parameters_registry,
         request_response_protocols,
+        request_timeouts,
         reserved_peers,
         max_established_incoming_connections,
         max_established_outgoing_connections,
         max_pending_incoming_connections,
         max_pending_outgoing_connections,
         target_connections,
+        max_established_connections_per_peer,
+        dial_concurrency_factor,
+        provider_record_ttl,
+        provider_republication_interval,
+        provider_republication_batch_interval,
         temporary_bans_cache_size,
         temporary_ban_backoff,
-        metrics,
+        enable_metrics,
         protocol_version,
         peer_info_provider,
     } = config;
@@ -409,35 +628,97 @@ where
     )));
     let transport = build_transport(
         allow_non_global_addresses_in_dht,
+        enable_memory_transport,
+        enable_quic,
         &keypair,
         Arc::clone(&temporary_bans),
         timeout,
         yamux_config,
     )?;
+    let bandwidth_tracker = Arc::new(BandwidthTracker::default());
+    let transport = transport
+        .map({
+            let bandwidth_tracker = Arc::clone(&bandwidth_tracker);
+
+            move |(peer_id, muxer), _| {
+                let muxer = BandwidthMuxer::new(muxer, peer_id, Arc::clone(&bandwidth_tracker));
+                (peer_id, StreamMuxerBox::new(muxer))
+            }
+        })
+        .boxed();
+    let (transport, metrics) = if enable_metrics {
+        let (transport, bandwidth_sinks) = transport.with_bandwidth_logging();
+        let metrics = NetworkingMetrics::new(bandwidth_sinks, Arc::clone(&bandwidth_tracker));
+        (transport, Some(metrics))
+    } else {
+        (transport, None)
+    };
+    let (networking_metrics, metrics_recorder) = metrics.unzip();
 
     info!(
         %allow_non_global_addresses_in_dht,
+        %enable_quic,
         peer_id = %local_peer_id,
         %protocol_version,
         "DSN instance configured."
     );
 
+    kademlia
+        .set_connection_idle_timeout(connection_idle_timeout)
+        .set_provider_record_ttl(provider_record_ttl)
+        // Republication is done by node runner, such that it is spread over time
+        .set_provider_publication_interval(None);
+    let mut gossip_topic_validators_by_hash = HashMap::with_capacity(gossip_topic_validators.len());
+    for GossipTopicValidator {
+        topic_name,
+        topic_hash,
+        validator,
+    } in gossip_topic_validators
+    {
+        if gossip_topic_validators_by_hash
+            .insert(topic_hash, validator)
+            .is_some()
+        {
+            return Err(CreationError::DuplicateGossipTopic(topic_name));
+        }
+    }
+
+    let provider_republication_scheduler =
+        provider_republication_interval.map(|republication_interval| {
+            ProviderRepublicationScheduler::new(
+                republication_interval,
+                provider_republication_batch_interval,
+            )
+        });
+
     let connection_limits = ConnectionLimits::default()
-        .with_max_established_per_peer(SWARM_MAX_ESTABLISHED_CONNECTIONS_PER_PEER)
+        .with_max_established_per_peer(max_established_connections_per_peer)
         .with_max_pending_incoming(Some(max_pending_incoming_connections))
         .with_max_pending_outgoing(Some(max_pending_outgoing_connections))
         .with_max_established_incoming(Some(max_established_incoming_connections))
         .with_max_established_outgoing(Some(max_established_outgoing_connections));
 
-    debug!(?connection_limits, "DSN connection limits set.");
+    debug!(
+        ?connection_limits,
+        %dial_concurrency_factor,
+        "DSN connection limits set."
+    );
 
     let behaviour = Behavior::new(BehaviorConfig {
         peer_id: local_peer_id,
         identify,
         kademlia,
         gossipsub,
-        record_store: ProviderOnlyRecordStore::new(provider_storage),
+        autonat: autonat.map(|mut autonat| {
+            autonat.only_global_ips = !allow_non_global_addresses_in_dht;
+            autonat
+        }),
+        relay_server,
+        record_store: ProviderOnlyRecordStore::new(provider_storage, local_peer_id),
         request_response_protocols,
+        request_timeouts,
+        connection_idle_timeout,
+        keep_alive_all_connections: keep_alive_policy == KeepAlivePolicy::AllConnections,
         connection_limits,
         reserved_peers: ReservedPeersConfig {
             reserved_peers: reserved_peers.clone(),
@@ -445,10 +726,11 @@ where
         },
         peer_info_config: PeerInfoConfig::new(PEER_INFO_PROTOCOL_NAME),
         peer_info_provider,
-    });
+    })?;
 
     let mut swarm = SwarmBuilder::with_tokio_executor(transport, behaviour, local_peer_id)
         .max_negotiating_inbound_streams(SWARM_MAX_NEGOTIATING_INBOUND_STREAMS)
+        .dial_concurrency_factor(dial_concurrency_factor)
         .build();
 
     // Setup listen_on addresses
@@ -479,22 +761,29 @@ where
         command_sender,
         kademlia_tasks_semaphore,
         regular_tasks_semaphore,
+        kademlia_query_options,
+        bandwidth_tracker,
+        networking_metrics,
     ));
     let shared_weak = Arc::downgrade(&shared);
 
     let node = Node::new(shared);
     let node_runner = NodeRunner::<ProviderStorage>::new(NodeRunnerConfig::<ProviderStorage> {
         allow_non_global_addresses_in_dht,
+        prefer_quic_for_dials: enable_quic && prefer_quic_for_dials,
         command_receiver,
         swarm,
         shared_weak,
         next_random_query_interval: initial_random_query_interval,
         networking_parameters_registry,
+        gossip_topic_validators: gossip_topic_validators_by_hash,
         reserved_peers: convert_multiaddresses(reserved_peers).into_iter().collect(),
         target_connections,
         temporary_bans,
-        metrics,
+        metrics_recorder,
         protocol_version,
+        provider_record_ttl,
+        provider_republication_scheduler,
     });
 
     Ok((node, node_runner))
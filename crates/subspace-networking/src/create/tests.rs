@@ -1,4 +1,7 @@
+use crate::{Config, CreationError, GossipTopic, PieceByHashRequest, PieceByHashResponse};
 use futures::future::{select, Either};
+use libp2p::gossipsub::MessageAcceptance;
+use parity_scale_codec::{Decode, Encode};
 use std::num::NonZeroUsize;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
@@ -120,3 +123,39 @@ async fn maintain_semaphore_permits_capacity() {
         }
     }
 }
+
+#[tokio::test]
+async fn duplicate_request_response_protocol() {
+    let config = Config::default()
+        .with_request_response_protocol(|_, _: &PieceByHashRequest| async {
+            Some(PieceByHashResponse { piece: None })
+        })
+        .with_request_response_protocol(|_, _: &PieceByHashRequest| async {
+            Some(PieceByHashResponse { piece: None })
+        });
+
+    assert!(matches!(
+        super::create(config),
+        Err(CreationError::RequestResponseProtocolRegistration(_))
+    ));
+}
+
+#[derive(Encode, Decode)]
+struct TestGossipMessage;
+
+impl GossipTopic for TestGossipMessage {
+    const TOPIC_NAME: &'static str = "test-gossip-topic";
+    const LOG_TARGET: &'static str = "test-gossip-topic";
+}
+
+#[tokio::test]
+async fn duplicate_gossip_topic() {
+    let config = Config::default()
+        .with_gossip_topic(|_, _: &TestGossipMessage| MessageAcceptance::Accept)
+        .with_gossip_topic(|_, _: &TestGossipMessage| MessageAcceptance::Ignore);
+
+    assert!(matches!(
+        super::create(config),
+        Err(CreationError::DuplicateGossipTopic("test-gossip-topic"))
+    ));
+}
@@ -3,7 +3,7 @@ use crate::CreationError;
 use futures::future::Either;
 use libp2p::core::multiaddr::{Multiaddr, Protocol};
 use libp2p::core::muxing::StreamMuxerBox;
-use libp2p::core::transport::{Boxed, ListenerId, TransportError, TransportEvent};
+use libp2p::core::transport::{Boxed, ListenerId, MemoryTransport, TransportError, TransportEvent};
 use libp2p::core::Transport;
 use libp2p::dns::TokioDnsConfig;
 use libp2p::quic::tokio::Transport as QuicTransport;
@@ -24,11 +24,25 @@ use tracing::debug;
 // Builds the transport stack that LibP2P will communicate over along with a relay client.
 pub(super) fn build_transport(
     allow_non_global_addresses_in_dht: bool,
+    enable_memory_transport: bool,
+    enable_quic: bool,
     keypair: &identity::Keypair,
     temporary_bans: Arc<Mutex<TemporaryBans>>,
     timeout: Duration,
     yamux_config: YamuxConfig,
 ) -> Result<Boxed<(PeerId, StreamMuxerBox)>, CreationError> {
+    let memory_upgraded = enable_memory_transport.then(|| {
+        let noise =
+            noise::Config::new(keypair).expect("Signing libp2p-noise static DH keypair failed.");
+
+        MemoryTransport::default()
+            .upgrade(core::upgrade::Version::V1Lazy)
+            .authenticate(noise)
+            .multiplex(yamux_config.clone())
+            .timeout(timeout)
+            .boxed()
+    });
+
     let wrapped_tcp_ws = {
         let wrapped_tcp = CustomTransportWrapper::new(
             TokioTcpTransport::new(GenTcpConfig::default().nodelay(true)),
@@ -57,22 +71,37 @@ pub(super) fn build_transport(
             .boxed()
     };
 
-    let quic = QuicTransport::new(QuicConfig::new(keypair))
-        .map(|(peer_id, muxer), _| (peer_id, StreamMuxerBox::new(muxer)));
+    let tcp_ws_quic = if enable_quic {
+        let quic = QuicTransport::new(QuicConfig::new(keypair))
+            .map(|(peer_id, muxer), _| (peer_id, StreamMuxerBox::new(muxer)));
 
-    let wrapped_quic =
-        CustomTransportWrapper::new(quic, allow_non_global_addresses_in_dht, temporary_bans);
+        let wrapped_quic =
+            CustomTransportWrapper::new(quic, allow_non_global_addresses_in_dht, temporary_bans);
 
-    let tcp_ws_quic = tcp_ws_upgraded
-        .or_transport(wrapped_quic)
-        .map(|either, _| match either {
-            Either::Left((peer_id, muxer)) => (peer_id, muxer),
-            Either::Right((peer_id, muxer)) => (peer_id, muxer),
-        });
+        tcp_ws_upgraded
+            .or_transport(wrapped_quic)
+            .map(|either, _| match either {
+                Either::Left((peer_id, muxer)) => (peer_id, muxer),
+                Either::Right((peer_id, muxer)) => (peer_id, muxer),
+            })
+            .boxed()
+    } else {
+        tcp_ws_upgraded
+    };
 
     let dns_wrapped_upgraded_tcp_ws_quic = TokioDnsConfig::system(tcp_ws_quic)?;
 
-    Ok(dns_wrapped_upgraded_tcp_ws_quic.boxed())
+    let Some(memory_upgraded) = memory_upgraded else {
+        return Ok(dns_wrapped_upgraded_tcp_ws_quic.boxed());
+    };
+
+    Ok(dns_wrapped_upgraded_tcp_ws_quic
+        .or_transport(memory_upgraded)
+        .map(|either, _| match either {
+            Either::Left((peer_id, muxer)) => (peer_id, muxer),
+            Either::Right((peer_id, muxer)) => (peer_id, muxer),
+        })
+        .boxed())
 }
 
 #[derive(Debug, Clone)]
@@ -131,7 +160,8 @@ where
                 }
             }
             _ => {
-                // TODO: This will not catch DNS records pointing to private addresses
+                // DNS transport wraps this one, so DNS records are resolved before reaching this
+                // point and non-global addresses they point to are rejected above
             }
         }
 
@@ -0,0 +1,121 @@
+//! Typed publish/subscribe API on top of gossipsub.
+//!
+//! Every [`GossipTopic`] defines topic name and message type, messages are SCALE-encoded and
+//! validated by callback registered with [`crate::Config::with_gossip_topic()`] before being
+//! propagated further and delivered to local subscribers.
+
+use crate::node::TopicSubscription;
+use bytes::Bytes;
+use futures::Stream;
+use libp2p::gossipsub::{MessageAcceptance, Sha256Topic, TopicHash};
+use libp2p::PeerId;
+use parity_scale_codec::{Decode, Encode};
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tracing::debug;
+
+/// Gossip topic with associated message type.
+pub trait GossipTopic: Encode + Decode + Send + Sync + 'static {
+    /// Defines gossip topic name, must be unique.
+    const TOPIC_NAME: &'static str;
+    /// Specifies log-parameters for tracing.
+    const LOG_TARGET: &'static str;
+
+    /// Gossipsub topic messages are published to.
+    fn topic() -> Sha256Topic {
+        Sha256Topic::new(Self::TOPIC_NAME)
+    }
+}
+
+pub(crate) type MessageValidatorFn =
+    Arc<dyn (Fn(PeerId, &[u8]) -> MessageAcceptance) + Send + Sync + 'static>;
+
+/// Validator of incoming messages of a particular gossip topic.
+#[derive(Clone)]
+pub struct GossipTopicValidator {
+    pub(crate) topic_name: &'static str,
+    pub(crate) topic_hash: TopicHash,
+    pub(crate) validator: MessageValidatorFn,
+}
+
+impl GossipTopicValidator {
+    /// Create validator for messages of specified topic, messages that can't be decoded are
+    /// rejected without calling provided callback.
+    pub fn new<Topic, V>(validator: V) -> Self
+    where
+        Topic: GossipTopic,
+        V: (Fn(PeerId, &Topic) -> MessageAcceptance) + Send + Sync + 'static,
+    {
+        Self {
+            topic_name: Topic::TOPIC_NAME,
+            topic_hash: Topic::topic().hash(),
+            validator: Arc::new(move |peer_id, mut data| match Topic::decode(&mut data) {
+                Ok(message) => validator(peer_id, &message),
+                Err(error) => {
+                    debug!(
+                        target: Topic::LOG_TARGET,
+                        %peer_id,
+                        %error,
+                        "Failed to decode gossip message"
+                    );
+
+                    MessageAcceptance::Reject
+                }
+            }),
+        }
+    }
+}
+
+/// Subscription to gossip topic yielding decoded messages, will unsubscribe when last instance is
+/// dropped for a particular topic.
+#[derive(Debug)]
+#[pin_project::pin_project]
+pub struct TypedTopicSubscription<Topic> {
+    #[pin]
+    subscription: TopicSubscription,
+    _phantom: PhantomData<Topic>,
+}
+
+impl<Topic> TypedTopicSubscription<Topic> {
+    pub(crate) fn new(subscription: TopicSubscription) -> Self {
+        Self {
+            subscription,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<Topic> Stream for TypedTopicSubscription<Topic>
+where
+    Topic: GossipTopic,
+{
+    type Item = Topic;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut subscription = self.project().subscription;
+
+        loop {
+            let bytes: Bytes = match subscription.as_mut().poll_next(cx) {
+                Poll::Ready(Some(bytes)) => bytes,
+                Poll::Ready(None) => {
+                    return Poll::Ready(None);
+                }
+                Poll::Pending => {
+                    return Poll::Pending;
+                }
+            };
+
+            match Topic::decode(&mut bytes.as_ref()) {
+                Ok(message) => {
+                    return Poll::Ready(Some(message));
+                }
+                Err(error) => {
+                    // Only happens for topics without validator, skip such messages
+                    debug!(target: Topic::LOG_TARGET, %error, "Failed to decode gossip message");
+                }
+            }
+        }
+    }
+}
@@ -18,34 +18,50 @@
 #![feature(const_option, impl_trait_in_assoc_type, ip, try_blocks)]
 #![warn(missing_docs)]
 
+mod bandwidth;
 mod behavior;
 mod create;
+mod gossip;
+mod metrics;
 mod node;
 mod node_runner;
 mod peer_info;
+mod peer_reputation;
 mod request_handlers;
 mod request_responses;
 mod reserved_peers;
 mod shared;
 pub mod utils;
 
+pub use crate::bandwidth::BandwidthUsage;
 pub use crate::behavior::persistent_parameters::{
     BootstrappedNetworkingParameters, NetworkParametersPersistenceError,
     NetworkingParametersManager, ParityDbError,
 };
+pub use crate::gossip::{GossipTopic, GossipTopicValidator, TypedTopicSubscription};
+pub use crate::metrics::NetworkingMetrics;
 pub use crate::node::{
-    GetClosestPeersError, Node, SendRequestError, SubscribeError, TopicSubscription,
+    ConnectionEvent, GetClosestPeersError, GetRoutingTableError, IdentifyInfo,
+    KademliaQueryOptions, Node, ProvidersLookupTrace, PublishError, RoutingTableBucket,
+    RoutingTablePeer, SendRequestError, SubscribeError, TopicSubscription,
 };
 pub use crate::node_runner::{NodeRunner, KADEMLIA_PROVIDER_TTL_IN_SECS};
 pub use crate::peer_info::{
     Config as PeerInfoConfig, CuckooFilterDTO, CuckooFilterProvider, Notification,
     NotificationHandler, PeerInfo, PeerInfoProvider,
 };
+pub use crate::peer_reputation::PeerScore;
 pub use behavior::provider_storage::{
-    MemoryProviderStorage, ParityDbProviderStorage, ProviderStorage, VoidProviderStorage,
+    MemoryProviderStorage, ParityDbProviderStorage, ProviderStorage, ProviderStorageStats,
+    VoidProviderStorage,
+};
+pub use create::{
+    create, peer_id, Config, ConnectionLimitsPreset, CreationError, KeepAlivePolicy, RelayMode,
 };
-pub use create::{create, peer_id, Config, CreationError, RelayMode};
 pub use libp2p;
+pub use request_handlers::chunked::{
+    ChunkRequest, ChunkResponse, ChunkedRequest, ChunkedRequestHandler,
+};
 pub use request_handlers::generic_request_handler::{GenericRequest, GenericRequestHandler};
 pub use request_handlers::object_mappings::{
     ObjectMappingsRequest, ObjectMappingsRequestHandler, ObjectMappingsResponse,
@@ -60,7 +76,10 @@ pub use request_handlers::pieces_by_range::{
     PiecesByRangeRequest, PiecesByRangeRequestHandler, PiecesByRangeResponse, PiecesToPlot,
 };
 pub use request_handlers::segment_header::{
-    SegmentHeaderBySegmentIndexesRequestHandler, SegmentHeaderRequest, SegmentHeaderResponse,
+    verify_segment_headers_range, SegmentHeaderBySegmentIndexesRequestHandler,
+    SegmentHeaderRequest, SegmentHeaderResponse, SegmentHeadersVerificationError,
+    SEGMENT_INDEX_RANGE_LIMIT,
 };
+pub use request_responses::RegisterError;
 pub use utils::prometheus::start_prometheus_metrics_server;
 pub use utils::unique_record_binary_heap::UniqueRecordBinaryHeap;
@@ -0,0 +1,232 @@
+//! Metrics of the networking stack.
+//!
+//! Networking owns a separate `prometheus_client` registry with libp2p metrics (swarm events,
+//! Kademlia queries, etc.), transport bandwidth (total and per protocol), relay server usage and
+//! provider storage usage, which host application can merge into its own metrics.
+
+use crate::bandwidth::BandwidthTracker;
+use crate::behavior::provider_storage::ProviderStorageStats;
+use libp2p::bandwidth::BandwidthSinks;
+use libp2p::metrics::{Metrics, Recorder};
+use libp2p::relay::Event as RelayServerEvent;
+use parking_lot::Mutex;
+use prometheus_client::encoding::text::encode;
+use prometheus_client::metrics::counter::Counter;
+use prometheus_client::metrics::family::Family;
+use prometheus_client::metrics::gauge::Gauge;
+use prometheus_client::registry::Registry;
+use std::fmt;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+/// Marker at the end of metrics encoded in OpenMetrics text format.
+const EOF_MARKER: &str = "# EOF\n";
+
+/// Labels of per-protocol metrics, contain protocol name.
+type ProtocolLabels = Vec<(&'static str, String)>;
+
+/// Metrics collected by networking stack, cheap to clone.
+#[derive(Debug, Clone)]
+pub struct NetworkingMetrics {
+    registry: Arc<Mutex<Registry>>,
+}
+
+impl NetworkingMetrics {
+    pub(crate) fn new(
+        bandwidth_sinks: Arc<BandwidthSinks>,
+        bandwidth_tracker: Arc<BandwidthTracker>,
+    ) -> (Self, MetricsRecorder) {
+        let mut registry = Registry::default();
+        let libp2p = Metrics::new(&mut registry);
+
+        let inbound_bytes = Counter::default();
+        let outbound_bytes = Counter::default();
+        let bandwidth_registry = registry.sub_registry_with_prefix("libp2p_bandwidth");
+        bandwidth_registry.register(
+            "inbound_bytes",
+            "Number of bytes received over all connections",
+            inbound_bytes.clone(),
+        );
+        bandwidth_registry.register(
+            "outbound_bytes",
+            "Number of bytes sent over all connections",
+            outbound_bytes.clone(),
+        );
+        let protocol_inbound_bytes = Family::<ProtocolLabels, Counter>::default();
+        let protocol_outbound_bytes = Family::<ProtocolLabels, Counter>::default();
+        bandwidth_registry.register(
+            "protocol_inbound_bytes",
+            "Number of bytes received per protocol",
+            protocol_inbound_bytes.clone(),
+        );
+        bandwidth_registry.register(
+            "protocol_outbound_bytes",
+            "Number of bytes sent per protocol",
+            protocol_outbound_bytes.clone(),
+        );
+
+        // Relay server in libp2p 0.51 doesn't report number of relayed bytes, relayed traffic is
+        // only accounted for in bandwidth metrics above
+        let relay_active_reservations = Gauge::default();
+        let relay_active_circuits = Gauge::default();
+        let relay_server_registry = registry.sub_registry_with_prefix("libp2p_relay_server");
+        relay_server_registry.register(
+            "active_reservations",
+            "Number of active reservations of peers relayed through this node",
+            relay_active_reservations.clone(),
+        );
+        relay_server_registry.register(
+            "active_circuits",
+            "Number of active circuits relayed through this node",
+            relay_active_circuits.clone(),
+        );
+
+        let provider_storage_stored_keys = Gauge::default();
+        let provider_storage_evicted_keys = Counter::default();
+        let provider_storage_expired_records = Counter::default();
+        let provider_storage_registry = registry.sub_registry_with_prefix("provider_storage");
+        provider_storage_registry.register(
+            "stored_keys",
+            "Number of keys provider records are stored for",
+            provider_storage_stored_keys.clone(),
+        );
+        provider_storage_registry.register(
+            "evicted_keys",
+            "Number of keys whose provider records were evicted due to storage size limit",
+            provider_storage_evicted_keys.clone(),
+        );
+        provider_storage_registry.register(
+            "expired_records",
+            "Number of expired provider records removed from storage",
+            provider_storage_expired_records.clone(),
+        );
+
+        let networking_metrics = Self {
+            registry: Arc::new(Mutex::new(registry)),
+        };
+        let metrics_recorder = MetricsRecorder {
+            libp2p,
+            bandwidth_sinks,
+            inbound_bytes,
+            outbound_bytes,
+            bandwidth_tracker,
+            protocol_inbound_bytes,
+            protocol_outbound_bytes,
+            relay_active_reservations,
+            relay_active_circuits,
+            provider_storage_stored_keys,
+            provider_storage_evicted_keys,
+            provider_storage_expired_records,
+        };
+
+        (networking_metrics, metrics_recorder)
+    }
+
+    /// Encode current metrics in OpenMetrics text format.
+    pub fn encode(&self, output: &mut String) -> fmt::Result {
+        encode(output, &self.registry.lock())
+    }
+
+    /// Merge current metrics into metrics of the host application that were already encoded in
+    /// OpenMetrics text format into `output`, keeping `# EOF` marker at the very end.
+    pub fn merge_into(&self, output: &mut String) -> fmt::Result {
+        let had_eof_marker = output.ends_with(EOF_MARKER);
+        if had_eof_marker {
+            output.truncate(output.len() - EOF_MARKER.len());
+        }
+
+        let mut encoded = String::new();
+        self.encode(&mut encoded)?;
+        output.push_str(encoded.strip_suffix(EOF_MARKER).unwrap_or(&encoded));
+
+        if had_eof_marker {
+            output.push_str(EOF_MARKER);
+        }
+
+        Ok(())
+    }
+}
+
+/// Records networking events into [`NetworkingMetrics`].
+pub(crate) struct MetricsRecorder {
+    libp2p: Metrics,
+    bandwidth_sinks: Arc<BandwidthSinks>,
+    inbound_bytes: Counter,
+    outbound_bytes: Counter,
+    bandwidth_tracker: Arc<BandwidthTracker>,
+    protocol_inbound_bytes: Family<ProtocolLabels, Counter>,
+    protocol_outbound_bytes: Family<ProtocolLabels, Counter>,
+    relay_active_reservations: Gauge,
+    relay_active_circuits: Gauge,
+    provider_storage_stored_keys: Gauge,
+    provider_storage_evicted_keys: Counter,
+    provider_storage_expired_records: Counter,
+}
+
+impl MetricsRecorder {
+    /// Record libp2p event.
+    pub(crate) fn record<Event>(&self, event: &Event)
+    where
+        Metrics: Recorder<Event>,
+    {
+        self.libp2p.record(event);
+    }
+
+    /// Record relay server event, keeping track of active circuits.
+    pub(crate) fn record_relay_server_event(&self, event: &RelayServerEvent) {
+        self.libp2p.record(event);
+
+        match event {
+            RelayServerEvent::CircuitReqAccepted { .. } => {
+                self.relay_active_circuits.inc();
+            }
+            RelayServerEvent::CircuitClosed { .. } => {
+                self.relay_active_circuits.dec();
+            }
+            _ => {}
+        }
+    }
+
+    /// Set number of active relay reservations.
+    pub(crate) fn set_relay_active_reservations(&self, reservations: usize) {
+        self.relay_active_reservations.set(reservations as i64);
+    }
+
+    /// Update provider storage metrics with statistics tracked by storage.
+    pub(crate) fn update_provider_storage(
+        &self,
+        stats: ProviderStorageStats,
+        expired_records: u64,
+    ) {
+        self.provider_storage_stored_keys
+            .set(stats.stored_keys as i64);
+        self.provider_storage_evicted_keys
+            .inner()
+            .store(stats.evicted_keys, Ordering::Relaxed);
+        self.provider_storage_expired_records
+            .inner()
+            .store(expired_records, Ordering::Relaxed);
+    }
+
+    /// Update bandwidth counters with totals tracked by transport.
+    pub(crate) fn update_bandwidth(&self) {
+        self.inbound_bytes
+            .inner()
+            .store(self.bandwidth_sinks.total_inbound(), Ordering::Relaxed);
+        self.outbound_bytes
+            .inner()
+            .store(self.bandwidth_sinks.total_outbound(), Ordering::Relaxed);
+
+        for (protocol_name, usage) in self.bandwidth_tracker.protocols() {
+            let labels = vec![("protocol", protocol_name)];
+            self.protocol_inbound_bytes
+                .get_or_create(&labels)
+                .inner()
+                .store(usage.inbound_bytes, Ordering::Relaxed);
+            self.protocol_outbound_bytes
+                .get_or_create(&labels)
+                .inner()
+                .store(usage.outbound_bytes, Ordering::Relaxed);
+        }
+    }
+}
@@ -1,18 +1,28 @@
+use crate::bandwidth::BandwidthUsage;
+use crate::gossip::{GossipTopic, TypedTopicSubscription};
+use crate::metrics::NetworkingMetrics;
+use crate::peer_reputation::{PeerScore, RequestOutcome};
+use crate::request_handlers::chunked::{ChunkRequest, ChunkResponse, ChunkedRequest};
 use crate::request_handlers::generic_request_handler::GenericRequest;
-use crate::request_responses;
+use crate::request_responses::{self, OutboundFailure, RequestFailure};
 use crate::shared::{Command, CreatedSubscription, HandlerFn, Shared};
 use crate::utils::ResizableSemaphorePermit;
 use bytes::Bytes;
 use event_listener_primitives::HandlerId;
 use futures::channel::mpsc::SendError;
 use futures::channel::{mpsc, oneshot};
-use futures::{SinkExt, Stream};
+use futures::{future, stream, SinkExt, Stream, StreamExt};
+use libp2p::autonat::NatStatus;
 use libp2p::core::multihash::Multihash;
+use libp2p::core::ConnectedPoint;
 use libp2p::gossipsub::{Sha256Topic, SubscriptionError};
 use libp2p::kad::record::Key;
 use libp2p::kad::PeerRecord;
+use libp2p::swarm::DialError;
 use libp2p::{Multiaddr, PeerId};
-use parity_scale_codec::Decode;
+use std::collections::HashMap;
+use std::iter;
+use std::num::NonZeroUsize;
 use std::pin::Pin;
 use std::sync::Arc;
 use std::task::{Context, Poll};
@@ -68,6 +78,139 @@ impl PinnedDrop for TopicSubscription {
     }
 }
 
+/// Options of Kademlia `get_value`/`put_value`/`get_providers` queries, see
+/// [`Config::kademlia_query_options`](crate::Config::kademlia_query_options) for defaults used by
+/// [`Node`] and `*_with_options` methods of [`Node`] for overriding them for individual calls.
+///
+/// Number of peers queried in parallel is configured for all queries with
+/// [`KademliaConfig::set_parallelism`](libp2p::kad::KademliaConfig::set_parallelism).
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct KademliaQueryOptions {
+    /// Number of peers that must store the record for `put_value` to succeed, for `get_value` and
+    /// `get_providers` it is the number of records or providers that must be found for the query
+    /// to not be retried.
+    pub quorum: NonZeroUsize,
+    /// How many times the query is repeated from scratch if it didn't reach the quorum.
+    pub retries: usize,
+    /// Timeout for the whole operation including retries, after which the result stream ends,
+    /// `None` means individual queries are only bounded by Kademlia query timeout.
+    pub timeout: Option<Duration>,
+}
+
+impl Default for KademliaQueryOptions {
+    fn default() -> Self {
+        Self {
+            quorum: NonZeroUsize::new(1).expect("Not zero; qed"),
+            retries: 0,
+            timeout: None,
+        }
+    }
+}
+
+impl KademliaQueryOptions {
+    /// Ends result stream when timeout (if any) is reached.
+    fn with_timeout<S>(&self, stream: S) -> impl Stream<Item = S::Item>
+    where
+        S: Stream,
+    {
+        let timeout = self.timeout;
+
+        // Boxed such that resulting stream is `Unpin` just like underlying receiver
+        stream.take_until(Box::pin(async move {
+            match timeout {
+                Some(timeout) => sleep(timeout).await,
+                None => future::pending::<()>().await,
+            }
+        }))
+    }
+}
+
+/// Information connected peer has reported about itself using identify protocol.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct IdentifyInfo {
+    /// Protocol version (includes DSN protocol prefix, peers with a different one are banned).
+    pub protocol_version: String,
+    /// Name and version of the peer's software.
+    pub agent_version: String,
+    /// Protocols supported by the peer.
+    pub protocols: Vec<String>,
+    /// Addresses the peer is listening on (truncated to a reasonable number).
+    pub listen_addrs: Vec<Multiaddr>,
+    /// Address of local node as observed by the peer.
+    pub observed_addr: Multiaddr,
+}
+
+/// Connection-level event of the swarm, see [`Node::on_connection_event()`].
+#[derive(Debug, Clone)]
+pub enum ConnectionEvent {
+    /// Connection with peer was established.
+    ConnectionEstablished {
+        /// Peer ID.
+        peer_id: PeerId,
+        /// Endpoint of the connection.
+        endpoint: ConnectedPoint,
+        /// Number of established connections with this peer, including this one.
+        num_established: u32,
+    },
+    /// Connection with peer was closed.
+    ConnectionClosed {
+        /// Peer ID.
+        peer_id: PeerId,
+        /// Endpoint of the connection.
+        endpoint: ConnectedPoint,
+        /// Number of remaining established connections with this peer, peer is disconnected when
+        /// it is zero.
+        num_established: u32,
+    },
+    /// Outgoing connection attempt failed.
+    DialFailed {
+        /// Peer ID, if known in advance.
+        peer_id: Option<PeerId>,
+        /// Cause of the failure.
+        error: Arc<DialError>,
+    },
+}
+
+/// Peer in Kademlia routing table.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct RoutingTablePeer {
+    /// Peer ID.
+    pub peer_id: PeerId,
+    /// Known addresses of the peer.
+    pub addresses: Vec<Multiaddr>,
+    /// Whether peer is currently connected.
+    pub connected: bool,
+}
+
+/// Non-empty bucket of Kademlia routing table.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct RoutingTableBucket {
+    /// Base 2 logarithm of the distance between local peer and peers in this bucket.
+    pub distance_log2: u32,
+    /// Peers in the bucket.
+    pub peers: Vec<RoutingTablePeer>,
+}
+
+/// Result of provider lookup along with details of how it went, useful for investigating why
+/// specific pieces are not reachable.
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct ProvidersLookupTrace {
+    /// Providers that were found.
+    pub providers: Vec<PeerId>,
+    /// Peers closest to the key that were successfully contacted during lookup.
+    pub closest_peers: Vec<PeerId>,
+    /// Number of requests sent to peers.
+    pub requests: u32,
+    /// Number of successful requests.
+    pub successes: u32,
+    /// Number of failed requests.
+    pub failures: u32,
+    /// How long lookup took.
+    pub duration: Option<Duration>,
+    /// Whether lookup has timed out.
+    pub timed_out: bool,
+}
+
 #[derive(Debug, Error)]
 pub enum GetValueError {
     /// Failed to send command to the node runner
@@ -120,6 +263,23 @@ impl From<oneshot::Canceled> for GetClosestPeersError {
     }
 }
 
+#[derive(Debug, Error)]
+pub enum GetRoutingTableError {
+    /// Failed to send command to the node runner
+    #[error("Failed to send command to the node runner: {0}")]
+    SendCommand(#[from] SendError),
+    /// Node runner was dropped
+    #[error("Node runner was dropped")]
+    NodeRunnerDropped,
+}
+
+impl From<oneshot::Canceled> for GetRoutingTableError {
+    #[inline]
+    fn from(oneshot::Canceled: oneshot::Canceled) -> Self {
+        Self::NodeRunnerDropped
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum CheckConnectedPeersError {
     /// Did not connect within provided timeout window.
@@ -272,10 +432,42 @@ impl Node {
         self.shared.id
     }
 
+    /// Whether node is publicly reachable, `Unknown` until AutoNAT probes complete or if AutoNAT
+    /// is disabled.
+    pub fn nat_status(&self) -> NatStatus {
+        self.shared.nat_status.lock().clone()
+    }
+
+    /// Networking metrics, `None` unless enabled in configuration.
+    pub fn metrics(&self) -> Option<&NetworkingMetrics> {
+        self.shared.metrics.as_ref()
+    }
+
+    /// Bandwidth usage per protocol name since node start, substreams whose protocol couldn't be
+    /// determined are accounted for under `unknown`.
+    pub fn bandwidth_usage_by_protocol(&self) -> HashMap<String, BandwidthUsage> {
+        self.shared.bandwidth_tracker.protocols()
+    }
+
+    /// Bandwidth usage of currently connected peers since connection establishment.
+    pub fn bandwidth_usage_by_peer(&self) -> HashMap<PeerId, BandwidthUsage> {
+        self.shared.bandwidth_tracker.peers()
+    }
+
     /// Return a value from the Kademlia network of the DSN.
     pub async fn get_value(
         &self,
         key: Multihash,
+    ) -> Result<impl Stream<Item = PeerRecord>, GetValueError> {
+        self.get_value_with_options(key, self.shared.kademlia_query_options)
+            .await
+    }
+
+    /// Return a value from the Kademlia network of the DSN using custom query options.
+    pub async fn get_value_with_options(
+        &self,
+        key: Multihash,
+        options: KademliaQueryOptions,
     ) -> Result<impl Stream<Item = PeerRecord>, GetValueError> {
         let permit = self.shared.kademlia_tasks_semaphore.acquire().await;
         let (result_sender, result_receiver) = mpsc::unbounded();
@@ -285,13 +477,15 @@ impl Node {
             .clone()
             .send(Command::GetValue {
                 key,
+                quorum: options.quorum,
+                retries: options.retries,
                 result_sender,
                 permit,
             })
             .await?;
 
         // TODO: A wrapper that'll immediately cancel query on drop
-        Ok(result_receiver)
+        Ok(options.with_timeout(result_receiver))
     }
 
     /// Puts a value into the Kademlia network of the DSN.
@@ -299,6 +493,17 @@ impl Node {
         &self,
         key: Multihash,
         value: Vec<u8>,
+    ) -> Result<impl Stream<Item = ()>, PutValueError> {
+        self.put_value_with_options(key, value, self.shared.kademlia_query_options)
+            .await
+    }
+
+    /// Puts a value into the Kademlia network of the DSN using custom query options.
+    pub async fn put_value_with_options(
+        &self,
+        key: Multihash,
+        value: Vec<u8>,
+        options: KademliaQueryOptions,
     ) -> Result<impl Stream<Item = ()>, PutValueError> {
         let permit = self.shared.kademlia_tasks_semaphore.acquire().await;
         let (result_sender, result_receiver) = mpsc::unbounded();
@@ -309,13 +514,15 @@ impl Node {
             .send(Command::PutValue {
                 key,
                 value,
+                quorum: options.quorum,
+                retries: options.retries,
                 result_sender,
                 permit,
             })
             .await?;
 
         // TODO: A wrapper that'll immediately cancel query on drop
-        Ok(result_receiver)
+        Ok(options.with_timeout(result_receiver))
     }
 
     /// Subcribe to some topic on the DSN.
@@ -364,6 +571,26 @@ impl Node {
         result_receiver.await?.map_err(PublishError::Publish)
     }
 
+    /// Subscribe to gossip topic, yields decoded messages that were accepted by topic validator.
+    pub async fn subscribe_to_topic<Topic>(
+        &self,
+    ) -> Result<TypedTopicSubscription<Topic>, SubscribeError>
+    where
+        Topic: GossipTopic,
+    {
+        let subscription = self.subscribe(Topic::topic()).await?;
+
+        Ok(TypedTopicSubscription::new(subscription))
+    }
+
+    /// Publish message to gossip topic.
+    pub async fn publish_to_topic<Topic>(&self, message: &Topic) -> Result<(), PublishError>
+    where
+        Topic: GossipTopic,
+    {
+        self.publish(Topic::topic(), message.encode()).await
+    }
+
     /// Sends the generic request to the peer and awaits the result.
     pub async fn send_generic_request<Request>(
         &self,
@@ -375,18 +602,131 @@ impl Node {
     {
         let _permit = self.shared.regular_tasks_semaphore.acquire().await;
         let (result_sender, result_receiver) = oneshot::channel();
+        // Request is encoded for every supported version, the newest version supported by the
+        // remote peer will be used
+        let request = iter::once(Request::PROTOCOL_NAME)
+            .chain(Request::FALLBACK_PROTOCOL_NAMES.iter().copied())
+            .map(|protocol_name| (protocol_name, request.encode_request(protocol_name)))
+            .collect();
         let command = Command::GenericRequest {
             peer_id,
             protocol_name: Request::PROTOCOL_NAME,
-            request: request.encode(),
+            request,
             result_sender,
         };
 
         self.shared.command_sender.clone().send(command).await?;
 
-        let result = result_receiver.await??;
+        let result = result_receiver
+            .await?
+            .map_err(SendRequestError::from)
+            .and_then(|(protocol_name, response)| {
+                Request::decode_response(protocol_name, &mut response.as_slice())
+                    .map_err(Into::into)
+            });
+
+        let maybe_outcome = match &result {
+            Ok(_) => Some(RequestOutcome::Success),
+            Err(SendRequestError::ProtocolFailure(RequestFailure::Network(
+                OutboundFailure::Timeout,
+            ))) => Some(RequestOutcome::Timeout),
+            Err(SendRequestError::ProtocolFailure(
+                RequestFailure::NotConnected | RequestFailure::Refused | RequestFailure::Network(_),
+            )) => Some(RequestOutcome::Failure),
+            Err(SendRequestError::IncorrectResponseFormat(_)) => {
+                Some(RequestOutcome::InvalidResponse)
+            }
+            // Local issues, not peer's fault
+            Err(_) => None,
+        };
+        if let Some(outcome) = maybe_outcome {
+            self.shared.peer_reputation.lock().report(peer_id, outcome);
+        }
+
+        result
+    }
+
+    /// Report that peer returned response that was well-formed, but invalid (for instance piece
+    /// that doesn't match its commitment), which lowers reputation of the peer.
+    pub fn report_invalid_response(&self, peer_id: PeerId) {
+        self.shared
+            .peer_reputation
+            .lock()
+            .report(peer_id, RequestOutcome::InvalidResponse);
+    }
+
+    /// Reputation of the peer based on outcome of requests sent to it, `None` if no requests were
+    /// sent to the peer recently.
+    pub fn peer_score(&self, peer_id: &PeerId) -> Option<PeerScore> {
+        self.shared.peer_reputation.lock().peer_score(peer_id)
+    }
+
+    /// Reputation of all peers requests were sent to recently, useful for debugging.
+    pub fn peer_scores(&self) -> Vec<(PeerId, PeerScore)> {
+        self.shared.peer_reputation.lock().peer_scores()
+    }
+
+    /// Currently connected peers.
+    pub fn connected_peers(&self) -> Vec<PeerId> {
+        self.shared.connected_peers.lock().keys().copied().collect()
+    }
+
+    /// Information connected peer has reported about itself, `None` if peer is not connected or
+    /// didn't identify itself yet.
+    pub fn peer_info(&self, peer_id: &PeerId) -> Option<IdentifyInfo> {
+        self.shared
+            .connected_peers
+            .lock()
+            .get(peer_id)
+            .cloned()
+            .flatten()
+    }
 
-        Request::Response::decode(&mut result.as_slice()).map_err(Into::into)
+    /// Sends the chunked request to the peer, returning stream of response chunks.
+    ///
+    /// Each chunk is requested separately and only when stream is polled for it, so memory usage
+    /// is bounded by chunk size on both ends and consumer controls the pace of the transfer.
+    /// Stream ends after the last chunk or after the first error.
+    pub fn send_chunked_request<Request>(
+        &self,
+        peer_id: PeerId,
+        request: Request,
+    ) -> impl Stream<Item = Result<Request::Chunk, SendRequestError>> + Send + 'static
+    where
+        Request: ChunkedRequest,
+    {
+        let node = self.clone();
+
+        stream::unfold(Some(0), move |maybe_chunk_index| {
+            let node = node.clone();
+            let request = request.clone();
+
+            async move {
+                let chunk_index = maybe_chunk_index?;
+                let result = node
+                    .send_generic_request(
+                        peer_id,
+                        ChunkRequest {
+                            request,
+                            chunk_index,
+                        },
+                    )
+                    .await;
+
+                Some(match result {
+                    Ok(ChunkResponse { chunk, is_last }) => {
+                        let next_chunk_index = if is_last {
+                            None
+                        } else {
+                            chunk_index.checked_add(1)
+                        };
+
+                        (Ok(chunk), next_chunk_index)
+                    }
+                    Err(error) => (Err(error), None),
+                })
+            }
+        })
     }
 
     /// Get closest peers by multihash key using Kademlia DHT.
@@ -495,6 +835,16 @@ impl Node {
     pub async fn get_providers(
         &self,
         key: Multihash,
+    ) -> Result<impl Stream<Item = PeerId>, GetProvidersError> {
+        self.get_providers_with_options(key, self.shared.kademlia_query_options)
+            .await
+    }
+
+    /// Get item providers by its key using custom query options.
+    pub async fn get_providers_with_options(
+        &self,
+        key: Multihash,
+        options: KademliaQueryOptions,
     ) -> Result<impl Stream<Item = PeerId>, GetProvidersError> {
         let permit = self.shared.kademlia_tasks_semaphore.acquire().await;
         let (result_sender, result_receiver) = mpsc::unbounded();
@@ -506,13 +856,52 @@ impl Node {
             .clone()
             .send(Command::GetProviders {
                 key,
+                quorum: options.quorum,
+                retries: options.retries,
                 result_sender,
                 permit,
             })
             .await?;
 
         // TODO: A wrapper that'll immediately cancel query on drop
-        Ok(result_receiver)
+        Ok(options.with_timeout(result_receiver))
+    }
+
+    /// Get item providers by its key just like [`Node::get_providers()`], but wait for lookup to
+    /// finish and return details about it.
+    pub async fn trace_get_providers(
+        &self,
+        key: Multihash,
+    ) -> Result<ProvidersLookupTrace, GetProvidersError> {
+        let permit = self.shared.kademlia_tasks_semaphore.acquire().await;
+        let (result_sender, result_receiver) = oneshot::channel();
+
+        trace!(?key, "Starting traced 'get_providers' request.");
+
+        self.shared
+            .command_sender
+            .clone()
+            .send(Command::TraceGetProviders {
+                key,
+                result_sender,
+                permit,
+            })
+            .await?;
+
+        Ok(result_receiver.await?)
+    }
+
+    /// Non-empty buckets of Kademlia routing table.
+    pub async fn routing_table(&self) -> Result<Vec<RoutingTableBucket>, GetRoutingTableError> {
+        let (result_sender, result_receiver) = oneshot::channel();
+
+        self.shared
+            .command_sender
+            .clone()
+            .send(Command::GetRoutingTable { result_sender })
+            .await?;
+
+        Ok(result_receiver.await?)
     }
 
     /// Ban peer with specified peer ID.
@@ -541,11 +930,28 @@ impl Node {
         self.shared.listeners.lock().clone()
     }
 
-    /// Node's own addresses observed remotely.
+    /// Node's own external addresses that are advertised to other peers and included in provider
+    /// records.
+    ///
+    /// With AutoNAT enabled these are only addresses confirmed to be reachable by dial-back,
+    /// see [`Node::observed_addresses()`] for all addresses observed by connected peers.
     pub fn external_addresses(&self) -> Vec<Multiaddr> {
         self.shared.external_addresses.lock().clone()
     }
 
+    /// Node's own addresses as observed by connected peers (not necessarily reachable), along with
+    /// number of peers that observed each address.
+    pub fn observed_addresses(&self) -> Vec<(Multiaddr, usize)> {
+        let mut observed_addresses = HashMap::<Multiaddr, usize>::new();
+        for identify_info in self.shared.connected_peers.lock().values().flatten() {
+            *observed_addresses
+                .entry(identify_info.observed_addr.clone())
+                .or_default() += 1;
+        }
+
+        observed_addresses.into_iter().collect()
+    }
+
     /// Callback is called when node starts listening on new address.
     pub fn on_new_listener(&self, callback: HandlerFn<Multiaddr>) -> HandlerId {
         self.shared.handlers.new_listener.add(callback)
@@ -561,4 +967,26 @@ impl Node {
             .num_established_peer_connections_change
             .add(callback)
     }
+
+    /// Callback is called on connection-level events: connections established and closed with
+    /// specific peers and failed dial attempts.
+    pub fn on_connection_event(&self, callback: HandlerFn<ConnectionEvent>) -> HandlerId {
+        self.shared.handlers.connection_event.add(callback)
+    }
+
+    /// Stream of connection-level events, see [`Node::on_connection_event()`]. Events are only
+    /// delivered while stream is alive.
+    pub fn connection_events(&self) -> impl Stream<Item = ConnectionEvent> + Send + Unpin {
+        let (events_sender, events_receiver) = mpsc::unbounded();
+        let handler_id = self.on_connection_event(Arc::new(move |event| {
+            // Doesn't matter if sending failed here
+            let _ = events_sender.unbounded_send(event.clone());
+        }));
+
+        events_receiver.map(move |event| {
+            // Handler is removed once stream is dropped
+            let _handler_id = &handler_id;
+            event
+        })
+    }
 }
@@ -1,3 +1,5 @@
+pub(crate) mod provider_republication;
+
 use crate::behavior::persistent_parameters::NetworkingParametersRegistry;
 use crate::behavior::{provider_storage, Behavior, Event};
 use crate::create::temporary_bans::TemporaryBans;
@@ -5,38 +7,47 @@ use crate::create::{
     ProviderOnlyRecordStore, KADEMLIA_CONCURRENT_TASKS_BOOST_PER_PEER,
     REGULAR_CONCURRENT_TASKS_BOOST_PER_PEER,
 };
+use crate::gossip::MessageValidatorFn;
+use crate::metrics::MetricsRecorder;
+use crate::node::{
+    ConnectionEvent, IdentifyInfo, ProvidersLookupTrace, RoutingTableBucket, RoutingTablePeer,
+};
+use crate::node_runner::provider_republication::ProviderRepublicationScheduler;
 use crate::request_responses::{Event as RequestResponseEvent, IfDisconnected};
 use crate::shared::{Command, CreatedSubscription, Shared};
-use crate::utils::{is_global_address_or_dns, ResizableSemaphorePermit};
+use crate::utils::{is_global_address_or_dns, is_quic_address, ResizableSemaphorePermit};
 use bytes::Bytes;
-use futures::channel::mpsc;
+use futures::channel::{mpsc, oneshot};
 use futures::future::Fuse;
 use futures::{FutureExt, StreamExt};
+use libp2p::autonat::{Event as AutonatEvent, InboundProbeEvent, NatStatus, OutboundProbeEvent};
+use libp2p::core::multihash::Multihash;
 use libp2p::core::ConnectedPoint;
-use libp2p::gossipsub::{Event as GossipsubEvent, TopicHash};
+use libp2p::gossipsub::{Event as GossipsubEvent, MessageAcceptance, TopicHash};
 use libp2p::identify::Event as IdentifyEvent;
 use libp2p::kad::store::RecordStore;
 use libp2p::kad::{
     GetClosestPeersError, GetClosestPeersOk, GetProvidersError, GetProvidersOk, GetRecordError,
-    GetRecordOk, InboundRequest, Kademlia, KademliaEvent, PeerRecord, ProgressStep, ProviderRecord,
-    PutRecordOk, QueryId, QueryResult, Quorum, Record,
+    GetRecordOk, InboundRequest, Kademlia, KademliaEvent, NodeStatus, PeerRecord, ProgressStep,
+    ProviderRecord, PutRecordOk, QueryId, QueryResult, Quorum, Record,
 };
-use libp2p::metrics::{Metrics, Recorder};
+use libp2p::multiaddr::Protocol;
+use libp2p::relay::Event as RelayServerEvent;
 use libp2p::swarm::dial_opts::DialOpts;
 use libp2p::swarm::{DialError, SwarmEvent};
 use libp2p::{futures, Multiaddr, PeerId, Swarm, TransportError};
 use nohash_hasher::IntMap;
 use parking_lot::Mutex;
 use std::collections::hash_map::Entry;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Debug;
-use std::num::NonZeroUsize;
+use std::num::{NonZeroU8, NonZeroUsize};
 use std::pin::Pin;
 use std::sync::atomic::Ordering;
 use std::sync::{Arc, Weak};
 use std::time::{Duration, Instant};
 use tokio::time::Sleep;
-use tracing::{debug, error, trace, warn};
+use tracing::{debug, error, info, trace, warn};
 
 /// How many peers should node be connected to before boosting turns on.
 ///
@@ -44,11 +55,22 @@ use tracing::{debug, error, trace, warn};
 const CONCURRENT_TASKS_BOOST_PEERS_THRESHOLD: NonZeroUsize =
     NonZeroUsize::new(5).expect("Not zero; qed");
 
+/// How often snapshot of Kademlia routing table is handed over to networking parameters registry
+/// for persistence.
+const ROUTING_TABLE_SNAPSHOT_INTERVAL: Duration = Duration::from_secs(60);
+
 /// Defines an expiration interval for item providers in Kademlia network.
 pub const KADEMLIA_PROVIDER_TTL_IN_SECS: Option<Duration> = Some(Duration::from_secs(86400)); /* 1 day */
 
 enum QueryResultSender {
     Value {
+        key: Multihash,
+        /// Number of records that must be found for query to not be retried
+        quorum: NonZeroUsize,
+        /// How many more times query can be retried
+        retries: usize,
+        /// Number of records found so far
+        found: usize,
         sender: mpsc::UnboundedSender<PeerRecord>,
         // Just holding onto permit while data structure is not dropped
         _permit: ResizableSemaphorePermit,
@@ -59,11 +81,31 @@ enum QueryResultSender {
         _permit: ResizableSemaphorePermit,
     },
     Providers {
+        key: Multihash,
+        /// Number of providers that must be found for query to not be retried
+        quorum: NonZeroUsize,
+        /// How many more times query can be retried
+        retries: usize,
+        /// Number of providers found so far
+        found: usize,
         sender: mpsc::UnboundedSender<PeerId>,
         // Just holding onto permit while data structure is not dropped
         _permit: ResizableSemaphorePermit,
     },
+    ProvidersTrace {
+        trace: ProvidersLookupTrace,
+        sender: oneshot::Sender<ProvidersLookupTrace>,
+        // Just holding onto permit while data structure is not dropped
+        _permit: ResizableSemaphorePermit,
+    },
     PutValue {
+        record: Record,
+        /// Number of peers that must store the record
+        quorum: NonZeroUsize,
+        /// How many more times query can be retried
+        retries: usize,
+        /// Whether quorum was reached
+        succeeded: bool,
         sender: mpsc::UnboundedSender<()>,
         // Just holding onto permit while data structure is not dropped
         _permit: ResizableSemaphorePermit,
@@ -78,6 +120,8 @@ where
 {
     /// Should non-global addresses be added to the DHT?
     allow_non_global_addresses_in_dht: bool,
+    /// Dial QUIC addresses of known peers first and fall back to other addresses on failure.
+    prefer_quic_for_dials: bool,
     command_receiver: mpsc::Receiver<Command>,
     swarm: Swarm<Behavior<ProviderOnlyRecordStore<ProviderStorage>>>,
     shared_weak: Weak<Shared>,
@@ -90,9 +134,13 @@ where
     /// Topic subscription senders for logical subscriptions (multiple logical subscriptions can be
     /// present for the same physical subscription).
     topic_subscription_senders: HashMap<TopicHash, IntMap<usize, mpsc::UnboundedSender<Bytes>>>,
+    /// Validators of incoming messages for registered gossip topics.
+    gossip_topic_validators: HashMap<TopicHash, MessageValidatorFn>,
     random_query_timeout: Pin<Box<Fuse<Sleep>>>,
     /// Defines a timeout between swarm attempts to dial known addresses
     peer_dialing_timeout: Pin<Box<Fuse<Sleep>>>,
+    /// Defines a timeout between snapshots of Kademlia routing table
+    routing_table_snapshot_timeout: Pin<Box<Fuse<Sleep>>>,
     /// Manages the networking parameters like known peers and addresses
     networking_parameters_registry: Box<dyn NetworkingParametersRegistry>,
     /// Defines set of peers with a permanent connection (and reconnection if necessary).
@@ -102,11 +150,21 @@ where
     /// Temporarily banned peers.
     temporary_bans: Arc<Mutex<TemporaryBans>>,
     /// Prometheus metrics.
-    metrics: Option<Metrics>,
+    metrics_recorder: Option<MetricsRecorder>,
     /// Mapping from specific peer to number of established connections
     established_connections: HashMap<(PeerId, ConnectedPoint), usize>,
+    /// Number of active relay reservations by peer (when acting as relay server).
+    relay_reservations: HashMap<PeerId, usize>,
+    /// External addresses confirmed to be reachable by AutoNAT dial-back.
+    confirmed_external_addresses: HashSet<Multiaddr>,
     /// Defines protocol version for the network peers. Affects network partition.
     protocol_version: String,
+    /// Expiration interval of provider records created by this node.
+    provider_record_ttl: Option<Duration>,
+    /// Spreads republication of locally provided records over time.
+    provider_republication_scheduler: Option<ProviderRepublicationScheduler>,
+    /// Defines a timeout between republications of provider records batches.
+    provider_republication_timeout: Pin<Box<Fuse<Sleep>>>,
 }
 
 // Helper struct for NodeRunner configuration (clippy requirement).
@@ -115,16 +173,20 @@ where
     ProviderStorage: provider_storage::ProviderStorage + Send + Sync + 'static,
 {
     pub(crate) allow_non_global_addresses_in_dht: bool,
+    pub(crate) prefer_quic_for_dials: bool,
     pub(crate) command_receiver: mpsc::Receiver<Command>,
     pub(crate) swarm: Swarm<Behavior<ProviderOnlyRecordStore<ProviderStorage>>>,
     pub(crate) shared_weak: Weak<Shared>,
     pub(crate) next_random_query_interval: Duration,
     pub(crate) networking_parameters_registry: Box<dyn NetworkingParametersRegistry>,
+    pub(crate) gossip_topic_validators: HashMap<TopicHash, MessageValidatorFn>,
     pub(crate) reserved_peers: HashMap<PeerId, Multiaddr>,
     pub(crate) target_connections: u32,
     pub(crate) temporary_bans: Arc<Mutex<TemporaryBans>>,
-    pub(crate) metrics: Option<Metrics>,
+    pub(crate) metrics_recorder: Option<MetricsRecorder>,
     pub(crate) protocol_version: String,
+    pub(crate) provider_record_ttl: Option<Duration>,
+    pub(crate) provider_republication_scheduler: Option<ProviderRepublicationScheduler>,
 }
 
 impl<ProviderStorage> NodeRunner<ProviderStorage>
@@ -134,20 +196,30 @@ where
     pub(crate) fn new(
         NodeRunnerConfig {
             allow_non_global_addresses_in_dht,
+            prefer_quic_for_dials,
             command_receiver,
             swarm,
             shared_weak,
             next_random_query_interval,
             networking_parameters_registry,
+            gossip_topic_validators,
             reserved_peers,
             target_connections,
             temporary_bans,
-            metrics,
+            metrics_recorder,
             protocol_version,
+            provider_record_ttl,
+            provider_republication_scheduler,
         }: NodeRunnerConfig<ProviderStorage>,
     ) -> Self {
+        let provider_republication_timeout = match &provider_republication_scheduler {
+            Some(scheduler) => tokio::time::sleep(scheduler.batch_interval()).fuse(),
+            None => Fuse::terminated(),
+        };
+
         Self {
             allow_non_global_addresses_in_dht,
+            prefer_quic_for_dials,
             command_receiver,
             swarm,
             shared_weak,
@@ -155,22 +227,33 @@ where
             query_id_receivers: HashMap::default(),
             next_subscription_id: 0,
             topic_subscription_senders: HashMap::default(),
+            gossip_topic_validators,
             // We'll make the first query right away and continue at the interval.
             random_query_timeout: Box::pin(tokio::time::sleep(Duration::from_secs(0)).fuse()),
             // We'll make the first dial right away and continue at the interval.
             peer_dialing_timeout: Box::pin(tokio::time::sleep(Duration::from_secs(0)).fuse()),
+            routing_table_snapshot_timeout: Box::pin(
+                tokio::time::sleep(ROUTING_TABLE_SNAPSHOT_INTERVAL).fuse(),
+            ),
             networking_parameters_registry,
             reserved_peers,
             target_connections,
             temporary_bans,
-            metrics,
+            metrics_recorder,
             established_connections: HashMap::new(),
+            relay_reservations: HashMap::new(),
+            confirmed_external_addresses: HashSet::new(),
             protocol_version,
+            provider_record_ttl,
+            provider_republication_scheduler,
+            provider_republication_timeout: Box::pin(provider_republication_timeout),
         }
     }
 
     /// Drives the main networking future forward.
     pub async fn run(&mut self) {
+        self.restore_routing_table().await;
+
         loop {
             futures::select! {
                 _ = &mut self.random_query_timeout => {
@@ -207,10 +290,76 @@ where
                     self.peer_dialing_timeout =
                         Box::pin(tokio::time::sleep(Duration::from_secs(5)).fuse());
                 },
+                _ = &mut self.provider_republication_timeout => {
+                    self.handle_provider_republication();
+                },
+                _ = &mut self.routing_table_snapshot_timeout => {
+                    self.handle_routing_table_snapshot().await;
+
+                    self.routing_table_snapshot_timeout =
+                        Box::pin(tokio::time::sleep(ROUTING_TABLE_SNAPSHOT_INTERVAL).fuse());
+                },
             }
         }
     }
 
+    /// Add peers from persisted snapshot of routing table to Kademlia, such that restarted node
+    /// can query the network right away instead of starting from bootstrap nodes.
+    async fn restore_routing_table(&mut self) {
+        let routing_table = self.networking_parameters_registry.routing_table().await;
+        if routing_table.is_empty() {
+            return;
+        }
+
+        let local_peer_id = *self.swarm.local_peer_id();
+        let mut restored_peers = 0;
+        for (peer_id, addresses) in routing_table {
+            if peer_id == local_peer_id {
+                continue;
+            }
+
+            let mut restored = false;
+            for address in addresses {
+                if !self.allow_non_global_addresses_in_dht && !is_global_address_or_dns(&address) {
+                    continue;
+                }
+
+                self.swarm
+                    .behaviour_mut()
+                    .kademlia
+                    .add_address(&peer_id, address);
+                restored = true;
+            }
+
+            if restored {
+                restored_peers += 1;
+            }
+        }
+
+        debug!(%restored_peers, "Restored Kademlia routing table from persisted snapshot");
+    }
+
+    async fn handle_routing_table_snapshot(&mut self) {
+        let mut routing_table = HashMap::<PeerId, Vec<Multiaddr>>::new();
+        for kbucket in self.swarm.behaviour_mut().kademlia.kbuckets() {
+            for entry in kbucket.iter() {
+                routing_table.insert(
+                    *entry.node.key.preimage(),
+                    entry.node.value.iter().cloned().collect(),
+                );
+            }
+        }
+
+        trace!(
+            peers = routing_table.len(),
+            "Kademlia routing table snapshot"
+        );
+
+        self.networking_parameters_registry
+            .save_routing_table(routing_table)
+            .await;
+    }
+
     async fn handle_peer_dialing(&mut self) {
         let local_peer_id = *self.swarm.local_peer_id();
         let connected_peers = self.swarm.connected_peers().cloned().collect::<Vec<_>>();
@@ -267,12 +416,17 @@ where
 
             trace!(%local_peer_id, "Processing addresses batch: {:?}", addresses);
 
+            let mut peer_addresses = HashMap::<PeerId, Vec<Multiaddr>>::new();
             for (peer_id, addr) in addresses {
                 if connected_peers.contains(&peer_id) {
                     continue;
                 }
 
-                self.dial_peer(peer_id, addr)
+                peer_addresses.entry(peer_id).or_default().push(addr);
+            }
+
+            for (peer_id, addresses) in peer_addresses {
+                self.dial_peer(peer_id, addresses)
             }
         } else if established_connections < self.target_connections {
             self.networking_parameters_registry
@@ -280,12 +434,11 @@ where
         }
 
         // Renew known external addresses.
-        let mut external_addresses = self
-            .swarm
-            .external_addresses()
-            .cloned()
-            .map(|item| item.addr)
-            .collect::<Vec<_>>();
+        self.renew_external_addresses();
+    }
+
+    fn renew_external_addresses(&self) {
+        let mut external_addresses = self.advertised_external_addresses();
 
         if let Some(shared) = self.shared_weak.upgrade() {
             debug!(?external_addresses, "Renew external addresses.",);
@@ -295,25 +448,86 @@ where
         }
     }
 
-    fn dial_peer(&mut self, peer_id: PeerId, addr: Multiaddr) {
-        let local_peer_id = *self.swarm.local_peer_id();
-        trace!(%local_peer_id, remote_peer_id=%peer_id, %addr, "Dialing address ...");
+    /// External addresses that can be advertised to other peers, non-global addresses (observed by
+    /// peers in the same private network, for instance) are only advertised when allowed.
+    ///
+    /// With AutoNAT enabled only addresses confirmed by dial-back are advertised, addresses
+    /// observed by peers are otherwise not necessarily reachable (behind NAT, for instance).
+    fn advertised_external_addresses(&self) -> Vec<Multiaddr> {
+        let addresses = if self.swarm.behaviour().autonat.is_enabled() {
+            self.confirmed_external_addresses.iter().collect::<Vec<_>>()
+        } else {
+            self.swarm
+                .external_addresses()
+                .map(|record| &record.addr)
+                .collect()
+        };
+
+        addresses
+            .into_iter()
+            .filter(|address| {
+                self.allow_non_global_addresses_in_dht || is_global_address_or_dns(address)
+            })
+            .cloned()
+            .collect()
+    }
 
-        let dial_opts = DialOpts::peer_id(peer_id)
-            .addresses(vec![addr.clone()])
-            .build();
+    fn dial_peer(&mut self, peer_id: PeerId, mut addresses: Vec<Multiaddr>) {
+        let local_peer_id = *self.swarm.local_peer_id();
+        trace!(%local_peer_id, remote_peer_id=%peer_id, ?addresses, "Dialing addresses ...");
+
+        let dial_opts = DialOpts::peer_id(peer_id);
+        let dial_opts = if self.prefer_quic_for_dials && addresses.iter().any(is_quic_address) {
+            // Dial addresses one by one with QUIC addresses first, such that other addresses are
+            // only dialed if QUIC dial failed
+            addresses.sort_by_key(|address| !is_quic_address(address));
+            dial_opts
+                .addresses(addresses.clone())
+                .override_dial_concurrency_factor(NonZeroU8::new(1).expect("Not zero; qed"))
+                .build()
+        } else {
+            dial_opts.addresses(addresses.clone()).build()
+        };
 
         if let Err(err) = self.swarm.dial(dial_opts) {
             debug!(
                 %err,
                 %local_peer_id,
                 remote_peer_id = %peer_id,
-                %addr,
-                "Dialing error: failed to dial an address."
+                ?addresses,
+                "Dialing error: failed to dial addresses."
             );
         }
     }
 
+    fn handle_provider_republication(&mut self) {
+        let Some(scheduler) = &mut self.provider_republication_scheduler else {
+            return;
+        };
+
+        let kademlia = &mut self.swarm.behaviour_mut().kademlia;
+        let keys = scheduler.next_batch(|| {
+            kademlia
+                .store_mut()
+                .provided()
+                .map(|provider_record| provider_record.key.clone())
+                .collect()
+        });
+
+        if !keys.is_empty() {
+            debug!(count = %keys.len(), "Republishing batch of provider records");
+        }
+
+        for key in keys {
+            if let Err(error) = kademlia.start_providing(key.clone()) {
+                debug!(?key, ?error, "Failed to republish provider record");
+            }
+        }
+
+        self.provider_republication_timeout =
+            Box::pin(tokio::time::sleep(scheduler.batch_interval()).fuse());
+    }
+
     fn handle_random_query_interval(&mut self) {
         let random_peer_id = PeerId::random();
 
@@ -336,6 +550,12 @@ where
             SwarmEvent::Behaviour(Event::Gossipsub(event)) => {
                 self.handle_gossipsub_event(event).await;
             }
+            SwarmEvent::Behaviour(Event::Autonat(event)) => {
+                self.handle_autonat_event(event);
+            }
+            SwarmEvent::Behaviour(Event::RelayServer(event)) => {
+                self.handle_relay_server_event(event);
+            }
             SwarmEvent::Behaviour(Event::RequestResponse(event)) => {
                 self.handle_request_response_event(event).await;
             }
@@ -375,6 +595,15 @@ where
                     }
                 };
 
+                shared.connected_peers.lock().entry(peer_id).or_default();
+                shared.handlers.connection_event.call_simple(
+                    &ConnectionEvent::ConnectionEstablished {
+                        peer_id,
+                        endpoint: endpoint.clone(),
+                        num_established: num_established.get(),
+                    },
+                );
+
                 let is_reserved_peer = self.reserved_peers.contains_key(&peer_id);
                 debug!(
                     %peer_id,
@@ -428,6 +657,24 @@ where
                 };
                 debug!("Connection closed with peer {peer_id} [{num_established} from peer]");
 
+                shared
+                    .handlers
+                    .connection_event
+                    .call_simple(&ConnectionEvent::ConnectionClosed {
+                        peer_id,
+                        endpoint: endpoint.clone(),
+                        num_established,
+                    });
+
+                if num_established == 0 {
+                    shared.connected_peers.lock().remove(&peer_id);
+
+                    // Relay reservations don't outlive connections
+                    if self.relay_reservations.remove(&peer_id).is_some() {
+                        self.update_relay_reservations_metrics();
+                    }
+                }
+
                 // TODO: Workaround for https://github.com/libp2p/rust-libp2p/discussions/3418
                 {
                     match self.established_connections.entry((peer_id, endpoint)) {
@@ -477,6 +724,17 @@ where
                     .call_simple(&num_established_peer_connections);
             }
             SwarmEvent::OutgoingConnectionError { peer_id, error } => {
+                let error = Arc::new(error);
+                if let Some(shared) = self.shared_weak.upgrade() {
+                    shared
+                        .handlers
+                        .connection_event
+                        .call_simple(&ConnectionEvent::DialFailed {
+                            peer_id,
+                            error: Arc::clone(&error),
+                        });
+                }
+
                 if let Some(peer_id) = &peer_id {
                     // Create or extend temporary ban, but only if we are not offline
                     if let Some(shared) = self.shared_weak.upgrade() {
@@ -487,7 +745,7 @@ where
                             .load(Ordering::Relaxed)
                             > 1
                         {
-                            let should_temporary_ban = match &error {
+                            let should_temporary_ban = match error.as_ref() {
                                 DialError::Transport(addresses) => {
                                     // Ignoring other errors, those are likely temporary ban errors
                                     !matches!(
@@ -504,8 +762,8 @@ where
                     };
                 }
 
-                match error {
-                    DialError::Transport(ref addresses) => {
+                match error.as_ref() {
+                    DialError::Transport(addresses) => {
                         for (addr, _) in addresses {
                             debug!(?error, ?peer_id, %addr, "SwarmEvent::OutgoingConnectionError (DialError::Transport) for peer.");
                             if let Some(peer_id) = peer_id {
@@ -563,6 +821,29 @@ where
                 info.listen_addrs.truncate(30);
             }
 
+            if let Some(shared) = self.shared_weak.upgrade() {
+                if let Some(identify_info) = shared.connected_peers.lock().get_mut(&peer_id) {
+                    identify_info.replace(IdentifyInfo {
+                        protocol_version: info.protocol_version.clone(),
+                        agent_version: info.agent_version.clone(),
+                        protocols: info.protocols.clone(),
+                        listen_addrs: info.listen_addrs.clone(),
+                        observed_addr: info.observed_addr.clone(),
+                    });
+                }
+            }
+
+            // Kademlia in libp2p 0.51 doesn't support client mode, so peers that are not publicly
+            // reachable are treated as clients by not adding their addresses to the routing table
+            // until reachability is confirmed by dialing them or by AutoNAT dial-back
+            let reachability_confirmed = !self.swarm.behaviour().autonat.is_enabled()
+                || self
+                    .established_connections
+                    .keys()
+                    .any(|(connected_peer_id, endpoint)| {
+                        connected_peer_id == &peer_id && endpoint.is_dialer()
+                    });
+
             let kademlia = &mut self.swarm.behaviour_mut().kademlia;
             let full_kademlia_support = kademlia.protocol_names().iter().all(|local_protocol| {
                 info.protocols
@@ -570,7 +851,14 @@ where
                     .any(|remote_protocol| remote_protocol.as_bytes() == local_protocol.as_ref())
             });
 
-            if full_kademlia_support {
+            if full_kademlia_support && !reachability_confirmed {
+                debug!(
+                    %local_peer_id,
+                    %peer_id,
+                    "Peer reachability is not confirmed yet, not adding its addresses to Kademlia \
+                    DHT.",
+                );
+            } else if full_kademlia_support {
                 for address in info.listen_addrs {
                     if !self.allow_non_global_addresses_in_dht
                         && !is_global_address_or_dns(&address)
@@ -615,6 +903,126 @@ where
         }
     }
 
+    fn handle_autonat_event(&mut self, event: AutonatEvent) {
+        trace!(?event, "AutoNAT event received.");
+
+        match event {
+            AutonatEvent::InboundProbe(InboundProbeEvent::Response { peer, address, .. }) => {
+                // Peer is confirmed to be reachable at this address, so it can act as Kademlia DHT
+                // server
+                if self.allow_non_global_addresses_in_dht || is_global_address_or_dns(&address) {
+                    debug!(
+                        %peer,
+                        %address,
+                        "Peer reachability confirmed, adding address to Kademlia DHT."
+                    );
+                    self.swarm
+                        .behaviour_mut()
+                        .kademlia
+                        .add_address(&peer, address);
+                }
+            }
+            AutonatEvent::OutboundProbe(OutboundProbeEvent::Response { peer, address, .. }) => {
+                // Remote peer has dialed us back successfully, address is confirmed to be
+                // reachable
+                if self.confirmed_external_addresses.insert(address.clone()) {
+                    debug!(%peer, %address, "External address confirmed by dial-back.");
+
+                    self.renew_external_addresses();
+                }
+            }
+            AutonatEvent::StatusChanged { old, new } => {
+                debug!(?old, ?new, "NAT status changed.");
+
+                if !matches!(new, NatStatus::Public(_))
+                    && !self.confirmed_external_addresses.is_empty()
+                {
+                    // Previously confirmed addresses are no longer reachable
+                    self.confirmed_external_addresses.clear();
+                    self.renew_external_addresses();
+                }
+
+                match &new {
+                    NatStatus::Public(address) => {
+                        info!(
+                            %address,
+                            "DSN node is publicly reachable and acts as Kademlia DHT server."
+                        );
+                    }
+                    NatStatus::Private => {
+                        let mut listen_ports = self
+                            .swarm
+                            .listeners()
+                            .filter_map(|address| {
+                                address.iter().find_map(|protocol| match protocol {
+                                    Protocol::Tcp(port) => Some(format!("{port}/tcp")),
+                                    Protocol::Udp(port) => Some(format!("{port}/udp")),
+                                    _ => None,
+                                })
+                            })
+                            .collect::<Vec<_>>();
+                        listen_ports.sort();
+                        listen_ports.dedup();
+
+                        warn!(
+                            "DSN node is not publicly reachable and only acts as Kademlia DHT \
+                            client, which degrades its connectivity. Make sure listen ports ({}) \
+                            are open in firewall and forwarded in router (or UPnP is enabled \
+                            there).",
+                            listen_ports.join(", ")
+                        );
+
+                        if self.swarm.behaviour().relay_server.is_enabled() {
+                            warn!(
+                                "Relay server is enabled, but DSN node is not publicly reachable, \
+                                so it will not be able to relay connections."
+                            );
+                        }
+                    }
+                    NatStatus::Unknown => {}
+                }
+
+                if let Some(shared) = self.shared_weak.upgrade() {
+                    *shared.nat_status.lock() = new;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_relay_server_event(&mut self, event: RelayServerEvent) {
+        debug!(?event, "Relay server event received.");
+
+        match event {
+            RelayServerEvent::ReservationReqAccepted {
+                src_peer_id,
+                renewed: false,
+            } => {
+                *self.relay_reservations.entry(src_peer_id).or_default() += 1;
+            }
+            RelayServerEvent::ReservationTimedOut { src_peer_id } => {
+                if let Entry::Occupied(mut entry) = self.relay_reservations.entry(src_peer_id) {
+                    if *entry.get() <= 1 {
+                        entry.remove();
+                    } else {
+                        *entry.get_mut() -= 1;
+                    }
+                }
+            }
+            _ => {
+                return;
+            }
+        }
+
+        self.update_relay_reservations_metrics();
+    }
+
+    fn update_relay_reservations_metrics(&self) {
+        if let Some(metrics) = &self.metrics_recorder {
+            metrics.set_relay_active_reservations(self.relay_reservations.values().sum());
+        }
+    }
+
     async fn handle_kademlia_event(&mut self, event: KademliaEvent) {
         trace!("Kademlia event: {:?}", event);
 
@@ -691,7 +1099,7 @@ where
                 ..
             } => {
                 let mut cancelled = false;
-                if let Some(QueryResultSender::Value { sender, .. }) =
+                if let Some(QueryResultSender::Value { sender, found, .. }) =
                     self.query_id_receivers.get_mut(&id)
                 {
                     match result {
@@ -701,6 +1109,8 @@ where
                                 "Get record query succeeded",
                             );
 
+                            *found += 1;
+
                             cancelled = Self::unbounded_send_and_cancel_on_error(
                                 &mut self.swarm.behaviour_mut().kademlia,
                                 sender,
@@ -733,19 +1143,57 @@ where
                     }
                 }
 
-                if last || cancelled {
-                    // There will be no more progress
+                if cancelled {
                     self.query_id_receivers.remove(&id);
+                } else if last {
+                    // There will be no more progress, but query might need to be retried
+                    if let Some(query_result_sender) = self.query_id_receivers.remove(&id) {
+                        self.retry_query(query_result_sender);
+                    }
                 }
             }
             KademliaEvent::OutboundQueryProgressed {
                 step: ProgressStep { last, .. },
                 id,
                 result: QueryResult::GetProviders(result),
-                ..
+                stats,
             } => {
+                if let Some(QueryResultSender::ProvidersTrace { trace, .. }) =
+                    self.query_id_receivers.get_mut(&id)
+                {
+                    match result {
+                        Ok(GetProvidersOk::FoundProviders { providers, .. }) => {
+                            trace.providers.extend(providers);
+                        }
+                        Ok(GetProvidersOk::FinishedWithNoAdditionalRecord { closest_peers }) => {
+                            trace.closest_peers = closest_peers;
+                        }
+                        Err(GetProvidersError::Timeout { closest_peers, .. }) => {
+                            trace.closest_peers = closest_peers;
+                            trace.timed_out = true;
+                        }
+                    }
+
+                    if last {
+                        if let Some(QueryResultSender::ProvidersTrace {
+                            mut trace, sender, ..
+                        }) = self.query_id_receivers.remove(&id)
+                        {
+                            trace.requests = stats.num_requests();
+                            trace.successes = stats.num_successes();
+                            trace.failures = stats.num_failures();
+                            trace.duration = stats.duration();
+
+                            // Doesn't matter if receiver is already dropped
+                            let _ = sender.send(trace);
+                        }
+                    }
+
+                    return;
+                }
+
                 let mut cancelled = false;
-                if let Some(QueryResultSender::Providers { sender, .. }) =
+                if let Some(QueryResultSender::Providers { sender, found, .. }) =
                     self.query_id_receivers.get_mut(&id)
                 {
                     match result {
@@ -756,6 +1204,8 @@ where
                                 providers.len(),
                             );
 
+                            *found += providers.len();
+
                             for provider in providers {
                                 cancelled = Self::unbounded_send_and_cancel_on_error(
                                     &mut self.swarm.behaviour_mut().kademlia,
@@ -780,9 +1230,13 @@ where
                     }
                 }
 
-                if last || cancelled {
-                    // There will be no more progress
+                if cancelled {
                     self.query_id_receivers.remove(&id);
+                } else if last {
+                    // There will be no more progress, but query might need to be retried
+                    if let Some(query_result_sender) = self.query_id_receivers.remove(&id) {
+                        self.retry_query(query_result_sender);
+                    }
                 }
             }
             KademliaEvent::OutboundQueryProgressed {
@@ -792,13 +1246,16 @@ where
                 ..
             } => {
                 let mut cancelled = false;
-                if let Some(QueryResultSender::PutValue { sender, .. }) =
-                    self.query_id_receivers.get_mut(&id)
+                if let Some(QueryResultSender::PutValue {
+                    sender, succeeded, ..
+                }) = self.query_id_receivers.get_mut(&id)
                 {
                     match result {
                         Ok(PutRecordOk { key, .. }) => {
                             trace!("Put record query for {} succeeded", hex::encode(&key));
 
+                            *succeeded = true;
+
                             cancelled = Self::unbounded_send_and_cancel_on_error(
                                 &mut self.swarm.behaviour_mut().kademlia,
                                 sender,
@@ -813,15 +1270,182 @@ where
                     }
                 }
 
-                if last || cancelled {
-                    // There will be no more progress
+                if cancelled {
                     self.query_id_receivers.remove(&id);
+                } else if last {
+                    // There will be no more progress, but query might need to be retried
+                    if let Some(query_result_sender) = self.query_id_receivers.remove(&id) {
+                        self.retry_query(query_result_sender);
+                    }
                 }
             }
             _ => {}
         }
     }
 
+    fn start_get_value_query(
+        &mut self,
+        key: Multihash,
+        quorum: NonZeroUsize,
+        retries: usize,
+        sender: mpsc::UnboundedSender<PeerRecord>,
+        permit: ResizableSemaphorePermit,
+    ) {
+        let query_id = self
+            .swarm
+            .behaviour_mut()
+            .kademlia
+            .get_record(key.to_bytes().into());
+
+        self.query_id_receivers.insert(
+            query_id,
+            QueryResultSender::Value {
+                key,
+                quorum,
+                retries,
+                found: 0,
+                sender,
+                _permit: permit,
+            },
+        );
+    }
+
+    fn start_put_value_query(
+        &mut self,
+        record: Record,
+        quorum: NonZeroUsize,
+        retries: usize,
+        sender: mpsc::UnboundedSender<()>,
+        permit: ResizableSemaphorePermit,
+    ) {
+        let query_result = self
+            .swarm
+            .behaviour_mut()
+            .kademlia
+            .put_record(record.clone(), Quorum::N(quorum));
+
+        match query_result {
+            Ok(query_id) => {
+                self.query_id_receivers.insert(
+                    query_id,
+                    QueryResultSender::PutValue {
+                        record,
+                        quorum,
+                        retries,
+                        succeeded: false,
+                        sender,
+                        _permit: permit,
+                    },
+                );
+            }
+            Err(err) => {
+                warn!(?err, "Failed to put value.");
+            }
+        }
+    }
+
+    fn start_get_providers_query(
+        &mut self,
+        key: Multihash,
+        quorum: NonZeroUsize,
+        retries: usize,
+        sender: mpsc::UnboundedSender<PeerId>,
+        permit: ResizableSemaphorePermit,
+    ) {
+        let query_id = self
+            .swarm
+            .behaviour_mut()
+            .kademlia
+            .get_providers(key.into());
+
+        self.query_id_receivers.insert(
+            query_id,
+            QueryResultSender::Providers {
+                key,
+                quorum,
+                retries,
+                found: 0,
+                sender,
+                _permit: permit,
+            },
+        );
+    }
+
+    /// Starts finished query from scratch if it didn't reach the quorum, has retries left and
+    /// results are still awaited.
+    fn retry_query(&mut self, query_result_sender: QueryResultSender) {
+        match query_result_sender {
+            QueryResultSender::Value {
+                key,
+                quorum,
+                retries,
+                found,
+                sender,
+                _permit: permit,
+            } => {
+                if found >= quorum.get() || retries == 0 || sender.is_closed() {
+                    return;
+                }
+
+                debug!(
+                    ?key,
+                    %found,
+                    %quorum,
+                    %retries,
+                    "Get record query didn't reach quorum, retrying"
+                );
+
+                self.start_get_value_query(key, quorum, retries - 1, sender, permit);
+            }
+            QueryResultSender::Providers {
+                key,
+                quorum,
+                retries,
+                found,
+                sender,
+                _permit: permit,
+            } => {
+                if found >= quorum.get() || retries == 0 || sender.is_closed() {
+                    return;
+                }
+
+                debug!(
+                    ?key,
+                    %found,
+                    %quorum,
+                    %retries,
+                    "Get providers query didn't reach quorum, retrying"
+                );
+
+                self.start_get_providers_query(key, quorum, retries - 1, sender, permit);
+            }
+            QueryResultSender::PutValue {
+                record,
+                quorum,
+                retries,
+                succeeded,
+                sender,
+                _permit: permit,
+            } => {
+                if succeeded || retries == 0 || sender.is_closed() {
+                    return;
+                }
+
+                debug!(
+                    key = hex::encode(&record.key),
+                    %quorum,
+                    %retries,
+                    "Put record query didn't reach quorum, retrying"
+                );
+
+                self.start_put_value_query(record, quorum, retries - 1, sender, permit);
+            }
+            QueryResultSender::ClosestPeers { .. } | QueryResultSender::ProvidersTrace { .. } => {
+                // Closest peers and traced queries are not retried
+            }
+        }
+    }
+
     // Returns `true` if query was cancelled
     fn unbounded_send_and_cancel_on_error<T>(
         kademlia: &mut Kademlia<ProviderOnlyRecordStore<ProviderStorage>>,
@@ -844,7 +1468,43 @@ where
     }
 
     async fn handle_gossipsub_event(&mut self, event: GossipsubEvent) {
-        if let GossipsubEvent::Message { message, .. } = event {
+        if let GossipsubEvent::Message {
+            propagation_source,
+            message_id,
+            message,
+        } = event
+        {
+            let acceptance = match self.gossip_topic_validators.get(&message.topic) {
+                Some(validator) => validator(propagation_source, &message.data),
+                None => MessageAcceptance::Accept,
+            };
+            let accepted = matches!(acceptance, MessageAcceptance::Accept);
+
+            if let Some(gossipsub) = self.swarm.behaviour_mut().gossipsub.as_mut() {
+                // Message is only propagated further after validation result is reported, it
+                // doesn't matter if message validation is not enabled in gossipsub config
+                if let Err(error) = gossipsub.report_message_validation_result(
+                    &message_id,
+                    &propagation_source,
+                    acceptance,
+                ) {
+                    debug!(
+                        %message_id,
+                        %error,
+                        "Failed to report gossip message validation result"
+                    );
+                }
+            }
+
+            if !accepted {
+                debug!(
+                    %propagation_source,
+                    topic = %message.topic,
+                    "Gossip message was not accepted by validator"
+                );
+                return;
+            }
+
             if let Some(senders) = self.topic_subscription_senders.get(&message.topic) {
                 let bytes = Bytes::from(message.data);
 
@@ -865,26 +1525,18 @@ where
         match command {
             Command::GetValue {
                 key,
+                quorum,
+                retries,
                 result_sender,
                 permit,
             } => {
-                let query_id = self
-                    .swarm
-                    .behaviour_mut()
-                    .kademlia
-                    .get_record(key.to_bytes().into());
-
-                self.query_id_receivers.insert(
-                    query_id,
-                    QueryResultSender::Value {
-                        sender: result_sender,
-                        _permit: permit,
-                    },
-                );
+                self.start_get_value_query(key, quorum, retries, result_sender, permit);
             }
             Command::PutValue {
                 key,
                 value,
+                quorum,
+                retries,
                 result_sender,
                 permit,
             } => {
@@ -896,26 +1548,8 @@ where
                     publisher: Some(local_peer_id),
                     expires: None, // No time expiration.
                 };
-                let query_result = self
-                    .swarm
-                    .behaviour_mut()
-                    .kademlia
-                    .put_record(record, Quorum::One);
-
-                match query_result {
-                    Ok(query_id) => {
-                        self.query_id_receivers.insert(
-                            query_id,
-                            QueryResultSender::PutValue {
-                                sender: result_sender,
-                                _permit: permit,
-                            },
-                        );
-                    }
-                    Err(err) => {
-                        warn!(?err, "Failed to put value.");
-                    }
-                }
+
+                self.start_put_value_query(record, quorum, retries, result_sender, permit);
             }
             Command::Subscribe {
                 topic,
@@ -1056,17 +1690,13 @@ where
             }
             Command::StartLocalAnnouncing { key, result_sender } => {
                 let local_peer_id = *self.swarm.local_peer_id();
-                let addresses = self
-                    .swarm
-                    .external_addresses()
-                    .map(|rec| rec.addr.clone())
-                    .collect::<Vec<_>>();
+                let addresses = self.advertised_external_addresses();
 
                 let provider_record = ProviderRecord {
                     provider: local_peer_id,
                     key: key.clone(),
                     addresses,
-                    expires: KADEMLIA_PROVIDER_TTL_IN_SECS.map(|ttl| Instant::now() + ttl),
+                    expires: self.provider_record_ttl.map(|ttl| Instant::now() + ttl),
                 };
 
                 let res = self
@@ -1094,6 +1724,15 @@ where
                 let _ = result_sender.send(());
             }
             Command::GetProviders {
+                key,
+                quorum,
+                retries,
+                result_sender,
+                permit,
+            } => {
+                self.start_get_providers_query(key, quorum, retries, result_sender, permit);
+            }
+            Command::TraceGetProviders {
                 key,
                 result_sender,
                 permit,
@@ -1106,12 +1745,35 @@ where
 
                 self.query_id_receivers.insert(
                     query_id,
-                    QueryResultSender::Providers {
+                    QueryResultSender::ProvidersTrace {
+                        trace: ProvidersLookupTrace::default(),
                         sender: result_sender,
                         _permit: permit,
                     },
                 );
             }
+            Command::GetRoutingTable { result_sender } => {
+                let buckets = self
+                    .swarm
+                    .behaviour_mut()
+                    .kademlia
+                    .kbuckets()
+                    .map(|kbucket| RoutingTableBucket {
+                        distance_log2: kbucket.range().0.ilog2().unwrap_or_default(),
+                        peers: kbucket
+                            .iter()
+                            .map(|entry| RoutingTablePeer {
+                                peer_id: *entry.node.key.preimage(),
+                                addresses: entry.node.value.iter().cloned().collect(),
+                                connected: matches!(entry.status, NodeStatus::Connected),
+                            })
+                            .collect(),
+                    })
+                    .collect();
+
+                // Doesn't matter if receiver is already dropped
+                let _ = result_sender.send(buckets);
+            }
             Command::BanPeer { peer_id } => {
                 self.ban_peer(peer_id).await;
             }
@@ -1135,7 +1797,7 @@ where
     }
 
     fn register_event_metrics<E: Debug>(&mut self, swarm_event: &SwarmEvent<Event, E>) {
-        if let Some(ref mut metrics) = self.metrics {
+        if let Some(metrics) = &self.metrics_recorder {
             match swarm_event {
                 SwarmEvent::Behaviour(Event::Ping(ping_event)) => {
                     metrics.record(ping_event);
@@ -1149,6 +1811,9 @@ where
                 SwarmEvent::Behaviour(Event::Gossipsub(gossipsub_event)) => {
                     metrics.record(gossipsub_event);
                 }
+                SwarmEvent::Behaviour(Event::RelayServer(relay_server_event)) => {
+                    metrics.record_relay_server_event(relay_server_event);
+                }
                 // TODO: implement in the upstream repository
                 // SwarmEvent::Behaviour(Event::RequestResponse(request_response_event)) => {
                 //     self.metrics.record(request_response_event);
@@ -1157,6 +1822,12 @@ where
                     metrics.record(swarm_event);
                 }
             }
+
+            metrics.update_bandwidth();
+
+            let (provider_storage_stats, expired_provider_records) =
+                self.swarm.behaviour_mut().kademlia.store_mut().stats();
+            metrics.update_provider_storage(provider_storage_stats, expired_provider_records);
         }
     }
 }
@@ -0,0 +1,75 @@
+#[cfg(test)]
+mod tests;
+
+use libp2p::kad::record::Key;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Scheduler of provider records republication.
+///
+/// Instead of republishing all provided records at once when republication interval passes (which
+/// is what Kademlia does and what results in huge bursts on large farms), records provided at the
+/// beginning of the round are republished in small batches spread evenly over republication
+/// interval.
+#[derive(Debug)]
+pub(crate) struct ProviderRepublicationScheduler {
+    republication_interval: Duration,
+    batch_interval: Duration,
+    round_started_at: Instant,
+    pending_keys: VecDeque<Key>,
+    batch_size: usize,
+}
+
+impl ProviderRepublicationScheduler {
+    /// Create new scheduler, first republication round starts after one republication interval.
+    pub(crate) fn new(republication_interval: Duration, batch_interval: Duration) -> Self {
+        Self::new_at(republication_interval, batch_interval, Instant::now())
+    }
+
+    fn new_at(republication_interval: Duration, batch_interval: Duration, now: Instant) -> Self {
+        Self {
+            republication_interval,
+            // Batch interval larger than republication interval doesn't make sense
+            batch_interval: batch_interval.min(republication_interval),
+            round_started_at: now,
+            pending_keys: VecDeque::new(),
+            batch_size: 0,
+        }
+    }
+
+    /// How often [`Self::next_batch`] is supposed to be called.
+    pub(crate) fn batch_interval(&self) -> Duration {
+        self.batch_interval
+    }
+
+    /// Returns keys that should be republished now.
+    ///
+    /// `provided` is called to get all keys provided by local node when new round starts.
+    pub(crate) fn next_batch<Provided>(&mut self, provided: Provided) -> Vec<Key>
+    where
+        Provided: FnOnce() -> Vec<Key>,
+    {
+        self.next_batch_at(provided, Instant::now())
+    }
+
+    fn next_batch_at<Provided>(&mut self, provided: Provided, now: Instant) -> Vec<Key>
+    where
+        Provided: FnOnce() -> Vec<Key>,
+    {
+        if self.pending_keys.is_empty()
+            && now.saturating_duration_since(self.round_started_at) >= self.republication_interval
+        {
+            self.pending_keys = provided().into();
+            self.round_started_at = now;
+
+            let batches = (self.republication_interval.as_millis()
+                / self.batch_interval.as_millis().max(1))
+            .max(1) as usize;
+            // Round up, such that all keys are republished before next round
+            self.batch_size = (self.pending_keys.len() + batches - 1) / batches;
+        }
+
+        let batch_size = self.batch_size.min(self.pending_keys.len());
+        self.pending_keys.drain(..batch_size).collect()
+    }
+}
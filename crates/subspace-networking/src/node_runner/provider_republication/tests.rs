@@ -0,0 +1,70 @@
+use super::ProviderRepublicationScheduler;
+use libp2p::kad::record::Key;
+use std::time::{Duration, Instant};
+
+fn keys(count: u8) -> Vec<Key> {
+    (0..count).map(|i| Key::new(&[i])).collect()
+}
+
+#[test]
+fn test_republication_spread() {
+    let now = Instant::now();
+    let mut scheduler = ProviderRepublicationScheduler::new_at(
+        Duration::from_secs(100),
+        Duration::from_secs(10),
+        now,
+    );
+
+    // Nothing happens before the first round
+    assert!(scheduler
+        .next_batch_at(
+            || panic!("Must not be called"),
+            now + Duration::from_secs(50)
+        )
+        .is_empty());
+
+    // 25 keys over 10 batches are republished 3 at a time
+    let round_start = now + Duration::from_secs(100);
+    let mut republished = Vec::new();
+    let mut batches = 0;
+    loop {
+        let batch = scheduler.next_batch_at(|| keys(25), round_start + Duration::from_secs(1));
+        if batch.is_empty() {
+            break;
+        }
+        assert!(batch.len() <= 3);
+        republished.extend(batch);
+        batches += 1;
+    }
+    assert_eq!(batches, 9);
+    assert_eq!(republished, keys(25));
+
+    // Next round doesn't start until republication interval passes since previous round start
+    assert!(scheduler
+        .next_batch_at(
+            || panic!("Must not be called"),
+            round_start + Duration::from_secs(99)
+        )
+        .is_empty());
+    assert_eq!(
+        scheduler.next_batch_at(|| keys(5), round_start + Duration::from_secs(101)),
+        keys(1)
+    );
+}
+
+#[test]
+fn test_batch_interval_larger_than_republication_interval() {
+    let now = Instant::now();
+    let mut scheduler = ProviderRepublicationScheduler::new_at(
+        Duration::from_secs(10),
+        Duration::from_secs(100),
+        now,
+    );
+
+    assert_eq!(scheduler.batch_interval(), Duration::from_secs(10));
+    // Everything is republished in one batch
+    assert_eq!(
+        scheduler.next_batch_at(|| keys(25), now + Duration::from_secs(10)),
+        keys(25)
+    );
+}
@@ -0,0 +1,189 @@
+//! Reputation of remote peers based on the outcome of requests sent to them.
+//!
+//! Every peer starts with neutral score, which grows with successful requests and drops with
+//! failed requests, timeouts and invalid responses. Negative score slowly recovers over time, such
+//! that peers that misbehaved are deprioritized or temporarily banned rather than forever.
+
+#[cfg(test)]
+mod tests;
+
+use libp2p::PeerId;
+use lru::LruCache;
+use std::num::NonZeroUsize;
+use std::time::{Duration, Instant};
+
+/// How many peers to keep reputation for.
+const PEER_REPUTATION_CACHE_SIZE: NonZeroUsize = NonZeroUsize::new(10_000).expect("Not zero; qed");
+/// Maximum score peer can accumulate, such that previously good peer can't misbehave for long.
+const MAX_SCORE: i32 = 100;
+/// Minimum score peer can have.
+const MIN_SCORE: i32 = -100;
+const SUCCESSFUL_REQUEST_SCORE_CHANGE: i32 = 1;
+const FAILED_REQUEST_SCORE_CHANGE: i32 = -5;
+const TIMED_OUT_REQUEST_SCORE_CHANGE: i32 = -10;
+const INVALID_RESPONSE_SCORE_CHANGE: i32 = -50;
+/// Negative score recovers by one point every interval.
+const SCORE_RECOVERY_INTERVAL: Duration = Duration::from_secs(30);
+/// Peers with score below this threshold are queried only if there is nobody else to query.
+pub(crate) const LOW_REPUTATION_THRESHOLD: i32 = 0;
+/// Peers with score at or below this threshold are not queried at all until score recovers.
+pub(crate) const BANNED_REPUTATION_THRESHOLD: i32 = -90;
+
+/// Outcome of the request sent to the peer.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) enum RequestOutcome {
+    /// Peer returned well-formed response.
+    Success,
+    /// Request failed (peer not reachable, refused to respond, etc.).
+    Failure,
+    /// Peer didn't respond in time.
+    Timeout,
+    /// Peer returned malformed or invalid response.
+    InvalidResponse,
+}
+
+impl RequestOutcome {
+    fn score_change(&self) -> i32 {
+        match self {
+            Self::Success => SUCCESSFUL_REQUEST_SCORE_CHANGE,
+            Self::Failure => FAILED_REQUEST_SCORE_CHANGE,
+            Self::Timeout => TIMED_OUT_REQUEST_SCORE_CHANGE,
+            Self::InvalidResponse => INVALID_RESPONSE_SCORE_CHANGE,
+        }
+    }
+}
+
+/// Reputation of the peer, exposed for debugging purposes.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub struct PeerScore {
+    /// Current score of the peer, positive is good, negative is bad.
+    pub score: i32,
+    /// Number of successful requests.
+    pub successful_requests: u64,
+    /// Number of failed requests.
+    pub failed_requests: u64,
+    /// Number of timed out requests.
+    pub timed_out_requests: u64,
+    /// Number of malformed or invalid responses.
+    pub invalid_responses: u64,
+}
+
+impl PeerScore {
+    /// Peer should only be queried if there is nobody else to query.
+    pub fn is_low(&self) -> bool {
+        self.score < LOW_REPUTATION_THRESHOLD
+    }
+
+    /// Peer should not be queried until its score recovers.
+    pub fn is_banned(&self) -> bool {
+        self.score <= BANNED_REPUTATION_THRESHOLD
+    }
+}
+
+#[derive(Debug)]
+struct PeerReputationEntry {
+    peer_score: PeerScore,
+    last_updated: Instant,
+}
+
+impl PeerReputationEntry {
+    /// Recover negative score according to time passed since last update
+    fn recover(&mut self, now: Instant) {
+        if self.peer_score.score >= 0 {
+            self.last_updated = now;
+            return;
+        }
+
+        let recovery_intervals = now.saturating_duration_since(self.last_updated).as_secs()
+            / SCORE_RECOVERY_INTERVAL.as_secs();
+        // Score can't recover by more than `-MIN_SCORE` points anyway
+        let recovery_intervals = recovery_intervals.min(MIN_SCORE.unsigned_abs().into()) as u32;
+        if recovery_intervals == 0 {
+            return;
+        }
+
+        self.peer_score.score = (self.peer_score.score + recovery_intervals as i32).min(0);
+        self.last_updated += SCORE_RECOVERY_INTERVAL * recovery_intervals;
+    }
+}
+
+/// Reputation of recently seen peers.
+#[derive(Debug)]
+pub(crate) struct PeerReputation {
+    peers: LruCache<PeerId, PeerReputationEntry>,
+}
+
+impl Default for PeerReputation {
+    fn default() -> Self {
+        Self {
+            peers: LruCache::new(PEER_REPUTATION_CACHE_SIZE),
+        }
+    }
+}
+
+impl PeerReputation {
+    /// Update reputation of the peer according to request outcome.
+    pub(crate) fn report(&mut self, peer_id: PeerId, outcome: RequestOutcome) {
+        self.report_at(peer_id, outcome, Instant::now());
+    }
+
+    fn report_at(&mut self, peer_id: PeerId, outcome: RequestOutcome, now: Instant) {
+        if !self.peers.contains(&peer_id) {
+            self.peers.put(
+                peer_id,
+                PeerReputationEntry {
+                    peer_score: PeerScore::default(),
+                    last_updated: now,
+                },
+            );
+        }
+        let Some(entry) = self.peers.get_mut(&peer_id) else {
+            return;
+        };
+        entry.recover(now);
+
+        let peer_score = &mut entry.peer_score;
+        match outcome {
+            RequestOutcome::Success => {
+                peer_score.successful_requests += 1;
+            }
+            RequestOutcome::Failure => {
+                peer_score.failed_requests += 1;
+            }
+            RequestOutcome::Timeout => {
+                peer_score.timed_out_requests += 1;
+            }
+            RequestOutcome::InvalidResponse => {
+                peer_score.invalid_responses += 1;
+            }
+        }
+        peer_score.score = peer_score
+            .score
+            .saturating_add(outcome.score_change())
+            .clamp(MIN_SCORE, MAX_SCORE);
+    }
+
+    /// Score of the peer, `None` if nothing is known about peer.
+    pub(crate) fn peer_score(&mut self, peer_id: &PeerId) -> Option<PeerScore> {
+        self.peer_score_at(peer_id, Instant::now())
+    }
+
+    fn peer_score_at(&mut self, peer_id: &PeerId, now: Instant) -> Option<PeerScore> {
+        let entry = self.peers.peek_mut(peer_id)?;
+        entry.recover(now);
+        Some(entry.peer_score)
+    }
+
+    /// Scores of all known peers.
+    pub(crate) fn peer_scores(&mut self) -> Vec<(PeerId, PeerScore)> {
+        let now = Instant::now();
+
+        self.peers
+            .iter_mut()
+            .map(|(peer_id, entry)| {
+                entry.recover(now);
+                (*peer_id, entry.peer_score)
+            })
+            .collect()
+    }
+}
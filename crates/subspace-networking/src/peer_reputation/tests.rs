@@ -0,0 +1,93 @@
+use super::{
+    PeerReputation, RequestOutcome, INVALID_RESPONSE_SCORE_CHANGE, MAX_SCORE, MIN_SCORE,
+    SCORE_RECOVERY_INTERVAL,
+};
+use libp2p::PeerId;
+use std::time::Instant;
+
+#[test]
+fn test_peer_score_counters() {
+    let mut peer_reputation = PeerReputation::default();
+    let peer_id = PeerId::random();
+    let now = Instant::now();
+
+    assert_eq!(peer_reputation.peer_score_at(&peer_id, now), None);
+
+    peer_reputation.report_at(peer_id, RequestOutcome::Success, now);
+    peer_reputation.report_at(peer_id, RequestOutcome::Success, now);
+    peer_reputation.report_at(peer_id, RequestOutcome::Failure, now);
+    peer_reputation.report_at(peer_id, RequestOutcome::Timeout, now);
+
+    let peer_score = peer_reputation.peer_score_at(&peer_id, now).unwrap();
+    assert_eq!(peer_score.successful_requests, 2);
+    assert_eq!(peer_score.failed_requests, 1);
+    assert_eq!(peer_score.timed_out_requests, 1);
+    assert_eq!(peer_score.invalid_responses, 0);
+    assert_eq!(peer_score.score, 2 - 5 - 10);
+    assert!(peer_score.is_low());
+    assert!(!peer_score.is_banned());
+}
+
+#[test]
+fn test_peer_score_bounds() {
+    let mut peer_reputation = PeerReputation::default();
+    let good_peer_id = PeerId::random();
+    let bad_peer_id = PeerId::random();
+    let now = Instant::now();
+
+    for _ in 0..MAX_SCORE * 2 {
+        peer_reputation.report_at(good_peer_id, RequestOutcome::Success, now);
+    }
+    for _ in 0..5 {
+        peer_reputation.report_at(bad_peer_id, RequestOutcome::InvalidResponse, now);
+    }
+
+    let good_peer_score = peer_reputation.peer_score_at(&good_peer_id, now).unwrap();
+    assert_eq!(good_peer_score.score, MAX_SCORE);
+    assert!(!good_peer_score.is_low());
+
+    let bad_peer_score = peer_reputation.peer_score_at(&bad_peer_id, now).unwrap();
+    assert_eq!(bad_peer_score.score, MIN_SCORE);
+    assert!(bad_peer_score.is_banned());
+
+    assert_eq!(peer_reputation.peer_scores().len(), 2);
+}
+
+#[test]
+fn test_peer_score_recovery() {
+    let mut peer_reputation = PeerReputation::default();
+    let peer_id = PeerId::random();
+    let now = Instant::now();
+
+    peer_reputation.report_at(peer_id, RequestOutcome::InvalidResponse, now);
+    assert_eq!(
+        peer_reputation.peer_score_at(&peer_id, now).unwrap().score,
+        INVALID_RESPONSE_SCORE_CHANGE
+    );
+
+    // Partial interval doesn't recover anything
+    let later = now + SCORE_RECOVERY_INTERVAL / 2;
+    assert_eq!(
+        peer_reputation
+            .peer_score_at(&peer_id, later)
+            .unwrap()
+            .score,
+        INVALID_RESPONSE_SCORE_CHANGE
+    );
+
+    let later = now + SCORE_RECOVERY_INTERVAL * 10;
+    assert_eq!(
+        peer_reputation
+            .peer_score_at(&peer_id, later)
+            .unwrap()
+            .score,
+        INVALID_RESPONSE_SCORE_CHANGE + 10
+    );
+
+    // Score never recovers above neutral
+    let much_later = now + SCORE_RECOVERY_INTERVAL * 1_000;
+    let peer_score = peer_reputation.peer_score_at(&peer_id, much_later).unwrap();
+    assert_eq!(peer_score.score, 0);
+    assert!(!peer_score.is_low());
+    assert_eq!(peer_score.invalid_responses, 1);
+}
@@ -1,3 +1,4 @@
+pub mod chunked;
 pub mod generic_request_handler;
 pub mod object_mappings;
 pub mod piece_announcement;
@@ -0,0 +1,50 @@
+//! Helper for chunked (streamed) request-response exchanges.
+//!
+//! Large responses (multiple pieces, whole segments) are split into chunks, each chunk is
+//! transferred with a separate request sent via `RequestResponsesBehaviour`. Next chunk is only
+//! requested once previous one was consumed, so neither side needs to buffer the whole response
+//! in memory and slow consumer naturally applies backpressure to the remote peer, see
+//! [`Node::send_chunked_request()`](crate::Node::send_chunked_request).
+
+use crate::request_handlers::generic_request_handler::{GenericRequest, GenericRequestHandler};
+use parity_scale_codec::{Decode, Encode};
+
+/// Request with chunked response.
+pub trait ChunkedRequest: Encode + Decode + Clone + Send + Sync + 'static {
+    /// Defines request-response protocol name.
+    const PROTOCOL_NAME: &'static str;
+    /// Specifies log-parameters for tracing.
+    const LOG_TARGET: &'static str;
+    /// Chunk of the response that corresponds to this request
+    type Chunk: Encode + Decode + Send + Sync + 'static;
+}
+
+/// Request for a single chunk of the response to the original request.
+#[derive(Debug, Clone, Eq, PartialEq, Encode, Decode)]
+pub struct ChunkRequest<Request> {
+    /// Original request
+    pub request: Request,
+    /// Index of the requested chunk, starting with zero
+    pub chunk_index: u32,
+}
+
+impl<Request> GenericRequest for ChunkRequest<Request>
+where
+    Request: ChunkedRequest,
+{
+    const PROTOCOL_NAME: &'static str = Request::PROTOCOL_NAME;
+    const LOG_TARGET: &'static str = Request::LOG_TARGET;
+    type Response = ChunkResponse<Request::Chunk>;
+}
+
+/// Single chunk of the response to the original request.
+#[derive(Debug, Clone, Eq, PartialEq, Encode, Decode)]
+pub struct ChunkResponse<Chunk> {
+    /// Returned data.
+    pub chunk: Chunk,
+    /// Whether this is the last chunk of the response.
+    pub is_last: bool,
+}
+
+/// Create a new chunked request handler, handler is called for each requested chunk.
+pub type ChunkedRequestHandler<Request> = GenericRequestHandler<ChunkRequest<Request>>;
@@ -29,12 +29,43 @@ const REQUESTS_BUFFER_SIZE: usize = 50;
 
 /// Generic request with associated response
 pub trait GenericRequest: Encode + Decode + Send + Sync + 'static {
-    /// Defines request-response protocol name.
+    /// Defines request-response protocol name (the newest version of the protocol).
     const PROTOCOL_NAME: &'static str;
+    /// Names of older versions of the protocol that are still supported, newest first.
+    ///
+    /// The newest version supported by both sides is negotiated with every peer, such that
+    /// protocol can be upgraded without all peers upgrading at the same time.
+    const FALLBACK_PROTOCOL_NAMES: &'static [&'static str] = &[];
     /// Specifies log-parameters for tracing.
     const LOG_TARGET: &'static str;
     /// Response type that corresponds to this request
     type Response: Encode + Decode + Send + Sync + 'static;
+
+    /// Encode request for specified protocol version.
+    fn encode_request(&self, _protocol_name: &'static str) -> Vec<u8> {
+        self.encode()
+    }
+
+    /// Decode request received with specified protocol version.
+    fn decode_request(
+        _protocol_name: &'static str,
+        input: &mut &[u8],
+    ) -> Result<Self, parity_scale_codec::Error> {
+        Self::decode(input)
+    }
+
+    /// Encode response for specified protocol version.
+    fn encode_response(_protocol_name: &'static str, response: &Self::Response) -> Vec<u8> {
+        response.encode()
+    }
+
+    /// Decode response received with specified protocol version.
+    fn decode_response(
+        _protocol_name: &'static str,
+        input: &mut &[u8],
+    ) -> Result<Self::Response, parity_scale_codec::Error> {
+        Self::Response::decode(input)
+    }
 }
 
 pub type RequestHandlerFn<Request> = Arc<
@@ -65,6 +96,7 @@ impl<Request: GenericRequest> GenericRequestHandler<Request> {
         let (request_sender, request_receiver) = mpsc::channel(REQUESTS_BUFFER_SIZE);
 
         let mut protocol_config = ProtocolConfig::new(Request::PROTOCOL_NAME);
+        protocol_config.fallback_names = Request::FALLBACK_PROTOCOL_NAMES.to_vec();
         protocol_config.inbound_queue = Some(request_sender);
 
         Box::new(Self {
@@ -80,14 +112,17 @@ impl<Request: GenericRequest> GenericRequestHandler<Request> {
     async fn handle_request(
         &mut self,
         peer: PeerId,
+        protocol_name: &'static str,
         payload: Vec<u8>,
     ) -> Result<Vec<u8>, RequestHandlerError> {
-        trace!(%peer, protocol=Request::LOG_TARGET, "Handling request...");
-        let request = Request::decode(&mut payload.as_slice())
+        trace!(%peer, protocol=Request::LOG_TARGET, %protocol_name, "Handling request...");
+        let request = Request::decode_request(protocol_name, &mut payload.as_slice())
             .map_err(|_| RequestHandlerError::InvalidRequestFormat)?;
-        let response = (self.request_handler)(peer, &request).await;
+        let response = (self.request_handler)(peer, &request)
+            .await
+            .ok_or(RequestHandlerError::NoResponse)?;
 
-        Ok(response.ok_or(RequestHandlerError::NoResponse)?.encode())
+        Ok(Request::encode_response(protocol_name, &response))
     }
 }
 
@@ -98,11 +133,12 @@ impl<Request: GenericRequest> RequestHandler for GenericRequestHandler<Request>
         while let Some(request) = self.request_receiver.next().await {
             let IncomingRequest {
                 peer,
+                protocol_name,
                 payload,
                 pending_response,
             } = request;
 
-            match self.handle_request(peer, payload).await {
+            match self.handle_request(peer, protocol_name, payload).await {
                 Ok(response_data) => {
                     let response = OutgoingResponse {
                         result: Ok(response_data),
@@ -158,6 +194,7 @@ impl<Request: GenericRequest> RequestHandler for GenericRequestHandler<Request>
         let (request_sender, request_receiver) = mpsc::channel(REQUESTS_BUFFER_SIZE);
 
         let mut protocol_config = ProtocolConfig::new(Request::PROTOCOL_NAME);
+        protocol_config.fallback_names = Request::FALLBACK_PROTOCOL_NAMES.to_vec();
         protocol_config.inbound_queue = Some(request_sender);
 
         Box::new(Self {
@@ -16,6 +16,9 @@ pub struct PieceByHashRequest {
 
 impl GenericRequest for PieceByHashRequest {
     const PROTOCOL_NAME: &'static str = "/subspace/piece-by-hash/0.1.0";
+    // Older versions are to be added here when protocol is upgraded, with versioned encoding
+    // implemented in `encode_request`/`decode_request`/`encode_response`/`decode_response`
+    const FALLBACK_PROTOCOL_NAMES: &'static [&'static str] = &[];
     const LOG_TARGET: &'static str = "piece-by-hash-request-response-handler";
     type Response = PieceByHashResponse;
 }
@@ -3,9 +3,19 @@
 //! Handle (i.e. answer) incoming segment headers requests from a remote peer received via
 //! `RequestResponsesBehaviour` with generic [`GenericRequestHandler`].
 
+#[cfg(test)]
+mod tests;
+
 use crate::request_handlers::generic_request_handler::{GenericRequest, GenericRequestHandler};
 use parity_scale_codec::{Decode, Encode};
 use subspace_core_primitives::{SegmentHeader, SegmentIndex};
+use thiserror::Error;
+
+/// Maximum number of segment headers returned in response to
+/// [`SegmentHeaderRequest::SegmentIndexRange`], larger ranges are truncated.
+pub const SEGMENT_INDEX_RANGE_LIMIT: u64 = 1000;
+
+const PROTOCOL_NAME_V1: &str = "/subspace/segment-headers-by-indexes/0.1.0";
 
 /// Segment header by segment indexes protocol request.
 #[derive(Debug, Clone, Eq, PartialEq, Encode, Decode)]
@@ -20,12 +30,36 @@ pub enum SegmentHeaderRequest {
         /// Number of segment headers to return.
         segment_header_number: u64,
     },
+    /// Segment headers in the range of segment indexes (inclusive), in ascending order.
+    ///
+    /// Only first [`SEGMENT_INDEX_RANGE_LIMIT`] segment headers of the range are returned.
+    SegmentIndexRange {
+        /// First segment index to get.
+        start: SegmentIndex,
+        /// Last segment index to get.
+        end: SegmentIndex,
+    },
 }
 
 impl GenericRequest for SegmentHeaderRequest {
-    const PROTOCOL_NAME: &'static str = "/subspace/segment-headers-by-indexes/0.1.0";
+    const PROTOCOL_NAME: &'static str = "/subspace/segment-headers-by-indexes/0.2.0";
+    const FALLBACK_PROTOCOL_NAMES: &'static [&'static str] = &[PROTOCOL_NAME_V1];
     const LOG_TARGET: &'static str = "segment-headers-by-indexes-request-response-handler";
     type Response = SegmentHeaderResponse;
+
+    fn encode_request(&self, protocol_name: &'static str) -> Vec<u8> {
+        match self {
+            // Older version doesn't support ranges, but the same can be requested with explicit
+            // segment indexes
+            Self::SegmentIndexRange { start, end } if protocol_name == PROTOCOL_NAME_V1 => {
+                Self::SegmentIndexes {
+                    segment_indexes: (*start..=*end).collect(),
+                }
+                .encode()
+            }
+            _ => self.encode(),
+        }
+    }
 }
 
 /// Segment header by segment indexes protocol response.
@@ -39,3 +73,102 @@ pub struct SegmentHeaderResponse {
 #[allow(dead_code)]
 /// Create a new segment-header-by-segment-indexes request handler.
 pub type SegmentHeaderBySegmentIndexesRequestHandler = GenericRequestHandler<SegmentHeaderRequest>;
+
+/// Segment headers verification error.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Error)]
+pub enum SegmentHeadersVerificationError {
+    /// Unexpected number of segment headers
+    #[error("Unexpected number of segment headers: expected {expected}, actual {actual}")]
+    UnexpectedNumberOfSegmentHeaders {
+        /// Expected number of segment headers
+        expected: u64,
+        /// Actual number of segment headers
+        actual: u64,
+    },
+    /// Unexpected segment index
+    #[error("Unexpected segment index: expected {expected}, actual {actual}")]
+    UnexpectedSegmentIndex {
+        /// Expected segment index
+        expected: SegmentIndex,
+        /// Actual segment index
+        actual: SegmentIndex,
+    },
+    /// Segment header doesn't reference hash of the previous segment header
+    #[error(
+        "Segment header {segment_index} doesn't reference hash of the previous segment header"
+    )]
+    BrokenChain {
+        /// Segment index of the segment header
+        segment_index: SegmentIndex,
+    },
+    /// Segment headers don't match known segment header
+    #[error("Segment headers don't match known segment header {segment_index}")]
+    KnownSegmentHeaderMismatch {
+        /// Segment index of the known segment header
+        segment_index: SegmentIndex,
+    },
+}
+
+/// Verify segment headers received in response to [`SegmentHeaderRequest::SegmentIndexRange`].
+///
+/// Checks that segment headers correspond to segment indexes from `start` to `end` (inclusive)
+/// and form a chain of segment headers. If `known_segment_header` (trusted one, for instance
+/// already verified or committed on chain) is within the range, it must be present in the
+/// response, if it directly follows the range it must reference hash of the last segment header
+/// in the response, otherwise it is not used.
+pub fn verify_segment_headers_range(
+    start: SegmentIndex,
+    end: SegmentIndex,
+    segment_headers: &[SegmentHeader],
+    known_segment_header: Option<&SegmentHeader>,
+) -> Result<(), SegmentHeadersVerificationError> {
+    let expected = if start > end {
+        0
+    } else {
+        u64::from(end - start) + 1
+    };
+    let actual = segment_headers.len() as u64;
+    if actual != expected {
+        return Err(
+            SegmentHeadersVerificationError::UnexpectedNumberOfSegmentHeaders { expected, actual },
+        );
+    }
+
+    for (expected, segment_header) in (start..=end).zip(segment_headers) {
+        let actual = segment_header.segment_index();
+        if actual != expected {
+            return Err(SegmentHeadersVerificationError::UnexpectedSegmentIndex {
+                expected,
+                actual,
+            });
+        }
+    }
+
+    for pair in segment_headers.windows(2) {
+        if pair[1].prev_segment_header_hash() != pair[0].hash() {
+            return Err(SegmentHeadersVerificationError::BrokenChain {
+                segment_index: pair[1].segment_index(),
+            });
+        }
+    }
+
+    if let Some(known_segment_header) = known_segment_header {
+        let segment_index = known_segment_header.segment_index();
+        let matches = if (start..=end).contains(&segment_index) {
+            segment_headers[u64::from(segment_index - start) as usize] == *known_segment_header
+        } else if segment_index > end && segment_index - SegmentIndex::ONE == end {
+            segment_headers.last().map(SegmentHeader::hash)
+                == Some(known_segment_header.prev_segment_header_hash())
+        } else {
+            true
+        };
+
+        if !matches {
+            return Err(
+                SegmentHeadersVerificationError::KnownSegmentHeaderMismatch { segment_index },
+            );
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,121 @@
+use crate::request_handlers::segment_header::{
+    verify_segment_headers_range, SegmentHeadersVerificationError,
+};
+use subspace_core_primitives::{
+    ArchivedBlockProgress, Blake2b256Hash, LastArchivedBlock, SegmentCommitment, SegmentHeader,
+    SegmentIndex,
+};
+
+fn segment_headers_chain(number: u64) -> Vec<SegmentHeader> {
+    let mut prev_segment_header_hash = Blake2b256Hash::default();
+
+    (0..number)
+        .map(|segment_index| {
+            let segment_header = SegmentHeader::V0 {
+                segment_index: SegmentIndex::from(segment_index),
+                segment_commitment: SegmentCommitment::default(),
+                prev_segment_header_hash,
+                last_archived_block: LastArchivedBlock {
+                    number: segment_index as u32,
+                    archived_progress: ArchivedBlockProgress::Complete,
+                },
+            };
+            prev_segment_header_hash = segment_header.hash();
+
+            segment_header
+        })
+        .collect()
+}
+
+#[test]
+fn verify_valid_segment_headers_range() {
+    let segment_headers = segment_headers_chain(10);
+    let start = SegmentIndex::from(2);
+    let end = SegmentIndex::from(5);
+
+    assert_eq!(
+        verify_segment_headers_range(start, end, &segment_headers[2..=5], None),
+        Ok(())
+    );
+    // Known segment header within the range
+    assert_eq!(
+        verify_segment_headers_range(
+            start,
+            end,
+            &segment_headers[2..=5],
+            Some(&segment_headers[3])
+        ),
+        Ok(())
+    );
+    // Known segment header directly following the range
+    assert_eq!(
+        verify_segment_headers_range(
+            start,
+            end,
+            &segment_headers[2..=5],
+            Some(&segment_headers[6])
+        ),
+        Ok(())
+    );
+}
+
+#[test]
+fn verify_invalid_segment_headers_range() {
+    let segment_headers = segment_headers_chain(10);
+    let start = SegmentIndex::from(2);
+    let end = SegmentIndex::from(5);
+
+    assert_eq!(
+        verify_segment_headers_range(start, end, &segment_headers[2..5], None),
+        Err(
+            SegmentHeadersVerificationError::UnexpectedNumberOfSegmentHeaders {
+                expected: 4,
+                actual: 3,
+            }
+        )
+    );
+    assert_eq!(
+        verify_segment_headers_range(start, end, &segment_headers[3..=6], None),
+        Err(SegmentHeadersVerificationError::UnexpectedSegmentIndex {
+            expected: SegmentIndex::from(2),
+            actual: SegmentIndex::from(3),
+        })
+    );
+
+    let mut broken_chain = segment_headers[2..=5].to_vec();
+    broken_chain[2] = SegmentHeader::V0 {
+        segment_index: SegmentIndex::from(4),
+        segment_commitment: SegmentCommitment::default(),
+        prev_segment_header_hash: Blake2b256Hash::default(),
+        last_archived_block: broken_chain[2].last_archived_block(),
+    };
+    assert_eq!(
+        verify_segment_headers_range(start, end, &broken_chain, None),
+        Err(SegmentHeadersVerificationError::BrokenChain {
+            segment_index: SegmentIndex::from(4),
+        })
+    );
+
+    let unknown_segment_header = SegmentHeader::V0 {
+        segment_index: SegmentIndex::from(3),
+        segment_commitment: SegmentCommitment::default(),
+        prev_segment_header_hash: segment_headers[2].hash(),
+        last_archived_block: LastArchivedBlock {
+            number: 100,
+            archived_progress: ArchivedBlockProgress::Complete,
+        },
+    };
+    assert_eq!(
+        verify_segment_headers_range(
+            start,
+            end,
+            &segment_headers[2..=5],
+            Some(&unknown_segment_header)
+        ),
+        Err(
+            SegmentHeadersVerificationError::KnownSegmentHeaderMismatch {
+                segment_index: SegmentIndex::from(3),
+            }
+        )
+    );
+}
@@ -31,6 +31,11 @@
 //!
 //! - If provided, a ["requests processing"](ProtocolConfig::inbound_queue) channel
 //! is used to handle incoming requests.
+//!
+//! - Protocol might have [fallback names](ProtocolConfig::fallback_names) for older versions, in
+//! which case the newest version supported by both sides is negotiated and its name is attached
+//! to incoming requests and to responses, such that payload can be encoded and decoded according
+//! to the version.
 
 //! Original file commit: <https://github.com/paritytech/substrate/commit/c2fc4b3ca0d7a15cc3f9cb1e5f441d99ec8d6e0b>
 
@@ -93,6 +98,11 @@ pub struct ProtocolConfig {
     /// Name of the protocol on the wire. Should be something like `/foo/bar`.
     pub name: &'static str,
 
+    /// Names of older versions of the protocol that are still supported, in order of preference.
+    ///
+    /// The first name (starting with [`ProtocolConfig::name`]) supported by remote peer is used.
+    pub fallback_names: Vec<&'static str>,
+
     /// Maximum allowed size, in bytes, of a request.
     ///
     /// Any request larger than this value will be declined as a way to avoid allocating too
@@ -138,6 +148,7 @@ impl ProtocolConfig {
     pub fn new(protocol_name: &'static str) -> ProtocolConfig {
         ProtocolConfig {
             name: protocol_name,
+            fallback_names: Vec::new(),
             max_request_size: 1024 * 1024,
             max_response_size: 16 * 1024 * 1024,
             request_timeout: Duration::from_secs(20),
@@ -152,6 +163,10 @@ pub struct IncomingRequest {
     /// Who sent the request.
     pub peer: PeerId,
 
+    /// Name of the protocol version negotiated with the remote, either [`ProtocolConfig::name`]
+    /// or one of [`ProtocolConfig::fallback_names`].
+    pub protocol_name: &'static str,
+
     /// Request sent by the remote. Will always be smaller than
     /// [`ProtocolConfig::max_request_size`].
     pub payload: Vec<u8>,
@@ -278,8 +293,13 @@ pub struct RequestResponsesBehaviour {
     >,
 
     /// Pending requests, passed down to a [`RequestResponse`] behaviour, awaiting a reply.
-    pending_requests:
-        HashMap<ProtocolRequestId, (Instant, oneshot::Sender<Result<Vec<u8>, RequestFailure>>)>,
+    pending_requests: HashMap<
+        ProtocolRequestId,
+        (
+            Instant,
+            oneshot::Sender<Result<VersionedPayload, RequestFailure>>,
+        ),
+    >,
 
     /// Whenever an incoming request arrives, a `Future` is added to this list and will yield the
     /// start time and the response to send back to the remote.
@@ -299,8 +319,8 @@ pub struct RequestResponsesBehaviour {
 struct MessageRequest {
     peer: PeerId,
     request_id: RequestId,
-    request: Vec<u8>,
-    channel: ResponseChannel<Result<Vec<u8>, ()>>,
+    request: Vec<VersionedPayload>,
+    channel: ResponseChannel<Result<VersionedPayload, ()>>,
     protocol: String,
     response_builder: Option<mpsc::Sender<IncomingRequest>>,
 }
@@ -309,15 +329,20 @@ struct MessageRequest {
 struct RequestProcessingOutcome {
     request_id: RequestId,
     protocol: Cow<'static, str>,
-    inner_channel: ResponseChannel<Result<Vec<u8>, ()>>,
+    /// Protocol version negotiated for the request.
+    protocol_name: &'static str,
+    inner_channel: ResponseChannel<Result<VersionedPayload, ()>>,
     response: OutgoingResponse,
 }
 
 impl RequestResponsesBehaviour {
-    /// Creates a new behaviour. Must be passed a list of supported protocols. Returns an error if
-    /// the same protocol is passed twice.
+    /// Creates a new behaviour. Must be passed a list of supported protocols, request timeouts
+    /// overriding those in protocol configs (by protocol name) and how long idle connections
+    /// should be kept alive. Returns an error if the same protocol is passed twice.
     pub fn new(
         list: impl IntoIterator<Item = Box<dyn RequestHandler>>,
+        request_timeouts: &HashMap<&'static str, Duration>,
+        connection_keep_alive: Duration,
     ) -> Result<Self, RegisterError> {
         let mut protocols = HashMap::new();
         let mut request_handlers = Vec::new();
@@ -325,8 +350,13 @@ impl RequestResponsesBehaviour {
             let config = handler.protocol_config();
 
             let mut cfg = RequestResponseConfig::default();
-            cfg.set_connection_keep_alive(Duration::from_secs(10));
-            cfg.set_request_timeout(config.request_timeout);
+            cfg.set_connection_keep_alive(connection_keep_alive);
+            cfg.set_request_timeout(
+                request_timeouts
+                    .get(config.name)
+                    .copied()
+                    .unwrap_or(config.request_timeout),
+            );
 
             let protocol_support = if config.inbound_queue.is_some() {
                 ProtocolSupport::Full
@@ -334,12 +364,20 @@ impl RequestResponsesBehaviour {
                 ProtocolSupport::Outbound
             };
 
+            // Newest version goes first, such that it is preferred during negotiation
+            let protocol_names = iter::once(config.name)
+                .chain(config.fallback_names.iter().copied())
+                .collect::<Vec<_>>();
+
             let rq_rp = RequestResponse::new(
                 GenericCodec {
                     max_request_size: config.max_request_size,
                     max_response_size: config.max_response_size,
+                    protocol_names: protocol_names.clone(),
                 },
-                iter::once((config.name.as_bytes().to_vec(), protocol_support)),
+                protocol_names
+                    .into_iter()
+                    .map(|protocol_name| (protocol_name.as_bytes().to_vec(), protocol_support)),
                 cfg,
             );
 
@@ -370,13 +408,17 @@ impl RequestResponsesBehaviour {
     /// If there is no established connection to the target peer, the behavior is determined by the
     /// choice of `connect`.
     ///
+    /// `request` contains payloads encoded for the protocol and its fallback versions, the one
+    /// that corresponds to the version negotiated with the remote is sent. Response is returned
+    /// along with the name of negotiated version.
+    ///
     /// An error is returned if the protocol doesn't match one that has been registered.
     pub fn send_request(
         &mut self,
         target: &PeerId,
         protocol_name: &str,
-        request: Vec<u8>,
-        pending_response: oneshot::Sender<Result<Vec<u8>, RequestFailure>>,
+        request: Vec<VersionedPayload>,
+        pending_response: oneshot::Sender<Result<VersionedPayload, RequestFailure>>,
         connect: IfDisconnected,
     ) {
         if let Some((protocol, _)) = self.protocols.get_mut(protocol_name) {
@@ -606,6 +648,11 @@ impl NetworkBehaviour for RequestResponsesBehaviour {
 
                 let (tx, rx) = oneshot::channel();
 
+                let (protocol_name, payload) = request
+                    .into_iter()
+                    .next()
+                    .expect("Codec always reads exactly one payload of inbound request; qed");
+
                 // Submit the request to the "response builder" passed by the user at
                 // initialization.
                 if let Some(mut response_builder) = response_builder {
@@ -614,7 +661,8 @@ impl NetworkBehaviour for RequestResponsesBehaviour {
                     // an `InboundFailure::Omission` event.
                     let _ = response_builder.try_send(IncomingRequest {
                         peer,
-                        payload: request,
+                        protocol_name,
+                        payload,
                         pending_response: tx,
                     });
                 } else {
@@ -629,6 +677,7 @@ impl NetworkBehaviour for RequestResponsesBehaviour {
                         Some(RequestProcessingOutcome {
                             request_id,
                             protocol: Cow::from(protocol),
+                            protocol_name,
                             inner_channel: channel,
                             response,
                         })
@@ -646,6 +695,7 @@ impl NetworkBehaviour for RequestResponsesBehaviour {
                 let RequestProcessingOutcome {
                     request_id,
                     protocol: protocol_name,
+                    protocol_name: negotiated_protocol_name,
                     inner_channel,
                     response: OutgoingResponse { result, .. },
                 } = match outcome {
@@ -657,7 +707,10 @@ impl NetworkBehaviour for RequestResponsesBehaviour {
 
                 if let Ok(payload) = result {
                     if let Some((protocol, _)) = self.protocols.get_mut(&*protocol_name) {
-                        if protocol.send_response(inner_channel, Ok(payload)).is_err() {
+                        if protocol
+                            .send_response(inner_channel, Ok((negotiated_protocol_name, payload)))
+                            .is_err()
+                        {
                             // Note: Failure is handled further below when receiving
                             // `InboundFailure` event from `RequestResponse` behaviour.
                             debug!(
@@ -898,6 +951,10 @@ pub enum ResponseFailure {
     Network(InboundFailure),
 }
 
+/// Payload of request or response along with the name of the protocol version it is encoded
+/// for.
+pub type VersionedPayload = (&'static str, Vec<u8>);
+
 /// Implements the libp2p [`RequestResponseCodec`] trait. Defines how streams of bytes are turned
 /// into requests and responses and vice-versa.
 #[derive(Debug, Clone)]
@@ -905,22 +962,47 @@ pub enum ResponseFailure {
 pub struct GenericCodec {
     max_request_size: u64,
     max_response_size: u64,
+    /// Names of all supported versions of the protocol.
+    protocol_names: Vec<&'static str>,
+}
+
+impl GenericCodec {
+    /// Find name of the protocol version that was negotiated.
+    fn negotiated_protocol_name(&self, protocol: &[u8]) -> io::Result<&'static str> {
+        self.protocol_names
+            .iter()
+            .find(|protocol_name| protocol_name.as_bytes() == protocol)
+            .copied()
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "Negotiated unknown protocol: {}",
+                        String::from_utf8_lossy(protocol)
+                    ),
+                )
+            })
+    }
 }
 
 #[async_trait::async_trait]
 impl RequestResponseCodec for GenericCodec {
     type Protocol = Vec<u8>;
-    type Request = Vec<u8>;
-    type Response = Result<Vec<u8>, ()>;
+    /// Outbound request contains payload for every supported protocol version, inbound request
+    /// contains single payload for the negotiated version.
+    type Request = Vec<VersionedPayload>;
+    type Response = Result<VersionedPayload, ()>;
 
     async fn read_request<T>(
         &mut self,
-        _: &Self::Protocol,
+        protocol: &Self::Protocol,
         mut io: &mut T,
     ) -> io::Result<Self::Request>
     where
         T: AsyncRead + Unpin + Send,
     {
+        let protocol_name = self.negotiated_protocol_name(protocol)?;
+
         // Read the length.
         let length = unsigned_varint::aio::read_usize(&mut io)
             .await
@@ -938,17 +1020,19 @@ impl RequestResponseCodec for GenericCodec {
         // Read the payload.
         let mut buffer = vec![0; length];
         io.read_exact(&mut buffer).await?;
-        Ok(buffer)
+        Ok(vec![(protocol_name, buffer)])
     }
 
     async fn read_response<T>(
         &mut self,
-        _: &Self::Protocol,
+        protocol: &Self::Protocol,
         mut io: &mut T,
     ) -> io::Result<Self::Response>
     where
         T: AsyncRead + Unpin + Send,
     {
+        let protocol_name = self.negotiated_protocol_name(protocol)?;
+
         // Note that this function returns a `Result<Result<...>>`. Returning an `Err` is
         // considered as a protocol error and will result in the entire connection being closed.
         // Returning `Ok(Err(_))` signifies that a response has successfully been fetched, and
@@ -978,18 +1062,29 @@ impl RequestResponseCodec for GenericCodec {
         // Read the payload.
         let mut buffer = vec![0; length];
         io.read_exact(&mut buffer).await?;
-        Ok(Ok(buffer))
+        Ok(Ok((protocol_name, buffer)))
     }
 
     async fn write_request<T>(
         &mut self,
-        _: &Self::Protocol,
+        protocol: &Self::Protocol,
         io: &mut T,
         req: Self::Request,
     ) -> io::Result<()>
     where
         T: AsyncWrite + Unpin + Send,
     {
+        let protocol_name = self.negotiated_protocol_name(protocol)?;
+        let req = req
+            .into_iter()
+            .find_map(|(name, payload)| (name == protocol_name).then_some(payload))
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Request has no payload for negotiated protocol {protocol_name}"),
+                )
+            })?;
+
         // Write the length.
         {
             let mut buffer = unsigned_varint::encode::usize_buffer();
@@ -1014,7 +1109,7 @@ impl RequestResponseCodec for GenericCodec {
         T: AsyncWrite + Unpin + Send,
     {
         // If `res` is an `Err`, we jump to closing the substream without writing anything on it.
-        if let Ok(res) = res {
+        if let Ok((_protocol_name, res)) = res {
             // Write the length.
             {
                 let mut buffer = unsigned_varint::encode::usize_buffer();
@@ -12,6 +12,7 @@ use libp2p::core::upgrade;
 use libp2p::identity::Keypair;
 use libp2p::swarm::{Swarm, SwarmBuilder, SwarmEvent};
 use libp2p::{noise, Multiaddr};
+use std::collections::HashMap;
 use std::iter;
 use std::time::Duration;
 
@@ -50,7 +51,8 @@ fn build_swarm(
         .into_iter()
         .map(|config| Box::new(MockRunner(config)) as Box<dyn RequestHandler>)
         .collect::<Vec<_>>();
-    let behaviour = RequestResponsesBehaviour::new(configs).unwrap();
+    let behaviour =
+        RequestResponsesBehaviour::new(configs, &HashMap::new(), Duration::from_secs(10)).unwrap();
 
     let mut swarm =
         SwarmBuilder::with_tokio_executor(transport, behaviour, keypair.public().to_peer_id())
@@ -91,6 +93,7 @@ async fn basic_request_response_works() {
 
             let protocol_config = ProtocolConfig {
                 name: protocol_name,
+                fallback_names: Vec::new(),
                 max_request_size: 1024,
                 max_response_size: 1024 * 1024,
                 request_timeout: Duration::from_secs(30),
@@ -140,7 +143,7 @@ async fn basic_request_response_works() {
                     swarm.behaviour_mut().send_request(
                         &peer_id,
                         protocol_name,
-                        b"this is a request".to_vec(),
+                        vec![(protocol_name, b"this is a request".to_vec())],
                         sender,
                         IfDisconnected::ImmediateError,
                     );
@@ -157,7 +160,7 @@ async fn basic_request_response_works() {
 
         assert_eq!(
             response_receiver.unwrap().await.unwrap().unwrap(),
-            b"this is a response"
+            (protocol_name, b"this is a response".to_vec())
         );
     });
 }
@@ -190,6 +193,7 @@ async fn max_response_size_exceeded() {
 
             let protocol_config = ProtocolConfig {
                 name: protocol_name,
+                fallback_names: Vec::new(),
                 max_request_size: 1024,
                 max_response_size: 8, // <-- important for the test
                 request_timeout: Duration::from_secs(30),
@@ -241,7 +245,7 @@ async fn max_response_size_exceeded() {
                     swarm.behaviour_mut().send_request(
                         &peer_id,
                         protocol_name,
-                        b"this is a request".to_vec(),
+                        vec![(protocol_name, b"this is a request".to_vec())],
                         sender,
                         IfDisconnected::ImmediateError,
                     );
@@ -283,6 +287,7 @@ async fn request_id_collision() {
         let protocol_configs = vec![
             ProtocolConfig {
                 name: protocol_name_1,
+                fallback_names: Vec::new(),
                 max_request_size: 1024,
                 max_response_size: 1024 * 1024,
                 request_timeout: Duration::from_secs(30),
@@ -290,6 +295,7 @@ async fn request_id_collision() {
             },
             ProtocolConfig {
                 name: protocol_name_2,
+                fallback_names: Vec::new(),
                 max_request_size: 1024,
                 max_response_size: 1024 * 1024,
                 request_timeout: Duration::from_secs(30),
@@ -307,6 +313,7 @@ async fn request_id_collision() {
         let protocol_configs = vec![
             ProtocolConfig {
                 name: protocol_name_1,
+                fallback_names: Vec::new(),
                 max_request_size: 1024,
                 max_response_size: 1024 * 1024,
                 request_timeout: Duration::from_secs(30),
@@ -314,6 +321,7 @@ async fn request_id_collision() {
             },
             ProtocolConfig {
                 name: protocol_name_2,
+                fallback_names: Vec::new(),
                 max_request_size: 1024,
                 max_response_size: 1024 * 1024,
                 request_timeout: Duration::from_secs(30),
@@ -392,14 +400,14 @@ async fn request_id_collision() {
                     swarm_1.behaviour_mut().send_request(
                         &peer_id,
                         protocol_name_1,
-                        b"this is a request".to_vec(),
+                        vec![(protocol_name_1, b"this is a request".to_vec())],
                         sender_1,
                         IfDisconnected::ImmediateError,
                     );
                     swarm_1.behaviour_mut().send_request(
                         &peer_id,
                         protocol_name_2,
-                        b"this is a request".to_vec(),
+                        vec![(protocol_name_2, b"this is a request".to_vec())],
                         sender_2,
                         IfDisconnected::ImmediateError,
                     );
@@ -419,11 +427,118 @@ async fn request_id_collision() {
         let (response_receiver_1, response_receiver_2) = response_receivers.unwrap();
         assert_eq!(
             response_receiver_1.await.unwrap().unwrap(),
-            b"this is a response"
+            (protocol_name_1, b"this is a response".to_vec())
         );
         assert_eq!(
             response_receiver_2.await.unwrap().unwrap(),
-            b"this is a response"
+            (protocol_name_2, b"this is a response".to_vec())
+        );
+    });
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn protocol_version_negotiation() {
+    let protocol_name_v1 = "/test/req-resp/1";
+    let protocol_name_v2 = "/test/req-resp/2";
+    let mut pool = LocalPool::new();
+
+    // Swarm 1 supports both versions of the protocol
+    let mut swarm_1 = {
+        let protocol_config = ProtocolConfig {
+            name: protocol_name_v2,
+            fallback_names: vec![protocol_name_v1],
+            max_request_size: 1024,
+            max_response_size: 1024 * 1024,
+            request_timeout: Duration::from_secs(30),
+            inbound_queue: None,
+        };
+
+        build_swarm(iter::once(protocol_config)).0
+    };
+
+    // Swarm 2 only supports old version of the protocol
+    let (mut swarm_2, listen_addr_2) = {
+        let (tx, mut rx) = mpsc::channel::<IncomingRequest>(64);
+
+        pool.spawner()
+            .spawn_obj(
+                async move {
+                    while let Some(rq) = rx.next().await {
+                        assert_eq!(rq.protocol_name, protocol_name_v1);
+                        assert_eq!(rq.payload, b"this is a v1 request");
+                        let _ = rq.pending_response.send(OutgoingResponse {
+                            result: Ok(b"this is a v1 response".to_vec()),
+                            sent_feedback: None,
+                        });
+                    }
+                }
+                .boxed()
+                .into(),
+            )
+            .unwrap();
+
+        let protocol_config = ProtocolConfig {
+            name: protocol_name_v1,
+            fallback_names: Vec::new(),
+            max_request_size: 1024,
+            max_response_size: 1024 * 1024,
+            request_timeout: Duration::from_secs(30),
+            inbound_queue: Some(tx),
+        };
+
+        build_swarm(iter::once(protocol_config))
+    };
+
+    swarm_1.dial(listen_addr_2).unwrap();
+
+    pool.spawner()
+        .spawn_obj(
+            async move {
+                loop {
+                    if let SwarmEvent::Behaviour(Event::InboundRequest { result, .. }) =
+                        swarm_2.select_next_some().await
+                    {
+                        result.unwrap();
+                    }
+                }
+            }
+            .boxed()
+            .into(),
+        )
+        .unwrap();
+
+    pool.run_until(async move {
+        let mut response_receiver = None;
+
+        loop {
+            match swarm_1.select_next_some().await {
+                SwarmEvent::ConnectionEstablished { peer_id, .. } => {
+                    let (sender, receiver) = oneshot::channel();
+                    swarm_1.behaviour_mut().send_request(
+                        &peer_id,
+                        protocol_name_v2,
+                        vec![
+                            (protocol_name_v2, b"this is a v2 request".to_vec()),
+                            (protocol_name_v1, b"this is a v1 request".to_vec()),
+                        ],
+                        sender,
+                        IfDisconnected::ImmediateError,
+                    );
+                    assert!(response_receiver.is_none());
+                    response_receiver = Some(receiver);
+                }
+                SwarmEvent::Behaviour(Event::RequestFinished { result, .. }) => {
+                    result.unwrap();
+                    break;
+                }
+                _ => {}
+            }
+        }
+
+        // Old version was negotiated, since it is the newest one supported by both peers
+        assert_eq!(
+            response_receiver.unwrap().await.unwrap().unwrap(),
+            (protocol_name_v1, b"this is a v1 response".to_vec())
         );
     });
 }
@@ -1,17 +1,26 @@
 //! Data structures shared between node and node runner, facilitating exchange and creation of
 //! queries, subscriptions, various events and shared information.
 
-use crate::request_responses::RequestFailure;
+use crate::bandwidth::BandwidthTracker;
+use crate::metrics::NetworkingMetrics;
+use crate::node::{
+    ConnectionEvent, IdentifyInfo, KademliaQueryOptions, ProvidersLookupTrace, RoutingTableBucket,
+};
+use crate::peer_reputation::PeerReputation;
+use crate::request_responses::{RequestFailure, VersionedPayload};
 use crate::utils::{ResizableSemaphore, ResizableSemaphorePermit};
 use bytes::Bytes;
 use event_listener_primitives::Bag;
 use futures::channel::{mpsc, oneshot};
+use libp2p::autonat::NatStatus;
 use libp2p::core::multihash::Multihash;
 use libp2p::gossipsub::{PublishError, Sha256Topic, SubscriptionError};
 use libp2p::kad::record::Key;
 use libp2p::kad::PeerRecord;
 use libp2p::{Multiaddr, PeerId};
 use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::num::NonZeroUsize;
 use std::sync::atomic::AtomicUsize;
 use std::sync::Arc;
 
@@ -27,12 +36,16 @@ pub(crate) struct CreatedSubscription {
 pub(crate) enum Command {
     GetValue {
         key: Multihash,
+        quorum: NonZeroUsize,
+        retries: usize,
         result_sender: mpsc::UnboundedSender<PeerRecord>,
         permit: ResizableSemaphorePermit,
     },
     PutValue {
         key: Multihash,
         value: Vec<u8>,
+        quorum: NonZeroUsize,
+        retries: usize,
         result_sender: mpsc::UnboundedSender<()>,
         permit: ResizableSemaphorePermit,
     },
@@ -57,8 +70,8 @@ pub(crate) enum Command {
     GenericRequest {
         peer_id: PeerId,
         protocol_name: &'static str,
-        request: Vec<u8>,
-        result_sender: oneshot::Sender<Result<Vec<u8>, RequestFailure>>,
+        request: Vec<VersionedPayload>,
+        result_sender: oneshot::Sender<Result<VersionedPayload, RequestFailure>>,
     },
     CheckConnectedPeers {
         result_sender: oneshot::Sender<bool>,
@@ -73,9 +86,19 @@ pub(crate) enum Command {
     },
     GetProviders {
         key: Multihash,
+        quorum: NonZeroUsize,
+        retries: usize,
         result_sender: mpsc::UnboundedSender<PeerId>,
         permit: ResizableSemaphorePermit,
     },
+    TraceGetProviders {
+        key: Multihash,
+        result_sender: oneshot::Sender<ProvidersLookupTrace>,
+        permit: ResizableSemaphorePermit,
+    },
+    GetRoutingTable {
+        result_sender: oneshot::Sender<Vec<RoutingTableBucket>>,
+    },
     BanPeer {
         peer_id: PeerId,
     },
@@ -91,6 +114,7 @@ type Handler<A> = Bag<HandlerFn<A>, A>;
 pub(crate) struct Handlers {
     pub(crate) new_listener: Handler<Multiaddr>,
     pub(crate) num_established_peer_connections_change: Handler<usize>,
+    pub(crate) connection_event: Handler<ConnectionEvent>,
 }
 
 #[derive(Debug)]
@@ -101,10 +125,21 @@ pub(crate) struct Shared {
     pub(crate) listeners: Mutex<Vec<Multiaddr>>,
     pub(crate) external_addresses: Mutex<Vec<Multiaddr>>,
     pub(crate) num_established_peer_connections: Arc<AtomicUsize>,
+    /// Connected peers along with information they have reported about themselves (if any).
+    pub(crate) connected_peers: Mutex<HashMap<PeerId, Option<IdentifyInfo>>>,
     /// Sender end of the channel for sending commands to the swarm.
     pub(crate) command_sender: mpsc::Sender<Command>,
     pub(crate) kademlia_tasks_semaphore: ResizableSemaphore,
     pub(crate) regular_tasks_semaphore: ResizableSemaphore,
+    /// Default options of Kademlia queries.
+    pub(crate) kademlia_query_options: KademliaQueryOptions,
+    /// Bandwidth usage per protocol and per peer.
+    pub(crate) bandwidth_tracker: Arc<BandwidthTracker>,
+    /// Reputation of peers based on outcome of requests sent to them.
+    pub(crate) peer_reputation: Mutex<PeerReputation>,
+    pub(crate) metrics: Option<NetworkingMetrics>,
+    /// Whether node is publicly reachable according to AutoNAT.
+    pub(crate) nat_status: Mutex<NatStatus>,
 }
 
 impl Shared {
@@ -113,6 +148,9 @@ impl Shared {
         command_sender: mpsc::Sender<Command>,
         kademlia_tasks_semaphore: ResizableSemaphore,
         regular_tasks_semaphore: ResizableSemaphore,
+        kademlia_query_options: KademliaQueryOptions,
+        bandwidth_tracker: Arc<BandwidthTracker>,
+        metrics: Option<NetworkingMetrics>,
     ) -> Self {
         Self {
             handlers: Handlers::default(),
@@ -120,9 +158,15 @@ impl Shared {
             listeners: Mutex::default(),
             external_addresses: Mutex::default(),
             num_established_peer_connections: Arc::new(AtomicUsize::new(0)),
+            connected_peers: Mutex::default(),
             command_sender,
             kademlia_tasks_semaphore,
             regular_tasks_semaphore,
+            kademlia_query_options,
+            bandwidth_tracker,
+            peer_reputation: Mutex::default(),
+            metrics,
+            nat_status: Mutex::new(NatStatus::Unknown),
         }
     }
 }
@@ -1,5 +1,6 @@
 //! Miscellaneous utilities for networking.
 
+pub(crate) mod dnsaddr;
 pub mod multihash;
 pub mod piece_announcement;
 pub mod piece_provider;
@@ -28,6 +29,12 @@ pub(crate) fn is_global_address_or_dns(addr: &Multiaddr) -> bool {
     }
 }
 
+/// Whether address is a QUIC address (contains `/quic` or `/quic-v1`).
+pub(crate) fn is_quic_address(addr: &Multiaddr) -> bool {
+    addr.iter()
+        .any(|protocol| matches!(protocol, Protocol::Quic | Protocol::QuicV1))
+}
+
 // Generic collection batching helper.
 #[derive(Clone)]
 pub(crate) struct CollectionBatcher<T: Clone> {
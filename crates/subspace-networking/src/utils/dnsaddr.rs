@@ -0,0 +1,190 @@
+//! Resolution of `/dnsaddr` bootstrap addresses.
+//!
+//! `/dnsaddr/<domain>` is resolved into addresses stored in `dnsaddr=<multiaddr>` TXT records of
+//! `_dnsaddr.<domain>`, such that set of bootstrap nodes can be changed by updating DNS records
+//! instead of configuration of every node and farmer.
+
+#[cfg(test)]
+mod tests;
+
+use crate::utils::{convert_multiaddresses, PeerAddress};
+use libp2p::multiaddr::Protocol;
+use libp2p::Multiaddr;
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+use tracing::{debug, warn};
+use trust_dns_resolver::error::{ResolveError, ResolveErrorKind};
+use trust_dns_resolver::TokioAsyncResolver;
+
+/// Prefix of TXT records with addresses.
+const DNSADDR_TXT_RECORD_PREFIX: &str = "dnsaddr=";
+/// Limits the number of DNS lookups when resolving nested `/dnsaddr` addresses.
+const MAX_DNSADDR_LOOKUPS: usize = 32;
+/// How often `/dnsaddr` bootstrap addresses are resolved again, such that changes in DNS records
+/// are picked up without restart.
+const BOOTSTRAP_ADDRESSES_RESOLUTION_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+/// Bootstrap addresses that might contain `/dnsaddr` entries, which are resolved lazily and
+/// periodically re-resolved.
+#[derive(Clone, Default)]
+pub(crate) struct BootstrapAddresses {
+    addresses: Vec<Multiaddr>,
+    /// Last successfully resolved addresses for every `/dnsaddr` entry.
+    resolved: HashMap<Multiaddr, Vec<Multiaddr>>,
+    last_resolution: Option<Instant>,
+    resolver: Option<TokioAsyncResolver>,
+}
+
+impl BootstrapAddresses {
+    pub(crate) fn new(addresses: Vec<Multiaddr>) -> Self {
+        Self {
+            addresses,
+            ..Self::default()
+        }
+    }
+
+    /// Bootstrap addresses with `/dnsaddr` entries resolved.
+    pub(crate) async fn peer_addresses(&mut self) -> Vec<PeerAddress> {
+        if !self.addresses.iter().any(is_dnsaddr) {
+            return convert_multiaddresses(self.addresses.clone());
+        }
+
+        let resolution_needed = self.last_resolution.map_or(true, |last_resolution| {
+            last_resolution.elapsed() >= BOOTSTRAP_ADDRESSES_RESOLUTION_INTERVAL
+        });
+        if resolution_needed {
+            self.resolve().await;
+            self.last_resolution.replace(Instant::now());
+        }
+
+        let addresses = self
+            .addresses
+            .iter()
+            .flat_map(|address| match self.resolved.get(address) {
+                Some(resolved) => resolved.clone(),
+                None => {
+                    if is_dnsaddr(address) {
+                        Vec::new()
+                    } else {
+                        vec![address.clone()]
+                    }
+                }
+            })
+            .collect();
+
+        convert_multiaddresses(addresses)
+    }
+
+    async fn resolve(&mut self) {
+        let resolver = match &self.resolver {
+            Some(resolver) => resolver.clone(),
+            None => {
+                let resolver = match TokioAsyncResolver::tokio_from_system_conf() {
+                    Ok(resolver) => resolver,
+                    Err(error) => {
+                        warn!(%error, "Failed to create DNS resolver for bootstrap addresses");
+                        return;
+                    }
+                };
+                self.resolver.replace(resolver.clone());
+                resolver
+            }
+        };
+
+        for address in self.addresses.iter().filter(|address| is_dnsaddr(address)) {
+            match resolve_dnsaddr(&resolver, address.clone()).await {
+                Ok(resolved) => {
+                    debug!(%address, ?resolved, "Resolved bootstrap address");
+
+                    if resolved.is_empty() {
+                        warn!(%address, "Bootstrap address resolved to no addresses");
+                    }
+                    self.resolved.insert(address.clone(), resolved);
+                }
+                Err(error) => {
+                    // Previously resolved addresses (if any) are kept
+                    warn!(%address, %error, "Failed to resolve bootstrap address");
+                }
+            }
+        }
+    }
+}
+
+fn is_dnsaddr(address: &Multiaddr) -> bool {
+    matches!(address.iter().next(), Some(Protocol::Dnsaddr(_)))
+}
+
+/// Resolve `/dnsaddr` address (including nested `/dnsaddr` addresses) into regular addresses.
+async fn resolve_dnsaddr(
+    resolver: &TokioAsyncResolver,
+    address: Multiaddr,
+) -> Result<Vec<Multiaddr>, ResolveError> {
+    let mut resolved = Vec::new();
+    let mut pending = VecDeque::from([address]);
+    let mut lookups = 0;
+
+    while let Some(address) = pending.pop_front() {
+        let Some((domain, suffix)) = split_dnsaddr(&address) else {
+            resolved.push(address);
+            continue;
+        };
+
+        if lookups == MAX_DNSADDR_LOOKUPS {
+            warn!(%address, "Too many DNS lookups, skipping nested address");
+            continue;
+        }
+        lookups += 1;
+
+        let txt_lookup = match resolver.txt_lookup(format!("_dnsaddr.{domain}")).await {
+            Ok(txt_lookup) => txt_lookup,
+            Err(error) if matches!(error.kind(), ResolveErrorKind::NoRecordsFound { .. }) => {
+                continue;
+            }
+            Err(error) => {
+                return Err(error);
+            }
+        };
+
+        for txt in txt_lookup.iter() {
+            let record = txt
+                .txt_data()
+                .iter()
+                .map(|data| String::from_utf8_lossy(data))
+                .collect::<String>();
+
+            if let Some(address) = parse_dnsaddr_txt_record(&record) {
+                if address_ends_with(&address, &suffix) {
+                    pending.push_back(address);
+                }
+            }
+        }
+    }
+
+    Ok(resolved)
+}
+
+/// Split `/dnsaddr/<domain>/<suffix>` into domain and suffix that resolved addresses must match.
+fn split_dnsaddr(address: &Multiaddr) -> Option<(String, Multiaddr)> {
+    let mut protocols = address.iter();
+    let Some(Protocol::Dnsaddr(domain)) = protocols.next() else {
+        return None;
+    };
+
+    Some((domain.to_string(), protocols.collect()))
+}
+
+/// Parse `dnsaddr=<multiaddr>` TXT record.
+fn parse_dnsaddr_txt_record(record: &str) -> Option<Multiaddr> {
+    record
+        .strip_prefix(DNSADDR_TXT_RECORD_PREFIX)?
+        .trim()
+        .parse()
+        .ok()
+}
+
+fn address_ends_with(address: &Multiaddr, suffix: &Multiaddr) -> bool {
+    let address = address.iter().collect::<Vec<_>>();
+    let suffix = suffix.iter().collect::<Vec<_>>();
+
+    address.ends_with(&suffix)
+}
@@ -0,0 +1,78 @@
+use super::{
+    address_ends_with, is_dnsaddr, parse_dnsaddr_txt_record, split_dnsaddr, BootstrapAddresses,
+};
+use libp2p::{Multiaddr, PeerId};
+
+#[test]
+fn test_parse_dnsaddr_txt_record() {
+    let address = "/ip4/1.2.3.4/tcp/30533/p2p/12D3KooWHdiAxVd8uMQR1hGWXccidmfCwLqcMpGwR6QcTP6QRMuD"
+        .parse::<Multiaddr>()
+        .unwrap();
+
+    assert_eq!(
+        parse_dnsaddr_txt_record(&format!("dnsaddr={address}")),
+        Some(address)
+    );
+    assert_eq!(parse_dnsaddr_txt_record("dnsaddr=not-an-address"), None);
+    assert_eq!(parse_dnsaddr_txt_record("v=spf1 -all"), None);
+}
+
+#[test]
+fn test_split_dnsaddr() {
+    let peer_id = PeerId::random();
+
+    assert_eq!(
+        split_dnsaddr(
+            &format!("/dnsaddr/bootstrap.example.com/p2p/{peer_id}")
+                .parse()
+                .unwrap()
+        ),
+        Some((
+            "bootstrap.example.com".to_string(),
+            format!("/p2p/{peer_id}").parse().unwrap()
+        ))
+    );
+    assert_eq!(
+        split_dnsaddr(&"/dnsaddr/bootstrap.example.com".parse().unwrap()),
+        Some(("bootstrap.example.com".to_string(), Multiaddr::empty()))
+    );
+    assert_eq!(
+        split_dnsaddr(&"/dns/example.com/tcp/30533".parse().unwrap()),
+        None
+    );
+}
+
+#[test]
+fn test_dnsaddr_suffix_matching() {
+    let peer_id = PeerId::random();
+    let address = format!("/ip4/1.2.3.4/tcp/30533/p2p/{peer_id}")
+        .parse::<Multiaddr>()
+        .unwrap();
+
+    assert!(address_ends_with(&address, &Multiaddr::empty()));
+    assert!(address_ends_with(
+        &address,
+        &format!("/p2p/{peer_id}").parse().unwrap()
+    ));
+    assert!(!address_ends_with(
+        &address,
+        &format!("/p2p/{}", PeerId::random()).parse().unwrap()
+    ));
+
+    assert!(is_dnsaddr(&"/dnsaddr/example.com".parse().unwrap()));
+    assert!(!is_dnsaddr(&address));
+}
+
+#[tokio::test]
+async fn test_bootstrap_addresses_without_dnsaddr() {
+    let peer_id = PeerId::random();
+    let mut bootstrap_addresses =
+        BootstrapAddresses::new(vec![format!("/ip4/1.2.3.4/tcp/30533/p2p/{peer_id}")
+            .parse()
+            .unwrap()]);
+
+    assert_eq!(
+        bootstrap_addresses.peer_addresses().await,
+        vec![(peer_id, "/ip4/1.2.3.4/tcp/30533".parse().unwrap())]
+    );
+}
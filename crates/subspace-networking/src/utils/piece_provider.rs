@@ -5,11 +5,16 @@ use crate::{Node, PieceByHashRequest, PieceByHashResponse};
 use async_trait::async_trait;
 use backoff::future::retry;
 use backoff::ExponentialBackoff;
-use futures::StreamExt;
+use futures::stream::FuturesUnordered;
+use futures::{stream, StreamExt};
 use libp2p::PeerId;
+use lru::LruCache;
+use parking_lot::Mutex;
+use std::collections::VecDeque;
 use std::error::Error;
+use std::num::NonZeroUsize;
 use std::sync::atomic::{AtomicU64, Ordering};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use subspace_core_primitives::{Piece, PieceIndex};
 use tracing::{debug, error, trace, warn};
 
@@ -17,6 +22,12 @@ use tracing::{debug, error, trace, warn};
 const GET_PIECE_INITIAL_INTERVAL: Duration = Duration::from_secs(3);
 /// Defines max duration between get_piece calls.
 const GET_PIECE_MAX_INTERVAL: Duration = Duration::from_secs(40);
+/// How many providers to query for the same piece concurrently.
+const PROVIDER_REQUESTS_CONCURRENCY: usize = 3;
+/// Providers that take longer than this to return a valid piece are deprioritized.
+const SLOW_PROVIDER_THRESHOLD: Duration = Duration::from_secs(5);
+/// How many slow providers to remember.
+const SLOW_PROVIDERS_CACHE_SIZE: NonZeroUsize = NonZeroUsize::new(1_000).expect("Not zero; qed");
 
 /// Validates piece against using its commitment.
 #[async_trait]
@@ -60,6 +71,8 @@ impl PieceValidator for NoPieceValidator {
 pub struct PieceProvider<PV> {
     node: Node,
     piece_validator: Option<PV>,
+    slow_providers: Mutex<LruCache<PeerId, ()>>,
+    preferred_providers: Vec<PeerId>,
 }
 
 impl<PV> PieceProvider<PV>
@@ -71,53 +84,144 @@ where
         Self {
             node,
             piece_validator,
+            slow_providers: Mutex::new(LruCache::new(SLOW_PROVIDERS_CACHE_SIZE)),
+            preferred_providers: Vec::new(),
         }
     }
 
+    /// Query specified providers (for instance farmer running on the same host) for pieces along
+    /// with providers found in DHT, whichever returns valid piece first wins.
+    pub fn with_preferred_providers(mut self, preferred_providers: Vec<PeerId>) -> Self {
+        self.preferred_providers = preferred_providers;
+        self
+    }
+
     // Get from piece cache (L2) or archival storage (L1)
     async fn get_piece_from_storage(&self, piece_index: PieceIndex) -> Option<Piece> {
-        let piece_index_hash = piece_index.hash();
-        let key = piece_index_hash.to_multihash();
+        // Preferred providers race against providers found in DHT rather than delaying DHT query,
+        // such that unavailable preferred provider doesn't slow down piece retrieval
+        let mut preferred_requests = self
+            .preferred_providers
+            .iter()
+            .map(|&provider_id| self.request_piece(provider_id, piece_index))
+            .collect::<FuturesUnordered<_>>();
+
+        let key = piece_index.hash().to_multihash();
+
+        let mut get_providers_stream = match self.node.get_providers(key).await {
+            Ok(get_providers_stream) => get_providers_stream.left_stream(),
+            Err(err) => {
+                warn!(%piece_index,?key, ?err, "get_providers returned an error");
+                stream::empty().right_stream()
+            }
+        };
 
-        let get_providers_result = self.node.get_providers(key).await;
+        // Several providers are queried concurrently and the first valid piece wins, such that
+        // dead or slow providers do not stall piece retrieval
+        let mut requests = FuturesUnordered::new();
+        // Providers that were slow recently or have low reputation are only queried when nobody
+        // else is left
+        let mut deferred_providers = VecDeque::new();
+        let mut providers_exhausted = false;
 
-        match get_providers_result {
-            Ok(mut get_providers_stream) => {
-                while let Some(provider_id) = get_providers_stream.next().await {
+        loop {
+            let can_request_more = requests.len() < PROVIDER_REQUESTS_CONCURRENCY;
+            if can_request_more && providers_exhausted {
+                if let Some(provider_id) = deferred_providers.pop_front() {
+                    requests.push(self.request_piece(provider_id, piece_index));
+                    continue;
+                }
+            }
+
+            tokio::select! {
+                Some(maybe_piece) = preferred_requests.next(), if !preferred_requests.is_empty() => {
+                    if maybe_piece.is_some() {
+                        return maybe_piece;
+                    }
+                }
+                maybe_provider_id = get_providers_stream.next(),
+                    if can_request_more && !providers_exhausted =>
+                {
+                    let Some(provider_id) = maybe_provider_id else {
+                        providers_exhausted = true;
+                        continue;
+                    };
                     trace!(%piece_index, %provider_id, "get_providers returned an item");
 
-                    let request_result = self
-                        .node
-                        .send_generic_request(provider_id, PieceByHashRequest { piece_index_hash })
+                    if self.preferred_providers.contains(&provider_id) {
+                        // Already requested
+                        continue;
+                    }
+
+                    let peer_score = self.node.peer_score(&provider_id).unwrap_or_default();
+                    if peer_score.is_banned() {
+                        debug!(%piece_index, %provider_id, "Skipping provider with bad reputation");
+                    } else if peer_score.is_low()
+                        || self.slow_providers.lock().contains(&provider_id)
+                    {
+                        deferred_providers.push_back(provider_id);
+                    } else {
+                        requests.push(self.request_piece(provider_id, piece_index));
+                    }
+                }
+                Some(maybe_piece) = requests.next(), if !requests.is_empty() => {
+                    if maybe_piece.is_some() {
+                        return maybe_piece;
+                    }
+                }
+                else => {
+                    return None;
+                }
+            }
+        }
+    }
+
+    // Request piece from specific provider, keeps track of slow providers
+    async fn request_piece(&self, provider_id: PeerId, piece_index: PieceIndex) -> Option<Piece> {
+        let piece_index_hash = piece_index.hash();
+        let started_at = Instant::now();
+
+        let request_result = self
+            .node
+            .send_generic_request(provider_id, PieceByHashRequest { piece_index_hash })
+            .await;
+
+        let maybe_piece = match request_result {
+            Ok(PieceByHashResponse { piece: Some(piece) }) => {
+                trace!(%provider_id, %piece_index, "Piece request succeeded.");
+
+                if let Some(validator) = &self.piece_validator {
+                    let maybe_piece = validator
+                        .validate_piece(provider_id, piece_index, piece)
                         .await;
 
-                    match request_result {
-                        Ok(PieceByHashResponse { piece: Some(piece) }) => {
-                            trace!(%provider_id, %piece_index, ?key, "Piece request succeeded.");
-
-                            if let Some(validator) = &self.piece_validator {
-                                return validator
-                                    .validate_piece(provider_id, piece_index, piece)
-                                    .await;
-                            } else {
-                                return Some(piece);
-                            }
-                        }
-                        Ok(PieceByHashResponse { piece: None }) => {
-                            debug!(%provider_id, %piece_index, ?key, "Piece request returned empty piece.");
-                        }
-                        Err(error) => {
-                            debug!(%provider_id, %piece_index, ?key, ?error, "Piece request failed.");
-                        }
+                    if maybe_piece.is_none() {
+                        self.node.report_invalid_response(provider_id);
                     }
+
+                    maybe_piece
+                } else {
+                    Some(piece)
                 }
             }
-            Err(err) => {
-                warn!(%piece_index,?key, ?err, "get_providers returned an error");
+            Ok(PieceByHashResponse { piece: None }) => {
+                debug!(%provider_id, %piece_index, "Piece request returned empty piece.");
+                None
+            }
+            Err(error) => {
+                debug!(%provider_id, %piece_index, ?error, "Piece request failed.");
+                None
             }
+        };
+
+        // Providers that failed to return valid piece quickly are deprioritized
+        if maybe_piece.is_some() && started_at.elapsed() < SLOW_PROVIDER_THRESHOLD {
+            self.slow_providers.lock().pop(&provider_id);
+        } else {
+            self.slow_providers.lock().put(provider_id, ());
         }
 
-        None
+        maybe_piece
     }
 
     /// Returns piece by its index. Uses retry policy for error handling.
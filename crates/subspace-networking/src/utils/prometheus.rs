@@ -1,34 +1,30 @@
+use crate::NetworkingMetrics;
 use actix_web::http::StatusCode;
 use actix_web::web::Data;
 use actix_web::{get, App, HttpResponse, HttpServer};
-use parking_lot::Mutex;
-use prometheus_client::encoding::text::encode;
-use prometheus_client::registry::Registry;
 use std::error::Error;
 use std::net::SocketAddr;
-use std::sync::Arc;
 use std::thread;
 use tracing::{error, info};
 
-type SharedRegistry = Arc<Mutex<Registry>>;
-
 #[get("/metrics")]
-async fn metrics(registry: Data<SharedRegistry>) -> Result<HttpResponse, Box<dyn Error>> {
+async fn metrics(
+    networking_metrics: Data<NetworkingMetrics>,
+) -> Result<HttpResponse, Box<dyn Error>> {
     let mut encoded = String::new();
-    encode(&mut encoded, &registry.lock())?;
+    networking_metrics.encode(&mut encoded)?;
 
     let resp = HttpResponse::build(StatusCode::OK).body(encoded);
 
     Ok(resp)
 }
 
-/// Start prometheus metrics server on the provided address.
+/// Start prometheus metrics server with networking metrics on the provided address.
 pub async fn start_prometheus_metrics_server(
     address: SocketAddr,
-    registry: Registry,
+    networking_metrics: NetworkingMetrics,
 ) -> std::io::Result<()> {
-    let shared_registry = Arc::new(Mutex::new(registry));
-    let data = Data::new(shared_registry);
+    let data = Data::new(networking_metrics);
 
     info!("Starting metrics server on {} ...", address);
 
@@ -1,6 +1,38 @@
-use super::{CollectionBatcher, ResizableSemaphore};
+use super::{is_global_address_or_dns, CollectionBatcher, ResizableSemaphore};
+use libp2p::Multiaddr;
 use std::num::NonZeroUsize;
 
+#[test]
+fn test_global_address_or_dns() {
+    let address = |address: &str| address.parse::<Multiaddr>().unwrap();
+
+    assert!(is_global_address_or_dns(&address("/ip4/8.8.8.8/tcp/30433")));
+    assert!(is_global_address_or_dns(&address(
+        "/ip6/2001:4860:4860::8888/tcp/30433"
+    )));
+    assert!(is_global_address_or_dns(&address(
+        "/dns/bootstrap.example.com/tcp/30433"
+    )));
+
+    assert!(!is_global_address_or_dns(&address(
+        "/ip4/127.0.0.1/tcp/30433"
+    )));
+    assert!(!is_global_address_or_dns(&address(
+        "/ip4/10.0.0.1/tcp/30433"
+    )));
+    assert!(!is_global_address_or_dns(&address(
+        "/ip4/172.16.0.1/tcp/30433"
+    )));
+    assert!(!is_global_address_or_dns(&address(
+        "/ip4/192.168.1.1/tcp/30433"
+    )));
+    assert!(!is_global_address_or_dns(&address(
+        "/ip4/169.254.1.1/tcp/30433"
+    )));
+    assert!(!is_global_address_or_dns(&address("/ip6/::1/tcp/30433")));
+    assert!(!is_global_address_or_dns(&address("/memory/1")));
+}
+
 #[test]
 fn test_empty_collection() {
     let collection = vec![];
@@ -29,12 +29,18 @@ use futures::StreamExt;
 use sc_cli::{ChainSpec, CliConfiguration, SubstrateCli};
 use sc_client_api::BlockchainEvents;
 use sc_consensus_slots::SlotProportion;
-use sc_service::{BasePath, PartialComponents};
+use sc_service::{BasePath, Configuration, PartialComponents};
 use sc_storage_monitor::StorageMonitorService;
 use sp_core::crypto::Ss58AddressFormat;
 use sp_core::traits::SpawnEssentialNamed;
 use sp_domains::GenerateGenesisStateRoot;
+use std::fs;
+use std::num::NonZeroU64;
 use std::sync::Arc;
+use std::time::Duration;
+use subspace_networking::libp2p::multiaddr::Protocol;
+use subspace_networking::libp2p::PeerId;
+use subspace_networking::KeepAlivePolicy;
 use subspace_node::domain::{
     AccountId32ToAccountId20Converter, DomainCli, DomainGenesisBlockBuilder, DomainSubcommand,
     EVMDomainExecutorDispatch,
@@ -42,10 +48,15 @@ use subspace_node::domain::{
 use subspace_node::{Cli, ExecutorDispatch, Subcommand};
 use subspace_proof_of_space::chia::ChiaTable;
 use subspace_runtime::{Block, RuntimeApi};
-use subspace_service::{DsnConfig, SubspaceConfiguration, SubspaceNetworking};
+use subspace_service::reward_signing::RemoteRewardSignerConfig;
+use subspace_service::{DsnConfig, DsnSyncConfig, SubspaceConfiguration, SubspaceNetworking};
 
 type PosTable = ChiaTable;
 
+/// Minimal number of RPC connections with `--farmer-rpc-endpoint`, every farmer keeps one
+/// connection open for the duration of its operation.
+const FARMER_RPC_ENDPOINT_MAX_CONNECTIONS: u32 = 1_000;
+
 /// Subspace node error.
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
@@ -93,6 +104,14 @@ fn set_default_ss58_version<C: AsRef<dyn ChainSpec>>(chain_spec: C) {
     }
 }
 
+/// Raise RPC server limits for deployments that serve as RPC endpoint for many farmers, limits
+/// that are already higher are kept as is.
+fn tune_rpc_for_farmers(config: &mut Configuration) {
+    config.rpc_max_connections = config
+        .rpc_max_connections
+        .max(FARMER_RPC_ENDPOINT_MAX_CONNECTIONS);
+}
+
 fn main() -> Result<(), Error> {
     let cli = Cli::from_args();
 
@@ -192,7 +211,10 @@ fn main() -> Result<(), Error> {
                     &subspace_link,
                     client.clone(),
                     None,
-                );
+                    None,
+                    None,
+                )
+                .map_err(sc_service::Error::from)?;
 
                 task_manager
                     .spawn_essential_handle()
@@ -209,6 +231,39 @@ fn main() -> Result<(), Error> {
                 ))
             })?;
         }
+        Some(Subcommand::ExportSnapshot(cmd)) => {
+            let runner = cli.create_runner(cmd)?;
+            runner.sync_run(|config| {
+                cmd.run(&config, |config| {
+                    subspace_service::new_partial::<PosTable, RuntimeApi, ExecutorDispatch>(
+                        config, None,
+                    )
+                })
+            })?;
+        }
+        Some(Subcommand::ImportSnapshot(cmd)) => {
+            let runner = cli.create_runner(cmd)?;
+            runner.sync_run(|config| cmd.run(&config))?;
+        }
+        Some(Subcommand::Rearchive(cmd)) => {
+            let runner = cli.create_runner(cmd)?;
+            set_default_ss58_version(&runner.config().chain_spec);
+            runner.async_run(|config| {
+                let PartialComponents {
+                    client,
+                    task_manager,
+                    other: (_block_import, subspace_link, _telemetry, _bundle_validator),
+                    ..
+                } = subspace_service::new_partial::<PosTable, RuntimeApi, ExecutorDispatch>(
+                    &config, None,
+                )?;
+
+                Ok((
+                    cmd.run(client, subspace_link).map_err(Error::SubstrateCli),
+                    task_manager,
+                ))
+            })?;
+        }
         Some(Subcommand::PurgeChain(cmd)) => {
             // This is a compatibility layer to make sure we wipe old data from disks of our users
             if let Some(base_dir) = dirs::data_local_dir() {
@@ -386,7 +441,11 @@ fn main() -> Result<(), Error> {
         None => {
             let runner = cli.create_runner(&cli.run)?;
             set_default_ss58_version(&runner.config().chain_spec);
-            runner.run_node_until_exit(|consensus_chain_config| async move {
+            runner.run_node_until_exit(|mut consensus_chain_config| async move {
+                if cli.farmer_rpc_endpoint {
+                    tune_rpc_for_farmers(&mut consensus_chain_config);
+                }
+
                 let tokio_handle = consensus_chain_config.tokio_handle.clone();
                 let database_source = consensus_chain_config.database.clone();
 
@@ -400,6 +459,38 @@ fn main() -> Result<(), Error> {
                     );
                     let _enter = span.enter();
 
+                    let dsn_sync_piece_sources = cli
+                        .dsn_sync_piece_source
+                        .iter()
+                        .map(|address| match address.iter().last() {
+                            Some(Protocol::P2p(multihash)) => PeerId::from_multihash(multihash)
+                                .map_err(|_multihash| {
+                                    sc_service::Error::Other(format!(
+                                        "Invalid peer ID in DSN-sync piece source {address}"
+                                    ))
+                                }),
+                            _ => Err(sc_service::Error::Other(format!(
+                                "DSN-sync piece source {address} must end with /p2p/<peer ID>"
+                            ))),
+                        })
+                        .collect::<Result<Vec<_>, _>>()?;
+
+                    let reward_signer_authorization = match &cli.reward_signer_authorization_file {
+                        Some(path) => Some(
+                            fs::read_to_string(path)
+                                .map_err(|error| {
+                                    sc_service::Error::Other(format!(
+                                        "Failed to read reward signer authorization from {}: \
+                                        {error}",
+                                        path.display()
+                                    ))
+                                })?
+                                .trim_end()
+                                .to_string(),
+                        ),
+                        None => cli.reward_signer_authorization,
+                    };
+
                     let dsn_config = {
                         let network_keypair = consensus_chain_config
                             .network
@@ -412,7 +503,12 @@ fn main() -> Result<(), Error> {
                                 ))
                             })?;
 
-                        let dsn_bootstrap_nodes = if cli.dsn_bootstrap_nodes.is_empty() {
+                        let dsn_bootstrap_nodes = if !cli.dsn_bootstrap_nodes.is_empty()
+                            || cli.dsn_no_chain_spec_bootstrap_nodes
+                            || cli.dev_dsn
+                        {
+                            cli.dsn_bootstrap_nodes
+                        } else {
                             consensus_chain_config
                                 .chain_spec
                                 .properties()
@@ -425,8 +521,6 @@ fn main() -> Result<(), Error> {
                                     ))
                                 })?
                                 .unwrap_or_default()
-                        } else {
-                            cli.dsn_bootstrap_nodes
                         };
 
                         // TODO: Libp2p versions for Substrate and Subspace diverged.
@@ -447,15 +541,36 @@ fn main() -> Result<(), Error> {
                                     .config_dir(consensus_chain_config.chain_spec.id())
                                     .join("dsn")
                             }),
+                            max_provider_records: cli.dsn_max_provider_records,
                             listen_on: cli.dsn_listen_on,
                             bootstrap_nodes: dsn_bootstrap_nodes,
-                            reserved_peers: cli.dsn_reserved_peers,
+                            // Connections to DSN-sync piece sources need to be maintained
+                            reserved_peers: cli
+                                .dsn_reserved_peers
+                                .into_iter()
+                                .chain(cli.dsn_sync_piece_source)
+                                .collect(),
                             allow_non_global_addresses_in_dht: !cli.dsn_disable_private_ips,
+                            enable_memory_transport: cli.dev_dsn,
+                            enable_quic: !cli.dsn_disable_quic,
+                            prefer_quic_for_dials: cli.dsn_prefer_quic,
+                            enable_relay_server: cli.dsn_relay_server,
+                            relay_max_reservations: cli.dsn_relay_max_reservations,
+                            relay_max_reservations_per_peer: cli
+                                .dsn_relay_max_reservations_per_peer,
                             max_in_connections: cli.dsn_in_connections,
                             max_out_connections: cli.dsn_out_connections,
                             max_pending_in_connections: cli.dsn_pending_in_connections,
                             max_pending_out_connections: cli.dsn_pending_out_connections,
                             target_connections: cli.dsn_target_connections,
+                            connection_idle_timeout: Duration::from_secs(
+                                cli.dsn_connection_idle_timeout,
+                            ),
+                            keep_alive_policy: if cli.dsn_keep_alive_all_connections {
+                                KeepAlivePolicy::AllConnections
+                            } else {
+                                KeepAlivePolicy::ReservedPeers
+                            },
                         }
                     };
 
@@ -466,8 +581,42 @@ fn main() -> Result<(), Error> {
                         subspace_networking: SubspaceNetworking::Create {
                             config: dsn_config,
                             piece_cache_size: cli.piece_cache_size.as_u64(),
+                            piece_cache_memory_size: cli.piece_cache_memory_size.as_u64(),
                         },
                         sync_from_dsn: cli.sync_from_dsn,
+                        dsn_sync: DsnSyncConfig {
+                            no_imported_blocks_timeout: Duration::from_secs(
+                                cli.dsn_sync_no_imported_blocks_timeout,
+                            ),
+                            check_online_status_interval: Duration::from_secs(
+                                cli.dsn_sync_check_online_status_interval,
+                            ),
+                            max_sync_attempts: cli.dsn_sync_max_attempts,
+                            sync_on_dsn_online: !cli.dsn_sync_ignore_dsn_online,
+                            dsn_online_debounce: Duration::from_secs(
+                                cli.dsn_sync_dsn_online_debounce,
+                            ),
+                            max_queued_blocks: cli.dsn_sync_max_queued_blocks,
+                            fell_behind_threshold: cli.dsn_sync_fell_behind_threshold,
+                            block_origin: cli.dsn_sync_block_origin.into(),
+                            force_import: cli.dsn_sync_force_import,
+                            download_rate_limit: cli
+                                .dsn_sync_download_rate_limit
+                                .and_then(|rate_limit| NonZeroU64::new(rate_limit.as_u64())),
+                            segment_cache_size: cli.dsn_sync_segment_cache_size,
+                            reconstruction_threads: cli.dsn_sync_reconstruction_threads,
+                            preferred_piece_providers: dsn_sync_piece_sources,
+                            ..DsnSyncConfig::default()
+                        },
+                        archive_object_mappings: cli.archive_object_mappings,
+                        segment_headers_retention: cli.segment_headers_retention,
+                        remote_reward_signer: cli.reward_signer_url.map(|url| {
+                            RemoteRewardSignerConfig {
+                                url,
+                                authorization: reward_signer_authorization,
+                            }
+                        }),
+                        archiver_threads: cli.archiver_threads,
                         enable_subspace_block_relay: cli.enable_subspace_block_relay
                             || cli.run.is_dev().unwrap_or(false),
                     };
@@ -23,7 +23,7 @@ use sp_runtime::traits::Block as BlockT;
 use std::sync::Arc;
 use subspace_networking::libp2p::Multiaddr;
 use subspace_networking::{BootstrappedNetworkingParameters, Config, PieceByHashRequestHandler};
-use subspace_service::dsn::import_blocks::initial_block_import_from_dsn;
+use subspace_service::dsn::import_blocks::{initial_block_import_from_dsn, ImportBlocksOptions};
 
 /// The `import-blocks-from-network` command used to import blocks from Subspace Network DSN.
 #[derive(Debug, Parser)]
@@ -85,9 +85,15 @@ impl ImportBlocksFromDsnCmd {
 
         // Repeat until no new blocks are imported
         loop {
-            let new_imported_blocks =
-                initial_block_import_from_dsn(&node, Arc::clone(&client), &mut import_queue, false)
-                    .await?;
+            let new_imported_blocks = initial_block_import_from_dsn(
+                &node,
+                Arc::clone(&client),
+                None,
+                &mut import_queue,
+                &ImportBlocksOptions::default(),
+                None,
+            )
+            .await?;
 
             if new_imported_blocks == 0 {
                 break;
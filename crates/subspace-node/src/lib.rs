@@ -20,10 +20,14 @@ mod chain_spec;
 mod chain_spec_utils;
 pub mod domain;
 mod import_blocks_from_dsn;
+mod rearchive;
+mod snapshot;
 
 pub use crate::import_blocks_from_dsn::ImportBlocksFromDsnCmd;
+pub use crate::rearchive::RearchiveCmd;
+pub use crate::snapshot::{ExportSnapshotCmd, ImportSnapshotCmd};
 use bytesize::ByteSize;
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use sc_cli::{RunCmd, SubstrateCli};
 use sc_executor::{NativeExecutionDispatch, RuntimeVersion};
 use sc_service::ChainSpec;
@@ -31,7 +35,10 @@ use sc_storage_monitor::StorageMonitorParams;
 use sc_subspace_chain_specs::ConsensusChainSpec;
 use sc_telemetry::serde_json;
 use serde_json::Value;
+use sp_consensus::BlockOrigin;
 use std::io::Write;
+use std::num::{NonZeroU32, NonZeroU64, NonZeroUsize};
+use std::path::PathBuf;
 use std::{fs, io};
 use subspace_networking::libp2p::Multiaddr;
 
@@ -125,6 +132,25 @@ impl PurgeChainCmd {
     }
 }
 
+/// Origin with which blocks from DSN are imported.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, ValueEnum)]
+pub enum DsnSyncBlockOrigin {
+    /// Import blocks as if they were received during initial sync (equivocations are not
+    /// reported).
+    NetworkInitialSync,
+    /// Import blocks as if they were broadcast by peers.
+    NetworkBroadcast,
+}
+
+impl From<DsnSyncBlockOrigin> for BlockOrigin {
+    fn from(origin: DsnSyncBlockOrigin) -> Self {
+        match origin {
+            DsnSyncBlockOrigin::NetworkInitialSync => BlockOrigin::NetworkInitialSync,
+            DsnSyncBlockOrigin::NetworkBroadcast => BlockOrigin::NetworkBroadcast,
+        }
+    }
+}
+
 /// Utilities for working with a node.
 #[derive(Debug, clap::Subcommand)]
 #[allow(clippy::large_enum_variant)]
@@ -151,6 +177,15 @@ pub enum Subcommand {
     /// Import blocks from Subspace Network DSN.
     ImportBlocksFromDsn(ImportBlocksFromDsnCmd),
 
+    /// Export snapshot of the database that can be used to bootstrap other nodes.
+    ExportSnapshot(ExportSnapshotCmd),
+
+    /// Bootstrap database from a snapshot produced by `export-snapshot`.
+    ImportSnapshot(ImportSnapshotCmd),
+
+    /// Re-run archiver from a given block, regenerating segments.
+    Rearchive(RearchiveCmd),
+
     /// Remove the whole chain.
     PurgeChain(PurgeChainCmd),
 
@@ -185,16 +220,26 @@ pub struct Cli {
     #[clap(flatten)]
     pub run: RunCmd,
 
-    /// Where local DSN node will listen for incoming connections.
-    // TODO: Add more DSN-related parameters
+    /// Where local DSN node will listen for incoming connections, multiple are supported.
     #[arg(long, default_value = "/ip4/0.0.0.0/tcp/30433")]
     pub dsn_listen_on: Vec<Multiaddr>,
 
-    /// Bootstrap nodes for DSN.
+    /// Bootstrap nodes for DSN, multiple are supported.
+    ///
+    /// `/dnsaddr/<domain>` entries are supported and periodically resolved from DNS TXT records.
+    ///
+    /// When not specified, bootstrap nodes from `dsnBootstrapNodes` property of the chain spec are
+    /// used.
     #[arg(long)]
     pub dsn_bootstrap_nodes: Vec<Multiaddr>,
 
-    /// Reserved peers for DSN.
+    /// Do not use bootstrap nodes from the chain spec when `--dsn-bootstrap-nodes` is not
+    /// specified, useful for private networks and test chains that run their own DSN.
+    #[arg(long, default_value_t = false)]
+    pub dsn_no_chain_spec_bootstrap_nodes: bool,
+
+    /// Reserved peers for DSN, node will maintain permanent connections to them, multiple are
+    /// supported.
     #[arg(long)]
     pub dsn_reserved_peers: Vec<Multiaddr>,
 
@@ -218,19 +263,192 @@ pub struct Cli {
     #[arg(long, default_value_t = 50)]
     pub dsn_target_connections: u32,
 
+    /// Number of seconds idle DSN connections are kept alive before being closed.
+    #[arg(long, default_value_t = 10)]
+    pub dsn_connection_idle_timeout: u64,
+
+    /// Keep all DSN connections alive instead of closing them once idle, such that frequently
+    /// used peers are not re-dialed all the time. Connections to reserved peers are always kept
+    /// alive.
+    #[arg(long, default_value_t = false)]
+    pub dsn_keep_alive_all_connections: bool,
+
     /// Determines whether we allow keeping non-global (private, shared, loopback..) addresses
     /// in Kademlia DHT for the DSN.
     #[arg(long, default_value_t = false)]
     pub dsn_disable_private_ips: bool,
 
+    /// Disable QUIC transport for the DSN, only TCP and WebSocket will be used.
+    #[arg(long, default_value_t = false)]
+    pub dsn_disable_quic: bool,
+
+    /// Dial QUIC addresses of DSN peers first and only fall back to their other addresses if QUIC
+    /// dial failed, QUIC listen address like `/ip4/0.0.0.0/udp/30433/quic-v1` needs to be added to
+    /// `--dsn-listen-on` for other peers to be able to dial this node over QUIC.
+    #[arg(long, default_value_t = false, conflicts_with = "dsn_disable_quic")]
+    pub dsn_prefer_quic: bool,
+
+    /// Max number of keys provider records of other DSN peers are stored for, records are stored
+    /// on disk and each key takes ~1KB, records for the farthest keys are evicted first.
+    #[arg(long, default_value = "100000")]
+    pub dsn_max_provider_records: NonZeroUsize,
+
+    /// Act as circuit relay v2 server, such that DSN peers that are not publicly reachable
+    /// (farmers behind NAT for instance) can be reached through this node. Only makes sense for
+    /// publicly reachable nodes with good connectivity.
+    #[arg(long, default_value_t = false)]
+    pub dsn_relay_server: bool,
+
+    /// Defines max number of active relay reservations when `--dsn-relay-server` is used.
+    #[arg(long, default_value_t = 128)]
+    pub dsn_relay_max_reservations: usize,
+
+    /// Defines max number of active relay reservations per peer when `--dsn-relay-server` is used.
+    #[arg(long, default_value_t = 4)]
+    pub dsn_relay_max_reservations_per_peer: usize,
+
+    /// Run DSN in local development mode.
+    ///
+    /// Node doesn't use any bootstrap nodes (neither from CLI nor from the chain spec), allows
+    /// private addresses in DHT and enables in-memory transport. Archiving and serving of pieces
+    /// works as usual, so that farmer can be pointed at this node for fully offline development
+    /// and CI tests.
+    #[arg(
+        long,
+        default_value_t = false,
+        conflicts_with_all = ["dsn_bootstrap_nodes", "dsn_disable_private_ips", "sync_from_dsn"]
+    )]
+    pub dev_dsn: bool,
+
+    /// Number of most recent segment headers to keep, by default all segment headers are kept.
+    ///
+    /// Pruned segment headers are no longer served to farmers and DSN peers by this node.
+    #[arg(long)]
+    pub segment_headers_retention: Option<NonZeroU64>,
+
+    /// URL of JSON-RPC endpoint of remote signer to delegate reward signing to.
+    ///
+    /// Remote signer must implement `subspace_signRewardHash` method, which allows keeping plot
+    /// identity keys outside of the node and farmer (for instance in HSM).
+    #[arg(long)]
+    pub reward_signer_url: Option<String>,
+
+    /// Value of `Authorization` header sent to remote signer, for instance `Bearer <token>`.
+    ///
+    /// Prefer `SUBSPACE_REWARD_SIGNER_AUTHORIZATION` environment variable or
+    /// `--reward-signer-authorization-file` to passing secret as an argument, which is visible to
+    /// other users of the system.
+    #[arg(
+        long,
+        env = "SUBSPACE_REWARD_SIGNER_AUTHORIZATION",
+        hide_env_values = true,
+        requires = "reward_signer_url"
+    )]
+    pub reward_signer_authorization: Option<String>,
+
+    /// File with value of `Authorization` header sent to remote signer, trailing whitespace is
+    /// ignored.
+    #[arg(
+        long,
+        requires = "reward_signer_url",
+        conflicts_with = "reward_signer_authorization"
+    )]
+    pub reward_signer_authorization_file: Option<PathBuf>,
+
+    /// Number of threads used by archiver for erasure coding of archived segments, by default
+    /// number of logical CPUs is used.
+    #[arg(long)]
+    pub archiver_threads: Option<NonZeroUsize>,
+
+    /// Tune RPC server for serving many farmers (hundreds of farmers connected to one node).
+    ///
+    /// Raises RPC connection limit to at least 1000, other RPC limits can be adjusted with
+    /// `--rpc-max-connections`, `--rpc-max-subscriptions-per-connection`,
+    /// `--rpc-max-request-size` and `--rpc-max-response-size`.
+    #[arg(long, default_value_t = false)]
+    pub farmer_rpc_endpoint: bool,
+
     /// Enables DSN-sync on startup.
     #[arg(long, default_value_t = false)]
     pub sync_from_dsn: bool,
 
+    /// How long to wait in seconds for new blocks to be imported before starting DSN-sync.
+    #[arg(long, default_value_t = 600)]
+    pub dsn_sync_no_imported_blocks_timeout: u64,
+
+    /// How often to check in seconds whether node is online, going online triggers DSN-sync.
+    #[arg(long, default_value_t = 10)]
+    pub dsn_sync_check_online_status_interval: u64,
+
+    /// How many times to attempt DSN-sync (with exponential backoff between attempts) before
+    /// giving up until it is triggered again.
+    #[arg(long, default_value = "5")]
+    pub dsn_sync_max_attempts: NonZeroU32,
+
+    /// Do not start DSN-sync when node connects to DSN peers, only Substrate networking and block
+    /// import timeout will trigger it.
+    #[arg(long, default_value_t = false)]
+    pub dsn_sync_ignore_dsn_online: bool,
+
+    /// How long node must stay connected to DSN peers in seconds before DSN-sync starts.
+    #[arg(long, default_value_t = 30)]
+    pub dsn_sync_dsn_online_debounce: u64,
+
+    /// Max number of blocks from DSN queued for import at any time during DSN-sync.
+    #[arg(long, default_value_t = 2048)]
+    pub dsn_sync_max_queued_blocks: u32,
+
+    /// How many blocks node must be behind the median best block of connected peers to start
+    /// DSN-sync without waiting for imported blocks timeout.
+    #[arg(long, default_value_t = 1000)]
+    pub dsn_sync_fell_behind_threshold: u32,
+
+    /// Origin with which blocks from DSN are imported during DSN-sync.
+    #[arg(long, value_enum, default_value_t = DsnSyncBlockOrigin::NetworkBroadcast)]
+    pub dsn_sync_block_origin: DsnSyncBlockOrigin,
+
+    /// Import and fully verify blocks from DSN during DSN-sync even if they are already known to
+    /// the node.
+    #[arg(long, default_value_t = false)]
+    pub dsn_sync_force_import: bool,
+
+    /// Max download rate of pieces per second during DSN-sync in human readable format (e.g.
+    /// 10MB, 1MiB) or just bytes (e.g. 4096), unlimited by default.
+    #[arg(long)]
+    pub dsn_sync_download_rate_limit: Option<ByteSize>,
+
+    /// Number of recently downloaded segments cached on disk during DSN-sync, such that
+    /// interrupted DSN-sync doesn't download them again. Each segment takes about 128 MiB of disk
+    /// space, disabled by default.
+    #[arg(long, default_value_t = 0)]
+    pub dsn_sync_segment_cache_size: usize,
+
+    /// Number of threads used to reconstruct downloaded segments during DSN-sync.
+    #[arg(long, default_value = "4")]
+    pub dsn_sync_reconstruction_threads: NonZeroUsize,
+
+    /// Trusted source of pieces for DSN-sync (typically farmer running on the same host) that is
+    /// queried concurrently with providers found in the rest of the network, must include
+    /// `/p2p/<peer ID>`. Also added to DSN reserved peers.
+    #[arg(long)]
+    pub dsn_sync_piece_source: Vec<Multiaddr>,
+
+    /// Store object mappings of segments archived by this node, required for `subspace_fetchObject`
+    /// RPC. Mappings of segments archived before this option was enabled or imported from DSN are
+    /// not available.
+    #[arg(long)]
+    pub archive_object_mappings: bool,
+
     /// Piece cache size in human readable format (e.g. 10GB, 2TiB) or just bytes (e.g. 4096).
     #[arg(long, default_value = "1GiB")]
     pub piece_cache_size: ByteSize,
 
+    /// Size of the part of piece cache that is also kept in memory for faster serving of the most
+    /// recently archived or requested pieces, in human readable format (e.g. 256MiB) or just bytes.
+    /// Increases memory usage by the same amount, disabled by default.
+    #[arg(long, default_value = "0")]
+    pub piece_cache_memory_size: ByteSize,
+
     /// Domain arguments
     ///
     /// The command-line arguments provided first will be passed to the embedded consensus node,
@@ -289,7 +507,7 @@ impl SubstrateCli for Cli {
             "devnet-compiled" => chain_spec::devnet_config_compiled()?,
             "dev" => chain_spec::dev_config()?,
             "" | "local" => chain_spec::local_config()?,
-            path => ConsensusChainSpec::from_json_file(std::path::PathBuf::from(path))?,
+            path => ConsensusChainSpec::from_json_file(PathBuf::from(path))?,
         };
 
         // In case there are bootstrap nodes specified explicitly, ignore those that are in the
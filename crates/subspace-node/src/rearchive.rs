@@ -0,0 +1,155 @@
+// Copyright (C) 2023 Subspace Labs, Inc.
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use clap::Parser;
+use futures::{select, FutureExt, StreamExt};
+use log::{debug, info};
+use sc_cli::{CliConfiguration, ImportParams, SharedParams};
+use sc_client_api::{AuxStore, BlockBackend, HeaderBackend};
+use sc_consensus_subspace::{ArchivedSegmentNotification, SubspaceLink};
+use sp_api::ProvideRuntimeApi;
+use sp_consensus_subspace::{FarmerPublicKey, SubspaceApi};
+use sp_objects::ObjectsApi;
+use sp_runtime::traits::Block as BlockT;
+use std::num::NonZeroUsize;
+use std::sync::Arc;
+use subspace_core_primitives::BlockNumber;
+use subspace_service::object_mappings::ObjectMappingCache;
+use subspace_service::segment_headers::SegmentHeaderCache;
+
+/// The `rearchive` command used to re-run archiver from a given block.
+///
+/// Segments starting with the last one committed to the chain at or before the block are
+/// regenerated and segment headers and object mappings of the node are updated with them. This
+/// is useful for recovery after corruption of archival state or to rebuild object mappings.
+///
+/// Node must not be running while this command is executed.
+#[derive(Debug, Parser)]
+pub struct RearchiveCmd {
+    /// Block number to re-run archiver from.
+    #[arg(long, default_value_t = 0)]
+    pub from_block: BlockNumber,
+
+    /// Number of threads used by archiver for erasure coding of archived segments, by default
+    /// number of logical CPUs is used.
+    #[arg(long)]
+    pub archiver_threads: Option<NonZeroUsize>,
+
+    #[allow(missing_docs)]
+    #[clap(flatten)]
+    pub shared_params: SharedParams,
+
+    #[allow(missing_docs)]
+    #[clap(flatten)]
+    pub import_params: ImportParams,
+}
+
+impl RearchiveCmd {
+    /// Run the rearchive command
+    pub async fn run<Block, Client>(
+        &self,
+        client: Arc<Client>,
+        subspace_link: SubspaceLink<Block>,
+    ) -> sc_cli::Result<()>
+    where
+        Block: BlockT,
+        Client: ProvideRuntimeApi<Block>
+            + BlockBackend<Block>
+            + HeaderBackend<Block>
+            + AuxStore
+            + Send
+            + Sync
+            + 'static,
+        Client::Api: SubspaceApi<Block, FarmerPublicKey> + ObjectsApi<Block>,
+    {
+        let segment_header_cache =
+            SegmentHeaderCache::new(client.clone(), None).map_err(|error| {
+                sc_service::Error::Other(format!(
+                    "Failed to instantiate segment header cache: {error}"
+                ))
+            })?;
+        let object_mapping_cache = ObjectMappingCache::new(client.clone());
+
+        // Subscribe before archiving starts, such that no notifications are missed
+        let mut archived_segment_notification_stream = subspace_link
+            .archived_segment_notification_stream()
+            .subscribe();
+
+        info!("Re-archiving from block #{}", self.from_block);
+
+        let mut rearchiving = Box::pin(
+            sc_consensus_subspace::rearchive_from_block(
+                &subspace_link,
+                client.clone(),
+                self.from_block.into(),
+                self.archiver_threads,
+            )
+            .fuse(),
+        );
+
+        let regenerated_segments = loop {
+            select! {
+                result = rearchiving => {
+                    break result?;
+                }
+                maybe_notification = archived_segment_notification_stream.next() => {
+                    let Some(ArchivedSegmentNotification {
+                        archived_segment,
+                        ..
+                    }) = maybe_notification else {
+                        continue;
+                    };
+
+                    let segment_header = archived_segment.segment_header;
+                    let segment_index = segment_header.segment_index();
+
+                    segment_header_cache
+                        .add_segment_header(segment_header)
+                        .and_then(|()| {
+                            object_mapping_cache.add_object_mappings(
+                                segment_index,
+                                &archived_segment.object_mapping,
+                            )
+                        })
+                        .map_err(|error| {
+                            sc_service::Error::Other(format!(
+                                "Failed to store re-archived segment {segment_index}: {error}"
+                            ))
+                        })?;
+
+                    debug!("Re-archived segment {segment_index}");
+                }
+            }
+        };
+
+        info!(
+            "Re-archived {regenerated_segments} segments, last segment index {}",
+            segment_header_cache.max_segment_index()
+        );
+
+        Ok(())
+    }
+}
+
+impl CliConfiguration for RearchiveCmd {
+    fn shared_params(&self) -> &SharedParams {
+        &self.shared_params
+    }
+
+    fn import_params(&self) -> Option<&ImportParams> {
+        Some(&self.import_params)
+    }
+}
@@ -0,0 +1,397 @@
+// Copyright (C) 2023 Subspace Labs, Inc.
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use blake2::digest::typenum::U32;
+use blake2::digest::{FixedOutput, Update};
+use blake2::Blake2b;
+use clap::Parser;
+use fs2::FileExt;
+use log::info;
+use sc_cli::{CliConfiguration, DatabaseParams, SharedParams};
+use sc_service::config::Configuration;
+use sc_service::ChainSpec;
+use serde::{Deserialize, Serialize};
+use sp_core::hashing::blake2_256;
+use sp_core::H256;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::{fs, io};
+
+/// Version of the snapshot format, snapshots with different version can't be imported.
+const SNAPSHOT_FORMAT_VERSION: u32 = 2;
+/// File with snapshot metadata within snapshot directory.
+const SNAPSHOT_MANIFEST_FILE: &str = "manifest.json";
+/// Directory with database files within snapshot directory.
+const SNAPSHOT_DATABASE_DIR: &str = "db";
+/// File within database directory that is exclusively locked by the database while it is open.
+const DATABASE_LOCK_FILE: &str = "lock";
+/// Suffix of directory next to the database where snapshot is copied during import.
+const IMPORTING_DATABASE_SUFFIX: &str = "importing";
+/// Suffix of directory next to the database where replaced database is moved during import.
+const REPLACED_DATABASE_SUFFIX: &str = "replaced";
+
+/// Metadata stored alongside database files in the snapshot.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SnapshotManifest {
+    format_version: u32,
+    chain_id: String,
+    /// `0x`-prefixed hex-encoded BLAKE2b-256 hash of the raw chain spec the database was created
+    /// with.
+    chain_spec_hash: String,
+    /// `0x`-prefixed hex-encoded BLAKE2b-256 checksum of database files in the snapshot, see
+    /// [`database_checksum`].
+    database_checksum: String,
+}
+
+/// The `export-snapshot` command used to export a snapshot of the client database.
+///
+/// Node must not be running while snapshot is exported and can't be started until export is
+/// finished, resulting directory can be archived and uploaded to be used by other operators with
+/// `import-snapshot` command.
+#[derive(Debug, Parser)]
+pub struct ExportSnapshotCmd {
+    /// Directory where snapshot will be written, must not exist or be empty.
+    #[arg(long)]
+    pub output: PathBuf,
+
+    #[allow(missing_docs)]
+    #[clap(flatten)]
+    pub shared_params: SharedParams,
+
+    #[allow(missing_docs)]
+    #[clap(flatten)]
+    pub database_params: DatabaseParams,
+}
+
+impl ExportSnapshotCmd {
+    /// Run the export-snapshot command.
+    ///
+    /// `open_database` is expected to open the database and keep it open (and locked) until
+    /// returned handle is dropped, which fails if database is used by a running node. Database is
+    /// opened and closed once to flush pending data to disk, files are copied while database is
+    /// closed and its lock file is held exclusively instead.
+    pub fn run<F, Database>(&self, config: &Configuration, open_database: F) -> sc_cli::Result<()>
+    where
+        F: Fn(&Configuration) -> sc_service::Result<Database>,
+    {
+        let database_path = database_path(config)?;
+        if !database_path.exists() {
+            return Err(sc_cli::Error::Input(format!(
+                "Database doesn't exist at {}",
+                database_path.display()
+            )));
+        }
+        ensure_empty_directory(&self.output)?;
+        // Closing database flushes all pending data to disk and stops any background work
+        drop(open_database(config)?);
+        // Database stays closed while files are copied, holding its lock prevents node from being
+        // started and modifying the database in the middle of export
+        let _database_lock = lock_database(database_path)?;
+
+        info!(
+            "Exporting snapshot of database at {} to {}",
+            database_path.display(),
+            self.output.display()
+        );
+
+        let snapshot_database_path = self.output.join(SNAPSHOT_DATABASE_DIR);
+        copy_dir_all(database_path, &snapshot_database_path)?;
+
+        let manifest = SnapshotManifest {
+            format_version: SNAPSHOT_FORMAT_VERSION,
+            chain_id: config.chain_spec.id().to_string(),
+            chain_spec_hash: chain_spec_hash(config.chain_spec.as_ref())?,
+            database_checksum: database_checksum(&snapshot_database_path)?,
+        };
+        // Manifest is written last, such that incomplete snapshot can't be imported
+        fs::write(
+            self.output.join(SNAPSHOT_MANIFEST_FILE),
+            serde_json::to_vec_pretty(&manifest)
+                .map_err(|error| sc_cli::Error::Application(error.into()))?,
+        )?;
+
+        info!("Snapshot exported successfully");
+
+        Ok(())
+    }
+}
+
+impl CliConfiguration for ExportSnapshotCmd {
+    fn shared_params(&self) -> &SharedParams {
+        &self.shared_params
+    }
+
+    fn database_params(&self) -> Option<&DatabaseParams> {
+        Some(&self.database_params)
+    }
+}
+
+/// The `import-snapshot` command used to bootstrap node database from a snapshot produced by
+/// `export-snapshot` command.
+///
+/// Node will continue syncing from the state in the snapshot using DSN and regular peers.
+#[derive(Debug, Parser)]
+pub struct ImportSnapshotCmd {
+    /// Directory with snapshot produced by `export-snapshot` command.
+    #[arg(long)]
+    pub input: PathBuf,
+
+    /// Replace existing database, otherwise import will fail if database already exists.
+    ///
+    /// Existing database is only removed once snapshot was imported successfully.
+    #[arg(long, default_value_t = false)]
+    pub force: bool,
+
+    #[allow(missing_docs)]
+    #[clap(flatten)]
+    pub shared_params: SharedParams,
+
+    #[allow(missing_docs)]
+    #[clap(flatten)]
+    pub database_params: DatabaseParams,
+}
+
+impl ImportSnapshotCmd {
+    /// Run the import-snapshot command.
+    pub fn run(&self, config: &Configuration) -> sc_cli::Result<()> {
+        let manifest_path = self.input.join(SNAPSHOT_MANIFEST_FILE);
+        let manifest_bytes = fs::read(&manifest_path).map_err(|error| {
+            sc_cli::Error::Input(format!(
+                "Failed to read snapshot manifest {}: {error}",
+                manifest_path.display()
+            ))
+        })?;
+        let manifest = serde_json::from_slice::<SnapshotManifest>(&manifest_bytes)
+            .map_err(|error| sc_cli::Error::Input(format!("Invalid snapshot manifest: {error}")))?;
+
+        if manifest.format_version != SNAPSHOT_FORMAT_VERSION {
+            return Err(sc_cli::Error::Input(format!(
+                "Unsupported snapshot format version {}, expected {SNAPSHOT_FORMAT_VERSION}",
+                manifest.format_version
+            )));
+        }
+        let chain_spec_hash = chain_spec_hash(config.chain_spec.as_ref())?;
+        if manifest.chain_spec_hash != chain_spec_hash {
+            return Err(sc_cli::Error::Input(format!(
+                "Snapshot was created for chain {} with chain spec hash {}, but chain spec hash of \
+                this node is {chain_spec_hash}",
+                manifest.chain_id, manifest.chain_spec_hash
+            )));
+        }
+
+        let database_path = database_path(config)?;
+        if database_path.exists() && !self.force {
+            return Err(sc_cli::Error::Input(format!(
+                "Database already exists at {}, use --force to replace it",
+                database_path.display()
+            )));
+        }
+
+        info!(
+            "Importing snapshot from {} to {}",
+            self.input.display(),
+            database_path.display()
+        );
+
+        // Snapshot is copied next to the database first and moved into place once complete, such
+        // that interrupted import doesn't leave partially imported database behind
+        let importing_path = sibling_path(database_path, IMPORTING_DATABASE_SUFFIX);
+        if importing_path.exists() {
+            // Leftover of previously interrupted import
+            fs::remove_dir_all(&importing_path)?;
+        }
+        if let Err(error) = copy_dir_all(&self.input.join(SNAPSHOT_DATABASE_DIR), &importing_path) {
+            let _ = fs::remove_dir_all(&importing_path);
+            return Err(error.into());
+        }
+
+        // Checksum is verified on the copy, which covers both corrupted snapshot and corruption
+        // during copying
+        let database_checksum = match database_checksum(&importing_path) {
+            Ok(database_checksum) => database_checksum,
+            Err(error) => {
+                let _ = fs::remove_dir_all(&importing_path);
+                return Err(error);
+            }
+        };
+        if manifest.database_checksum != database_checksum {
+            let _ = fs::remove_dir_all(&importing_path);
+            return Err(sc_cli::Error::Input(format!(
+                "Snapshot database checksum {database_checksum} doesn't match checksum {} in \
+                manifest, snapshot is corrupted",
+                manifest.database_checksum
+            )));
+        }
+
+        if database_path.exists() {
+            let replaced_path = sibling_path(database_path, REPLACED_DATABASE_SUFFIX);
+            if replaced_path.exists() {
+                fs::remove_dir_all(&replaced_path)?;
+            }
+            fs::rename(database_path, &replaced_path)?;
+
+            if let Err(error) = fs::rename(&importing_path, database_path) {
+                // Put original database back
+                fs::rename(&replaced_path, database_path)?;
+                return Err(error.into());
+            }
+
+            fs::remove_dir_all(replaced_path)?;
+        } else {
+            fs::rename(&importing_path, database_path)?;
+        }
+
+        info!("Snapshot imported successfully");
+
+        Ok(())
+    }
+}
+
+impl CliConfiguration for ImportSnapshotCmd {
+    fn shared_params(&self) -> &SharedParams {
+        &self.shared_params
+    }
+
+    fn database_params(&self) -> Option<&DatabaseParams> {
+        Some(&self.database_params)
+    }
+}
+
+fn database_path(config: &Configuration) -> sc_cli::Result<&Path> {
+    config.database.path().ok_or_else(|| {
+        sc_cli::Error::Input("Snapshots are only supported for on-disk databases".to_string())
+    })
+}
+
+fn chain_spec_hash(chain_spec: &dyn ChainSpec) -> sc_cli::Result<String> {
+    let chain_spec_json = chain_spec.as_json(true).map_err(sc_cli::Error::Input)?;
+
+    Ok(format!(
+        "{:?}",
+        H256::from(blake2_256(chain_spec_json.as_bytes()))
+    ))
+}
+
+/// Path next to `path` with `suffix` appended to its file name.
+fn sibling_path(path: &Path, suffix: &str) -> PathBuf {
+    let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(format!(".{suffix}"));
+    path.with_file_name(file_name)
+}
+
+/// Exclusively lock database lock file, fails if database is open by another process.
+///
+/// Lock is held until returned file is dropped.
+fn lock_database(database_path: &Path) -> sc_cli::Result<File> {
+    let lock_file = fs::OpenOptions::new()
+        .create(true)
+        .read(true)
+        .write(true)
+        .open(database_path.join(DATABASE_LOCK_FILE))?;
+    lock_file.try_lock_exclusive().map_err(|error| {
+        sc_cli::Error::Input(format!(
+            "Failed to lock database at {}, is node running? {error}",
+            database_path.display()
+        ))
+    })?;
+
+    Ok(lock_file)
+}
+
+/// Checksum of all files in the database directory.
+///
+/// Files are hashed in order of their paths relative to the database directory, each file
+/// contributes its relative path, size and contents. Lock file is skipped since it is not part of
+/// the database contents.
+fn database_checksum(database_path: &Path) -> sc_cli::Result<String> {
+    let mut files = Vec::new();
+    list_files(database_path, Path::new(""), &mut files)?;
+    files.sort();
+
+    let mut hasher = Blake2b::<U32>::default();
+    let mut buffer = vec![0u8; 1024 * 1024];
+    for relative_path in files {
+        if relative_path == Path::new(DATABASE_LOCK_FILE) {
+            continue;
+        }
+
+        let mut file = File::open(database_path.join(&relative_path))?;
+        hasher.update(relative_path.to_string_lossy().as_bytes());
+        hasher.update(&file.metadata()?.len().to_le_bytes());
+        loop {
+            let read = file.read(&mut buffer)?;
+            if read == 0 {
+                break;
+            }
+            hasher.update(&buffer[..read]);
+        }
+    }
+
+    Ok(format!(
+        "{:?}",
+        H256::from(<[u8; 32]>::from(hasher.finalize_fixed()))
+    ))
+}
+
+/// Collect paths of all files under `directory` relative to the root of the traversal.
+fn list_files(directory: &Path, relative: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(directory)? {
+        let entry = entry?;
+        let relative = relative.join(entry.file_name());
+
+        if entry.file_type()?.is_dir() {
+            list_files(&entry.path(), &relative, files)?;
+        } else {
+            files.push(relative);
+        }
+    }
+
+    Ok(())
+}
+
+fn ensure_empty_directory(path: &Path) -> io::Result<()> {
+    if path.exists() {
+        if fs::read_dir(path)?.next().is_some() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("Directory {} is not empty", path.display()),
+            ));
+        }
+    } else {
+        fs::create_dir_all(path)?;
+    }
+
+    Ok(())
+}
+
+fn copy_dir_all(source: &Path, destination: &Path) -> io::Result<()> {
+    fs::create_dir_all(destination)?;
+
+    for entry in fs::read_dir(source)? {
+        let entry = entry?;
+        let destination = destination.join(entry.file_name());
+
+        if entry.file_type()?.is_dir() {
+            copy_dir_all(&entry.path(), &destination)?;
+        } else {
+            fs::copy(entry.path(), destination)?;
+        }
+    }
+
+    Ok(())
+}
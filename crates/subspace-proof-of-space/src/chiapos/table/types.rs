@@ -5,7 +5,7 @@ use crate::chiapos::utils::EvaluatableUsize;
 use bitvec::prelude::*;
 use core::ops::Deref;
 use core::{fmt, mem};
-use std::cmp::Ordering;
+use core::cmp::Ordering;
 
 /// Copy `size` bits from `source` starting at `source_offset` into `destination` at
 /// `destination_offset`
@@ -87,3 +87,42 @@ pub struct RewardSignatureResponse {
     /// Pre-header or vote hash signature.
     pub signature: Option<RewardSignature>,
 }
+
+/// Declaration that farmer sends to the node periodically for every farm, such that node operator
+/// can see which farmers are attached to the node.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FarmerDeclaration {
+    /// Public key of the farm.
+    pub public_key: PublicKey,
+    /// Space plotted so far in bytes.
+    pub plotted_space: u64,
+}
+
+/// Farm that was recently declared to the node.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeclaredFarm {
+    /// Public key of the farm.
+    pub public_key: PublicKey,
+    /// Space plotted so far in bytes.
+    pub plotted_space: u64,
+    /// Number of seconds since the last declaration.
+    pub last_declared_secs_ago: u64,
+}
+
+/// Information about farmers connected to the node.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectedFarmers {
+    /// Number of active slot info subscriptions, each farmer connected over RPC has one.
+    pub rpc_subscriptions: u64,
+    /// Number of established connections with DSN peers, which includes farmers.
+    pub dsn_connected_peers: u64,
+    /// Total plotted space of declared farms in bytes, saturates at `u64::MAX`.
+    ///
+    /// Based on self-reported unauthenticated declarations, informational only.
+    pub declared_plotted_space: u64,
+    /// Farms that were recently declared by farmers, declarations are not authenticated.
+    pub declared_farms: Vec<DeclaredFarm>,
+}
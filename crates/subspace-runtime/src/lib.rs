@@ -52,8 +52,8 @@ pub use pallet_subspace::AllowAuthoringBy;
 use sp_api::{impl_runtime_apis, BlockT};
 use sp_consensus_slots::SlotDuration;
 use sp_consensus_subspace::{
-    ChainConstants, EquivocationProof, FarmerPublicKey, GlobalRandomnesses, SignedVote,
-    SolutionRanges, Vote,
+    total_space_pledged, ChainConstants, EquivocationProof, FarmerPublicKey, GlobalRandomnesses,
+    SignedVote, SolutionRanges, Vote,
 };
 use sp_core::crypto::{ByteArray, KeyTypeId};
 use sp_core::{OpaqueMetadata, H256};
@@ -68,8 +68,8 @@ use sp_version::RuntimeVersion;
 use subspace_core_primitives::crypto::Scalar;
 use subspace_core_primitives::objects::BlockObjectMapping;
 use subspace_core_primitives::{
-    HistorySize, Piece, Randomness, Record, SegmentCommitment, SegmentHeader, SegmentIndex,
-    SolutionRange, U256,
+    HistorySize, Randomness, Record, SegmentCommitment, SegmentHeader, SegmentIndex, SolutionRange,
+    U256,
 };
 use subspace_runtime_primitives::{
     opaque, AccountId, Balance, BlockNumber, Hash, Index, Moment, Signature,
@@ -351,20 +351,7 @@ pub struct TotalSpacePledged;
 
 impl Get<u128> for TotalSpacePledged {
     fn get() -> u128 {
-        // Operations reordered to avoid data loss, but essentially are:
-        // SolutionRange::MAX * SlotProbability
-        //     / solution_range * Piece::SIZE
-        //     / Record::NUM_S_BUCKETS * Record::NUM_CHUNKS
-        //     / SolutionRange::SIZE * Scalar::FULL_BYTES
-        u128::from(u64::MAX)
-            .saturating_mul(Piece::SIZE as u128)
-            .saturating_mul(u128::from(SlotProbability::get().0))
-            / Record::NUM_S_BUCKETS as u128
-            * Record::NUM_CHUNKS as u128
-            / u128::from(Subspace::solution_ranges().current)
-            / u128::from(SlotProbability::get().1)
-            * Scalar::FULL_BYTES as u128
-            / mem::size_of::<SolutionRange>() as u128
+        total_space_pledged(Subspace::solution_ranges().current, SlotProbability::get())
     }
 }
 
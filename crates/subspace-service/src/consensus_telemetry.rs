@@ -0,0 +1,73 @@
+//! Reporting of Subspace consensus information to Substrate telemetry, such that telemetry
+//! dashboard can show consensus-level health in addition to generic chain stats.
+
+use sc_client_api::HeaderBackend;
+use sc_telemetry::{telemetry, TelemetryHandle, SUBSTRATE_INFO};
+use sp_api::ProvideRuntimeApi;
+use sp_consensus_subspace::{total_space_pledged, FarmerPublicKey, SubspaceApi};
+use sp_runtime::traits::{Block as BlockT, SaturatedConversion};
+use std::sync::Arc;
+use std::time::Duration;
+use subspace_core_primitives::BlockNumber;
+use tracing::{debug, trace};
+
+/// How often consensus information is reported to telemetry
+const CONSENSUS_TELEMETRY_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Start periodic reporting of consensus information (solution ranges, space pledged, era and
+/// archived history) at the best block to telemetry.
+pub(crate) async fn start_consensus_telemetry<Block, Client>(
+    client: Arc<Client>,
+    telemetry: TelemetryHandle,
+) where
+    Block: BlockT,
+    Client: HeaderBackend<Block> + ProvideRuntimeApi<Block> + Send + Sync + 'static,
+    Client::Api: SubspaceApi<Block, FarmerPublicKey>,
+{
+    trace!("Consensus telemetry started.");
+
+    loop {
+        tokio::time::sleep(CONSENSUS_TELEMETRY_INTERVAL).await;
+
+        let info = client.info();
+        let best_hash = info.best_hash;
+        let best_number = info.best_number.saturated_into::<BlockNumber>();
+        let runtime_api = client.runtime_api();
+
+        let result = runtime_api
+            .solution_ranges(best_hash)
+            .and_then(|solution_ranges| {
+                Ok((
+                    solution_ranges,
+                    runtime_api.chain_constants(best_hash)?,
+                    runtime_api.history_size(best_hash)?,
+                ))
+            });
+        let (solution_ranges, chain_constants, history_size) = match result {
+            Ok(result) => result,
+            Err(error) => {
+                debug!(%error, "Failed to collect consensus information for telemetry");
+                continue;
+            }
+        };
+
+        let era_duration = chain_constants.era_duration().max(1);
+
+        telemetry!(
+            telemetry;
+            SUBSTRATE_INFO;
+            "subspace.consensus_info";
+            "best_number" => best_number,
+            "solution_range" => solution_ranges.current,
+            "voting_solution_range" => solution_ranges.voting_current,
+            "total_space_pledged" => total_space_pledged(
+                solution_ranges.current,
+                chain_constants.slot_probability(),
+            )
+            .saturated_into::<u64>(),
+            "era" => best_number / era_duration,
+            "blocks_until_next_era" => era_duration - best_number % era_duration,
+            "last_archived_segment" => u64::from(history_size.segment_index()),
+        );
+    }
+}
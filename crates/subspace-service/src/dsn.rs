@@ -1,34 +1,48 @@
 pub mod import_blocks;
+mod metrics;
+mod networking_metrics;
 pub mod node_provider_storage;
+pub mod segment_header_piece_validator;
 
+pub use crate::dsn::metrics::DsnMetrics;
+pub(crate) use crate::dsn::networking_metrics::NetworkingMetricsCollector;
 use crate::dsn::node_provider_storage::NodeProviderStorage;
 use crate::piece_cache::PieceCache;
 use crate::SegmentHeaderCache;
+use backoff::backoff::Backoff;
+use backoff::ExponentialBackoff;
 use either::Either;
+use futures::stream::FuturesUnordered;
+use futures::StreamExt;
 use sc_client_api::AuxStore;
 use sc_consensus_subspace_rpc::SegmentHeaderProvider;
 use std::num::NonZeroUsize;
 use std::path::PathBuf;
-use std::time::Instant;
-use subspace_core_primitives::{SegmentHeader, SegmentIndex};
+use std::time::{Duration, Instant};
+use subspace_core_primitives::{PieceIndex, SegmentHeader, SegmentIndex};
 use subspace_networking::libp2p::kad::ProviderRecord;
+use subspace_networking::libp2p::relay::Config as RelayServerConfig;
 use subspace_networking::libp2p::{identity, Multiaddr};
+use subspace_networking::utils::piece_announcement::announce_single_piece_index_hash;
 use subspace_networking::{
-    peer_id, BootstrappedNetworkingParameters, CreationError, MemoryProviderStorage,
-    NetworkParametersPersistenceError, NetworkingParametersManager, Node, NodeRunner,
-    ParityDbError, ParityDbProviderStorage, PeerInfoProvider, PieceAnnouncementRequestHandler,
-    PieceAnnouncementResponse, PieceByHashRequestHandler, PieceByHashResponse, ProviderStorage,
+    peer_id, BootstrappedNetworkingParameters, ConnectionLimitsPreset, CreationError,
+    KeepAlivePolicy, MemoryProviderStorage, NetworkParametersPersistenceError,
+    NetworkingParametersManager, Node, NodeRunner, ParityDbError, ParityDbProviderStorage,
+    PeerInfoProvider, PieceAnnouncementRequestHandler, PieceAnnouncementResponse,
+    PieceByHashRequestHandler, PieceByHashResponse, ProviderStorage,
     SegmentHeaderBySegmentIndexesRequestHandler, SegmentHeaderRequest, SegmentHeaderResponse,
-    KADEMLIA_PROVIDER_TTL_IN_SECS,
+    KADEMLIA_PROVIDER_TTL_IN_SECS, SEGMENT_INDEX_RANGE_LIMIT,
 };
 use thiserror::Error;
 use tracing::{debug, error, trace};
 
-/// Provider records cache size
-const MAX_PROVIDER_RECORDS_LIMIT: usize = 100000; // ~ 10 MB
-
 const ROOT_BLOCK_NUMBER_LIMIT: u64 = 100;
 
+/// Delay before the first retry of failed piece announcement
+const PIECE_ANNOUNCEMENT_INITIAL_RETRY_INTERVAL: Duration = Duration::from_secs(1);
+/// Max delay between retries of failed piece announcement
+const PIECE_ANNOUNCEMENT_MAX_RETRY_INTERVAL: Duration = Duration::from_secs(30);
+
 /// Errors that might happen during DSN configuration.
 #[derive(Debug, Error)]
 pub enum DsnConfigurationError {
@@ -61,9 +75,31 @@ pub struct DsnConfig {
     /// Determines whether we allow keeping non-global (private, shared, loopback..) addresses in Kademlia DHT.
     pub allow_non_global_addresses_in_dht: bool,
 
+    /// Enables in-memory transport, only useful for local development and tests.
+    pub enable_memory_transport: bool,
+
+    /// Enables QUIC transport in addition to TCP and WebSocket.
+    pub enable_quic: bool,
+
+    /// Dial QUIC addresses of peers first and only fall back to other addresses on failure.
+    pub prefer_quic_for_dials: bool,
+
+    /// Act as circuit relay v2 server for peers that are not publicly reachable.
+    pub enable_relay_server: bool,
+
+    /// Defines max number of active relay reservations when acting as relay server.
+    pub relay_max_reservations: usize,
+
+    /// Defines max number of active relay reservations per peer when acting as relay server.
+    pub relay_max_reservations_per_peer: usize,
+
     /// System base path.
     pub base_path: Option<PathBuf>,
 
+    /// Max number of keys provider records of other peers are stored for in on-disk provider
+    /// storage (only used with base path), records for the farthest keys are evicted first.
+    pub max_provider_records: NonZeroUsize,
+
     /// Defines max established incoming swarm connection limit.
     pub max_in_connections: u32,
 
@@ -78,6 +114,12 @@ pub struct DsnConfig {
 
     /// Defines target total (in and out) connection number for DSN that should be maintained.
     pub target_connections: u32,
+
+    /// How long idle connections are kept alive before being closed.
+    pub connection_idle_timeout: Duration,
+
+    /// Defines which connections are kept alive regardless of idle timeout.
+    pub keep_alive_policy: KeepAlivePolicy,
 }
 
 type DsnProviderStorage<AS> =
@@ -88,6 +130,7 @@ pub(crate) fn create_dsn_instance<AS>(
     dsn_config: DsnConfig,
     piece_cache: PieceCache<AS>,
     segment_header_cache: SegmentHeaderCache<AS>,
+    metrics: Option<DsnMetrics>,
 ) -> Result<(Node, NodeRunner<DsnProviderStorage<AS>>), DsnConfigurationError>
 where
     AS: AuxStore + Sync + Send + 'static,
@@ -99,10 +142,11 @@ where
     let external_provider_storage = if let Some(path) = &dsn_config.base_path {
         let db_path = path.join("storage_providers_db");
 
-        let cache_size: NonZeroUsize = NonZeroUsize::new(MAX_PROVIDER_RECORDS_LIMIT)
-            .expect("Manual value should be greater than zero.");
-
-        Either::Left(ParityDbProviderStorage::new(&db_path, cache_size, peer_id)?)
+        Either::Left(ParityDbProviderStorage::new(
+            &db_path,
+            dsn_config.max_provider_records,
+            peer_id,
+        )?)
     } else {
         Either::Right(MemoryProviderStorage::new(peer_id))
     };
@@ -125,24 +169,32 @@ where
     let provider_storage =
         NodeProviderStorage::new(peer_id, piece_cache.clone(), external_provider_storage);
     let keypair = dsn_config.keypair.clone();
-    let mut default_networking_config = subspace_networking::Config::new(
+    let default_networking_config = subspace_networking::Config::new(
         dsn_protocol_version,
         keypair,
         provider_storage.clone(),
         PeerInfoProvider::new_node(),
-    );
-
-    default_networking_config
-        .kademlia
-        .set_provider_record_ttl(KADEMLIA_PROVIDER_TTL_IN_SECS);
+    )
+    .with_connection_limits_preset(ConnectionLimitsPreset::Node);
 
     let networking_config = subspace_networking::Config {
         keypair: dsn_config.keypair.clone(),
         listen_on: dsn_config.listen_on,
         allow_non_global_addresses_in_dht: dsn_config.allow_non_global_addresses_in_dht,
+        enable_memory_transport: dsn_config.enable_memory_transport,
+        enable_quic: dsn_config.enable_quic,
+        prefer_quic_for_dials: dsn_config.prefer_quic_for_dials,
+        enable_metrics: metrics.is_some(),
+        relay_server: dsn_config.enable_relay_server.then(|| RelayServerConfig {
+            max_reservations: dsn_config.relay_max_reservations,
+            max_reservations_per_peer: dsn_config.relay_max_reservations_per_peer,
+            ..RelayServerConfig::default()
+        }),
         networking_parameters_registry,
         request_response_protocols: vec![
             PieceAnnouncementRequestHandler::create({
+                let metrics = metrics.clone();
+
                 move |peer_id, req| {
                     trace!(?req, %peer_id, "Piece announcement request received.");
 
@@ -153,7 +205,16 @@ where
                         expires: KADEMLIA_PROVIDER_TTL_IN_SECS.map(|ttl| Instant::now() + ttl),
                     };
 
-                    let result = match provider_storage.add_provider(provider_record) {
+                    let result = provider_storage.add_provider(provider_record);
+                    if let Some(metrics) = &metrics {
+                        metrics.on_piece_announcement_received(if result.is_ok() {
+                            "success"
+                        } else {
+                            "failure"
+                        });
+                    }
+
+                    let result = match result {
                         Ok(()) => Some(PieceAnnouncementResponse::Success),
                         Err(error) => {
                             error!(
@@ -170,18 +231,40 @@ where
                     async move { result }
                 }
             }),
-            PieceByHashRequestHandler::create(move |_, req| {
-                let result = match piece_cache.get_piece(req.piece_index_hash) {
-                    Ok(maybe_piece) => maybe_piece,
-                    Err(error) => {
-                        error!(piece_index_hash = ?req.piece_index_hash, %error, "Failed to get piece from cache");
-                        None
+            PieceByHashRequestHandler::create({
+                let metrics = metrics.clone();
+
+                move |_, req| {
+                    if let Some(metrics) = &metrics {
+                        metrics.on_piece_request_received();
                     }
-                };
 
-                async { Some(PieceByHashResponse { piece: result }) }
+                    let result = match piece_cache.get_piece(req.piece_index_hash) {
+                        Ok(maybe_piece) => maybe_piece,
+                        Err(error) => {
+                            error!(
+                                piece_index_hash = ?req.piece_index_hash,
+                                %error,
+                                "Failed to get piece from cache"
+                            );
+                            None
+                        }
+                    };
+
+                    if let Some(metrics) = &metrics {
+                        if result.is_some() {
+                            metrics.on_piece_request_served();
+                        }
+                    }
+
+                    async { Some(PieceByHashResponse { piece: result }) }
+                }
             }),
             SegmentHeaderBySegmentIndexesRequestHandler::create(move |_, req| {
+                if let Some(metrics) = &metrics {
+                    metrics.on_segment_header_request_received();
+                }
+
                 let segment_indexes = match req {
                     SegmentHeaderRequest::SegmentIndexes { segment_indexes } => {
                         segment_indexes.clone()
@@ -207,6 +290,9 @@ where
                             .take(block_limit as usize)
                             .collect::<Vec<_>>()
                     }
+                    SegmentHeaderRequest::SegmentIndexRange { start, end } => (*start..=*end)
+                        .take(SEGMENT_INDEX_RANGE_LIMIT as usize)
+                        .collect::<Vec<_>>(),
                 };
 
                 let internal_result = segment_indexes
@@ -236,10 +322,75 @@ where
         max_pending_incoming_connections: dsn_config.max_pending_in_connections,
         max_pending_outgoing_connections: dsn_config.max_pending_out_connections,
         target_connections: dsn_config.target_connections,
+        connection_idle_timeout: dsn_config.connection_idle_timeout,
+        keep_alive_policy: dsn_config.keep_alive_policy,
         reserved_peers: dsn_config.reserved_peers,
+        provider_record_ttl: KADEMLIA_PROVIDER_TTL_IN_SECS,
 
         ..default_networking_config
     };
 
     subspace_networking::create(networking_config).map_err(Into::into)
 }
+
+/// Announce the node as a provider of pieces of the archived segment, retrying failed
+/// announcements for as long as pieces remain in the piece cache.
+///
+/// Afterwards provider records are republished by Kademlia for pieces that are still in the cache.
+pub(crate) async fn announce_segment_pieces<AS>(
+    node: &Node,
+    piece_cache: &PieceCache<AS>,
+    segment_index: SegmentIndex,
+) where
+    AS: AuxStore,
+{
+    let announce_piece = |piece_index: PieceIndex| async move {
+        let mut backoff = ExponentialBackoff {
+            initial_interval: PIECE_ANNOUNCEMENT_INITIAL_RETRY_INTERVAL,
+            max_interval: PIECE_ANNOUNCEMENT_MAX_RETRY_INTERVAL,
+            max_elapsed_time: None,
+            ..ExponentialBackoff::default()
+        };
+
+        loop {
+            match piece_cache.contains_piece(piece_index.hash()) {
+                Ok(true) => {}
+                Ok(false) => {
+                    trace!(%piece_index, "Piece is not in cache anymore, not announcing it");
+                    return false;
+                }
+                Err(error) => {
+                    error!(%piece_index, %error, "Failed to check whether piece is in cache");
+                    return false;
+                }
+            }
+
+            if announce_single_piece_index_hash(piece_index.hash(), node)
+                .await
+                .is_ok()
+            {
+                return true;
+            }
+
+            let delay = backoff
+                .next_backoff()
+                .unwrap_or(PIECE_ANNOUNCEMENT_MAX_RETRY_INTERVAL);
+            trace!(%piece_index, ?delay, "Piece announcement failed, will retry");
+            tokio::time::sleep(delay).await;
+        }
+    };
+
+    let mut announcements = segment_index
+        .segment_piece_indexes()
+        .map(announce_piece)
+        .collect::<FuturesUnordered<_>>();
+
+    let mut pieces_announced = 0_usize;
+    while let Some(announced) = announcements.next().await {
+        if announced {
+            pieces_announced += 1;
+        }
+    }
+
+    debug!(%segment_index, %pieces_announced, "Finished announcing pieces of archived segment");
+}
@@ -14,29 +14,49 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
+mod cursor;
+mod metrics;
 mod piece_validator;
+mod rate_limiter;
+mod segment_cache;
 mod segment_headers;
 
+use crate::dsn::import_blocks::cursor::DsnSyncCursor;
+pub use crate::dsn::import_blocks::metrics::DsnSyncMetrics;
 use crate::dsn::import_blocks::piece_validator::SegmentCommitmentPieceValidator;
+use crate::dsn::import_blocks::rate_limiter::DownloadRateLimiter;
+use crate::dsn::import_blocks::segment_cache::SegmentCache;
 use crate::dsn::import_blocks::segment_headers::SegmentHeaderHandler;
-use futures::FutureExt;
+use crate::segment_headers::SegmentHeaderCache;
+use crate::sync_from_dsn::DsnSyncState;
+use futures::channel::oneshot;
+use futures::{stream, FutureExt, StreamExt};
 use parity_scale_codec::Encode;
-use sc_client_api::{BlockBackend, HeaderBackend};
+use parking_lot::Mutex;
+use rayon::ThreadPoolBuilder;
+use sc_client_api::{AuxStore, BlockBackend, HeaderBackend};
 use sc_consensus::import_queue::ImportQueueService;
 use sc_consensus::{BlockImportError, BlockImportStatus, IncomingBlock, Link};
 use sc_service::ImportQueue;
-use sc_tracing::tracing::{debug, info, trace};
+use sc_tracing::tracing::{debug, debug_span, info, trace, warn, Instrument, Span};
+use sp_api::ProvideRuntimeApi;
 use sp_consensus::BlockOrigin;
-use sp_runtime::traits::{Block as BlockT, Header, NumberFor};
+use sp_consensus_subspace::{FarmerPublicKey, SubspaceApi};
+use sp_runtime::traits::{Block as BlockT, Header, NumberFor, SaturatedConversion, Saturating};
 use static_assertions::const_assert;
+use std::mem;
+use std::num::{NonZeroU64, NonZeroUsize};
+use std::ops::RangeInclusive;
 use std::sync::Arc;
 use std::task::Poll;
-use std::time::Duration;
-use subspace_archiving::reconstructor::Reconstructor;
+use std::time::{Duration, Instant};
+use subspace_archiving::archiver::Segment;
+use subspace_archiving::reconstructor::{Reconstructor, ReconstructorError};
 use subspace_core_primitives::crypto::kzg::{embedded_kzg_settings, Kzg};
 use subspace_core_primitives::{
     ArchivedHistorySegment, BlockNumber, Piece, RecordedHistorySegment, SegmentHeader, SegmentIndex,
 };
+use subspace_networking::libp2p::PeerId;
 use subspace_networking::utils::piece_provider::{PieceProvider, RetryPolicy};
 use subspace_networking::Node;
 
@@ -45,10 +65,68 @@ const_assert!(std::mem::size_of::<usize>() >= std::mem::size_of::<u64>());
 
 /// How long to wait for peers before giving up
 const WAIT_FOR_PEERS_TIMEOUT: Duration = Duration::from_secs(10);
-/// How many blocks to queue before pausing and waiting for blocks to be imported
-const QUEUED_BLOCKS_LIMIT: BlockNumber = 2048;
+/// Default number of blocks to queue before pausing and waiting for blocks to be imported
+pub const QUEUED_BLOCKS_LIMIT: BlockNumber = 2048;
+/// Default number of recently downloaded segments to cache on disk, caching is opt-in since each
+/// segment takes about 128 MiB of disk space
+pub const SEGMENT_CACHE_SIZE: usize = 0;
+/// Default number of threads used for segment reconstruction
+pub const RECONSTRUCTION_THREADS: NonZeroUsize = NonZeroUsize::new(4).expect("Not zero; qed");
 /// Time to wait for blocks to import if import is too slow
 const WAIT_FOR_BLOCKS_TO_IMPORT: Duration = Duration::from_secs(1);
+/// Log sync progress summary every this many segments
+const PROGRESS_LOG_SEGMENTS_INTERVAL: u64 = 10;
+/// How many segments to download concurrently, each buffered segment takes about half of the
+/// segment size in memory
+const SEGMENT_DOWNLOAD_CONCURRENCY: usize = 4;
+/// How many times to re-fetch segments with missing blocks before giving up
+const MAX_GAP_REFETCHES: u32 = 3;
+
+/// Options of importing blocks from DSN.
+#[derive(Debug, Clone)]
+pub struct ImportBlocksOptions {
+    /// Origin blocks are queued for import with.
+    pub block_origin: BlockOrigin,
+    /// Import blocks even if node already has them.
+    pub force: bool,
+    /// No more than this many blocks are queued for import at any time, reconstruction of further
+    /// segments is paused until import queue catches up.
+    pub max_queued_blocks: BlockNumber,
+    /// Limit of piece downloads in bytes per second.
+    pub download_rate_limit: Option<NonZeroU64>,
+    /// Number of recently downloaded segments cached on disk and reused by subsequent syncs, 0
+    /// disables the cache.
+    pub segment_cache_size: usize,
+    /// Number of threads downloaded segments are recovered on while next segments are
+    /// downloading.
+    pub reconstruction_threads: NonZeroUsize,
+    /// Peers to request pieces from concurrently with the rest of the network.
+    pub preferred_piece_providers: Vec<PeerId>,
+}
+
+impl Default for ImportBlocksOptions {
+    fn default() -> Self {
+        Self {
+            block_origin: BlockOrigin::NetworkInitialSync,
+            force: false,
+            max_queued_blocks: QUEUED_BLOCKS_LIMIT,
+            download_rate_limit: None,
+            segment_cache_size: SEGMENT_CACHE_SIZE,
+            reconstruction_threads: RECONSTRUCTION_THREADS,
+            preferred_piece_providers: Vec::new(),
+        }
+    }
+}
+
+/// Segment downloaded from DSN and recovered, but not yet reconstructed into blocks
+struct DownloadedSegment {
+    segment_index: SegmentIndex,
+    recovered_segment: Result<Segment, ReconstructorError>,
+    /// Span covering all phases of segment processing
+    segment_span: Span,
+    download_duration: Duration,
+    recovery_duration: Duration,
+}
 
 struct WaitLinkError<B: BlockT> {
     error: BlockImportError,
@@ -98,12 +176,21 @@ impl<B: BlockT> Link<B> for WaitLink<B> {
 pub async fn initial_block_import_from_dsn<Block, IQ, Client>(
     node: &Node,
     client: Arc<Client>,
+    segment_header_cache: Option<&SegmentHeaderCache<Client>>,
     import_queue: &mut IQ,
-    force: bool,
+    options: &ImportBlocksOptions,
+    metrics: Option<&DsnSyncMetrics>,
 ) -> Result<u64, sc_service::Error>
 where
     Block: BlockT,
-    Client: HeaderBackend<Block> + BlockBackend<Block> + Send + Sync + 'static,
+    Client: HeaderBackend<Block>
+        + BlockBackend<Block>
+        + AuxStore
+        + ProvideRuntimeApi<Block>
+        + Send
+        + Sync
+        + 'static,
+    Client::Api: SubspaceApi<Block, FarmerPublicKey>,
     IQ: ImportQueue<Block> + 'static,
 {
     let mut link = WaitLink::new();
@@ -112,9 +199,11 @@ where
     let import_blocks_fut = import_blocks_from_dsn(
         node,
         client.as_ref(),
+        segment_header_cache,
         import_queue_service.as_mut(),
-        BlockOrigin::NetworkInitialSync,
-        force,
+        options,
+        metrics,
+        None,
     );
     let drive_import_queue_fut = async {
         let mut last_imported_blocks = link.imported_blocks;
@@ -177,34 +266,71 @@ where
 //  than from genesis
 /// Starts the process of importing blocks.
 ///
+/// See [`ImportBlocksOptions`] for how import can be tuned.
+///
+/// Blocks are queued strictly in ascending order, each on top of the previous one. If blocks are
+/// missing between already queued blocks and the next reconstructed block, segments are re-fetched
+/// starting with the one containing the first missing block, instead of queueing blocks whose
+/// parents are unknown.
+///
 /// Returns number of downloaded blocks.
 pub async fn import_blocks_from_dsn<Block, IQS, Client>(
     node: &Node,
     client: &Client,
+    segment_header_cache: Option<&SegmentHeaderCache<Client>>,
     import_queue_service: &mut IQS,
-    block_origin: BlockOrigin,
-    force: bool,
+    options: &ImportBlocksOptions,
+    metrics: Option<&DsnSyncMetrics>,
+    sync_state: Option<&Mutex<DsnSyncState>>,
 ) -> Result<u64, sc_service::Error>
 where
     Block: BlockT,
-    Client: HeaderBackend<Block> + BlockBackend<Block> + Send + Sync + 'static,
+    Client: HeaderBackend<Block>
+        + BlockBackend<Block>
+        + AuxStore
+        + ProvideRuntimeApi<Block>
+        + Send
+        + Sync
+        + 'static,
+    Client::Api: SubspaceApi<Block, FarmerPublicKey>,
     IQS: ImportQueueService<Block> + ?Sized,
 {
+    let ImportBlocksOptions {
+        block_origin,
+        force,
+        max_queued_blocks,
+        download_rate_limit,
+        segment_cache_size,
+        reconstruction_threads,
+        ref preferred_piece_providers,
+    } = *options;
+
+    let sync_started_at = Instant::now();
+    let on_failure = |reason: &str| {
+        if let Some(metrics) = metrics {
+            metrics.on_failure(reason);
+        }
+    };
+
     debug!("Waiting for connected peers...");
     if node
         .wait_for_connected_peers(WAIT_FOR_PEERS_TIMEOUT)
         .await
         .is_err()
     {
+        on_failure("no_peers");
         info!("Was not able to find any DSN peers, cancelling sync from DSN");
         return Ok(0);
     }
     debug!("Connected to peers.");
 
     let segment_headers = SegmentHeaderHandler::new(node.clone())
-        .get_segment_headers()
+        .get_segment_headers(segment_header_cache)
         .await
-        .map_err(|error| error.to_string())?;
+        .map_err(|error| {
+            on_failure("segment_headers");
+            error.to_string()
+        })?;
 
     debug!("Found {} segment headers", segment_headers.len());
 
@@ -212,137 +338,563 @@ where
         return Ok(0);
     }
 
+    verify_segment_headers(client, &segment_headers).map_err(|error| {
+        on_failure("segment_headers_verification");
+        error
+    })?;
+
+    // Persist verified segment headers, so they don't need to be retrieved from DSN again
+    if let Some(segment_header_cache) = segment_header_cache {
+        if let Err(error) = segment_header_cache.add_segment_headers(&segment_headers) {
+            warn!(%error, "Failed to store segment headers received from DSN");
+        }
+    }
+
+    let last_archived_block_in_dsn = segment_headers
+        .last()
+        .map(|segment_header| segment_header.last_archived_block().number)
+        .unwrap_or_default();
+    let sync_lag = || -> u64 {
+        NumberFor::<Block>::from(last_archived_block_in_dsn)
+            .saturating_sub(client.info().best_number)
+            .saturated_into()
+    };
+
     // TODO: Consider introducing and using global in-memory segment header cache (this comment is
     //  in multiple files)
     let segment_commitments = segment_headers
         .iter()
         .map(SegmentHeader::segment_commitment)
         .collect::<Vec<_>>();
+    let kzg = Kzg::new(embedded_kzg_settings());
 
-    let segments_found = segment_commitments.len();
     let piece_provider = PieceProvider::<SegmentCommitmentPieceValidator>::new(
         node.clone(),
         Some(SegmentCommitmentPieceValidator::new(
             node.clone(),
-            Kzg::new(embedded_kzg_settings()),
-            segment_commitments,
+            kzg.clone(),
+            segment_commitments.clone(),
         )),
-    );
+    )
+    .with_preferred_providers(preferred_piece_providers.to_vec());
+    let download_rate_limiter = download_rate_limit.map(DownloadRateLimiter::new);
 
     let mut downloaded_blocks = 0;
+    let mut segments_fetched = 0;
+    let mut blocks_reconstructed = 0;
     let mut reconstructor = Reconstructor::new().map_err(|error| error.to_string())?;
+    // Recovery of segments doesn't depend on reconstructor state, so it is done by a separate
+    // instance shared with reconstruction threads
+    let segment_recoverer = Arc::new(reconstructor.clone());
+    let reconstruction_thread_pool = ThreadPoolBuilder::new()
+        .thread_name(|thread_index| format!("dsn-sync-reconstruction-{thread_index}"))
+        .num_threads(reconstruction_threads.get())
+        .build()
+        .map_err(|error| {
+            sc_service::Error::Other(format!(
+                "Failed to create reconstruction thread pool: {error}"
+            ))
+        })?;
+
+    let best_block_number = client.info().best_number;
+    let segment_cache = SegmentCache::load(client, segment_cache_size, kzg, segment_commitments)?;
+    let mut dsn_sync_cursor = DsnSyncCursor::load(client)?;
+    let last_imported_segment = dsn_sync_cursor.segment_index();
+    if let Some(last_imported_segment) = last_imported_segment {
+        debug!(%last_imported_segment, "Resuming sync from DSN");
+    }
+    dsn_sync_cursor.update::<Block>(&segment_headers, best_block_number)?;
 
-    // Skip the first segment, everyone has it locally
-    for segment_index in (SegmentIndex::ZERO..).take(segments_found).skip(1) {
-        let pieces_indices = segment_index.segment_piece_indexes_source_first();
-
-        if let Some(segment_header) = segment_headers.get(u64::from(segment_index) as usize) {
-            let last_archived_block =
-                NumberFor::<Block>::from(segment_header.last_archived_block().number);
-            if last_archived_block <= client.info().best_number {
-                // Reset reconstructor instance
-                reconstructor = Reconstructor::new().map_err(|error| error.to_string())?;
-                continue;
-            }
-        }
-
-        let mut segment_pieces = vec![None::<Piece>; ArchivedHistorySegment::NUM_PIECES];
-        let mut pieces_received = 0;
-
-        for piece_index in pieces_indices {
-            let maybe_piece = piece_provider
-                .get_piece(piece_index, RetryPolicy::Limited(0))
-                .await?;
-
-            trace!(
-                ?piece_index,
-                success = maybe_piece.is_some(),
-                "Piece request completed.",
-            );
-
-            if let Some(received_piece) = maybe_piece {
-                segment_pieces
-                    .get_mut(piece_index.position() as usize)
-                    .expect("Piece position is by definition within segment; qed")
-                    .replace(received_piece);
+    let Some(missing_segments) =
+        missing_segments::<Block>(&segment_headers, best_block_number, last_imported_segment)
+    else {
+        debug!("All blocks archived in DSN are already imported");
+        return Ok(0);
+    };
+    info!(
+        "Syncing segments {}..={} from DSN",
+        missing_segments.start(),
+        missing_segments.end()
+    );
 
-                pieces_received += 1;
-            }
+    // Segments are downloaded and recovered concurrently, but yielded (and reconstructed) in
+    // order
+    let download_segments = |segments: RangeInclusive<SegmentIndex>| {
+        let segment_recoverer = &segment_recoverer;
+        let reconstruction_thread_pool = &reconstruction_thread_pool;
+        let segment_cache = &segment_cache;
+        let piece_provider = &piece_provider;
+        let download_rate_limiter = download_rate_limiter.as_ref();
+
+        stream::iter(segments)
+            .map(move |segment_index| {
+                let segment_recoverer = Arc::clone(segment_recoverer);
+                let segment_span = debug_span!("dsn_segment", %segment_index);
+
+                async move {
+                    let download_started = Instant::now();
+                    let (segment_index, segment_pieces) = download_segment_pieces(
+                        segment_index,
+                        piece_provider,
+                        segment_cache,
+                        download_rate_limiter,
+                        metrics,
+                    )
+                    .instrument(debug_span!(parent: &segment_span, "download"))
+                    .await?;
+                    let download_duration = download_started.elapsed();
+
+                    let recovery_started = Instant::now();
+                    let (result_sender, result_receiver) = oneshot::channel();
+                    let recovery_span = debug_span!(parent: &segment_span, "recovery");
+                    reconstruction_thread_pool.spawn(move || {
+                        let result = recovery_span
+                            .in_scope(|| segment_recoverer.recover_segment(&segment_pieces));
+                        // Receiver might be gone if sync was stopped, nothing to do in that case
+                        let _ = result_sender.send(result);
+                    });
+
+                    let result = result_receiver.await.map_err(|_canceled| {
+                        sc_service::Error::Other(
+                            "Reconstruction thread exited before recovering segment".to_string(),
+                        )
+                    })?;
+                    let recovery_duration = recovery_started.elapsed();
+
+                    Ok::<_, sc_service::Error>(DownloadedSegment {
+                        segment_index,
+                        recovered_segment: result,
+                        segment_span,
+                        download_duration,
+                        recovery_duration,
+                    })
+                }
+            })
+            .buffered(SEGMENT_DOWNLOAD_CONCURRENCY)
+    };
+    let mut segments_stream = download_segments(missing_segments);
 
-            if pieces_received >= RecordedHistorySegment::NUM_RAW_RECORDS {
-                trace!(%segment_index, "Received half of the segment.");
-                break;
-            }
+    // Number and hash of the last block that is known to the client or queued for import, next
+    // queued block must be its child
+    let mut last_block = {
+        let info = client.info();
+        (info.best_number, info.best_hash)
+    };
+    let mut gap_refetches = 0;
+
+    while let Some(result) = segments_stream.next().await {
+        let DownloadedSegment {
+            segment_index,
+            recovered_segment,
+            segment_span,
+            download_duration,
+            recovery_duration,
+        } = result?;
+
+        segments_fetched += 1;
+        if let Some(sync_state) = sync_state {
+            sync_state
+                .lock()
+                .current_segment_index
+                .replace(segment_index);
         }
 
-        let reconstructed_contents = reconstructor
-            .add_segment(segment_pieces.as_ref())
-            .map_err(|error| error.to_string())?;
-        drop(segment_pieces);
+        let reconstruction_started = Instant::now();
+        let reconstructed_contents = debug_span!(parent: &segment_span, "reconstruction")
+            .in_scope(|| {
+                recovered_segment.and_then(|segment| reconstructor.add_recovered_segment(segment))
+            })
+            .map_err(|error| {
+                on_failure("reconstruction");
+                error.to_string()
+            })?;
+        let reconstruction_duration = reconstruction_started.elapsed();
+
+        let reconstructed_blocks = reconstructed_contents.blocks.len() as u64;
+        blocks_reconstructed += reconstructed_blocks;
+        if let Some(metrics) = metrics {
+            metrics.on_blocks_reconstructed(reconstructed_blocks);
+        }
 
         let mut blocks_to_import = Vec::with_capacity(reconstructed_contents.blocks.len());
+        let mut segment_blocks_queued = 0;
+        let mut segment_blocks_skipped = 0;
+
+        let mut first_missing_block = None;
+
+        let enqueue_started = Instant::now();
+        async {
+            let best_block_number = client.info().best_number;
+            for (block_number, block_bytes) in reconstructed_contents.blocks {
+                {
+                    let block_number = block_number.into();
+                    if block_number <= best_block_number {
+                        if block_number == 0u32.into() {
+                            let block = client
+                                .block(client.hash(block_number)?.expect(
+                                    "Block before best block number must always be found; qed",
+                                ))?
+                                .expect("Block before best block number must always be found; qed");
+
+                            if block.encode() != block_bytes {
+                                on_failure("wrong_genesis");
+                                return Err(sc_service::Error::Other(
+                                    "Wrong genesis block, block import failed".to_string(),
+                                ));
+                            }
+                        }
 
-        let best_block_number = client.info().best_number;
-        for (block_number, block_bytes) in reconstructed_contents.blocks {
-            {
-                let block_number = block_number.into();
-                if block_number <= best_block_number {
-                    if block_number == 0u32.into() {
-                        let block = client
-                            .block(client.hash(block_number)?.expect(
-                                "Block before best block number must always be found; qed",
-                            ))?
-                            .expect("Block before best block number must always be found; qed");
-
-                        if block.encode() != block_bytes {
-                            return Err(sc_service::Error::Other(
-                                "Wrong genesis block, block import failed".to_string(),
-                            ));
+                        if block_number > last_block.0 {
+                            if let Some(hash) = client.hash(block_number)? {
+                                last_block = (block_number, hash);
+                            }
                         }
+                        continue;
+                    }
+                    if block_number <= last_block.0 {
+                        // Already queued for import
+                        continue;
+                    }
+
+                    if block_number > last_block.0 + 1u32.into() {
+                        first_missing_block.replace(last_block.0 + 1u32.into());
+                        break;
                     }
 
-                    continue;
+                    // Limit number of queued blocks for import
+                    while block_number.saturating_sub(client.info().best_number)
+                        >= max_queued_blocks.into()
+                    {
+                        if !blocks_to_import.is_empty() {
+                            // Best block will not move until blocks accumulated so far are queued
+                            segment_blocks_queued += blocks_to_import.len();
+                            import_queue_service
+                                .import_blocks(block_origin, mem::take(&mut blocks_to_import));
+                        }
+                        tokio::time::sleep(WAIT_FOR_BLOCKS_TO_IMPORT).await;
+                    }
                 }
 
-                // Limit number of queued blocks for import
-                while block_number - best_block_number >= QUEUED_BLOCKS_LIMIT.into() {
-                    tokio::time::sleep(WAIT_FOR_BLOCKS_TO_IMPORT).await;
+                let block = Block::decode(&mut block_bytes.as_slice()).map_err(|error| {
+                    on_failure("block_decoding");
+                    error.to_string()
+                })?;
+
+                let (header, extrinsics) = block.deconstruct();
+                let hash = header.hash();
+
+                if *header.parent_hash() != last_block.1
+                    && client.header(*header.parent_hash())?.is_none()
+                {
+                    on_failure("unknown_parent");
+                    return Err(sc_service::Error::Other(format!(
+                        "Block #{block_number} ({hash}) reconstructed from DSN doesn't extend \
+                        block #{} ({}) queued before it, block import failed",
+                        last_block.0, last_block.1
+                    )));
                 }
-            }
 
-            let block =
-                Block::decode(&mut block_bytes.as_slice()).map_err(|error| error.to_string())?;
-
-            let (header, extrinsics) = block.deconstruct();
-            let hash = header.hash();
-
-            blocks_to_import.push(IncomingBlock {
-                hash,
-                header: Some(header),
-                body: Some(extrinsics),
-                indexed_body: None,
-                justifications: None,
-                origin: None,
-                allow_missing_state: false,
-                import_existing: force,
-                state: None,
-                skip_execution: false,
-            });
-
-            downloaded_blocks += 1;
-
-            if downloaded_blocks % 1000 == 0 {
-                info!("Imported block {} from DSN", block_number);
+                // Blocks might have been imported already by concurrent Substrate sync, skip them
+                // to avoid wasting time on verification
+                let import_existing = if force {
+                    true
+                } else if client.header(hash)?.is_some() {
+                    if client.block_body(hash)?.is_some() {
+                        segment_blocks_skipped += 1;
+                        last_block = (*header.number(), hash);
+                        continue;
+                    }
+
+                    // Header is known, but body is missing, import again to fill it in
+                    true
+                } else {
+                    false
+                };
+
+                last_block = (*header.number(), hash);
+                blocks_to_import.push(IncomingBlock {
+                    hash,
+                    header: Some(header),
+                    body: Some(extrinsics),
+                    indexed_body: None,
+                    justifications: None,
+                    origin: None,
+                    allow_missing_state: false,
+                    import_existing,
+                    state: None,
+                    skip_execution: false,
+                });
+
+                downloaded_blocks += 1;
+
+                if downloaded_blocks % 1000 == 0 {
+                    info!("Imported block {} from DSN", block_number);
+                }
             }
-        }
 
-        if blocks_to_import.is_empty() {
+            Ok::<_, sc_service::Error>(())
+        }
+        .instrument(debug_span!(parent: &segment_span, "enqueue"))
+        .await?;
+        let enqueue_duration = enqueue_started.elapsed();
+
+        segment_blocks_queued += blocks_to_import.len();
+        if segment_blocks_queued == 0
+            && segment_blocks_skipped == 0
+            && first_missing_block.is_none()
+        {
             break;
         }
 
+        if let Some(metrics) = metrics {
+            metrics.on_blocks_queued(segment_blocks_queued as u64);
+            metrics.set_sync_lag(sync_lag());
+            metrics.on_segment_phase_finished("download", download_duration);
+            metrics.on_segment_phase_finished("recovery", recovery_duration);
+            metrics.on_segment_phase_finished("reconstruction", reconstruction_duration);
+            metrics.on_segment_phase_finished("enqueue", enqueue_duration);
+        }
+
+        debug!(
+            parent: &segment_span,
+            %segment_index,
+            blocks_reconstructed = %reconstructed_blocks,
+            blocks_queued = %segment_blocks_queued,
+            blocks_skipped = %segment_blocks_skipped,
+            ?download_duration,
+            ?recovery_duration,
+            ?reconstruction_duration,
+            ?enqueue_duration,
+            "Segment processed"
+        );
+        if segments_fetched % PROGRESS_LOG_SEGMENTS_INTERVAL == 0 {
+            info!(
+                "DSN sync progress: {segments_fetched} segments fetched, {blocks_reconstructed} \
+                blocks reconstructed, {downloaded_blocks} blocks queued for import, {} blocks \
+                behind DSN",
+                sync_lag()
+            );
+        }
+
         // import queue handles verification and importing it into the client.
-        import_queue_service.import_blocks(block_origin, blocks_to_import);
+        if !blocks_to_import.is_empty() {
+            import_queue_service.import_blocks(block_origin, blocks_to_import);
+        }
+
+        // Blocks of previous segments were likely imported by now
+        dsn_sync_cursor.update::<Block>(&segment_headers, client.info().best_number)?;
+
+        if let Some(first_missing_block) = first_missing_block {
+            gap_refetches += 1;
+            if gap_refetches > MAX_GAP_REFETCHES {
+                on_failure("gap");
+                return Err(sc_service::Error::Other(format!(
+                    "Block #{first_missing_block} is missing in segments reconstructed from DSN \
+                    after {MAX_GAP_REFETCHES} attempts, block import failed"
+                )));
+            }
+
+            // Cursor doesn't help here, the gap is after the last block queued for import
+            let Some(refetch_segments) =
+                missing_segments::<Block>(&segment_headers, last_block.0, None)
+            else {
+                on_failure("gap");
+                return Err(sc_service::Error::Other(format!(
+                    "Block #{first_missing_block} is not archived in DSN, block import failed"
+                )));
+            };
+            debug!(
+                %segment_index,
+                %first_missing_block,
+                "Gap in blocks reconstructed from DSN, re-fetching segments {}..={}",
+                refetch_segments.start(),
+                refetch_segments.end()
+            );
+
+            // Restart reconstruction from the segment that contains the first missing block
+            reconstructor = Reconstructor::new().map_err(|error| error.to_string())?;
+            segments_stream = download_segments(refetch_segments);
+        }
+    }
+
+    if let Some(metrics) = metrics {
+        metrics.set_sync_lag(sync_lag());
+        metrics.on_sync_finished(sync_started_at.elapsed());
+    }
+    if segments_fetched > 0 {
+        info!(
+            "DSN sync pass finished: {segments_fetched} segments fetched, {blocks_reconstructed} \
+            blocks reconstructed, {downloaded_blocks} blocks queued for import"
+        );
     }
 
     Ok(downloaded_blocks)
 }
+
+/// Check segment headers received from DSN against segment commitments known to the chain at the
+/// best block.
+///
+/// Segment headers are linked by hashes, so verifying the last segment header known to the chain
+/// also verifies all segment headers before it. Blocks reconstructed from segments after it are
+/// verified by block import, which prevents malicious DSN peers from feeding bogus history.
+fn verify_segment_headers<Block, Client>(
+    client: &Client,
+    segment_headers: &[SegmentHeader],
+) -> Result<(), sc_service::Error>
+where
+    Block: BlockT,
+    Client: HeaderBackend<Block> + ProvideRuntimeApi<Block>,
+    Client::Api: SubspaceApi<Block, FarmerPublicKey>,
+{
+    let best_hash = client.info().best_hash;
+    let runtime_api = client.runtime_api();
+    let segment_commitment = |segment_index: SegmentIndex| {
+        runtime_api
+            .segment_commitment(best_hash, segment_index)
+            .map_err(|error| sc_service::Error::Client(error.into()))
+    };
+
+    // Segment commitments known to the chain form a prefix, find where it ends with binary search
+    let mut known_segments = 0;
+    let mut unknown_segments_start = segment_headers.len();
+    while known_segments < unknown_segments_start {
+        let middle = known_segments + (unknown_segments_start - known_segments) / 2;
+        if segment_commitment(SegmentIndex::from(middle as u64))?.is_some() {
+            known_segments = middle + 1;
+        } else {
+            unknown_segments_start = middle;
+        }
+    }
+
+    let Some(last_known_segment_header) = known_segments
+        .checked_sub(1)
+        .and_then(|segment_index| segment_headers.get(segment_index))
+    else {
+        return Ok(());
+    };
+
+    let segment_index = last_known_segment_header.segment_index();
+    if segment_commitment(segment_index)? != Some(last_known_segment_header.segment_commitment()) {
+        return Err(sc_service::Error::Other(format!(
+            "Segment header {segment_index} received from DSN doesn't match segment commitment \
+            known to the chain"
+        )));
+    }
+
+    trace!(%segment_index, "Segment headers from DSN verified against the chain");
+
+    Ok(())
+}
+
+/// Range of segments that contain blocks that are not imported yet, `None` if all blocks archived
+/// in DSN are already imported.
+///
+/// The first segment is never included since everyone has it locally, reconstruction is expected
+/// to start with fresh reconstructor at the beginning of the range.
+///
+/// `last_imported_segment` is the persisted DSN sync cursor, segments up to and including it are
+/// skipped as long as best block confirms that their blocks are imported (the cursor is ignored
+/// otherwise, for instance if the database was reverted to an earlier block since).
+fn missing_segments<Block>(
+    segment_headers: &[SegmentHeader],
+    best_block_number: NumberFor<Block>,
+    last_imported_segment: Option<SegmentIndex>,
+) -> Option<RangeInclusive<SegmentIndex>>
+where
+    Block: BlockT,
+{
+    let is_imported = |segment_header: &SegmentHeader| {
+        NumberFor::<Block>::from(segment_header.last_archived_block().number) <= best_block_number
+    };
+
+    let imported_segments = last_imported_segment
+        .and_then(|segment_index| {
+            let segment_index = usize::try_from(u64::from(segment_index)).ok()?;
+            segment_headers
+                .get(segment_index)
+                .filter(|segment_header| is_imported(segment_header))
+                .map(|_segment_header| segment_index + 1)
+        })
+        .unwrap_or_default();
+
+    // Segment headers are ordered by segment index and so are last archived blocks in them
+    let first_missing_segment = (imported_segments
+        + segment_headers[imported_segments..].partition_point(is_imported))
+    .max(1);
+
+    if first_missing_segment >= segment_headers.len() {
+        return None;
+    }
+
+    Some(
+        SegmentIndex::from(first_missing_segment as u64)
+            ..=SegmentIndex::from(segment_headers.len() as u64 - 1),
+    )
+}
+
+/// Download enough pieces of the segment to reconstruct it (unless segment is cached already),
+/// returns segment index alongside pieces for convenience
+async fn download_segment_pieces<AS>(
+    segment_index: SegmentIndex,
+    piece_provider: &PieceProvider<SegmentCommitmentPieceValidator>,
+    segment_cache: &SegmentCache<'_, AS>,
+    download_rate_limiter: Option<&DownloadRateLimiter>,
+    metrics: Option<&DsnSyncMetrics>,
+) -> Result<(SegmentIndex, Vec<Option<Piece>>), sc_service::Error>
+where
+    AS: AuxStore,
+{
+    if let Some(segment_pieces) = segment_cache.get(segment_index)? {
+        trace!(%segment_index, "Segment pieces found in cache.");
+        return Ok((segment_index, segment_pieces));
+    }
+
+    let mut segment_pieces = vec![None::<Piece>; ArchivedHistorySegment::NUM_PIECES];
+    let mut pieces_received = 0;
+
+    for piece_index in segment_index.segment_piece_indexes_source_first() {
+        if let Some(download_rate_limiter) = download_rate_limiter {
+            download_rate_limiter.acquire(Piece::SIZE as u64).await;
+        }
+
+        let maybe_piece = piece_provider
+            .get_piece(piece_index, RetryPolicy::Limited(0))
+            .await
+            .map_err(|error| {
+                if let Some(metrics) = metrics {
+                    metrics.on_failure("piece_request");
+                }
+                error
+            })?;
+
+        trace!(
+            ?piece_index,
+            success = maybe_piece.is_some(),
+            "Piece request completed.",
+        );
+
+        if let Some(received_piece) = maybe_piece {
+            segment_pieces
+                .get_mut(piece_index.position() as usize)
+                .expect("Piece position is by definition within segment; qed")
+                .replace(received_piece);
+
+            pieces_received += 1;
+        }
+
+        if pieces_received >= RecordedHistorySegment::NUM_RAW_RECORDS {
+            trace!(%segment_index, "Received half of the segment.");
+            break;
+        }
+    }
+
+    if let Some(metrics) = metrics {
+        metrics.on_segment_fetched();
+    }
+
+    if pieces_received >= RecordedHistorySegment::NUM_RAW_RECORDS {
+        segment_cache.insert(segment_index, &segment_pieces)?;
+    }
+
+    Ok((segment_index, segment_pieces))
+}
@@ -0,0 +1,69 @@
+use parity_scale_codec::{Decode, Encode};
+use sc_client_api::backend::AuxStore;
+use sp_runtime::traits::{Block as BlockT, NumberFor};
+use subspace_core_primitives::{SegmentHeader, SegmentIndex};
+
+/// Aux storage key of the last segment which blocks were all imported
+const DSN_SYNC_CURSOR_KEY: &[u8] = b"dsn-sync-cursor";
+
+/// Last segment which blocks were all imported, persisted in aux storage such that sync from DSN
+/// resumes from where it left off after restart.
+pub(super) struct DsnSyncCursor<'a, AS> {
+    aux_store: &'a AS,
+    segment_index: Option<SegmentIndex>,
+}
+
+impl<'a, AS> DsnSyncCursor<'a, AS>
+where
+    AS: AuxStore,
+{
+    pub(super) fn load(aux_store: &'a AS) -> sp_blockchain::Result<Self> {
+        let segment_index = aux_store
+            .get_aux(DSN_SYNC_CURSOR_KEY)?
+            .and_then(|bytes| SegmentIndex::decode(&mut bytes.as_slice()).ok());
+
+        Ok(Self {
+            aux_store,
+            segment_index,
+        })
+    }
+
+    pub(super) fn segment_index(&self) -> Option<SegmentIndex> {
+        self.segment_index
+    }
+
+    /// Move cursor to the last segment which blocks are all imported according to best block
+    /// number, cursor never moves backwards.
+    pub(super) fn update<Block>(
+        &mut self,
+        segment_headers: &[SegmentHeader],
+        best_block_number: NumberFor<Block>,
+    ) -> sp_blockchain::Result<()>
+    where
+        Block: BlockT,
+    {
+        let imported_segments = segment_headers.partition_point(|segment_header| {
+            NumberFor::<Block>::from(segment_header.last_archived_block().number)
+                <= best_block_number
+        });
+        let Some(last_imported_segment) = imported_segments.checked_sub(1) else {
+            return Ok(());
+        };
+        let last_imported_segment = SegmentIndex::from(last_imported_segment as u64);
+
+        if self.segment_index >= Some(last_imported_segment) {
+            return Ok(());
+        }
+
+        self.aux_store.insert_aux(
+            &[(
+                DSN_SYNC_CURSOR_KEY,
+                last_imported_segment.encode().as_slice(),
+            )],
+            &[],
+        )?;
+        self.segment_index.replace(last_imported_segment);
+
+        Ok(())
+    }
+}
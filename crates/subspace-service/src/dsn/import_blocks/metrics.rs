@@ -0,0 +1,126 @@
+use std::time::Duration;
+use substrate_prometheus_endpoint::{
+    exponential_buckets, register, Counter, CounterVec, Gauge, Histogram, HistogramOpts,
+    HistogramVec, Opts, PrometheusError, Registry, U64,
+};
+
+/// Metrics of block import from DSN
+#[derive(Debug, Clone)]
+pub struct DsnSyncMetrics {
+    segments_fetched: Counter<U64>,
+    blocks_reconstructed: Counter<U64>,
+    blocks_queued: Counter<U64>,
+    blocks_failed_import: Counter<U64>,
+    failures: CounterVec<U64>,
+    sync_lag: Gauge<U64>,
+    segment_phase_duration: HistogramVec,
+    sync_duration: Histogram,
+}
+
+impl DsnSyncMetrics {
+    /// Create and register metrics in provided registry
+    pub fn new(registry: &Registry) -> Result<Self, PrometheusError> {
+        Ok(Self {
+            segments_fetched: register(
+                Counter::new(
+                    "subspace_node_dsn_sync_segments_fetched",
+                    "Total number of segments fetched from DSN",
+                )?,
+                registry,
+            )?,
+            blocks_reconstructed: register(
+                Counter::new(
+                    "subspace_node_dsn_sync_blocks_reconstructed",
+                    "Total number of blocks reconstructed from segments fetched from DSN",
+                )?,
+                registry,
+            )?,
+            blocks_queued: register(
+                Counter::new(
+                    "subspace_node_dsn_sync_blocks_queued",
+                    "Total number of blocks from DSN queued for import",
+                )?,
+                registry,
+            )?,
+            blocks_failed_import: register(
+                Counter::new(
+                    "subspace_node_dsn_sync_blocks_failed_import",
+                    "Total number of blocks from DSN that failed verification or import",
+                )?,
+                registry,
+            )?,
+            failures: register(
+                CounterVec::new(
+                    Opts::new(
+                        "subspace_node_dsn_sync_failures",
+                        "Total number of failed DSN sync attempts by reason",
+                    ),
+                    &["reason"],
+                )?,
+                registry,
+            )?,
+            sync_lag: register(
+                Gauge::new(
+                    "subspace_node_dsn_sync_lag",
+                    "Number of blocks archived in DSN that are not imported yet",
+                )?,
+                registry,
+            )?,
+            segment_phase_duration: register(
+                HistogramVec::new(
+                    HistogramOpts::new(
+                        "subspace_node_dsn_sync_segment_phase_duration_seconds",
+                        "Time spent on each phase of segment processing during DSN sync",
+                    )
+                    .buckets(exponential_buckets(0.01, 2.0, 14)?),
+                    &["phase"],
+                )?,
+                registry,
+            )?,
+            sync_duration: register(
+                Histogram::with_opts(
+                    HistogramOpts::new(
+                        "subspace_node_dsn_sync_duration_seconds",
+                        "Duration of completed sync from DSN passes",
+                    )
+                    .buckets(exponential_buckets(0.1, 2.0, 16)?),
+                )?,
+                registry,
+            )?,
+        })
+    }
+
+    pub(crate) fn on_segment_fetched(&self) {
+        self.segments_fetched.inc();
+    }
+
+    pub(crate) fn on_blocks_reconstructed(&self, blocks: u64) {
+        self.blocks_reconstructed.inc_by(blocks);
+    }
+
+    pub(crate) fn on_blocks_queued(&self, blocks: u64) {
+        self.blocks_queued.inc_by(blocks);
+    }
+
+    pub(crate) fn on_blocks_failed_import(&self, blocks: u64) {
+        self.blocks_failed_import.inc_by(blocks);
+    }
+
+    pub(crate) fn on_failure(&self, reason: &str) {
+        self.failures.with_label_values(&[reason]).inc();
+    }
+
+    pub(crate) fn set_sync_lag(&self, blocks: u64) {
+        self.sync_lag.set(blocks);
+    }
+
+    pub(crate) fn on_segment_phase_finished(&self, phase: &str, duration: Duration) {
+        self.segment_phase_duration
+            .with_label_values(&[phase])
+            .observe(duration.as_secs_f64());
+    }
+
+    pub(crate) fn on_sync_finished(&self, duration: Duration) {
+        self.sync_duration.observe(duration.as_secs_f64());
+    }
+}
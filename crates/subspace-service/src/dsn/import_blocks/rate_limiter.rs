@@ -0,0 +1,37 @@
+use parking_lot::Mutex;
+use std::num::NonZeroU64;
+use std::time::{Duration, Instant};
+
+/// Limits download rate of pieces during sync from DSN, such that nodes on metered or shared
+/// connections don't saturate the link.
+pub(super) struct DownloadRateLimiter {
+    bytes_per_second: NonZeroU64,
+    /// Time at which next download is allowed to start
+    next_download_at: Mutex<Instant>,
+}
+
+impl DownloadRateLimiter {
+    pub(super) fn new(bytes_per_second: NonZeroU64) -> Self {
+        Self {
+            bytes_per_second,
+            next_download_at: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Wait until `bytes` can be downloaded without exceeding the rate limit.
+    pub(super) async fn acquire(&self, bytes: u64) {
+        let delay = {
+            let mut next_download_at = self.next_download_at.lock();
+            let now = Instant::now();
+            let download_at = (*next_download_at).max(now);
+            *next_download_at = download_at
+                + Duration::from_secs_f64(bytes as f64 / self.bytes_per_second.get() as f64);
+
+            download_at - now
+        };
+
+        if !delay.is_zero() {
+            tokio::time::sleep(delay).await;
+        }
+    }
+}
@@ -0,0 +1,214 @@
+use parity_scale_codec::{Decode, Encode};
+use parking_lot::Mutex;
+use sc_client_api::backend::AuxStore;
+use sc_tracing::tracing::warn;
+use std::collections::VecDeque;
+use subspace_archiving::archiver::is_piece_valid;
+use subspace_core_primitives::crypto::kzg::Kzg;
+use subspace_core_primitives::{
+    ArchivedHistorySegment, Piece, PieceIndex, RecordedHistorySegment, SegmentCommitment,
+    SegmentIndex,
+};
+
+/// Aux storage key of the list of cached segments
+const SEGMENT_CACHE_KEY: &[u8] = b"dsn-sync-cached-segments";
+/// Aux storage key of the list of cached segments in the old format, where all pieces of a segment
+/// were stored under a single key
+const LEGACY_SEGMENT_CACHE_KEY: &[u8] = b"dsn-sync-segment-cache";
+/// Aux storage key prefix of pieces of cached segment in the old format
+const LEGACY_SEGMENT_PIECES_KEY_PREFIX: &[u8] = b"dsn-sync-segment-pieces";
+/// Aux storage key prefix of cached pieces, each piece is stored under its own key
+const CACHED_PIECE_KEY_PREFIX: &[u8] = b"dsn-sync-cached-piece";
+
+fn cached_piece_key(piece_index: PieceIndex) -> Vec<u8> {
+    (CACHED_PIECE_KEY_PREFIX, piece_index).encode()
+}
+
+fn segment_piece_keys(segment_index: SegmentIndex) -> impl Iterator<Item = Vec<u8>> {
+    segment_index.segment_piece_indexes().map(cached_piece_key)
+}
+
+/// Cache of pieces of recently downloaded segments in aux storage, such that interrupted sync from
+/// DSN (or re-import of blocks) doesn't need to download the same segments again.
+///
+/// Disabled when `max_segments` is 0. Pieces are stored under separate keys and verified against
+/// segment commitments again when read, pieces that fail verification are treated as missing.
+pub(super) struct SegmentCache<'a, AS> {
+    aux_store: &'a AS,
+    max_segments: usize,
+    kzg: Kzg,
+    /// Segment commitments in order from segment 0 to the last one known to DSN
+    segment_commitments: Vec<SegmentCommitment>,
+    /// Cached segments, oldest first
+    cached_segments: Mutex<VecDeque<SegmentIndex>>,
+}
+
+impl<'a, AS> SegmentCache<'a, AS>
+where
+    AS: AuxStore,
+{
+    pub(super) fn load(
+        aux_store: &'a AS,
+        max_segments: usize,
+        kzg: Kzg,
+        segment_commitments: Vec<SegmentCommitment>,
+    ) -> sp_blockchain::Result<Self> {
+        remove_legacy_cache(aux_store)?;
+
+        let mut cached_segments = aux_store
+            .get_aux(SEGMENT_CACHE_KEY)?
+            .and_then(|bytes| VecDeque::<SegmentIndex>::decode(&mut bytes.as_slice()).ok())
+            .unwrap_or_default();
+
+        // Cache size might have been decreased (or cache disabled) since last run
+        let evicted_segments = cached_segments.len().saturating_sub(max_segments);
+        if evicted_segments > 0 {
+            let delete_keys = cached_segments
+                .drain(..evicted_segments)
+                .flat_map(segment_piece_keys)
+                .collect::<Vec<_>>();
+
+            aux_store.insert_aux(
+                &[(SEGMENT_CACHE_KEY, cached_segments.encode().as_slice())],
+                &delete_keys.iter().map(Vec::as_slice).collect::<Vec<_>>(),
+            )?;
+        }
+
+        Ok(Self {
+            aux_store,
+            max_segments,
+            kzg,
+            segment_commitments,
+            cached_segments: Mutex::new(cached_segments),
+        })
+    }
+
+    /// Cached pieces of the segment, `None` unless enough valid pieces to recover the segment are
+    /// cached.
+    pub(super) fn get(
+        &self,
+        segment_index: SegmentIndex,
+    ) -> sp_blockchain::Result<Option<Vec<Option<Piece>>>> {
+        if !self.cached_segments.lock().contains(&segment_index) {
+            return Ok(None);
+        }
+
+        let Some(segment_commitment) = self
+            .segment_commitments
+            .get(u64::from(segment_index) as usize)
+        else {
+            return Ok(None);
+        };
+
+        let mut segment_pieces = vec![None::<Piece>; ArchivedHistorySegment::NUM_PIECES];
+        let mut valid_pieces = 0;
+        for piece_index in segment_index.segment_piece_indexes() {
+            let Some(piece) = self
+                .aux_store
+                .get_aux(&cached_piece_key(piece_index))?
+                .and_then(|bytes| Piece::decode(&mut bytes.as_slice()).ok())
+            else {
+                continue;
+            };
+
+            if !is_piece_valid(
+                &self.kzg,
+                &piece,
+                segment_commitment,
+                piece_index.position(),
+            ) {
+                warn!(%piece_index, "Cached piece failed verification, ignoring it");
+                continue;
+            }
+
+            segment_pieces
+                .get_mut(piece_index.position() as usize)
+                .expect("Piece position is by definition within segment; qed")
+                .replace(piece);
+            valid_pieces += 1;
+        }
+
+        if valid_pieces < RecordedHistorySegment::NUM_RAW_RECORDS {
+            self.remove(segment_index)?;
+            return Ok(None);
+        }
+
+        Ok(Some(segment_pieces))
+    }
+
+    pub(super) fn insert(
+        &self,
+        segment_index: SegmentIndex,
+        segment_pieces: &[Option<Piece>],
+    ) -> sp_blockchain::Result<()> {
+        if self.max_segments == 0 {
+            return Ok(());
+        }
+
+        let mut cached_segments = self.cached_segments.lock();
+        if cached_segments.contains(&segment_index) {
+            return Ok(());
+        }
+
+        let mut delete_keys = Vec::new();
+        while cached_segments.len() >= self.max_segments {
+            if let Some(evicted_segment_index) = cached_segments.pop_front() {
+                delete_keys.extend(segment_piece_keys(evicted_segment_index));
+            }
+        }
+        cached_segments.push_back(segment_index);
+
+        let pieces = segment_index
+            .segment_piece_indexes()
+            .zip(segment_pieces)
+            .filter_map(|(piece_index, maybe_piece)| {
+                maybe_piece
+                    .as_ref()
+                    .map(|piece| (cached_piece_key(piece_index), piece.encode()))
+            })
+            .collect::<Vec<_>>();
+        let cached_segments = cached_segments.encode();
+
+        self.aux_store.insert_aux(
+            &pieces
+                .iter()
+                .map(|(key, piece)| (key.as_slice(), piece.as_slice()))
+                .chain([(SEGMENT_CACHE_KEY, cached_segments.as_slice())])
+                .collect::<Vec<_>>(),
+            &delete_keys.iter().map(Vec::as_slice).collect::<Vec<_>>(),
+        )
+    }
+
+    fn remove(&self, segment_index: SegmentIndex) -> sp_blockchain::Result<()> {
+        let mut cached_segments = self.cached_segments.lock();
+        cached_segments.retain(|cached_segment_index| *cached_segment_index != segment_index);
+
+        let delete_keys = segment_piece_keys(segment_index).collect::<Vec<_>>();
+        self.aux_store.insert_aux(
+            &[(SEGMENT_CACHE_KEY, cached_segments.encode().as_slice())],
+            &delete_keys.iter().map(Vec::as_slice).collect::<Vec<_>>(),
+        )
+    }
+}
+
+/// Remove segments cached in the old format, which stored whole segments as single values
+fn remove_legacy_cache<AS>(aux_store: &AS) -> sp_blockchain::Result<()>
+where
+    AS: AuxStore,
+{
+    let Some(bytes) = aux_store.get_aux(LEGACY_SEGMENT_CACHE_KEY)? else {
+        return Ok(());
+    };
+
+    let delete_keys = VecDeque::<SegmentIndex>::decode(&mut bytes.as_slice())
+        .unwrap_or_default()
+        .into_iter()
+        .map(|segment_index| (LEGACY_SEGMENT_PIECES_KEY_PREFIX, segment_index).encode())
+        .chain([LEGACY_SEGMENT_CACHE_KEY.to_vec()])
+        .collect::<Vec<_>>();
+
+    aux_store.insert_aux(
+        &[],
+        &delete_keys.iter().map(Vec::as_slice).collect::<Vec<_>>(),
+    )
+}
@@ -1,10 +1,14 @@
 use futures::StreamExt;
+use sc_consensus_subspace_rpc::SegmentHeaderProvider;
 use std::collections::{BTreeSet, HashMap};
 use std::error::Error;
 use subspace_core_primitives::{SegmentHeader, SegmentIndex};
 use subspace_networking::libp2p::PeerId;
-use subspace_networking::{Node, SegmentHeaderRequest, SegmentHeaderResponse};
-use tracing::{debug, error, trace, warn};
+use subspace_networking::{
+    verify_segment_headers_range, Node, SegmentHeaderRequest, SegmentHeaderResponse,
+    SEGMENT_INDEX_RANGE_LIMIT,
+};
+use tracing::{debug, trace, warn};
 
 const SEGMENT_HEADER_NUMBER_PER_REQUEST: u64 = 1000;
 /// Initial number of peers to query for segment header
@@ -21,7 +25,16 @@ impl SegmentHeaderHandler {
     }
 
     /// Returns segment headers known to DSN, ordered from 0 to the last known.
-    pub async fn get_segment_headers(&self) -> Result<Vec<SegmentHeader>, Box<dyn Error>> {
+    ///
+    /// Segment headers that are already present in `cached_segment_headers` and match the chain
+    /// of segment header hashes are not requested from DSN again.
+    pub async fn get_segment_headers<SHP>(
+        &self,
+        cached_segment_headers: Option<&SHP>,
+    ) -> Result<Vec<SegmentHeader>, Box<dyn Error>>
+    where
+        SHP: SegmentHeaderProvider,
+    {
         trace!("Getting segment headers...");
 
         let Some((mut last_segment_header, peers)) = self.get_last_segment_header().await? else {
@@ -39,31 +52,30 @@ impl SegmentHeaderHandler {
         all_segment_headers.push(last_segment_header);
 
         while last_segment_header.segment_index() > SegmentIndex::ZERO {
-            let segment_indexes: Vec<_> = (SegmentIndex::ZERO..last_segment_header.segment_index())
-                .rev()
-                .take(SEGMENT_HEADER_NUMBER_PER_REQUEST as usize)
-                .collect();
+            let maybe_cached_segment_header = cached_segment_headers.and_then(|provider| {
+                provider
+                    .get_segment_header(last_segment_header.segment_index() - SegmentIndex::ONE)
+                    .ok()
+                    .flatten()
+            });
+            if let Some(segment_header) = maybe_cached_segment_header {
+                if segment_header.hash() == last_segment_header.prev_segment_header_hash() {
+                    last_segment_header = segment_header;
+                    all_segment_headers.push(segment_header);
+                    continue;
+                }
+            }
 
-            let (peer_id, segment_headers) = self
-                .get_segment_headers_batch(&peers, segment_indexes)
-                .await?;
+            let end = last_segment_header.segment_index() - SegmentIndex::ONE;
+            let start =
+                SegmentIndex::from(u64::from(end).saturating_sub(SEGMENT_INDEX_RANGE_LIMIT - 1));
 
-            for segment_header in segment_headers {
-                if segment_header.hash() != last_segment_header.prev_segment_header_hash() {
-                    error!(
-                        %peer_id,
-                        segment_index=%last_segment_header.segment_index() - SegmentIndex::ONE,
-                        actual_hash=?segment_header.hash(),
-                        expected_hash=?last_segment_header.prev_segment_header_hash(),
-                        "Segment header hash doesn't match expected hash from the last block."
-                    );
-
-                    return Err(
-                        "Segment header hash doesn't match expected hash from the last block."
-                            .into(),
-                    );
-                }
+            // Segment headers are verified to form a chain with the last segment header
+            let segment_headers = self
+                .get_segment_headers_batch(&peers, start, end, &last_segment_header)
+                .await?;
 
+            for segment_header in segment_headers.into_iter().rev() {
                 last_segment_header = segment_header;
                 all_segment_headers.push(segment_header);
             }
@@ -253,12 +265,16 @@ impl SegmentHeaderHandler {
         self.is_segment_headers_response_valid(peer_id, &segment_indexes, segment_headers)
     }
 
+    /// Get segment headers in the range of segment indexes (inclusive, in ascending order) that
+    /// form a chain with `next_segment_header`.
     async fn get_segment_headers_batch(
         &self,
         peers: &[PeerId],
-        segment_indexes: Vec<SegmentIndex>,
-    ) -> Result<(PeerId, Vec<SegmentHeader>), Box<dyn Error>> {
-        trace!(?segment_indexes, "Getting segment header batch...");
+        start: SegmentIndex,
+        end: SegmentIndex,
+        next_segment_header: &SegmentHeader,
+    ) -> Result<Vec<SegmentHeader>, Box<dyn Error>> {
+        trace!(%start, %end, "Getting segment header batch...");
 
         for &peer_id in peers {
             trace!(%peer_id, "get_closest_peers returned an item");
@@ -267,30 +283,30 @@ impl SegmentHeaderHandler {
                 .dsn_node
                 .send_generic_request(
                     peer_id,
-                    SegmentHeaderRequest::SegmentIndexes {
-                        segment_indexes: segment_indexes.clone(),
-                    },
+                    SegmentHeaderRequest::SegmentIndexRange { start, end },
                 )
                 .await;
 
             match request_result {
                 Ok(SegmentHeaderResponse { segment_headers }) => {
-                    trace!(%peer_id, ?segment_indexes, "Segment header request succeeded.");
+                    trace!(%peer_id, %start, %end, "Segment header request succeeded.");
 
-                    if !self.is_segment_headers_response_valid(
-                        peer_id,
-                        &segment_indexes,
+                    if let Err(error) = verify_segment_headers_range(
+                        start,
+                        end,
                         &segment_headers,
+                        Some(next_segment_header),
                     ) {
-                        warn!(%peer_id, "Received segment headers were invalid.");
+                        warn!(%peer_id, %error, "Received segment headers were invalid.");
 
                         let _ = self.dsn_node.ban_peer(peer_id).await;
+                        continue;
                     }
 
-                    return Ok((peer_id, segment_headers));
+                    return Ok(segment_headers);
                 }
                 Err(error) => {
-                    debug!(%peer_id, ?segment_indexes, ?error, "Segment header request failed.");
+                    debug!(%peer_id, %start, %end, ?error, "Segment header request failed.");
                 }
             };
         }
@@ -0,0 +1,81 @@
+use substrate_prometheus_endpoint::{
+    register, Counter, CounterVec, Gauge, Opts, PrometheusError, Registry, U64,
+};
+
+/// Metrics of the node's DSN instance
+#[derive(Debug, Clone)]
+pub struct DsnMetrics {
+    connected_peers: Gauge<U64>,
+    piece_requests_received: Counter<U64>,
+    piece_requests_served: Counter<U64>,
+    segment_header_requests_received: Counter<U64>,
+    piece_announcements_received: CounterVec<U64>,
+}
+
+impl DsnMetrics {
+    /// Create and register metrics in provided registry
+    pub fn new(registry: &Registry) -> Result<Self, PrometheusError> {
+        Ok(Self {
+            connected_peers: register(
+                Gauge::new(
+                    "subspace_node_dsn_connected_peers",
+                    "Number of established connections with DSN peers",
+                )?,
+                registry,
+            )?,
+            piece_requests_received: register(
+                Counter::new(
+                    "subspace_node_dsn_piece_requests_received",
+                    "Total number of piece requests received from DSN peers",
+                )?,
+                registry,
+            )?,
+            piece_requests_served: register(
+                Counter::new(
+                    "subspace_node_dsn_piece_requests_served",
+                    "Total number of piece requests from DSN peers served from piece cache",
+                )?,
+                registry,
+            )?,
+            segment_header_requests_received: register(
+                Counter::new(
+                    "subspace_node_dsn_segment_header_requests_received",
+                    "Total number of segment header requests received from DSN peers",
+                )?,
+                registry,
+            )?,
+            piece_announcements_received: register(
+                CounterVec::new(
+                    Opts::new(
+                        "subspace_node_dsn_piece_announcements_received",
+                        "Total number of piece announcements received from DSN peers by result",
+                    ),
+                    &["result"],
+                )?,
+                registry,
+            )?,
+        })
+    }
+
+    pub(crate) fn set_connected_peers(&self, peers: usize) {
+        self.connected_peers.set(peers as u64);
+    }
+
+    pub(crate) fn on_piece_request_received(&self) {
+        self.piece_requests_received.inc();
+    }
+
+    pub(crate) fn on_piece_request_served(&self) {
+        self.piece_requests_served.inc();
+    }
+
+    pub(crate) fn on_segment_header_request_received(&self) {
+        self.segment_header_requests_received.inc();
+    }
+
+    pub(crate) fn on_piece_announcement_received(&self, result: &str) {
+        self.piece_announcements_received
+            .with_label_values(&[result])
+            .inc();
+    }
+}
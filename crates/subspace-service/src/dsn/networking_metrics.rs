@@ -0,0 +1,180 @@
+//! Bridge between networking metrics collected with `prometheus_client` and Substrate's
+//! Prometheus registry.
+//!
+//! Networking metrics are encoded in OpenMetrics text format and every sample is exposed as an
+//! untyped metric, which is enough for scraping and doesn't require both libraries to agree on
+//! metric types.
+
+#[cfg(test)]
+mod tests;
+
+use std::collections::HashMap;
+use subspace_networking::NetworkingMetrics;
+use substrate_prometheus_endpoint::prometheus::core::{Collector, Desc};
+use substrate_prometheus_endpoint::prometheus::proto::{
+    LabelPair, Metric, MetricFamily, MetricType, Untyped,
+};
+use tracing::warn;
+
+/// Suffixes of sample names that OpenMetrics appends to the metric family name.
+const SAMPLE_NAME_SUFFIXES: &[&str] = &["_total", "_created", "_bucket", "_sum", "_count", "_info"];
+
+#[derive(Debug, PartialEq)]
+struct Sample {
+    name: String,
+    labels: Vec<(String, String)>,
+    value: f64,
+}
+
+/// Exposes networking metrics through Substrate's Prometheus registry.
+pub(crate) struct NetworkingMetricsCollector {
+    networking_metrics: NetworkingMetrics,
+}
+
+impl NetworkingMetricsCollector {
+    pub(crate) fn new(networking_metrics: NetworkingMetrics) -> Self {
+        Self { networking_metrics }
+    }
+}
+
+impl Collector for NetworkingMetricsCollector {
+    fn desc(&self) -> Vec<&Desc> {
+        // Set of metrics is dynamic, hence no descriptors
+        Vec::new()
+    }
+
+    fn collect(&self) -> Vec<MetricFamily> {
+        let mut encoded = String::new();
+        if let Err(error) = self.networking_metrics.encode(&mut encoded) {
+            warn!(%error, "Failed to encode networking metrics");
+            return Vec::new();
+        }
+
+        let (helps, samples) = parse_metrics(&encoded);
+
+        let mut metric_families = HashMap::<String, MetricFamily>::new();
+        for Sample {
+            name,
+            labels,
+            value,
+        } in samples
+        {
+            let metric_family = metric_families.entry(name.clone()).or_insert_with(|| {
+                let help = helps
+                    .get(&name)
+                    .or_else(|| {
+                        SAMPLE_NAME_SUFFIXES
+                            .iter()
+                            .find_map(|suffix| helps.get(name.strip_suffix(suffix)?))
+                    })
+                    .cloned()
+                    .unwrap_or_else(|| name.clone());
+
+                let mut metric_family = MetricFamily::default();
+                metric_family.set_name(name);
+                metric_family.set_help(help);
+                metric_family.set_field_type(MetricType::UNTYPED);
+                metric_family
+            });
+
+            let mut metric = Metric::default();
+            for (name, value) in labels {
+                let mut label_pair = LabelPair::default();
+                label_pair.set_name(name);
+                label_pair.set_value(value);
+                metric.mut_label().push(label_pair);
+            }
+            let mut untyped = Untyped::default();
+            untyped.set_value(value);
+            metric.set_untyped(untyped);
+
+            metric_family.mut_metric().push(metric);
+        }
+
+        metric_families.into_values().collect()
+    }
+}
+
+/// Parse metrics encoded in OpenMetrics text format into help texts by metric family name and
+/// samples, lines that can't be parsed are skipped.
+fn parse_metrics(encoded: &str) -> (HashMap<String, String>, Vec<Sample>) {
+    let mut helps = HashMap::new();
+    let mut samples = Vec::new();
+
+    for line in encoded.lines() {
+        if let Some(help) = line.strip_prefix("# HELP ") {
+            if let Some((name, help)) = help.split_once(' ') {
+                helps.insert(name.to_string(), help.to_string());
+            }
+        } else if !line.starts_with('#') && !line.is_empty() {
+            match parse_sample(line) {
+                Some(sample) => {
+                    samples.push(sample);
+                }
+                None => {
+                    warn!(%line, "Failed to parse networking metrics sample");
+                }
+            }
+        }
+    }
+
+    (helps, samples)
+}
+
+/// Parse sample line like `name{label="value"} 1`.
+fn parse_sample(line: &str) -> Option<Sample> {
+    let (name, labels, rest) = match line.find(|c| c == '{' || c == ' ') {
+        Some(position) if line[position..].starts_with('{') => {
+            let (labels, rest) = parse_labels(&line[position + 1..])?;
+            (&line[..position], labels, rest)
+        }
+        Some(position) => (&line[..position], Vec::new(), &line[position..]),
+        None => {
+            return None;
+        }
+    };
+
+    let value = match rest.split_whitespace().next()? {
+        "+Inf" => f64::INFINITY,
+        "-Inf" => f64::NEG_INFINITY,
+        value => value.parse().ok()?,
+    };
+
+    Some(Sample {
+        name: name.to_string(),
+        labels,
+        value,
+    })
+}
+
+/// Parse labels after opening `{` up to and including closing `}`, returns labels and the rest of
+/// the line.
+fn parse_labels(mut input: &str) -> Option<(Vec<(String, String)>, &str)> {
+    let mut labels = Vec::new();
+
+    loop {
+        input = input.trim_start_matches(',');
+        if let Some(rest) = input.strip_prefix('}') {
+            return Some((labels, rest));
+        }
+
+        let (name, rest) = input.split_once("=\"")?;
+        let mut value = String::new();
+        let mut chars = rest.char_indices();
+        let end = loop {
+            match chars.next()? {
+                (_, '\\') => match chars.next()?.1 {
+                    'n' => value.push('\n'),
+                    c => value.push(c),
+                },
+                (position, '"') => {
+                    break position;
+                }
+                (_, c) => value.push(c),
+            }
+        };
+
+        labels.push((name.to_string(), value));
+        input = &rest[end + 1..];
+    }
+}
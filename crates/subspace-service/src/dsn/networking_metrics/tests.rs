@@ -0,0 +1,58 @@
+use crate::dsn::networking_metrics::{parse_metrics, parse_sample, Sample};
+
+#[test]
+fn parse_sample_without_labels() {
+    assert_eq!(
+        parse_sample("libp2p_bandwidth_inbound_bytes_total 42"),
+        Some(Sample {
+            name: "libp2p_bandwidth_inbound_bytes_total".to_string(),
+            labels: Vec::new(),
+            value: 42.0,
+        })
+    );
+}
+
+#[test]
+fn parse_sample_with_labels() {
+    assert_eq!(
+        parse_sample(
+            r#"libp2p_swarm_connections_incoming_total{protocols="/ip4/tcp",x="a\"b,}"} 3"#
+        ),
+        Some(Sample {
+            name: "libp2p_swarm_connections_incoming_total".to_string(),
+            labels: vec![
+                ("protocols".to_string(), "/ip4/tcp".to_string()),
+                ("x".to_string(), "a\"b,}".to_string()),
+            ],
+            value: 3.0,
+        })
+    );
+    assert_eq!(
+        parse_sample(r#"libp2p_kad_query_result_num_requests_bucket{le="+Inf"} 1.5"#)
+            .map(|sample| sample.value),
+        Some(1.5)
+    );
+    assert_eq!(parse_sample(r#"broken{label="value" 1"#), None);
+    assert_eq!(parse_sample("no_value"), None);
+}
+
+#[test]
+fn parse_encoded_metrics() {
+    let encoded = "\
+# HELP libp2p_bandwidth_inbound_bytes Number of bytes received over all connections.
+# TYPE libp2p_bandwidth_inbound_bytes counter
+libp2p_bandwidth_inbound_bytes_total 42
+# EOF
+";
+
+    let (helps, samples) = parse_metrics(encoded);
+
+    assert_eq!(
+        helps
+            .get("libp2p_bandwidth_inbound_bytes")
+            .map(String::as_str),
+        Some("Number of bytes received over all connections.")
+    );
+    assert_eq!(samples.len(), 1);
+    assert_eq!(samples[0].name, "libp2p_bandwidth_inbound_bytes_total");
+}
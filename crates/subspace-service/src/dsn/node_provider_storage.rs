@@ -1,7 +1,7 @@
 use subspace_networking::libp2p::kad::record::Key;
 use subspace_networking::libp2p::kad::ProviderRecord;
 use subspace_networking::libp2p::PeerId;
-use subspace_networking::ProviderStorage;
+use subspace_networking::{ProviderStorage, ProviderStorageStats};
 
 pub struct NodeProviderStorage<ImplicitProviderStorage, PersistentProviderStorage> {
     local_peer_id: PeerId,
@@ -79,4 +79,9 @@ where
         self.persistent_provider_storage
             .remove_provider(key, peer_id);
     }
+
+    fn stats(&self) -> ProviderStorageStats {
+        // Implicit provider records are not stored
+        self.persistent_provider_storage.stats()
+    }
 }
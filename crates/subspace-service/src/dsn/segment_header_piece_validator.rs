@@ -0,0 +1,80 @@
+use async_trait::async_trait;
+use sc_consensus_subspace_rpc::SegmentHeaderProvider;
+use subspace_archiving::archiver::is_piece_valid;
+use subspace_core_primitives::crypto::kzg::Kzg;
+use subspace_core_primitives::{Piece, PieceIndex};
+use subspace_networking::libp2p::PeerId;
+use subspace_networking::utils::piece_provider::PieceValidator;
+use subspace_networking::Node;
+use tracing::{debug, error, warn};
+
+/// Validates pieces against segment headers known to the node
+pub struct SegmentHeaderPieceValidator<SHP> {
+    dsn_node: Node,
+    kzg: Kzg,
+    segment_header_provider: SHP,
+}
+
+impl<SHP> SegmentHeaderPieceValidator<SHP> {
+    /// Create new instance
+    pub fn new(dsn_node: Node, kzg: Kzg, segment_header_provider: SHP) -> Self {
+        Self {
+            dsn_node,
+            kzg,
+            segment_header_provider,
+        }
+    }
+}
+
+#[async_trait]
+impl<SHP> PieceValidator for SegmentHeaderPieceValidator<SHP>
+where
+    SHP: SegmentHeaderProvider + Send + Sync,
+{
+    async fn validate_piece(
+        &self,
+        source_peer_id: PeerId,
+        piece_index: PieceIndex,
+        piece: Piece,
+    ) -> Option<Piece> {
+        if source_peer_id != self.dsn_node.id() {
+            let segment_index = piece_index.segment_index();
+
+            let segment_commitment = match self
+                .segment_header_provider
+                .get_segment_header(segment_index)
+            {
+                Ok(Some(segment_header)) => segment_header.segment_commitment(),
+                Ok(None) => {
+                    debug!(%segment_index, "Segment header is not known to the node");
+
+                    return None;
+                }
+                Err(error) => {
+                    error!(%segment_index, %error, "Failed to get segment header");
+
+                    return None;
+                }
+            };
+
+            if !is_piece_valid(
+                &self.kzg,
+                &piece,
+                &segment_commitment,
+                piece_index.position(),
+            ) {
+                warn!(
+                    %piece_index,
+                    %source_peer_id,
+                    "Received invalid piece from peer"
+                );
+
+                // We don't care about result here
+                let _ = self.dsn_node.ban_peer(source_peer_id).await;
+                return None;
+            }
+        }
+
+        Some(piece)
+    }
+}
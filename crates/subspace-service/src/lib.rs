@@ -15,23 +15,37 @@
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
 //! Service and ServiceFactory implementation. Specialized wrapper over substrate service.
-#![feature(type_alias_impl_trait, type_changing_struct_update)]
+#![feature(const_option, type_alias_impl_trait, type_changing_struct_update)]
 
+mod consensus_telemetry;
 pub mod dsn;
 mod genesis_block_builder;
 mod metrics;
+pub mod object_mappings;
 pub mod piece_cache;
+pub mod reward_signing;
 pub mod rpc;
 pub mod segment_headers;
 mod sync_from_dsn;
 pub mod tx_pre_validator;
 
-use crate::dsn::import_blocks::initial_block_import_from_dsn;
-use crate::dsn::{create_dsn_instance, DsnConfigurationError};
+use crate::consensus_telemetry::start_consensus_telemetry;
+use crate::dsn::import_blocks::{
+    initial_block_import_from_dsn, DsnSyncMetrics, ImportBlocksOptions,
+};
+use crate::dsn::{
+    announce_segment_pieces, create_dsn_instance, DsnConfigurationError, DsnMetrics,
+    NetworkingMetricsCollector,
+};
 use crate::genesis_block_builder::SubspaceGenesisBlockBuilder;
 use crate::metrics::NodeMetrics;
+use crate::object_mappings::{start_object_mappings_archiver, ObjectMappingCache};
 use crate::piece_cache::PieceCache;
+use crate::reward_signing::{
+    create_remote_reward_signer_client, start_remote_reward_signer, RemoteRewardSignerConfig,
+};
 use crate::segment_headers::{start_segment_header_archiver, SegmentHeaderCache};
+use crate::sync_from_dsn::{DsnSyncParams, ImportResults, ObservedImportQueue};
 use crate::tx_pre_validator::ConsensusChainTxPreValidator;
 use cross_domain_message_gossip::cdm_gossip_peers_set_config;
 use derive_more::{Deref, DerefMut, Into};
@@ -54,18 +68,20 @@ use sc_consensus_subspace::{
     ArchivedSegmentNotification, BlockImportingNotification, NewSlotNotification,
     RewardSigningNotification, SubspaceLink, SubspaceParams,
 };
+use sc_consensus_subspace_rpc::SubspaceRpcMetrics;
 use sc_executor::{NativeElseWasmExecutor, NativeExecutionDispatch};
 use sc_network::NetworkService;
 use sc_service::error::Error as ServiceError;
 use sc_service::{
-    new_db_backend, Configuration, NetworkStarter, PartialComponents, SpawnTasksParams, TaskManager,
+    new_db_backend, ChainType, Configuration, NetworkStarter, PartialComponents, SpawnTasksParams,
+    TaskManager,
 };
 use sc_subspace_block_relay::{build_consensus_relay, NetworkWrapper};
 use sc_telemetry::{Telemetry, TelemetryWorker};
 use sp_api::{ApiExt, ConstructRuntimeApi, HeaderT, Metadata, ProvideRuntimeApi, TransactionFor};
 use sp_block_builder::BlockBuilder;
 use sp_blockchain::HeaderMetadata;
-use sp_consensus::{Error as ConsensusError, SyncOracle};
+use sp_consensus::{BlockOrigin, Error as ConsensusError, SyncOracle};
 use sp_consensus_slots::Slot;
 use sp_consensus_subspace::{FarmerPublicKey, KzgExtension, PosExtension, SubspaceApi};
 use sp_core::offchain;
@@ -79,6 +95,8 @@ use sp_runtime::traits::{Block as BlockT, BlockIdTo, NumberFor};
 use sp_session::SessionKeys;
 use sp_transaction_pool::runtime_api::TaggedTransactionQueue;
 use std::marker::PhantomData;
+use std::num::{NonZeroU64, NonZeroUsize};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
 use subspace_core_primitives::crypto::kzg::{embedded_kzg_settings, Kzg};
 use subspace_fraud_proof::domain_extrinsics_builder::DomainExtrinsicsBuilder;
@@ -91,6 +109,7 @@ use subspace_runtime_primitives::opaque::Block;
 use subspace_runtime_primitives::{AccountId, Balance, Hash, Index as Nonce};
 use subspace_transaction_pool::bundle_validator::BundleValidator;
 use subspace_transaction_pool::{FullPool, PreValidateTransaction};
+pub use sync_from_dsn::{DsnSyncConfig, DsnSyncState, DsnSyncStatus, DsnSyncTrigger};
 use tracing::{debug, error, info, Instrument};
 
 /// Error type for Subspace service.
@@ -187,6 +206,9 @@ pub enum SubspaceNetworking {
         config: DsnConfig,
         /// Piece cache size in bytes
         piece_cache_size: u64,
+        /// Size in bytes of the part of piece cache with the most recently archived or requested
+        /// pieces that is also kept in memory, disabled if smaller than one piece
+        piece_cache_memory_size: u64,
     },
 }
 
@@ -205,6 +227,23 @@ pub struct SubspaceConfiguration {
     pub subspace_networking: SubspaceNetworking,
     /// Enables DSN-sync on startup.
     pub sync_from_dsn: bool,
+    /// Configuration of DSN-sync.
+    pub dsn_sync: DsnSyncConfig,
+    /// Store object mappings of segments archived by this node, such that objects can be fetched
+    /// with `subspace_fetchObject` RPC.
+    pub archive_object_mappings: bool,
+    /// Number of most recent segment headers to keep in the node's storage, `None` keeps all of
+    /// them.
+    ///
+    /// Segment headers are served to farmers and other DSN peers, so pruned segment headers are
+    /// no longer available to them from this node.
+    pub segment_headers_retention: Option<NonZeroU64>,
+    /// Remote signer to delegate reward signing to, `None` leaves reward signing to farmers
+    /// connected over RPC.
+    pub remote_reward_signer: Option<RemoteRewardSignerConfig>,
+    /// Number of threads used by archiver for erasure coding of archived segments, `None` uses
+    /// number of logical CPUs.
+    pub archiver_threads: Option<NonZeroUsize>,
     /// Use the block request handler implementation from subspace
     /// instead of the default substrate handler.
     pub enable_subspace_block_relay: bool,
@@ -574,9 +613,23 @@ where
         other: (block_import, subspace_link, mut telemetry, mut bundle_validator),
     } = partial_components;
 
-    let segment_header_cache = SegmentHeaderCache::new(client.clone()).map_err(|error| {
-        Error::Other(format!("Failed to instantiate segment header cache: {error}").into())
-    })?;
+    let segment_header_cache =
+        SegmentHeaderCache::new(client.clone(), config.segment_headers_retention).map_err(
+            |error| {
+                Error::Other(format!("Failed to instantiate segment header cache: {error}").into())
+            },
+        )?;
+
+    let dsn_metrics =
+        config
+            .prometheus_registry()
+            .and_then(|registry| match DsnMetrics::new(registry) {
+                Ok(dsn_metrics) => Some(dsn_metrics),
+                Err(error) => {
+                    error!("Failed to initialize DSN metrics: {error:?}");
+                    None
+                }
+            });
 
     let (node, bootstrap_nodes, piece_cache) = match config.subspace_networking.clone() {
         SubspaceNetworking::Reuse {
@@ -587,6 +640,7 @@ where
         SubspaceNetworking::Create {
             config: dsn_config,
             piece_cache_size,
+            piece_cache_memory_size,
         } => {
             let dsn_protocol_version = hex::encode(client.chain_info().genesis_hash);
 
@@ -599,49 +653,29 @@ where
             let piece_cache = PieceCache::new(
                 client.clone(),
                 piece_cache_size,
+                piece_cache_memory_size,
                 peer_id(&dsn_config.keypair),
             );
 
-            // Start before archiver below, so we don't have potential race condition and miss pieces
-            task_manager
-                .spawn_handle()
-                .spawn_blocking("subspace-piece-cache", None, {
-                    let mut piece_cache = piece_cache.clone();
-                    let mut archived_segment_notification_stream = subspace_link
-                        .archived_segment_notification_stream()
-                        .subscribe();
-
-                    async move {
-                        while let Some(archived_segment_notification) =
-                            archived_segment_notification_stream.next().await
-                        {
-                            let segment_index = archived_segment_notification
-                                .archived_segment
-                                .segment_header
-                                .segment_index();
-                            if let Err(error) = piece_cache.add_pieces(
-                                segment_index.first_piece_index(),
-                                &archived_segment_notification.archived_segment.pieces,
-                            ) {
-                                error!(
-                                    %segment_index,
-                                    %error,
-                                    "Failed to store pieces for segment in cache"
-                                );
-                            }
-                        }
-                    }
-                });
-
             let (node, mut node_runner) = create_dsn_instance(
                 dsn_protocol_version,
                 dsn_config.clone(),
                 piece_cache.clone(),
                 segment_header_cache.clone(),
+                dsn_metrics.clone(),
             )?;
 
             info!("Subspace networking initialized: Node ID is {}", node.id());
 
+            if let (Some(registry), Some(networking_metrics)) =
+                (config.prometheus_registry(), node.metrics())
+            {
+                let collector = NetworkingMetricsCollector::new(networking_metrics.clone());
+                if let Err(error) = registry.register(Box::new(collector)) {
+                    error!("Failed to register networking metrics: {error:?}");
+                }
+            }
+
             node.on_new_listener(Arc::new({
                 let node = node.clone();
 
@@ -667,10 +701,84 @@ where
                     ),
                 );
 
+            // Start before archiver below, so we don't have potential race condition and miss pieces
+            task_manager
+                .spawn_handle()
+                .spawn_blocking("subspace-piece-cache", None, {
+                    let mut piece_cache = piece_cache.clone();
+                    let node = node.clone();
+                    let spawn_handle = task_manager.spawn_handle();
+                    let mut archived_segment_notification_stream = subspace_link
+                        .archived_segment_notification_stream()
+                        .subscribe();
+
+                    async move {
+                        while let Some(archived_segment_notification) =
+                            archived_segment_notification_stream.next().await
+                        {
+                            let segment_index = archived_segment_notification
+                                .archived_segment
+                                .segment_header
+                                .segment_index();
+                            if let Err(error) = piece_cache.add_pieces(
+                                segment_index.first_piece_index(),
+                                &archived_segment_notification.archived_segment.pieces,
+                            ) {
+                                error!(
+                                    %segment_index,
+                                    %error,
+                                    "Failed to store pieces for segment in cache"
+                                );
+                                continue;
+                            }
+
+                            // Announce the node as a provider of cached pieces, such that they
+                            // can be retrieved before farmers finish plotting them
+                            spawn_handle.spawn(
+                                "subspace-segment-pieces-announcement",
+                                Some("subspace-networking"),
+                                {
+                                    let node = node.clone();
+                                    let piece_cache = piece_cache.clone();
+
+                                    async move {
+                                        announce_segment_pieces(&node, &piece_cache, segment_index)
+                                            .await;
+                                    }
+                                    .in_current_span()
+                                },
+                            );
+                        }
+                    }
+                });
+
             (node, dsn_config.bootstrap_nodes, Some(piece_cache))
         }
     };
 
+    let dsn_connected_peers = Arc::<AtomicUsize>::default();
+    node.on_num_established_peer_connections_change(Arc::new({
+        let dsn_connected_peers = Arc::clone(&dsn_connected_peers);
+
+        move |&new_connections| {
+            dsn_connected_peers.store(new_connections, Ordering::Relaxed);
+            if let Some(dsn_metrics) = &dsn_metrics {
+                dsn_metrics.set_connected_peers(new_connections);
+            }
+        }
+    }))
+    .detach();
+
+    // Without bootstrap nodes there is nobody to connect to, in development mode node is often the
+    // only one in the network, neither should make node unhealthy due to lack of DSN peers
+    let dsn_peers_expected = !bootstrap_nodes.is_empty()
+        && config.chain_spec.chain_type() != ChainType::Development
+        && !matches!(
+            &config.subspace_networking,
+            SubspaceNetworking::Create { config: dsn_config, .. }
+                if dsn_config.enable_memory_transport
+        );
+
     let segment_header_archiving_fut = start_segment_header_archiver(
         segment_header_cache.clone(),
         subspace_link
@@ -686,6 +794,25 @@ where
             Box::pin(segment_header_archiving_fut.in_current_span()),
         );
 
+    let object_mapping_cache = config.archive_object_mappings.then(|| {
+        let object_mapping_cache = ObjectMappingCache::new(client.clone());
+
+        let object_mappings_archiving_fut = start_object_mappings_archiver(
+            object_mapping_cache.clone(),
+            subspace_link
+                .archived_segment_notification_stream()
+                .subscribe(),
+        );
+
+        task_manager.spawn_handle().spawn_blocking(
+            "object-mappings-archiver",
+            Some("subspace-networking"),
+            Box::pin(object_mappings_archiving_fut.in_current_span()),
+        );
+
+        object_mapping_cache
+    });
+
     let dsn_bootstrap_nodes = {
         // Fall back to node itself as bootstrap node for DSN so farmer always has someone to
         // connect to
@@ -737,26 +864,52 @@ where
         &subspace_link,
         client.clone(),
         telemetry.as_ref().map(|telemetry| telemetry.handle()),
-    );
+        config.archiver_threads,
+        config.prometheus_registry(),
+    )
+    .map_err(ServiceError::from)?;
 
     task_manager
         .spawn_essential_handle()
         .spawn_essential_blocking("subspace-archiver", None, Box::pin(subspace_archiver));
 
+    let dsn_sync_metrics = if config.sync_from_dsn {
+        config
+            .prometheus_registry()
+            .and_then(|registry| match DsnSyncMetrics::new(registry) {
+                Ok(dsn_sync_metrics) => Some(dsn_sync_metrics),
+                Err(error) => {
+                    error!("Failed to initialize DSN sync metrics: {error:?}");
+                    None
+                }
+            })
+    } else {
+        None
+    };
+
     // TODO: This prevents SIGINT from working properly
     if config.sync_from_dsn {
         let mut imported_blocks = 0;
+        let import_blocks_options = ImportBlocksOptions {
+            block_origin: BlockOrigin::NetworkInitialSync,
+            download_rate_limit: None,
+            ..config.dsn_sync.import_blocks_options(false)
+        };
 
         // Repeat until no new blocks are imported
         loop {
-            let new_imported_blocks =
-                initial_block_import_from_dsn(&node, client.clone(), &mut import_queue, false)
-                    .await
-                    .map_err(|error| {
-                        sc_service::Error::Other(format!(
-                            "Failed to import blocks from DSN: {error:?}"
-                        ))
-                    })?;
+            let new_imported_blocks = initial_block_import_from_dsn(
+                &node,
+                client.clone(),
+                Some(&segment_header_cache),
+                &mut import_queue,
+                &import_blocks_options,
+                dsn_sync_metrics.as_ref(),
+            )
+            .await
+            .map_err(|error| {
+                sc_service::Error::Other(format!("Failed to import blocks from DSN: {error:?}"))
+            })?;
 
             if new_imported_blocks == 0 {
                 break;
@@ -783,6 +936,8 @@ where
         }
     }
 
+    let import_results = ImportResults::default();
+    let import_queue = ObservedImportQueue::new(import_queue, import_results.clone());
     let import_queue_service = import_queue.service();
     let network_wrapper = Arc::new(NetworkWrapper::default());
     let block_relay = if config.enable_subspace_block_relay {
@@ -814,14 +969,21 @@ where
     if config.enable_subspace_block_relay {
         network_wrapper.set(network_service.clone());
     }
-    if config.sync_from_dsn {
-        let (observer, worker) = sync_from_dsn::create_observer_and_worker(
-            Arc::clone(&network_service),
-            node.clone(),
-            Arc::clone(&client),
-            import_queue_service,
-            sync_mode,
-        );
+    let dsn_sync_trigger = if config.sync_from_dsn {
+        let (observer, worker, dsn_sync_trigger) =
+            sync_from_dsn::create_observer_and_worker(DsnSyncParams {
+                network_service: Arc::clone(&network_service),
+                sync_service: Arc::clone(&sync_service),
+                node: node.clone(),
+                client: Arc::clone(&client),
+                segment_header_cache: segment_header_cache.clone(),
+                import_queue_service,
+                import_results,
+                sync_mode,
+                dsn_sync_config: config.dsn_sync.clone(),
+                metrics: dsn_sync_metrics,
+                telemetry: telemetry.as_ref().map(|telemetry| telemetry.handle()),
+            });
         task_manager
             .spawn_handle()
             .spawn("observer", Some("sync-from-dsn"), observer);
@@ -836,7 +998,11 @@ where
                     }
                 }),
             );
-    }
+
+        Some(dsn_sync_trigger)
+    } else {
+        None
+    };
 
     let sync_oracle = sync_service.clone();
     let best_hash = client.info().best_hash;
@@ -898,6 +1064,28 @@ where
     let block_importing_notification_stream = subspace_link.block_importing_notification_stream();
     let archived_segment_notification_stream = subspace_link.archived_segment_notification_stream();
 
+    if let Some(remote_reward_signer) = &config.remote_reward_signer {
+        let remote_reward_signer_client = create_remote_reward_signer_client(remote_reward_signer)?;
+
+        task_manager.spawn_handle().spawn(
+            "remote-reward-signer",
+            Some("block-authoring"),
+            start_remote_reward_signer(
+                remote_reward_signer_client,
+                reward_signing_notification_stream.subscribe(),
+            )
+            .in_current_span(),
+        );
+    }
+
+    if let Some(telemetry) = &telemetry {
+        task_manager.spawn_handle().spawn(
+            "consensus-telemetry",
+            None,
+            start_consensus_telemetry(client.clone(), telemetry.handle()).in_current_span(),
+        );
+    }
+
     if config.role.is_authority() || config.force_new_slot_notifications {
         let proposer_factory = ProposerFactory::new(
             task_manager.spawn_handle(),
@@ -977,6 +1165,17 @@ where
             let archived_segment_notification_stream = archived_segment_notification_stream.clone();
             let transaction_pool = transaction_pool.clone();
             let chain_spec = config.chain_spec.cloned_box();
+            let kzg = Kzg::new(embedded_kzg_settings());
+            // RPC builder may be called multiple times, so metrics are registered only once here
+            let subspace_rpc_metrics = config.prometheus_registry().and_then(|registry| {
+                match SubspaceRpcMetrics::new(registry) {
+                    Ok(subspace_rpc_metrics) => Some(subspace_rpc_metrics),
+                    Err(error) => {
+                        error!("Failed to initialize Subspace RPC metrics: {error:?}");
+                        None
+                    }
+                }
+            });
 
             Box::new(move |deny_unsafe, subscription_executor| {
                 let deps = rpc::FullDeps {
@@ -993,6 +1192,13 @@ where
                     subspace_link: subspace_link.clone(),
                     segment_headers_provider: segment_header_cache.clone(),
                     piece_provider: piece_cache.clone(),
+                    dsn_sync_trigger: dsn_sync_trigger.clone(),
+                    dsn_node: node.clone(),
+                    kzg: kzg.clone(),
+                    object_mapping_provider: object_mapping_cache.clone(),
+                    dsn_connected_peers: Arc::clone(&dsn_connected_peers),
+                    dsn_peers_expected,
+                    subspace_rpc_metrics: subspace_rpc_metrics.clone(),
                 };
 
                 rpc::create_full(deps).map_err(Into::into)
@@ -0,0 +1,144 @@
+use futures::{Stream, StreamExt};
+use parity_scale_codec::{Decode, Encode};
+use sc_client_api::backend::AuxStore;
+use sc_consensus_subspace::ArchivedSegmentNotification;
+use std::error::Error;
+use std::sync::Arc;
+use subspace_core_primitives::crypto::Blake2b256Hash;
+use subspace_core_primitives::objects::{GlobalObject, PieceObjectMapping};
+use subspace_core_primitives::SegmentIndex;
+use tracing::{debug, error, trace};
+
+/// Start an archiver that will listen for archived segments and store object mappings of their
+/// pieces
+pub(crate) async fn start_object_mappings_archiver<AS: AuxStore>(
+    object_mapping_cache: ObjectMappingCache<AS>,
+    mut archived_segment_notification_stream: impl Stream<Item = ArchivedSegmentNotification> + Unpin,
+) {
+    trace!("Subspace object mappings archiver started.");
+
+    while let Some(ArchivedSegmentNotification {
+        archived_segment, ..
+    }) = archived_segment_notification_stream.next().await
+    {
+        let segment_index = archived_segment.segment_header.segment_index();
+        let result = object_mapping_cache
+            .add_object_mappings(segment_index, &archived_segment.object_mapping);
+
+        if let Err(err) = result {
+            error!(%segment_index, ?err, "Object mappings archiving failed.");
+        } else {
+            debug!(%segment_index, "Object mappings archived.");
+        }
+    }
+}
+
+/// Cache of object mappings in aux storage, maps object hashes to their location in archived
+/// history.
+///
+/// Only contains mappings of segments archived by this node, mappings of segments that were
+/// imported from DSN are not available.
+pub struct ObjectMappingCache<AS> {
+    aux_store: Arc<AS>,
+}
+
+impl<AS> Clone for ObjectMappingCache<AS> {
+    fn clone(&self) -> Self {
+        Self {
+            aux_store: self.aux_store.clone(),
+        }
+    }
+}
+
+impl<AS> ObjectMappingCache<AS>
+where
+    AS: AuxStore,
+{
+    const KEY_PREFIX: &[u8] = b"object-mappings-cache";
+
+    /// Create new instance.
+    pub fn new(aux_store: Arc<AS>) -> Self {
+        Self { aux_store }
+    }
+
+    /// Add object mappings of all pieces of the segment to cache (likely as the result of
+    /// archiving).
+    ///
+    /// Object mapping at position `N` corresponds to the `N`-th source piece of the segment.
+    pub fn add_object_mappings(
+        &self,
+        segment_index: SegmentIndex,
+        object_mapping: &[PieceObjectMapping],
+    ) -> Result<(), Box<dyn Error>> {
+        let first_piece_index = segment_index.first_piece_index();
+        let items = object_mapping
+            .iter()
+            .zip((first_piece_index..).step_by(2))
+            .flat_map(|(piece_object_mapping, piece_index)| {
+                piece_object_mapping
+                    .objects
+                    .iter()
+                    .map(move |piece_object| {
+                        let global_object = GlobalObject::V0 {
+                            piece_index,
+                            offset: piece_object.offset(),
+                        };
+
+                        (Self::key(&piece_object.hash()), global_object.encode())
+                    })
+            })
+            .collect::<Vec<_>>();
+
+        if items.is_empty() {
+            return Ok(());
+        }
+
+        let insert_data = items
+            .iter()
+            .map(|(key, value)| (key.as_slice(), value.as_slice()))
+            .collect::<Vec<_>>();
+
+        self.aux_store.insert_aux(&insert_data, &Vec::new())?;
+
+        Ok(())
+    }
+
+    /// Get location of the object in archived history by its hash
+    pub fn get_object_mapping(
+        &self,
+        hash: &Blake2b256Hash,
+    ) -> Result<Option<GlobalObject>, Box<dyn Error>> {
+        Ok(self
+            .aux_store
+            .get_aux(&Self::key(hash))?
+            .map(|global_object| {
+                GlobalObject::decode(&mut global_object.as_slice())
+                    .expect("Always correct object mapping unless DB is corrupted; qed")
+            }))
+    }
+
+    fn key(hash: &Blake2b256Hash) -> Vec<u8> {
+        (Self::KEY_PREFIX, hash).encode()
+    }
+}
+
+/// Provides object mappings (location of the object in archived history by its hash)
+pub trait ObjectMappingProvider {
+    /// Get location of the object in archived history by its hash
+    fn get_object_mapping(
+        &self,
+        hash: &Blake2b256Hash,
+    ) -> Result<Option<GlobalObject>, Box<dyn Error>>;
+}
+
+impl<AS> ObjectMappingProvider for ObjectMappingCache<AS>
+where
+    AS: AuxStore,
+{
+    fn get_object_mapping(
+        &self,
+        hash: &Blake2b256Hash,
+    ) -> Result<Option<GlobalObject>, Box<dyn Error>> {
+        self.get_object_mapping(hash)
+    }
+}
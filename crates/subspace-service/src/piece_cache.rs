@@ -1,6 +1,7 @@
 #[cfg(test)]
 mod tests;
 
+use lru::LruCache;
 use parity_scale_codec::{Decode, Encode};
 use parking_lot::Mutex;
 use sc_client_api::backend::AuxStore;
@@ -9,6 +10,7 @@ use std::borrow::Cow;
 use std::collections::BTreeSet;
 use std::error::Error;
 use std::marker::PhantomData;
+use std::num::NonZeroUsize;
 use std::sync::Arc;
 use subspace_core_primitives::{FlatPieces, Piece, PieceIndex, PieceIndexHash};
 use subspace_networking::libp2p::kad::record::Key;
@@ -20,7 +22,8 @@ use tracing::{info, trace, warn};
 
 const LOCAL_PROVIDED_KEYS: &[u8] = b"LOCAL_PROVIDED_KEYS";
 
-/// Cache of recently produced pieces in aux storage
+/// Cache of recently produced pieces in aux storage, with the most recently archived or
+/// requested pieces optionally also kept in memory
 pub struct PieceCache<AS> {
     aux_store: Arc<AS>,
     /// Limit for number of pieces to be stored in cache
@@ -29,6 +32,8 @@ pub struct PieceCache<AS> {
     local_peer_id: PeerId,
     /// Local provided keys
     local_provided_keys: Arc<Mutex<BTreeSet<PieceIndex>>>,
+    /// Pieces kept in memory, indexed by piece index multihash, `None` if memory cache is disabled
+    memory_cache: Option<Arc<Mutex<LruCache<Vec<u8>, Piece>>>>,
 }
 
 impl<AS> Clone for PieceCache<AS> {
@@ -38,6 +43,7 @@ impl<AS> Clone for PieceCache<AS> {
             max_pieces_in_cache: self.max_pieces_in_cache,
             local_peer_id: self.local_peer_id,
             local_provided_keys: self.local_provided_keys.clone(),
+            memory_cache: self.memory_cache.clone(),
         }
     }
 }
@@ -48,9 +54,18 @@ where
 {
     const KEY_PREFIX: &[u8] = b"piece_cache";
 
-    /// Create new instance with specified size (in bytes)
-    pub fn new(aux_store: Arc<AS>, cache_size: u64, local_peer_id: PeerId) -> Self {
+    /// Create new instance with specified size (in bytes) of cache in aux storage and in memory.
+    ///
+    /// Memory cache is disabled if its size is smaller than one piece.
+    pub fn new(
+        aux_store: Arc<AS>,
+        cache_size: u64,
+        memory_cache_size: u64,
+        local_peer_id: PeerId,
+    ) -> Self {
         let max_pieces_in_cache = PieceIndex::from(cache_size / Piece::SIZE as u64);
+        let memory_cache = NonZeroUsize::new((memory_cache_size / Piece::SIZE as u64) as usize)
+            .map(|max_pieces_in_memory| Arc::new(Mutex::new(LruCache::new(max_pieces_in_memory))));
         let local_provided_keys = Self::get_local_provided_keys(aux_store.clone())
             .expect("DB loading should succeed.")
             .unwrap_or_default();
@@ -69,6 +84,7 @@ where
             max_pieces_in_cache,
             local_peer_id,
             local_provided_keys: Arc::new(Mutex::new(local_provided_keys)),
+            memory_cache,
         }
     }
 
@@ -111,6 +127,14 @@ where
         self.get_piece_by_index_multihash(&piece_index_hash.to_multihash().to_bytes())
     }
 
+    /// Check whether piece is in cache
+    pub fn contains_piece(
+        &self,
+        piece_index_hash: PieceIndexHash,
+    ) -> Result<bool, Box<dyn Error + Send + Sync + 'static>> {
+        self.contains_piece_by_index_multihash(&piece_index_hash.to_multihash().to_bytes())
+    }
+
     /// Add pieces to cache (likely as the result of archiving)
     pub fn add_pieces(
         &mut self,
@@ -155,6 +179,22 @@ where
                 .collect::<Vec<_>>(),
         )?;
 
+        if let Some(memory_cache) = &self.memory_cache {
+            let mut memory_cache = memory_cache.lock();
+
+            for piece_index in &delete_indexes {
+                memory_cache.pop(&piece_index.hash().to_multihash().to_bytes());
+            }
+
+            // Freshly archived pieces are the most likely to be requested
+            for (piece_index, piece) in insert_indexes.iter().zip(pieces.iter()) {
+                memory_cache.put(
+                    piece_index.hash().to_multihash().to_bytes(),
+                    Piece::from(piece),
+                );
+            }
+        }
+
         let local_provided_keys = {
             let mut local_provided_keys = self.local_provided_keys.lock();
 
@@ -185,6 +225,43 @@ where
     fn get_piece_by_index_multihash(
         &self,
         piece_index_multihash: &[u8],
+    ) -> Result<Option<Piece>, Box<dyn Error + Send + Sync + 'static>> {
+        let Some(memory_cache) = &self.memory_cache else {
+            return self.read_piece(piece_index_multihash);
+        };
+
+        if let Some(piece) = memory_cache.lock().get(piece_index_multihash) {
+            return Ok(Some(piece.clone()));
+        }
+
+        let maybe_piece = self.read_piece(piece_index_multihash)?;
+
+        if let Some(piece) = &maybe_piece {
+            memory_cache
+                .lock()
+                .put(piece_index_multihash.to_vec(), piece.clone());
+        }
+
+        Ok(maybe_piece)
+    }
+
+    /// Check whether piece is in cache without affecting which pieces are kept in memory
+    fn contains_piece_by_index_multihash(
+        &self,
+        piece_index_multihash: &[u8],
+    ) -> Result<bool, Box<dyn Error + Send + Sync + 'static>> {
+        if let Some(memory_cache) = &self.memory_cache {
+            if memory_cache.lock().contains(piece_index_multihash) {
+                return Ok(true);
+            }
+        }
+
+        Ok(self.read_piece(piece_index_multihash)?.is_some())
+    }
+
+    fn read_piece(
+        &self,
+        piece_index_multihash: &[u8],
     ) -> Result<Option<Piece>, Box<dyn Error + Send + Sync + 'static>> {
         Ok(self
             .aux_store
@@ -220,7 +297,10 @@ impl<AS> ProviderStorage for PieceCache<AS>
 where
     AS: AuxStore,
 {
-    type ProvidedIter<'a> = AuxStoreProviderRecordIterator<'a, AS> where Self:'a;
+    type ProvidedIter<'a>
+        = AuxStoreProviderRecordIterator<'a, AS>
+    where
+        Self: 'a;
 
     fn add_provider(
         &self,
@@ -232,10 +312,10 @@ where
     }
 
     fn providers(&self, key: &Key) -> Vec<ProviderRecord> {
-        let get_result = self.get_piece_by_index_multihash(key.as_ref());
+        let contains_result = self.contains_piece_by_index_multihash(key.as_ref());
 
-        let providers = match get_result {
-            Ok(result) => result.map(|_| {
+        let providers = match contains_result {
+            Ok(result) => result.then(|| {
                 vec![ProviderRecord {
                     key: key.clone(),
                     provider: self.local_peer_id,
@@ -311,10 +391,9 @@ impl<'a, AS: AuxStore> Iterator for AuxStoreProviderRecordIterator<'a, AS> {
 
         let result = self
             .piece_cache
-            .get_piece(piece_index_hash)
-            .ok()
-            .flatten()
-            .map(move |_| ProviderRecord {
+            .contains_piece_by_index_multihash(&piece_index_hash.to_multihash().to_bytes())
+            .unwrap_or_default()
+            .then(move || ProviderRecord {
                 key: key.clone(),
                 provider: peer_id,
                 expires: None,
@@ -47,6 +47,7 @@ fn basic() {
     let mut store = PieceCache::new(
         Arc::new(TestAuxStore::default()),
         ArchivedHistorySegment::SIZE as u64,
+        0,
         PeerId::random(),
     );
 
@@ -66,9 +67,60 @@ fn basic() {
     assert_eq!(piece_by_kad_key, piece);
 }
 
+#[test]
+fn memory_cache() {
+    let aux_store = Arc::new(TestAuxStore::default());
+    let mut store = PieceCache::new(
+        Arc::clone(&aux_store),
+        ArchivedHistorySegment::SIZE as u64,
+        ArchivedHistorySegment::SIZE as u64,
+        PeerId::random(),
+    );
+
+    store
+        .add_pieces(PieceIndex::default(), &ArchivedHistorySegment::default())
+        .unwrap();
+
+    // Remove freshly archived piece from aux storage, it is still served from memory
+    let key = PieceCache::<TestAuxStore>::key(PieceIndex::default());
+    aux_store.insert_aux(&[], &[key.as_slice()]).unwrap();
+
+    assert!(store
+        .get_piece(PieceIndex::default().hash())
+        .unwrap()
+        .is_some());
+    assert!(store
+        .contains_piece_by_index_multihash(&PieceIndex::default().hash().to_multihash().to_bytes())
+        .unwrap());
+}
+
+#[test]
+fn memory_cache_disabled() {
+    let aux_store = Arc::new(TestAuxStore::default());
+    // Memory cache smaller than one piece is disabled
+    let mut store = PieceCache::new(
+        Arc::clone(&aux_store),
+        ArchivedHistorySegment::SIZE as u64,
+        Piece::SIZE as u64 - 1,
+        PeerId::random(),
+    );
+
+    store
+        .add_pieces(PieceIndex::default(), &ArchivedHistorySegment::default())
+        .unwrap();
+
+    let key = PieceCache::<TestAuxStore>::key(PieceIndex::default());
+    aux_store.insert_aux(&[], &[key.as_slice()]).unwrap();
+
+    assert!(store
+        .get_piece(PieceIndex::default().hash())
+        .unwrap()
+        .is_none());
+}
+
 #[test]
 fn cache_nothing() {
-    let mut store = PieceCache::new(Arc::new(TestAuxStore::default()), 0, PeerId::random());
+    let mut store = PieceCache::new(Arc::new(TestAuxStore::default()), 0, 0, PeerId::random());
 
     store
         .add_pieces(PieceIndex::default(), &ArchivedHistorySegment::default())
@@ -84,6 +136,7 @@ fn auto_cleanup() {
     let mut store = PieceCache::new(
         Arc::new(TestAuxStore::default()),
         Piece::SIZE as u64,
+        0,
         PeerId::random(),
     );
 
@@ -0,0 +1,114 @@
+//! Delegation of reward signing to a remote signer, which allows keeping plot identity keys
+//! outside of the node (for instance in HSM).
+
+use futures::{Stream, StreamExt};
+use jsonrpsee::core::client::ClientT;
+use jsonrpsee::http_client::{HeaderMap, HeaderValue, HttpClient, HttpClientBuilder};
+use jsonrpsee::rpc_params;
+use parity_scale_codec::Decode;
+use sc_consensus_subspace::RewardSigningNotification;
+use sp_consensus_subspace::FarmerSignature;
+use sp_core::crypto::ByteArray;
+use sp_core::Bytes;
+use std::time::Duration;
+use tracing::{debug, error, trace, warn};
+
+/// JSON-RPC method that remote signer needs to implement.
+///
+/// Parameters are `0x`-prefixed hex-encoded hash to be signed and public key of the plot
+/// identity, result is `0x`-prefixed hex-encoded sr25519 signature.
+pub const REMOTE_SIGNER_SIGN_REWARD_HASH_METHOD: &str = "subspace_signRewardHash";
+/// Block author waits for reward signature until signature sender is dropped, so request must fit
+/// into the time budget of a slot for block to be produced in time, the same as for farmers
+/// signing over RPC
+const REMOTE_SIGNER_REQUEST_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Configuration of the remote reward signer.
+#[derive(Debug, Clone)]
+pub struct RemoteRewardSignerConfig {
+    /// HTTP(S) URL of the JSON-RPC endpoint of the remote signer.
+    pub url: String,
+    /// Value of `Authorization` header sent with every request (for instance `Bearer <token>`).
+    pub authorization: Option<String>,
+}
+
+/// Create client for remote reward signer, fails if configuration is invalid.
+pub(crate) fn create_remote_reward_signer_client(
+    config: &RemoteRewardSignerConfig,
+) -> Result<HttpClient, sc_service::Error> {
+    let mut headers = HeaderMap::new();
+    if let Some(authorization) = &config.authorization {
+        let mut authorization = HeaderValue::from_str(authorization).map_err(|error| {
+            sc_service::Error::Other(format!(
+                "Invalid remote signer authorization header value: {error}"
+            ))
+        })?;
+        authorization.set_sensitive(true);
+        headers.insert("Authorization", authorization);
+    }
+
+    Ok(HttpClientBuilder::default()
+        .request_timeout(REMOTE_SIGNER_REQUEST_TIMEOUT)
+        .set_headers(headers)
+        .build(&config.url)
+        .map_err(|error| {
+            sc_service::Error::Other(format!("Failed to create remote signer client: {error}"))
+        })?)
+}
+
+/// Start remote reward signer that will listen for reward signing notifications and sign them
+/// using remote signer.
+///
+/// Requests are processed concurrently, such that a slow response for one block doesn't delay
+/// signing of others. Signatures are verified by consensus before use, so invalid signatures from
+/// remote signer are ignored the same way as invalid signatures from farmers.
+pub(crate) async fn start_remote_reward_signer(
+    client: HttpClient,
+    reward_signing_notification_stream: impl Stream<Item = RewardSigningNotification> + Unpin,
+) {
+    trace!("Remote reward signer started.");
+
+    let client = &client;
+    reward_signing_notification_stream
+        .for_each_concurrent(None, |reward_signing_notification| {
+            sign_reward(client, reward_signing_notification)
+        })
+        .await;
+}
+
+async fn sign_reward(client: &HttpClient, reward_signing_notification: RewardSigningNotification) {
+    let RewardSigningNotification {
+        hash,
+        public_key,
+        signature_sender,
+    } = reward_signing_notification;
+
+    let result = client
+        .request::<Bytes, _>(
+            REMOTE_SIGNER_SIGN_REWARD_HASH_METHOD,
+            rpc_params![hash, Bytes(public_key.to_raw_vec())],
+        )
+        .await;
+
+    let signature = match result {
+        Ok(signature) => signature,
+        Err(error) => {
+            error!(?hash, %error, "Remote reward signing request failed");
+            return;
+        }
+    };
+
+    let signature = match FarmerSignature::decode(&mut signature.as_ref()) {
+        Ok(signature) => signature,
+        Err(error) => {
+            warn!(?hash, %error, "Remote signer returned malformed signature");
+            return;
+        }
+    };
+
+    if let Err(error) = signature_sender.unbounded_send(signature) {
+        debug!(?hash, %error, "Failed to send reward signature, signing is no longer needed");
+    } else {
+        debug!(?hash, "Reward signed by remote signer");
+    }
+}
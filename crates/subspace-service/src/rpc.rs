@@ -21,32 +21,65 @@
 
 #![warn(missing_docs)]
 
-use jsonrpsee::RpcModule;
+use crate::dsn::segment_header_piece_validator::SegmentHeaderPieceValidator;
+use crate::object_mappings::ObjectMappingProvider;
+use crate::{DsnSyncState, DsnSyncStatus, DsnSyncTrigger};
+use futures::{future, stream, FutureExt, StreamExt};
+use jsonrpsee::core::{async_trait, Error as JsonRpseeError, RpcResult};
+use jsonrpsee::proc_macros::rpc;
+use jsonrpsee::types::SubscriptionResult;
+use jsonrpsee::{RpcModule, SubscriptionSink};
 use pallet_transaction_payment_rpc::{TransactionPayment, TransactionPaymentApiServer};
+use parity_scale_codec::{Compact, Decode};
+use parking_lot::Mutex;
 use sc_client_api::BlockBackend;
 use sc_consensus_subspace::notification::SubspaceNotificationStream;
 use sc_consensus_subspace::{
     ArchivedSegmentNotification, NewSlotNotification, RewardSigningNotification, SubspaceLink,
 };
 use sc_consensus_subspace_rpc::{
-    PieceProvider, SegmentHeaderProvider, SubspaceRpc, SubspaceRpcApiServer,
+    PieceProvider, SegmentHeaderProvider, SubspaceRpc, SubspaceRpcApiServer, SubspaceRpcMetrics,
 };
 use sc_rpc::SubscriptionTaskExecutor;
 use sc_rpc_api::DenyUnsafe;
 use sc_rpc_spec_v2::chain_spec::{ChainSpec, ChainSpecApiServer};
 use sc_transaction_pool_api::TransactionPool;
+use serde::Serialize;
 use sp_api::ProvideRuntimeApi;
 use sp_block_builder::BlockBuilder;
 use sp_blockchain::{Error as BlockChainError, HeaderBackend, HeaderMetadata};
 use sp_consensus_subspace::FarmerPublicKey;
+use sp_core::H256;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
+use subspace_core_primitives::crypto::kzg::Kzg;
+use subspace_core_primitives::crypto::{blake2b_256_hash, Scalar};
+use subspace_core_primitives::{
+    ArchivedHistorySegment, BlockNumber, Piece, PieceIndex, SegmentIndex,
+};
 use subspace_networking::libp2p::Multiaddr;
+use subspace_networking::utils::piece_provider::{PieceProvider as DsnPieceProvider, RetryPolicy};
+use subspace_networking::Node;
 use subspace_runtime_primitives::opaque::Block;
 use subspace_runtime_primitives::{AccountId, Balance, Index};
 use substrate_frame_rpc_system::{System, SystemApiServer};
+use tokio::runtime::Handle;
+use tokio::sync::Semaphore;
+
+/// Max number of pieces requested from DSN by RPC concurrently
+const MAX_CONCURRENT_DSN_PIECE_REQUESTS: usize = 10;
+/// How many times to retry piece request to DSN before giving up
+const DSN_PIECE_REQUEST_RETRIES: u16 = 3;
+/// Max size of object that can be fetched, limits how many pieces are retrieved for one object
+pub const MAX_OBJECT_SIZE: usize = 5 * 1024 * 1024;
+/// How often node status is checked for changes to be sent to subscribers
+const NODE_STATUS_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+/// Active sync from DSN that made no progress for this long is considered stuck
+const DSN_SYNC_STUCK_TIMEOUT: Duration = Duration::from_secs(10 * 60);
 
 /// Full client dependencies.
-pub struct FullDeps<C, P, RBP, PP> {
+pub struct FullDeps<C, P, RBP, PP, OMP> {
     /// The client instance to use.
     pub client: Arc<C>,
     /// Transaction pool instance.
@@ -73,11 +106,607 @@ pub struct FullDeps<C, P, RBP, PP> {
     pub segment_headers_provider: RBP,
     /// Provides pieces from piece cache.
     pub piece_provider: Option<PP>,
+    /// Trigger for sync from DSN, `None` if sync from DSN is disabled.
+    pub dsn_sync_trigger: Option<DsnSyncTrigger>,
+    /// DSN node used to retrieve pieces that are not in piece cache.
+    pub dsn_node: Node,
+    /// Kzg instance used to verify pieces retrieved from DSN.
+    pub kzg: Kzg,
+    /// Provides locations of objects in archived history, `None` if object mappings are not
+    /// archived by this node.
+    pub object_mapping_provider: Option<OMP>,
+    /// Number of established connections with DSN peers.
+    pub dsn_connected_peers: Arc<AtomicUsize>,
+    /// Whether node is expected to have DSN peers, `false` if DSN has no bootstrap nodes to
+    /// connect to or runs in development mode.
+    pub dsn_peers_expected: bool,
+    /// Metrics of Subspace RPC, `None` if Prometheus is disabled.
+    pub subspace_rpc_metrics: Option<SubspaceRpcMetrics>,
+}
+
+/// Provides RPC methods for controlling and inspecting sync from DSN.
+#[rpc(server)]
+pub trait DsnSyncApi {
+    /// Start sync from DSN without waiting for node to detect that it is necessary.
+    ///
+    /// With `force_import` blocks are imported and fully verified even if they are already known
+    /// to the node.
+    #[method(name = "subspace_syncFromDsn")]
+    fn sync_from_dsn(&self, force_import: Option<bool>) -> RpcResult<()>;
+
+    /// Abort sync from DSN that is in progress, returns `false` if there was nothing to abort.
+    #[method(name = "subspace_abortDsnSync")]
+    fn abort_dsn_sync(&self) -> RpcResult<bool>;
+
+    /// Current state of sync from DSN.
+    #[method(name = "subspace_dsnSyncState")]
+    fn dsn_sync_state(&self) -> RpcResult<DsnSyncState>;
+}
+
+/// Implements the [`DsnSyncApiServer`] RPC trait.
+pub struct DsnSync {
+    dsn_sync_trigger: Option<DsnSyncTrigger>,
+    deny_unsafe: DenyUnsafe,
+}
+
+impl DsnSync {
+    /// Create new instance.
+    pub fn new(dsn_sync_trigger: Option<DsnSyncTrigger>, deny_unsafe: DenyUnsafe) -> Self {
+        Self {
+            dsn_sync_trigger,
+            deny_unsafe,
+        }
+    }
+}
+
+impl DsnSyncApiServer for DsnSync {
+    fn sync_from_dsn(&self, force_import: Option<bool>) -> RpcResult<()> {
+        self.deny_unsafe.check_if_safe()?;
+
+        let dsn_sync_trigger = self.dsn_sync_trigger.as_ref().ok_or_else(|| {
+            JsonRpseeError::Custom("Sync from DSN is disabled on this node".to_string())
+        })?;
+
+        if !dsn_sync_trigger.trigger(force_import.unwrap_or_default()) {
+            return Err(JsonRpseeError::Custom(
+                "Sync from DSN is not running anymore".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn abort_dsn_sync(&self) -> RpcResult<bool> {
+        self.deny_unsafe.check_if_safe()?;
+
+        let dsn_sync_trigger = self.dsn_sync_trigger.as_ref().ok_or_else(|| {
+            JsonRpseeError::Custom("Sync from DSN is disabled on this node".to_string())
+        })?;
+
+        Ok(dsn_sync_trigger.abort())
+    }
+
+    fn dsn_sync_state(&self) -> RpcResult<DsnSyncState> {
+        let dsn_sync_trigger = self.dsn_sync_trigger.as_ref().ok_or_else(|| {
+            JsonRpseeError::Custom("Sync from DSN is disabled on this node".to_string())
+        })?;
+
+        Ok(dsn_sync_trigger.state())
+    }
+}
+
+/// Unified status of the node.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeStatus {
+    /// Last segment archived by the node, `None` if no segments were archived yet.
+    pub last_archived_segment_index: Option<SegmentIndex>,
+    /// State of sync from DSN, `None` if sync from DSN is disabled.
+    pub dsn_sync: Option<DsnSyncState>,
+    /// Number of established connections with DSN peers.
+    pub dsn_connected_peers: usize,
+}
+
+/// Provides RPC methods for observing node status.
+#[rpc(server)]
+pub trait NodeStatusApi {
+    /// Node status subscription, current status is sent right away and then every time it
+    /// changes.
+    #[subscription(
+        name = "subspace_subscribeNodeStatus" => "subspace_node_status",
+        unsubscribe = "subspace_unsubscribeNodeStatus",
+        item = NodeStatus,
+    )]
+    fn subscribe_node_status(&self);
+}
+
+/// Implements the [`NodeStatusApiServer`] RPC trait.
+pub struct NodeStatusRpc<RBP> {
+    executor: SubscriptionTaskExecutor,
+    segment_headers_provider: RBP,
+    dsn_sync_trigger: Option<DsnSyncTrigger>,
+    dsn_connected_peers: Arc<AtomicUsize>,
+}
+
+impl<RBP> NodeStatusRpc<RBP> {
+    /// Create new instance.
+    pub fn new(
+        executor: SubscriptionTaskExecutor,
+        segment_headers_provider: RBP,
+        dsn_sync_trigger: Option<DsnSyncTrigger>,
+        dsn_connected_peers: Arc<AtomicUsize>,
+    ) -> Self {
+        Self {
+            executor,
+            segment_headers_provider,
+            dsn_sync_trigger,
+            dsn_connected_peers,
+        }
+    }
+}
+
+impl<RBP> NodeStatusApiServer for NodeStatusRpc<RBP>
+where
+    RBP: SegmentHeaderProvider + Clone + Send + Sync + 'static,
+{
+    fn subscribe_node_status(&self, mut sink: SubscriptionSink) -> SubscriptionResult {
+        let segment_headers_provider = self.segment_headers_provider.clone();
+        let dsn_sync_trigger = self.dsn_sync_trigger.clone();
+        let dsn_connected_peers = Arc::clone(&self.dsn_connected_peers);
+        let mut last_status = None::<NodeStatus>;
+
+        // Status is collected from multiple sources, some of which don't provide notifications,
+        // so it is checked periodically and only sent when it has actually changed
+        let stream = stream::unfold(Duration::ZERO, |delay| async move {
+            tokio::time::sleep(delay).await;
+            Some(((), NODE_STATUS_CHECK_INTERVAL))
+        })
+        .filter_map(move |()| {
+            let max_segment_index = segment_headers_provider.max_segment_index();
+            let last_archived_segment_index = matches!(
+                segment_headers_provider.get_segment_header(max_segment_index),
+                Ok(Some(_))
+            )
+            .then_some(max_segment_index);
+
+            let status = NodeStatus {
+                last_archived_segment_index,
+                dsn_sync: dsn_sync_trigger
+                    .as_ref()
+                    .map(|dsn_sync_trigger| dsn_sync_trigger.state()),
+                dsn_connected_peers: dsn_connected_peers.load(Ordering::Relaxed),
+            };
+
+            let maybe_status = if last_status.as_ref() == Some(&status) {
+                None
+            } else {
+                last_status.replace(status.clone());
+                Some(status)
+            };
+
+            future::ready(maybe_status)
+        });
+
+        let fut = async move {
+            sink.pipe_from_stream(stream).await;
+        };
+
+        self.executor.spawn(
+            "subspace-node-status-subscription",
+            Some("rpc"),
+            fut.boxed(),
+        );
+
+        Ok(())
+    }
+}
+
+/// Subspace-specific health of the node, complements `system_health`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeHealth {
+    /// Whether node is connected to DSN (if DSN peers are expected) and sync from DSN (if active)
+    /// is making progress.
+    pub is_healthy: bool,
+    /// Number of established connections with DSN peers.
+    pub dsn_connected_peers: usize,
+    /// Seconds since the last segment was archived by the node, `None` if no segments were
+    /// archived since node start.
+    pub last_archived_segment_secs_ago: Option<u64>,
+    /// Whether sync from DSN is active, but made no progress for a long time.
+    pub dsn_sync_stuck: bool,
+    /// Number of farmers subscribed to slot info over RPC.
+    pub farmer_subscriptions: usize,
+}
+
+/// Provides RPC methods for checking node health.
+#[rpc(server)]
+pub trait NodeHealthApi {
+    /// Subspace-specific node health, suitable for load balancer health checks.
+    #[method(name = "subspace_health")]
+    fn health(&self) -> RpcResult<NodeHealth>;
+}
+
+/// Progress of sync from DSN, changes whenever sync from DSN makes progress.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct DsnSyncProgress {
+    best_block_number: BlockNumber,
+    current_segment_index: Option<SegmentIndex>,
+    blocks_imported: u64,
+}
+
+/// Implements the [`NodeHealthApiServer`] RPC trait.
+pub struct NodeHealthRpc {
+    dsn_connected_peers: Arc<AtomicUsize>,
+    dsn_peers_expected: bool,
+    farmer_subscriptions: Arc<AtomicUsize>,
+    last_archived_segment_at: Arc<Mutex<Option<Instant>>>,
+    /// When active sync from DSN made progress last time, `None` if sync from DSN is not active
+    dsn_sync_progress_at: Arc<Mutex<Option<Instant>>>,
+}
+
+impl NodeHealthRpc {
+    /// Create new instance.
+    ///
+    /// With `dsn_peers_expected` set to `false` absence of DSN peers doesn't make node unhealthy.
+    pub fn new<C>(
+        executor: &SubscriptionTaskExecutor,
+        client: Arc<C>,
+        archived_segment_notification_stream: SubspaceNotificationStream<
+            ArchivedSegmentNotification,
+        >,
+        dsn_sync_trigger: Option<DsnSyncTrigger>,
+        dsn_connected_peers: Arc<AtomicUsize>,
+        dsn_peers_expected: bool,
+        farmer_subscriptions: Arc<AtomicUsize>,
+    ) -> Self
+    where
+        C: HeaderBackend<Block> + Send + Sync + 'static,
+    {
+        let last_archived_segment_at = Arc::<Mutex<Option<Instant>>>::default();
+        let dsn_sync_progress_at = Arc::<Mutex<Option<Instant>>>::default();
+
+        let fut = {
+            let last_archived_segment_at = Arc::clone(&last_archived_segment_at);
+            let mut archived_segment_notifications =
+                archived_segment_notification_stream.subscribe();
+
+            async move {
+                // Notification is dropped right away, which acknowledges it to the archiver
+                while archived_segment_notifications.next().await.is_some() {
+                    last_archived_segment_at.lock().replace(Instant::now());
+                }
+            }
+        };
+
+        executor.spawn(
+            "subspace-node-health-archived-segments",
+            Some("rpc"),
+            fut.boxed(),
+        );
+
+        if let Some(dsn_sync_trigger) = dsn_sync_trigger {
+            let dsn_sync_progress_at = Arc::clone(&dsn_sync_progress_at);
+
+            // Progress is tracked independently of RPC calls, such that stuck sync from DSN is
+            // detected regardless of how often health is checked
+            let fut = async move {
+                let mut last_dsn_sync_progress = None::<DsnSyncProgress>;
+
+                loop {
+                    let dsn_sync_state = dsn_sync_trigger.state();
+
+                    if dsn_sync_state.status == DsnSyncStatus::Active {
+                        let dsn_sync_progress = DsnSyncProgress {
+                            best_block_number: client.info().best_number,
+                            current_segment_index: dsn_sync_state.current_segment_index,
+                            blocks_imported: dsn_sync_state.blocks_imported,
+                        };
+
+                        if last_dsn_sync_progress != Some(dsn_sync_progress) {
+                            last_dsn_sync_progress.replace(dsn_sync_progress);
+                            dsn_sync_progress_at.lock().replace(Instant::now());
+                        }
+                    } else {
+                        last_dsn_sync_progress.take();
+                        dsn_sync_progress_at.lock().take();
+                    }
+
+                    tokio::time::sleep(NODE_STATUS_CHECK_INTERVAL).await;
+                }
+            };
+
+            executor.spawn(
+                "subspace-node-health-dsn-sync-progress",
+                Some("rpc"),
+                fut.boxed(),
+            );
+        }
+
+        Self {
+            dsn_connected_peers,
+            dsn_peers_expected,
+            farmer_subscriptions,
+            last_archived_segment_at,
+            dsn_sync_progress_at,
+        }
+    }
+}
+
+impl NodeHealthApiServer for NodeHealthRpc {
+    fn health(&self) -> RpcResult<NodeHealth> {
+        let dsn_connected_peers = self.dsn_connected_peers.load(Ordering::Relaxed);
+        let dsn_sync_stuck = self
+            .dsn_sync_progress_at
+            .lock()
+            .map(|progress_at| progress_at.elapsed() >= DSN_SYNC_STUCK_TIMEOUT)
+            .unwrap_or_default();
+
+        Ok(NodeHealth {
+            is_healthy: (dsn_connected_peers > 0 || !self.dsn_peers_expected) && !dsn_sync_stuck,
+            dsn_connected_peers,
+            last_archived_segment_secs_ago: self
+                .last_archived_segment_at
+                .lock()
+                .map(|archived_at| archived_at.elapsed().as_secs()),
+            dsn_sync_stuck,
+            farmer_subscriptions: self.farmer_subscriptions.load(Ordering::Relaxed),
+        })
+    }
+}
+
+/// Piece provider that serves pieces from node's piece cache and, if allowed, retrieves pieces that
+/// are not in the cache from DSN.
+pub struct DsnFallbackPieceProvider<PP, RBP> {
+    piece_cache: Option<PP>,
+    /// `None` if retrieval of pieces from DSN is not allowed
+    dsn_piece_provider: Option<Arc<DsnPieceProvider<SegmentHeaderPieceValidator<RBP>>>>,
+    dsn_requests_semaphore: Arc<Semaphore>,
+}
+
+impl<PP, RBP> Clone for DsnFallbackPieceProvider<PP, RBP>
+where
+    PP: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            piece_cache: self.piece_cache.clone(),
+            dsn_piece_provider: self.dsn_piece_provider.clone(),
+            dsn_requests_semaphore: Arc::clone(&self.dsn_requests_semaphore),
+        }
+    }
+}
+
+impl<PP, RBP> DsnFallbackPieceProvider<PP, RBP>
+where
+    PP: PieceProvider,
+    RBP: SegmentHeaderProvider + Send + Sync,
+{
+    /// Create new instance, pieces are only retrieved from DSN if unsafe RPC calls are allowed.
+    pub fn new(
+        piece_cache: Option<PP>,
+        dsn_node: Node,
+        kzg: Kzg,
+        segment_headers_provider: RBP,
+        deny_unsafe: DenyUnsafe,
+    ) -> Self {
+        let dsn_piece_provider = deny_unsafe.check_if_safe().is_ok().then(|| {
+            Arc::new(DsnPieceProvider::new(
+                dsn_node.clone(),
+                Some(SegmentHeaderPieceValidator::new(
+                    dsn_node,
+                    kzg,
+                    segment_headers_provider,
+                )),
+            ))
+        });
+
+        Self {
+            piece_cache,
+            dsn_piece_provider,
+            dsn_requests_semaphore: Arc::new(Semaphore::new(MAX_CONCURRENT_DSN_PIECE_REQUESTS)),
+        }
+    }
+
+    /// Get piece from piece cache or DSN, returns `None` if piece was not found.
+    pub async fn retrieve_piece(
+        &self,
+        piece_index: PieceIndex,
+    ) -> Result<Option<Piece>, Box<dyn std::error::Error + Send + Sync + 'static>> {
+        if let Some(piece) = self.get_cached_piece(piece_index)? {
+            return Ok(Some(piece));
+        }
+
+        let Some(dsn_piece_provider) = &self.dsn_piece_provider else {
+            return Ok(None);
+        };
+
+        let _permit = self
+            .dsn_requests_semaphore
+            .acquire()
+            .await
+            .expect("Semaphore is never closed; qed");
+
+        dsn_piece_provider
+            .get_piece(piece_index, RetryPolicy::Limited(DSN_PIECE_REQUEST_RETRIES))
+            .await
+    }
+
+    fn get_cached_piece(
+        &self,
+        piece_index: PieceIndex,
+    ) -> Result<Option<Piece>, Box<dyn std::error::Error + Send + Sync + 'static>> {
+        match &self.piece_cache {
+            Some(piece_cache) => piece_cache.get_piece_by_index(piece_index),
+            None => Ok(None),
+        }
+    }
+}
+
+impl<PP, RBP> PieceProvider for DsnFallbackPieceProvider<PP, RBP>
+where
+    PP: PieceProvider,
+    RBP: SegmentHeaderProvider + Send + Sync,
+{
+    fn get_piece_by_index(
+        &self,
+        piece_index: PieceIndex,
+    ) -> Result<Option<Piece>, Box<dyn std::error::Error + Send + Sync + 'static>> {
+        if let Some(piece) = self.get_cached_piece(piece_index)? {
+            return Ok(Some(piece));
+        }
+
+        if self.dsn_piece_provider.is_none() {
+            return Ok(None);
+        }
+
+        // Only called from blocking RPC methods (`subspace_piece`) that run on a dedicated thread,
+        // so waiting for DSN here doesn't stall async runtime
+        Handle::current().block_on(self.retrieve_piece(piece_index))
+    }
+}
+
+/// Provides RPC methods for retrieving archived history.
+#[rpc(server)]
+pub trait PiecesApi {
+    /// Fetch object by its hash, pieces containing the object are retrieved from node's piece
+    /// cache or DSN, returns `None` if object mapping is not known to this node.
+    ///
+    /// Objects larger than [`MAX_OBJECT_SIZE`] are rejected.
+    ///
+    /// Object mappings are only known for segments archived by this node.
+    #[method(name = "subspace_fetchObject")]
+    async fn fetch_object(&self, hash: H256) -> RpcResult<Option<Vec<u8>>>;
+}
+
+/// Implements the [`PiecesApiServer`] RPC trait.
+pub struct Pieces<PP, RBP, OMP> {
+    piece_provider: DsnFallbackPieceProvider<PP, RBP>,
+    object_mapping_provider: Option<OMP>,
+    deny_unsafe: DenyUnsafe,
+}
+
+impl<PP, RBP, OMP> Pieces<PP, RBP, OMP>
+where
+    PP: PieceProvider,
+    RBP: SegmentHeaderProvider + Send + Sync,
+{
+    /// Create new instance.
+    pub fn new(
+        piece_provider: DsnFallbackPieceProvider<PP, RBP>,
+        object_mapping_provider: Option<OMP>,
+        deny_unsafe: DenyUnsafe,
+    ) -> Self {
+        Self {
+            piece_provider,
+            object_mapping_provider,
+            deny_unsafe,
+        }
+    }
+
+    async fn retrieve_piece(&self, piece_index: PieceIndex) -> RpcResult<Option<Piece>> {
+        self.piece_provider
+            .retrieve_piece(piece_index)
+            .await
+            .map_err(|error| JsonRpseeError::Custom(format!("Failed to retrieve piece: {error}")))
+    }
+
+    /// Assemble object that starts at `offset` within raw record of source piece `piece_index`.
+    ///
+    /// Object is stored as SCALE-encoded `Vec<u8>` that may span raw records of multiple
+    /// consecutive source pieces of the same segment.
+    async fn assemble_object(&self, piece_index: PieceIndex, offset: u32) -> RpcResult<Vec<u8>> {
+        let offset = offset as usize;
+        let mut piece_index = piece_index;
+        let mut raw_data = Vec::new();
+
+        loop {
+            let piece = self.retrieve_piece(piece_index).await?.ok_or_else(|| {
+                JsonRpseeError::Custom(format!("Piece {piece_index} containing object not found"))
+            })?;
+
+            raw_data.extend(
+                piece
+                    .record()
+                    .iter()
+                    .flat_map(|chunk| &chunk[..Scalar::SAFE_BYTES]),
+            );
+
+            if let Some(mut object_bytes) = raw_data.get(offset..) {
+                // Decoding of length prefix only succeeds once all of its bytes were collected
+                if let Ok(Compact(object_size)) = Compact::<u32>::decode(&mut object_bytes) {
+                    let object_size = object_size as usize;
+                    if object_size > MAX_OBJECT_SIZE {
+                        return Err(JsonRpseeError::Custom(format!(
+                            "Object size {object_size} bytes exceeds limit of {MAX_OBJECT_SIZE} \
+                            bytes"
+                        )));
+                    }
+
+                    if let Some(object) = object_bytes.get(..object_size) {
+                        return Ok(object.to_vec());
+                    }
+                }
+            }
+
+            // Source pieces are located at even positions within a segment
+            let next_position = piece_index.position() as usize + 2;
+            if next_position >= ArchivedHistorySegment::NUM_PIECES {
+                return Err(JsonRpseeError::Custom(
+                    "Objects crossing segment boundary are not supported yet".to_string(),
+                ));
+            }
+            piece_index += PieceIndex::from(2);
+        }
+    }
+}
+
+#[async_trait]
+impl<PP, RBP, OMP> PiecesApiServer for Pieces<PP, RBP, OMP>
+where
+    PP: PieceProvider + Send + Sync + 'static,
+    RBP: SegmentHeaderProvider + Send + Sync + 'static,
+    OMP: ObjectMappingProvider + Send + Sync + 'static,
+{
+    async fn fetch_object(&self, hash: H256) -> RpcResult<Option<Vec<u8>>> {
+        self.deny_unsafe.check_if_safe()?;
+
+        let object_mapping_provider = self.object_mapping_provider.as_ref().ok_or_else(|| {
+            JsonRpseeError::Custom(
+                "Object mappings are not archived by this node, see `--archive-object-mappings`"
+                    .to_string(),
+            )
+        })?;
+
+        let hash = hash.to_fixed_bytes();
+        let maybe_global_object =
+            object_mapping_provider
+                .get_object_mapping(&hash)
+                .map_err(|error| {
+                    JsonRpseeError::Custom(format!("Failed to get object mapping: {error}"))
+                })?;
+
+        let Some(global_object) = maybe_global_object else {
+            return Ok(None);
+        };
+
+        let object = self
+            .assemble_object(global_object.piece_index(), global_object.offset())
+            .await?;
+
+        if blake2b_256_hash(&object) != hash {
+            return Err(JsonRpseeError::Custom(
+                "Retrieved object doesn't match requested hash".to_string(),
+            ));
+        }
+
+        Ok(Some(object))
+    }
 }
 
 /// Instantiate all full RPC extensions.
-pub fn create_full<C, P, RPB, PP>(
-    deps: FullDeps<C, P, RPB, PP>,
+pub fn create_full<C, P, RPB, PP, OMP>(
+    deps: FullDeps<C, P, RPB, PP, OMP>,
 ) -> Result<RpcModule<()>, Box<dyn std::error::Error + Send + Sync>>
 where
     C: ProvideRuntimeApi<Block>
@@ -92,8 +721,9 @@ where
         + BlockBuilder<Block>
         + sp_consensus_subspace::SubspaceApi<Block, FarmerPublicKey>,
     P: TransactionPool + 'static,
-    RPB: SegmentHeaderProvider + Send + Sync + 'static,
-    PP: PieceProvider + Send + Sync + 'static,
+    RPB: SegmentHeaderProvider + Clone + Send + Sync + 'static,
+    PP: PieceProvider + Clone + Send + Sync + 'static,
+    OMP: ObjectMappingProvider + Send + Sync + 'static,
 {
     let mut module = RpcModule::new(());
     let FullDeps {
@@ -109,6 +739,13 @@ where
         subspace_link,
         segment_headers_provider,
         piece_provider,
+        dsn_sync_trigger,
+        dsn_node,
+        kzg,
+        object_mapping_provider,
+        dsn_connected_peers,
+        dsn_peers_expected,
+        subspace_rpc_metrics,
     } = deps;
 
     let chain_name = chain_spec.name().to_string();
@@ -118,21 +755,54 @@ where
 
     module.merge(System::new(client.clone(), pool, deny_unsafe).into_rpc())?;
     module.merge(TransactionPayment::new(client.clone()).into_rpc())?;
+    module.merge(DsnSync::new(dsn_sync_trigger.clone(), deny_unsafe).into_rpc())?;
+    module.merge(
+        NodeStatusRpc::new(
+            subscription_executor.clone(),
+            segment_headers_provider.clone(),
+            dsn_sync_trigger.clone(),
+            Arc::clone(&dsn_connected_peers),
+        )
+        .into_rpc(),
+    )?;
+    let piece_provider = DsnFallbackPieceProvider::new(
+        piece_provider,
+        dsn_node,
+        kzg,
+        segment_headers_provider.clone(),
+        deny_unsafe,
+    );
+    module.merge(
+        Pieces::new(piece_provider.clone(), object_mapping_provider, deny_unsafe).into_rpc(),
+    )?;
 
+    let subspace_rpc = SubspaceRpc::new(
+        client.clone(),
+        subscription_executor.clone(),
+        new_slot_notification_stream,
+        reward_signing_notification_stream,
+        archived_segment_notification_stream.clone(),
+        dsn_bootstrap_nodes,
+        subspace_link,
+        segment_headers_provider,
+        Some(piece_provider),
+        Arc::clone(&dsn_connected_peers),
+        subspace_rpc_metrics,
+        deny_unsafe,
+    );
     module.merge(
-        SubspaceRpc::new(
+        NodeHealthRpc::new(
+            &subscription_executor,
             client,
-            subscription_executor,
-            new_slot_notification_stream,
-            reward_signing_notification_stream,
             archived_segment_notification_stream,
-            dsn_bootstrap_nodes,
-            subspace_link,
-            segment_headers_provider,
-            piece_provider,
+            dsn_sync_trigger,
+            dsn_connected_peers,
+            dsn_peers_expected,
+            subspace_rpc.slot_info_subscriptions(),
         )
         .into_rpc(),
     )?;
+    module.merge(subspace_rpc.into_rpc())?;
 
     Ok(module)
 }
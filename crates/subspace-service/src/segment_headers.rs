@@ -1,9 +1,11 @@
 use futures::{Stream, StreamExt};
 use parity_scale_codec::{Decode, Encode};
+use parking_lot::Mutex;
 use sc_client_api::backend::AuxStore;
 use sc_consensus_subspace::ArchivedSegmentNotification;
 use sc_consensus_subspace_rpc::SegmentHeaderProvider;
 use std::error::Error;
+use std::num::NonZeroU64;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use subspace_core_primitives::{SegmentHeader, SegmentIndex};
@@ -11,7 +13,7 @@ use tracing::{debug, error, trace};
 
 /// Start an archiver that will listen for archived segments and send segment header to the storage
 pub(crate) async fn start_segment_header_archiver<AS: AuxStore>(
-    mut segment_header_cache: SegmentHeaderCache<AS>,
+    segment_header_cache: SegmentHeaderCache<AS>,
     mut archived_segment_notification_stream: impl Stream<Item = ArchivedSegmentNotification> + Unpin,
 ) {
     trace!("Subspace segment header archiver started.");
@@ -31,12 +33,17 @@ pub(crate) async fn start_segment_header_archiver<AS: AuxStore>(
     }
 }
 
-/// Cache of recently produced segment headers in aux storage
+/// Cache of segment headers produced or received by the node in aux storage.
+///
+/// With retention set only that many most recent segment headers are kept, older ones are pruned
+/// as new segment headers are added.
 pub struct SegmentHeaderCache<AS> {
     aux_store: Arc<AS>,
     // TODO: Consider introducing and using global in-memory segment header cache (this comment is
     //  in multiple files)
     max_segment_index: Arc<AtomicU64>,
+    retention: Option<NonZeroU64>,
+    write_lock: Arc<Mutex<()>>,
 }
 
 impl<AS> Clone for SegmentHeaderCache<AS> {
@@ -44,6 +51,8 @@ impl<AS> Clone for SegmentHeaderCache<AS> {
         Self {
             aux_store: self.aux_store.clone(),
             max_segment_index: self.max_segment_index.clone(),
+            retention: self.retention,
+            write_lock: self.write_lock.clone(),
         }
     }
 }
@@ -53,15 +62,33 @@ where
     AS: AuxStore,
 {
     const KEY_PREFIX: &[u8] = b"segment-headers-cache";
+    const MAX_SEGMENT_INDEX_KEY: &[u8] = b"max-segment-index";
 
-    /// Create new instance.
-    pub fn new(aux_store: Arc<AS>) -> Result<Self, Box<dyn Error>> {
+    /// Create new instance, `retention` is the number of most recent segment headers to keep,
+    /// `None` keeps all of them.
+    pub fn new(aux_store: Arc<AS>, retention: Option<NonZeroU64>) -> Result<Self, Box<dyn Error>> {
         let instance = Self {
             aux_store,
             max_segment_index: Default::default(),
+            retention,
+            write_lock: Default::default(),
         };
 
-        // Crude way to find last segment index without checking every index
+        if let Some(max_segment_index) = instance
+            .aux_store
+            .get_aux(&Self::key_from_bytes(Self::MAX_SEGMENT_INDEX_KEY))?
+        {
+            let max_segment_index = u64::decode(&mut max_segment_index.as_slice())
+                .expect("Always correct segment index unless DB is corrupted; qed");
+            instance
+                .max_segment_index
+                .store(max_segment_index, Ordering::Release);
+
+            return Ok(instance);
+        }
+
+        // Databases created before last segment index was persisted explicitly are not pruned, so
+        // crude way to find last segment index without checking every index works for them
         // TODO: Binary search would be more efficient in the inner loop, but I'm lazy right now
         'outer: for segment_index in (SegmentIndex::ZERO..).step_by(100) {
             if instance.get_segment_header(segment_index)?.is_none() {
@@ -87,23 +114,74 @@ where
     }
 
     /// Add segment header to cache (likely as the result of archiving)
-    pub fn add_segment_header(
-        &mut self,
-        segment_header: SegmentHeader,
+    pub fn add_segment_header(&self, segment_header: SegmentHeader) -> Result<(), Box<dyn Error>> {
+        self.add_segment_headers(&[segment_header])
+    }
+
+    /// Add segment headers to cache (likely received from DSN), segment headers that are outside
+    /// of retention window are ignored.
+    pub fn add_segment_headers(
+        &self,
+        segment_headers: &[SegmentHeader],
     ) -> Result<(), Box<dyn Error>> {
-        let key = Self::key(segment_header.segment_index());
-        let value = segment_header.encode();
-        let insert_data = vec![(key.as_slice(), value.as_slice())];
+        let Some(last_segment_index) = segment_headers
+            .iter()
+            .map(|segment_header| u64::from(segment_header.segment_index()))
+            .max()
+        else {
+            return Ok(());
+        };
 
-        self.aux_store.insert_aux(&insert_data, &Vec::new())?;
+        let _guard = self.write_lock.lock();
+
+        let old_max_segment_index = self.max_segment_index.load(Ordering::Acquire);
+        let max_segment_index = old_max_segment_index.max(last_segment_index);
+        let retention_start = |max_segment_index: u64| {
+            self.retention.map_or(0, |retention| {
+                max_segment_index.saturating_sub(retention.get() - 1)
+            })
+        };
+        let old_first_retained_segment_index = retention_start(old_max_segment_index);
+        let first_retained_segment_index = retention_start(max_segment_index);
+
+        let mut insert_items = segment_headers
+            .iter()
+            .filter(|segment_header| {
+                u64::from(segment_header.segment_index()) >= first_retained_segment_index
+            })
+            .map(|segment_header| {
+                (
+                    Self::key(segment_header.segment_index()),
+                    segment_header.encode(),
+                )
+            })
+            .collect::<Vec<_>>();
+        insert_items.push((
+            Self::key_from_bytes(Self::MAX_SEGMENT_INDEX_KEY),
+            max_segment_index.encode(),
+        ));
+        // Only segment headers up to previous max segment index could have been stored before
+        let delete_keys = (old_first_retained_segment_index
+            ..first_retained_segment_index.min(old_max_segment_index + 1))
+            .map(|segment_index| Self::key(SegmentIndex::from(segment_index)))
+            .collect::<Vec<_>>();
+
+        let insert_data = insert_items
+            .iter()
+            .map(|(key, value)| (key.as_slice(), value.as_slice()))
+            .collect::<Vec<_>>();
+        let delete_data = delete_keys.iter().map(Vec::as_slice).collect::<Vec<_>>();
+
+        self.aux_store.insert_aux(&insert_data, &delete_data)?;
         self.max_segment_index
-            .store(u64::from(segment_header.segment_index()), Ordering::Release);
+            .store(max_segment_index, Ordering::Release);
 
         Ok(())
     }
 
-    /// Get segment header from storage
-    fn get_segment_header(
+    /// Get segment header from storage, returns `None` for unknown segment headers and those that
+    /// were pruned already.
+    pub fn get_segment_header(
         &self,
         segment_index: SegmentIndex,
     ) -> Result<Option<SegmentHeader>, Box<dyn Error>> {
@@ -136,4 +214,8 @@ where
     ) -> Result<Option<SegmentHeader>, Box<dyn Error>> {
         self.get_segment_header(segment_index)
     }
+
+    fn max_segment_index(&self) -> SegmentIndex {
+        self.max_segment_index()
+    }
 }
@@ -1,123 +1,503 @@
-use crate::dsn::import_blocks::import_blocks_from_dsn;
+mod import_results;
+#[cfg(test)]
+mod tests;
+
+pub(super) use crate::sync_from_dsn::import_results::{ImportResults, ObservedImportQueue};
+
+use crate::dsn::import_blocks::{
+    import_blocks_from_dsn, DsnSyncMetrics, ImportBlocksOptions, QUEUED_BLOCKS_LIMIT,
+    RECONSTRUCTION_THREADS, SEGMENT_CACHE_SIZE,
+};
+use crate::segment_headers::SegmentHeaderCache;
 use atomic::Atomic;
+use backoff::backoff::Backoff;
+use backoff::ExponentialBackoff;
 use futures::channel::mpsc;
-use futures::{FutureExt, StreamExt};
-use sc_client_api::{BlockBackend, BlockchainEvents};
+use futures::{future, FutureExt, Stream, StreamExt};
+use parking_lot::Mutex;
+use sc_client_api::{AuxStore, BlockBackend, BlockchainEvents};
 use sc_consensus::import_queue::ImportQueueService;
 use sc_network::config::SyncMode;
 use sc_network::{NetworkPeers, NetworkService};
-use sp_api::BlockT;
+use sc_network_sync::SyncingService;
+use sc_telemetry::{telemetry, TelemetryHandle, SUBSTRATE_INFO};
+use serde::{Deserialize, Serialize};
+use sp_api::{BlockT, ProvideRuntimeApi};
 use sp_blockchain::HeaderBackend;
-use sp_consensus::BlockOrigin;
+use sp_consensus::{BlockOrigin, SyncOracle};
+use sp_consensus_subspace::{FarmerPublicKey, SubspaceApi};
+use sp_runtime::traits::{NumberFor, One, SaturatedConversion, Saturating};
 use std::future::Future;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::num::{NonZeroU32, NonZeroU64, NonZeroUsize};
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+use subspace_core_primitives::{BlockNumber, SegmentIndex};
+use subspace_networking::libp2p::PeerId;
 use subspace_networking::Node;
-use tracing::{info, trace, warn};
+use tokio::sync::Notify;
+use tracing::{debug, info, warn};
 
 /// How much time to wait for new block to be imported before timing out and starting sync from DSN.
 const NO_IMPORTED_BLOCKS_TIMEOUT: Duration = Duration::from_secs(10 * 60);
 /// Frequency with which to check whether node is online or not
 const CHECK_ONLINE_STATUS_INTERVAL: Duration = Duration::from_secs(10);
+/// How many times to attempt sync from DSN before giving up until next notification
+const MAX_SYNC_ATTEMPTS: NonZeroU32 = NonZeroU32::new(5).expect("Not zero; qed");
+/// Delay before the first retry of failed sync from DSN, grows exponentially with every attempt
+const INITIAL_RETRY_INTERVAL: Duration = Duration::from_secs(5);
+/// Max delay between retries of failed sync from DSN
+const MAX_RETRY_INTERVAL: Duration = Duration::from_secs(5 * 60);
+/// How long node must stay connected to DSN peers after going online before sync from DSN starts
+const DSN_ONLINE_DEBOUNCE: Duration = Duration::from_secs(30);
+/// How many blocks node must be behind the median best block of connected peers to start sync
+/// from DSN
+const FELL_BEHIND_THRESHOLD: BlockNumber = 1000;
+/// How many consecutive syncs from DSN must fail (with all attempts exhausted) before automatic
+/// sync from DSN cools down
+const FAILURES_BEFORE_COOL_DOWN: u32 = 3;
+/// Initial cool-down duration, doubles every time sync from DSN fails during cool-down
+const INITIAL_COOL_DOWN: Duration = Duration::from_secs(10 * 60);
+/// Max cool-down duration
+const MAX_COOL_DOWN: Duration = Duration::from_secs(2 * 60 * 60);
+/// How long to wait for import queue to make progress before considering remaining blocks queued
+/// from DSN as failed to import
+const IMPORT_PROGRESS_TIMEOUT: Duration = Duration::from_secs(60);
+/// How often to check import progress of blocks queued from DSN
+const IMPORT_PROGRESS_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Configuration of sync from DSN.
+#[derive(Debug, Clone)]
+pub struct DsnSyncConfig {
+    /// How much time to wait for new block to be imported before timing out and starting sync
+    /// from DSN.
+    pub no_imported_blocks_timeout: Duration,
+    /// Frequency with which to check whether node is online or not.
+    pub check_online_status_interval: Duration,
+    /// How many times to attempt sync from DSN before giving up until next notification, halved
+    /// for notifications caused by node going online or major sync completing.
+    pub max_sync_attempts: NonZeroU32,
+    /// Delay before the first retry of failed sync from DSN, grows exponentially (with jitter)
+    /// with every attempt.
+    pub initial_retry_interval: Duration,
+    /// Max delay between retries of failed sync from DSN.
+    pub max_retry_interval: Duration,
+    /// Whether going online in DSN (Subspace networking) triggers sync from DSN, this allows
+    /// nodes without Substrate peers to sync.
+    pub sync_on_dsn_online: bool,
+    /// How long node must stay connected to DSN peers after going online before sync from DSN
+    /// starts, prevents repeated syncs when connectivity is flapping.
+    pub dsn_online_debounce: Duration,
+    /// Max number of blocks from DSN queued for import at any time, limits memory usage when
+    /// import is slower than download.
+    pub max_queued_blocks: BlockNumber,
+    /// How many blocks node must be behind the median best block reported by connected peers to
+    /// start sync from DSN without waiting for imported blocks timeout.
+    pub fell_behind_threshold: BlockNumber,
+    /// How many consecutive syncs from DSN must fail before notifications are ignored for a
+    /// cool-down period (manual requests are still served).
+    pub failures_before_cool_down: u32,
+    /// Cool-down duration after repeated failures, doubles with every subsequent failure.
+    pub initial_cool_down: Duration,
+    /// Max cool-down duration.
+    pub max_cool_down: Duration,
+    /// Origin with which blocks from DSN are imported.
+    pub block_origin: BlockOrigin,
+    /// Import and fully verify blocks from DSN even if they are already known to the node.
+    pub force_import: bool,
+    /// Max download rate of pieces in bytes per second, unlimited if `None`.
+    pub download_rate_limit: Option<NonZeroU64>,
+    /// Number of recently downloaded segments cached on disk, such that interrupted sync from DSN
+    /// doesn't download them again, 0 disables the cache.
+    pub segment_cache_size: usize,
+    /// Number of threads used to recover downloaded segments.
+    pub reconstruction_threads: NonZeroUsize,
+    /// Peers to request pieces from concurrently with the rest of the network, for instance farmer
+    /// running on the same host.
+    pub preferred_piece_providers: Vec<PeerId>,
+}
+
+impl Default for DsnSyncConfig {
+    fn default() -> Self {
+        Self {
+            no_imported_blocks_timeout: NO_IMPORTED_BLOCKS_TIMEOUT,
+            check_online_status_interval: CHECK_ONLINE_STATUS_INTERVAL,
+            max_sync_attempts: MAX_SYNC_ATTEMPTS,
+            initial_retry_interval: INITIAL_RETRY_INTERVAL,
+            max_retry_interval: MAX_RETRY_INTERVAL,
+            sync_on_dsn_online: true,
+            dsn_online_debounce: DSN_ONLINE_DEBOUNCE,
+            max_queued_blocks: QUEUED_BLOCKS_LIMIT,
+            fell_behind_threshold: FELL_BEHIND_THRESHOLD,
+            failures_before_cool_down: FAILURES_BEFORE_COOL_DOWN,
+            initial_cool_down: INITIAL_COOL_DOWN,
+            max_cool_down: MAX_COOL_DOWN,
+            block_origin: BlockOrigin::NetworkBroadcast,
+            force_import: false,
+            download_rate_limit: None,
+            segment_cache_size: SEGMENT_CACHE_SIZE,
+            reconstruction_threads: RECONSTRUCTION_THREADS,
+            preferred_piece_providers: Vec::new(),
+        }
+    }
+}
+
+impl DsnSyncConfig {
+    /// Options of importing blocks for a single sync from DSN
+    pub(crate) fn import_blocks_options(&self, force: bool) -> ImportBlocksOptions {
+        ImportBlocksOptions {
+            block_origin: self.block_origin,
+            force,
+            max_queued_blocks: self.max_queued_blocks,
+            download_rate_limit: self.download_rate_limit,
+            segment_cache_size: self.segment_cache_size,
+            reconstruction_threads: self.reconstruction_threads,
+            preferred_piece_providers: self.preferred_piece_providers.clone(),
+        }
+    }
+}
+
+/// Status of sync from DSN.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DsnSyncStatus {
+    /// Waiting for notification to start sync from DSN.
+    Idle,
+    /// Sync from DSN is in progress.
+    Active,
+    /// Sync from DSN failed repeatedly, notifications (except manual requests) are ignored for
+    /// some time.
+    CoolingDown,
+}
+
+/// State of sync from DSN.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DsnSyncState {
+    /// Current status.
+    pub status: DsnSyncStatus,
+    /// Reason of the last notification that started sync from DSN.
+    pub last_notification_reason: Option<String>,
+    /// Segment that is being processed right now, if sync from DSN is active.
+    pub current_segment_index: Option<SegmentIndex>,
+    /// Total number of blocks imported from DSN since node start.
+    pub blocks_imported: u64,
+}
+
+impl Default for DsnSyncState {
+    fn default() -> Self {
+        Self {
+            status: DsnSyncStatus::Idle,
+            last_notification_reason: None,
+            current_segment_index: None,
+            blocks_imported: 0,
+        }
+    }
+}
 
-#[derive(Debug)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 enum NotificationReason {
     NoImportedBlocks,
     WentOnlineSubspace,
     WentOnlineSubstrate,
+    FellBehind,
+    MajorSyncCompleted,
+    Manual { force_import: bool },
+}
+
+impl NotificationReason {
+    /// Priority of the reason, the higher the priority, the more certain it is that sync from DSN
+    /// is necessary
+    fn priority(&self) -> u8 {
+        match self {
+            Self::Manual { .. } => 3,
+            Self::FellBehind => 2,
+            Self::NoImportedBlocks => 1,
+            Self::WentOnlineSubspace | Self::WentOnlineSubstrate | Self::MajorSyncCompleted => 0,
+        }
+    }
+
+    /// Combine two pending notifications into one with the strongest reason
+    fn coalesce(self, other: Self) -> Self {
+        match (self, other) {
+            (
+                Self::Manual { force_import },
+                Self::Manual {
+                    force_import: other_force_import,
+                },
+            ) => Self::Manual {
+                force_import: force_import || other_force_import,
+            },
+            (reason, other) => {
+                if other.priority() > reason.priority() {
+                    other
+                } else {
+                    reason
+                }
+            }
+        }
+    }
+
+    /// Whether sync from DSN for this reason is not necessary anymore once node catches up with
+    /// peers. Only lagging behind is resolved by catching up, other reasons are about archived
+    /// history that Substrate sync might not be able to fetch or were requested explicitly.
+    fn cancelled_by_catching_up(&self) -> bool {
+        matches!(self, Self::FellBehind | Self::NoImportedBlocks)
+    }
+
+    /// Max number of sync attempts for this reason, notifications that are only a hint that sync
+    /// from DSN might be necessary give up sooner than those that are certain about it
+    fn max_sync_attempts(&self, max_sync_attempts: NonZeroU32) -> u32 {
+        match self.priority() {
+            0 => (max_sync_attempts.get() / 2).max(1),
+            _ => max_sync_attempts.get(),
+        }
+    }
+}
+
+/// Handle that allows to trigger sync from DSN manually, without waiting for node to detect that
+/// it is necessary, to abort it and to query state of sync from DSN.
+#[derive(Debug, Clone)]
+pub struct DsnSyncTrigger {
+    notifications_sender: mpsc::Sender<NotificationReason>,
+    state: Arc<Mutex<DsnSyncState>>,
+    abort_notify: Arc<Notify>,
+}
+
+impl DsnSyncTrigger {
+    /// Request sync from DSN, returns `false` if sync from DSN is not running anymore.
+    ///
+    /// With `force_import` blocks are imported and fully verified even if they are already known
+    /// to the node. Requests made while sync from DSN is already in progress are coalesced.
+    pub fn trigger(&self, force_import: bool) -> bool {
+        match self
+            .notifications_sender
+            .clone()
+            .try_send(NotificationReason::Manual { force_import })
+        {
+            Ok(()) => true,
+            Err(error) => !error.is_disconnected(),
+        }
+    }
+
+    /// Abort sync from DSN that is in progress, returns `false` if there was nothing to abort.
+    ///
+    /// Substrate sync is resumed right away, retries of failed sync from DSN are cancelled too.
+    pub fn abort(&self) -> bool {
+        if self.state.lock().status != DsnSyncStatus::Active {
+            return false;
+        }
+
+        self.abort_notify.notify_waiters();
+        true
+    }
+
+    /// Current state of sync from DSN.
+    pub fn state(&self) -> DsnSyncState {
+        self.state.lock().clone()
+    }
+}
+
+/// Parameters of sync from DSN observer and worker.
+pub(super) struct DsnSyncParams<Block, Client>
+where
+    Block: BlockT,
+{
+    pub(super) network_service: Arc<NetworkService<Block, <Block as BlockT>::Hash>>,
+    pub(super) sync_service: Arc<SyncingService<Block>>,
+    pub(super) node: Node,
+    pub(super) client: Arc<Client>,
+    pub(super) segment_header_cache: SegmentHeaderCache<Client>,
+    pub(super) import_queue_service: Box<dyn ImportQueueService<Block>>,
+    pub(super) import_results: ImportResults<Block>,
+    pub(super) sync_mode: Arc<Atomic<SyncMode>>,
+    pub(super) dsn_sync_config: DsnSyncConfig,
+    pub(super) metrics: Option<DsnSyncMetrics>,
+    pub(super) telemetry: Option<TelemetryHandle>,
 }
 
 /// Create node observer that will track node state and send notifications to worker to start sync
-/// from DSN.
+/// from DSN, also returns trigger that can be used to start sync from DSN manually.
 pub(super) fn create_observer_and_worker<Block, Client>(
-    network_service: Arc<NetworkService<Block, <Block as BlockT>::Hash>>,
-    node: Node,
-    client: Arc<Client>,
-    mut import_queue_service: Box<dyn ImportQueueService<Block>>,
-    sync_mode: Arc<Atomic<SyncMode>>,
+    params: DsnSyncParams<Block, Client>,
 ) -> (
     impl Future<Output = ()> + Send + 'static,
     impl Future<Output = Result<(), sc_service::Error>> + Send + 'static,
+    DsnSyncTrigger,
 )
 where
     Block: BlockT,
     Client: HeaderBackend<Block>
         + BlockBackend<Block>
         + BlockchainEvents<Block>
+        + AuxStore
+        + ProvideRuntimeApi<Block>
         + Send
         + Sync
         + 'static,
+    Client::Api: SubspaceApi<Block, FarmerPublicKey>,
 {
     let (tx, rx) = mpsc::channel(0);
+    let state = Arc::<Mutex<DsnSyncState>>::default();
+    let abort_notify = Arc::<Notify>::default();
+    let dsn_sync_trigger = DsnSyncTrigger {
+        notifications_sender: tx.clone(),
+        state: Arc::clone(&state),
+        abort_notify: Arc::clone(&abort_notify),
+    };
     let observer_fut = {
-        let node = node.clone();
-        let client = Arc::clone(&client);
+        let network_service = Arc::clone(&params.network_service);
+        let node = params.node.clone();
+        let client = Arc::clone(&params.client);
+        let sync_service = Arc::clone(&params.sync_service);
+        let dsn_sync_config = params.dsn_sync_config.clone();
 
-        async move { create_observer(network_service.as_ref(), &node, client.as_ref(), tx).await }
-    };
-    let worker_fut = async move {
-        create_worker(
-            &node,
-            client.as_ref(),
-            import_queue_service.as_mut(),
-            sync_mode,
-            rx,
-        )
-        .await
+        async move {
+            create_observer(
+                network_service.as_ref(),
+                sync_service.as_ref(),
+                &node,
+                client.as_ref(),
+                dsn_sync_config,
+                tx,
+            )
+            .await
+        }
     };
-    (observer_fut, worker_fut)
+    let worker_fut = async move { create_worker(params, state, &abort_notify, rx).await };
+    (observer_fut, worker_fut, dsn_sync_trigger)
 }
 
 async fn create_observer<Block, Client>(
     network_service: &NetworkService<Block, <Block as BlockT>::Hash>,
+    sync_service: &SyncingService<Block>,
     node: &Node,
     client: &Client,
+    dsn_sync_config: DsnSyncConfig,
     notifications_sender: mpsc::Sender<NotificationReason>,
 ) where
     Block: BlockT,
-    Client: BlockchainEvents<Block> + Send + Sync + 'static,
+    Client: HeaderBackend<Block> + BlockchainEvents<Block> + Send + Sync + 'static,
 {
-    // Separate reactive observer for Subspace networking that is not a future
-    let _handler_id = node.on_num_established_peer_connections_change({
-        // Assuming node is online by default
-        let was_online = AtomicBool::new(false);
-        let notifications_sender = notifications_sender.clone();
-
-        Arc::new(move |&new_connections| {
-            let is_online = new_connections > 0;
-            let was_online = was_online.swap(is_online, Ordering::AcqRel);
-
-            if is_online && !was_online {
-                // Doesn't matter if sending failed here
-                let _ = notifications_sender
-                    .clone()
-                    .try_send(NotificationReason::WentOnlineSubspace);
-            }
-        })
-    });
+    let (connections_sender, connections_receiver) = mpsc::unbounded();
+    // Reactive handler is not a future, forward updates into the stream
+    let _handler_id =
+        node.on_num_established_peer_connections_change(Arc::new(move |&new_connections| {
+            // Doesn't matter if sending failed here
+            let _ = connections_sender.unbounded_send(new_connections);
+        }));
+
+    let subspace_network_observer_fut = if dsn_sync_config.sync_on_dsn_online {
+        create_subspace_network_observer(
+            connections_receiver,
+            dsn_sync_config.dsn_online_debounce,
+            notifications_sender.clone(),
+        )
+        .boxed()
+    } else {
+        future::pending().boxed()
+    };
+
     futures::select! {
-        _ = create_imported_blocks_observer(client, notifications_sender.clone()).fuse() => {
+        _ = create_imported_blocks_observer(
+            client.every_import_notification_stream(),
+            dsn_sync_config.no_imported_blocks_timeout,
+            notifications_sender.clone(),
+        ).fuse() => {
+            // Runs indefinitely
+        }
+        _ = create_substrate_network_observer(
+            || network_service.sync_num_connected() > 0,
+            dsn_sync_config.check_online_status_interval,
+            notifications_sender.clone(),
+        ).fuse() => {
             // Runs indefinitely
         }
-        _ = create_substrate_network_observer(network_service, notifications_sender).fuse() => {
+        _ = create_peers_best_block_observer(
+            || is_behind_peers(sync_service, client, dsn_sync_config.fell_behind_threshold),
+            dsn_sync_config.check_online_status_interval,
+            notifications_sender.clone(),
+        ).fuse() => {
+            // Runs indefinitely
+        }
+        _ = create_major_sync_observer(
+            || sync_service.is_major_syncing(),
+            dsn_sync_config.check_online_status_interval,
+            notifications_sender,
+        ).fuse() => {
+            // Runs indefinitely
+        }
+        _ = subspace_network_observer_fut.fuse() => {
             // Runs indefinitely
         }
         // TODO: More sources
     }
 }
 
-async fn create_imported_blocks_observer<Block, Client>(
-    client: &Client,
+/// Notifies when node goes online in DSN, `connections_receiver` yields number of established
+/// peer connections every time it changes.
+async fn create_subspace_network_observer<S>(
+    mut connections_receiver: S,
+    online_debounce: Duration,
     mut notifications_sender: mpsc::Sender<NotificationReason>,
 ) where
-    Block: BlockT,
-    Client: BlockchainEvents<Block> + Send + Sync + 'static,
+    S: Stream<Item = usize> + Unpin,
+{
+    // Assuming node is offline by default
+    let mut is_online = false;
+
+    while let Some(new_connections) = connections_receiver.next().await {
+        let was_online = is_online;
+        is_online = new_connections > 0;
+
+        if !is_online || was_online {
+            continue;
+        }
+
+        // Node went online, make sure it stays online for a while before notifying
+        let mut debounce = Box::pin(tokio::time::sleep(online_debounce)).fuse();
+        loop {
+            futures::select! {
+                _ = debounce => {
+                    break;
+                }
+                maybe_new_connections = connections_receiver.next() => {
+                    match maybe_new_connections {
+                        Some(new_connections) => {
+                            is_online = new_connections > 0;
+                        }
+                        None => {
+                            return;
+                        }
+                    }
+                }
+            }
+        }
+
+        if !is_online {
+            continue;
+        }
+
+        if let Err(error) = notifications_sender.try_send(NotificationReason::WentOnlineSubspace) {
+            if error.is_disconnected() {
+                // Receiving side was closed
+                return;
+            }
+        }
+    }
+}
+
+/// Notifies when no blocks were imported for `no_imported_blocks_timeout`.
+async fn create_imported_blocks_observer<S>(
+    mut import_notification_stream: S,
+    no_imported_blocks_timeout: Duration,
+    mut notifications_sender: mpsc::Sender<NotificationReason>,
+) where
+    S: Stream + Unpin,
 {
-    let mut import_notification_stream = client.every_import_notification_stream();
     loop {
         match tokio::time::timeout(
-            NO_IMPORTED_BLOCKS_TIMEOUT,
+            no_imported_blocks_timeout,
             import_notification_stream.next(),
         )
         .await
@@ -143,19 +523,21 @@ async fn create_imported_blocks_observer<Block, Client>(
     }
 }
 
-async fn create_substrate_network_observer<Block>(
-    network_service: &NetworkService<Block, <Block as BlockT>::Hash>,
+/// Notifies when node goes online in Substrate networking.
+async fn create_substrate_network_observer<IsOnline>(
+    is_online: IsOnline,
+    check_online_status_interval: Duration,
     mut notifications_sender: mpsc::Sender<NotificationReason>,
 ) where
-    Block: BlockT,
+    IsOnline: Fn() -> bool,
 {
     // Assuming node is online by default
     let mut was_online = false;
 
     loop {
-        tokio::time::sleep(CHECK_ONLINE_STATUS_INTERVAL).await;
+        tokio::time::sleep(check_online_status_interval).await;
 
-        let is_online = network_service.sync_num_connected() > 0;
+        let is_online = is_online();
 
         if is_online && !was_online {
             if let Err(error) =
@@ -172,46 +554,498 @@ async fn create_substrate_network_observer<Block>(
     }
 }
 
-async fn create_worker<Block, IQS, Client>(
-    node: &Node,
+/// Notifies when node falls behind connected peers, `is_behind_peers` resolves with `None` when
+/// there is nobody to compare with.
+async fn create_peers_best_block_observer<IsBehindPeers, Fut>(
+    is_behind_peers: IsBehindPeers,
+    check_interval: Duration,
+    mut notifications_sender: mpsc::Sender<NotificationReason>,
+) where
+    IsBehindPeers: Fn() -> Fut,
+    Fut: Future<Output = Option<bool>>,
+{
+    let mut was_behind = false;
+
+    loop {
+        tokio::time::sleep(check_interval).await;
+
+        let Some(is_behind) = is_behind_peers().await else {
+            continue;
+        };
+
+        if is_behind && !was_behind {
+            if let Err(error) = notifications_sender.try_send(NotificationReason::FellBehind) {
+                if error.is_disconnected() {
+                    // Receiving side was closed
+                    return;
+                }
+            }
+        }
+
+        was_behind = is_behind;
+    }
+}
+
+/// Checks whether node is behind the median best block of connected peers by at least
+/// `fell_behind_threshold` blocks, `None` if there are no connected peers or syncing service has
+/// stopped.
+async fn is_behind_peers<Block, Client>(
+    sync_service: &SyncingService<Block>,
     client: &Client,
-    import_queue_service: &mut IQS,
-    sync_mode: Arc<Atomic<SyncMode>>,
-    mut notifications: mpsc::Receiver<NotificationReason>,
-) -> Result<(), sc_service::Error>
+    fell_behind_threshold: BlockNumber,
+) -> Option<bool>
 where
     Block: BlockT,
-    Client: HeaderBackend<Block> + BlockBackend<Block> + Send + Sync + 'static,
-    IQS: ImportQueueService<Block> + ?Sized,
+    Client: HeaderBackend<Block>,
 {
-    while let Some(reason) = notifications.next().await {
-        // TODO: Remove this condition once we switch to Subspace networking for everything
-        if matches!(reason, NotificationReason::WentOnlineSubspace) {
-            trace!("Ignoring Subspace networking for DSN sync for now");
-            continue;
+    let peers_info = sync_service.peers_info().await.ok()?;
+
+    let mut peers_best_blocks = peers_info
+        .into_iter()
+        .map(|(_peer_id, peer_info)| peer_info.best_number)
+        .collect::<Vec<_>>();
+    if peers_best_blocks.is_empty() {
+        return None;
+    }
+    // Median is used such that a few peers reporting bogus best blocks can't trigger sync
+    peers_best_blocks.sort_unstable();
+    let median_best_block = peers_best_blocks[peers_best_blocks.len() / 2];
+
+    Some(
+        median_best_block.saturating_sub(client.info().best_number)
+            >= NumberFor::<Block>::from(fell_behind_threshold),
+    )
+}
+
+/// Resolves once node is no longer behind connected peers, at which point sync from DSN is not
+/// necessary anymore.
+async fn wait_for_caught_up_with_peers<Block, Client>(
+    sync_service: &SyncingService<Block>,
+    client: &Client,
+    check_interval: Duration,
+    fell_behind_threshold: BlockNumber,
+) where
+    Block: BlockT,
+    Client: HeaderBackend<Block>,
+{
+    loop {
+        tokio::time::sleep(check_interval).await;
+
+        if is_behind_peers(sync_service, client, fell_behind_threshold).await == Some(false) {
+            return;
         }
+    }
+}
+
+/// Notifies when Substrate major sync completes. Peers might have pruned old blocks, in which case
+/// node can still be missing history that was already archived, sync from DSN fills such gaps
+/// (it does nothing if there are no missing archived segments).
+async fn create_major_sync_observer<IsMajorSyncing>(
+    is_major_syncing: IsMajorSyncing,
+    check_interval: Duration,
+    mut notifications_sender: mpsc::Sender<NotificationReason>,
+) where
+    IsMajorSyncing: Fn() -> bool,
+{
+    let mut was_major_syncing = false;
+
+    loop {
+        tokio::time::sleep(check_interval).await;
 
-        let prev_sync_mode = sync_mode.swap(SyncMode::Paused, Ordering::SeqCst);
+        let is_major_syncing = is_major_syncing();
 
-        while notifications.try_next().is_ok() {
-            // Just drain extra messages if there are any
+        if was_major_syncing && !is_major_syncing {
+            if let Err(error) =
+                notifications_sender.try_send(NotificationReason::MajorSyncCompleted)
+            {
+                if error.is_disconnected() {
+                    // Receiving side was closed
+                    return;
+                }
+            }
         }
 
-        info!(?reason, "Received notification to sync from DSN");
-        // TODO: Maybe handle failed block imports, additional helpful logging
-        if let Err(error) = import_blocks_from_dsn(
-            node,
+        was_major_syncing = is_major_syncing;
+    }
+}
+
+/// Single sync from DSN, abstracted away from the worker such that its retry, cool-down and
+/// cancellation logic doesn't depend on real DSN node, network and client.
+#[async_trait::async_trait]
+trait DsnSyncer {
+    /// Import blocks from DSN and wait for them to be imported, returns number of imported blocks
+    async fn sync(&mut self, force_import: bool) -> Result<u64, sc_service::Error>;
+
+    /// Resolves once node is no longer behind connected peers
+    fn caught_up_with_peers(&self) -> future::BoxFuture<'static, ()>;
+}
+
+struct NodeDsnSyncer<Block, Client>
+where
+    Block: BlockT,
+{
+    node: Node,
+    client: Arc<Client>,
+    sync_service: Arc<SyncingService<Block>>,
+    segment_header_cache: SegmentHeaderCache<Client>,
+    import_queue_service: Box<dyn ImportQueueService<Block>>,
+    import_results: ImportResults<Block>,
+    dsn_sync_config: DsnSyncConfig,
+    metrics: Option<DsnSyncMetrics>,
+    state: Arc<Mutex<DsnSyncState>>,
+}
+
+#[async_trait::async_trait]
+impl<Block, Client> DsnSyncer for NodeDsnSyncer<Block, Client>
+where
+    Block: BlockT,
+    Client: HeaderBackend<Block>
+        + BlockBackend<Block>
+        + AuxStore
+        + ProvideRuntimeApi<Block>
+        + Send
+        + Sync
+        + 'static,
+    Client::Api: SubspaceApi<Block, FarmerPublicKey>,
+{
+    async fn sync(&mut self, force_import: bool) -> Result<u64, sc_service::Error> {
+        let client = self.client.as_ref();
+        let best_block_number = client.info().best_number;
+        let failures_total = self.import_results.failures_total();
+
+        let downloaded_blocks = import_blocks_from_dsn(
+            &self.node,
             client,
-            import_queue_service,
-            BlockOrigin::NetworkBroadcast,
-            false,
+            Some(&self.segment_header_cache),
+            self.import_queue_service.as_mut(),
+            &self.dsn_sync_config.import_blocks_options(force_import),
+            self.metrics.as_ref(),
+            Some(self.state.as_ref()),
+        )
+        .await?;
+
+        // Blocks queued from DSN are contiguous and start right after the best block
+        let expected_best_block_number =
+            best_block_number + downloaded_blocks.saturated_into::<NumberFor<Block>>();
+        if let Err(failed_blocks) = wait_for_queued_blocks(
+            client,
+            &self.import_results,
+            failures_total,
+            expected_best_block_number,
         )
         .await
         {
-            warn!(%error, "Error when syncing blocks from DSN");
+            let first_not_imported = client.info().best_number + One::one();
+            if let Some(metrics) = &self.metrics {
+                metrics.on_failure("block_import");
+                metrics.on_blocks_failed_import(
+                    (expected_best_block_number + One::one())
+                        .saturating_sub(first_not_imported)
+                        .saturated_into::<u64>(),
+                );
+            }
+
+            return Err(sc_service::Error::Other(if failed_blocks.is_empty() {
+                format!(
+                    "Blocks #{first_not_imported}..=#{expected_best_block_number} from DSN were \
+                    not imported in time"
+                )
+            } else {
+                format!(
+                    "Blocks #{first_not_imported}..=#{expected_best_block_number} from DSN failed \
+                    to import, failed blocks: {failed_blocks:?}"
+                )
+            }));
+        }
+
+        Ok(downloaded_blocks)
+    }
+
+    fn caught_up_with_peers(&self) -> future::BoxFuture<'static, ()> {
+        let sync_service = Arc::clone(&self.sync_service);
+        let client = Arc::clone(&self.client);
+        let check_interval = self.dsn_sync_config.check_online_status_interval;
+        let fell_behind_threshold = self.dsn_sync_config.fell_behind_threshold;
+
+        async move {
+            wait_for_caught_up_with_peers(
+                sync_service.as_ref(),
+                client.as_ref(),
+                check_interval,
+                fell_behind_threshold,
+            )
+            .await
+        }
+        .boxed()
+    }
+}
+
+async fn create_worker<Block, Client>(
+    params: DsnSyncParams<Block, Client>,
+    state: Arc<Mutex<DsnSyncState>>,
+    abort_notify: &Notify,
+    notifications: mpsc::Receiver<NotificationReason>,
+) -> Result<(), sc_service::Error>
+where
+    Block: BlockT,
+    Client: HeaderBackend<Block>
+        + BlockBackend<Block>
+        + AuxStore
+        + ProvideRuntimeApi<Block>
+        + Send
+        + Sync
+        + 'static,
+    Client::Api: SubspaceApi<Block, FarmerPublicKey>,
+{
+    let DsnSyncParams {
+        network_service: _,
+        sync_service,
+        node,
+        client,
+        segment_header_cache,
+        import_queue_service,
+        import_results,
+        sync_mode,
+        dsn_sync_config,
+        metrics,
+        telemetry,
+    } = params;
+
+    let dsn_syncer = NodeDsnSyncer {
+        node,
+        client,
+        sync_service,
+        segment_header_cache,
+        import_queue_service,
+        import_results,
+        dsn_sync_config: dsn_sync_config.clone(),
+        metrics,
+        state: Arc::clone(&state),
+    };
+
+    run_worker(
+        dsn_syncer,
+        &sync_mode,
+        &dsn_sync_config,
+        &state,
+        abort_notify,
+        telemetry,
+        notifications,
+    )
+    .await;
+
+    Ok(())
+}
+
+/// Process notifications until notifications channel is closed: sync from DSN with retries, cool
+/// down after repeated failures and cancel sync that is not necessary anymore.
+async fn run_worker<Syncer>(
+    mut dsn_syncer: Syncer,
+    sync_mode: &Atomic<SyncMode>,
+    dsn_sync_config: &DsnSyncConfig,
+    state: &Mutex<DsnSyncState>,
+    abort_notify: &Notify,
+    telemetry: Option<TelemetryHandle>,
+    mut notifications: mpsc::Receiver<NotificationReason>,
+) where
+    Syncer: DsnSyncer,
+{
+    let mut consecutive_failures = 0;
+    let mut cool_down = dsn_sync_config.initial_cool_down;
+    let mut cool_down_until = None;
+
+    while let Some(mut reason) = notifications.next().await {
+        // Drain extra messages if there are any, keeping the strongest reason
+        while let Ok(Some(other_reason)) = notifications.try_next() {
+            reason = reason.coalesce(other_reason);
+        }
+        let force_import = dsn_sync_config.force_import
+            || matches!(reason, NotificationReason::Manual { force_import: true });
+        let max_sync_attempts = reason.max_sync_attempts(dsn_sync_config.max_sync_attempts);
+        let caught_up_with_peers = |dsn_syncer: &Syncer| {
+            if reason.cancelled_by_catching_up() {
+                dsn_syncer.caught_up_with_peers()
+            } else {
+                future::pending().boxed()
+            }
+        };
+
+        if let Some(until) = cool_down_until {
+            let now = tokio::time::Instant::now();
+            if now < until && !matches!(reason, NotificationReason::Manual { .. }) {
+                debug!(
+                    ?reason,
+                    remaining = ?until - now,
+                    "Sync from DSN is cooling down after repeated failures, ignoring notification"
+                );
+                continue;
+            }
+        }
+
+        info!(?reason, "Received notification to sync from DSN");
+        {
+            let mut state = state.lock();
+            state.status = DsnSyncStatus::Active;
+            state
+                .last_notification_reason
+                .replace(format!("{reason:?}"));
+        }
+        telemetry!(
+            telemetry;
+            SUBSTRATE_INFO;
+            "subspace.dsn_sync_started";
+            "reason" => ?reason,
+        );
+
+        let mut synced = false;
+        let mut cancelled = false;
+        let mut backoff = ExponentialBackoff {
+            initial_interval: dsn_sync_config.initial_retry_interval,
+            max_interval: dsn_sync_config.max_retry_interval,
+            max_elapsed_time: None,
+            ..ExponentialBackoff::default()
+        };
+        for attempt in 1..=max_sync_attempts {
+            let aborted = abort_notify.notified();
+            // Created before sync future, which borrows syncer mutably
+            let caught_up = caught_up_with_peers(&dsn_syncer);
+            let prev_sync_mode = sync_mode.swap(SyncMode::Paused, Ordering::SeqCst);
+            let maybe_result = futures::select! {
+                result = dsn_syncer.sync(force_import).fuse() => Some(result),
+                _ = aborted.fuse() => {
+                    info!("Sync from DSN aborted");
+                    None
+                }
+                _ = caught_up.fuse() => {
+                    info!("Node caught up with peers, sync from DSN is not necessary anymore");
+                    None
+                }
+            };
+            // Substrate sync is not paused while waiting for retry, it might succeed on its own
+            sync_mode.store(prev_sync_mode, Ordering::Release);
+
+            let Some(result) = maybe_result else {
+                cancelled = true;
+                break;
+            };
+            let error = match result {
+                Ok(imported_blocks) => {
+                    state.lock().blocks_imported += imported_blocks;
+                    synced = true;
+                    break;
+                }
+                Err(error) => error,
+            };
+
+            if attempt == max_sync_attempts {
+                warn!(
+                    %error,
+                    %attempt,
+                    "Error when syncing blocks from DSN, giving up until next notification"
+                );
+                break;
+            }
+
+            let delay = backoff
+                .next_backoff()
+                .unwrap_or(dsn_sync_config.max_retry_interval);
+            warn!(
+                %error,
+                %attempt,
+                ?delay,
+                "Error when syncing blocks from DSN, will retry"
+            );
+            let aborted = abort_notify.notified();
+            futures::select! {
+                _ = tokio::time::sleep(delay).fuse() => {}
+                _ = aborted.fuse() => {
+                    info!("Sync from DSN aborted");
+                    cancelled = true;
+                    break;
+                }
+                _ = caught_up_with_peers(&dsn_syncer).fuse() => {
+                    info!("Node caught up with peers, sync from DSN is not necessary anymore");
+                    cancelled = true;
+                    break;
+                }
+            }
+        }
+
+        if synced {
+            consecutive_failures = 0;
+            cool_down = dsn_sync_config.initial_cool_down;
+            cool_down_until = None;
+        } else if !cancelled {
+            consecutive_failures += 1;
+            if consecutive_failures >= dsn_sync_config.failures_before_cool_down {
+                warn!(
+                    %consecutive_failures,
+                    ?cool_down,
+                    "Sync from DSN failed repeatedly, pausing automatic sync from DSN"
+                );
+                cool_down_until = Some(tokio::time::Instant::now() + cool_down);
+                cool_down = (cool_down * 2).min(dsn_sync_config.max_cool_down);
+            }
         }
 
-        sync_mode.store(prev_sync_mode, Ordering::Release);
+        let blocks_imported = {
+            let mut state = state.lock();
+            state.status = if cool_down_until.is_some() {
+                DsnSyncStatus::CoolingDown
+            } else {
+                DsnSyncStatus::Idle
+            };
+            state.current_segment_index.take();
+            state.blocks_imported
+        };
+        telemetry!(
+            telemetry;
+            SUBSTRATE_INFO;
+            "subspace.dsn_sync_finished";
+            "success" => synced,
+            "cancelled" => cancelled,
+            "blocks_imported" => blocks_imported,
+        );
+    }
+}
+
+/// Wait for blocks queued for import to be imported.
+///
+/// Returns hashes of blocks that failed verification or import according to import queue since
+/// `failures_total` was observed. Substrate sync is paused during sync from DSN, so all failures
+/// are attributed to blocks from DSN. Empty list is returned if import queue stopped making
+/// progress without reporting any failures.
+async fn wait_for_queued_blocks<Block, Client>(
+    client: &Client,
+    import_results: &ImportResults<Block>,
+    failures_total: u64,
+    expected_best_block_number: NumberFor<Block>,
+) -> Result<(), Vec<Block::Hash>>
+where
+    Block: BlockT,
+    Client: HeaderBackend<Block>,
+{
+    let mut best_block_number = client.info().best_number;
+    let mut last_progress = Instant::now();
+
+    while best_block_number < expected_best_block_number {
+        let failed_blocks = import_results.failures_since(failures_total);
+        if !failed_blocks.is_empty() {
+            // Blocks are imported in order, those after failed block will not be imported either
+            return Err(failed_blocks);
+        }
+
+        if last_progress.elapsed() >= IMPORT_PROGRESS_TIMEOUT {
+            return Err(Vec::new());
+        }
+
+        tokio::time::sleep(IMPORT_PROGRESS_CHECK_INTERVAL).await;
+
+        let new_best_block_number = client.info().best_number;
+        if new_best_block_number > best_block_number {
+            best_block_number = new_best_block_number;
+            last_progress = Instant::now();
+        }
     }
 
     Ok(())
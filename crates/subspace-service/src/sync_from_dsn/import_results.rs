@@ -0,0 +1,180 @@
+use parking_lot::Mutex;
+use sc_consensus::import_queue::{ImportQueue, ImportQueueService, RuntimeOrigin};
+use sc_consensus::{BlockImportError, BlockImportStatus, Link};
+use sp_api::BlockT;
+use sp_runtime::traits::NumberFor;
+use std::collections::VecDeque;
+use std::ops::DerefMut;
+use std::sync::Arc;
+use std::task::Context;
+use tracing::debug;
+
+/// How many most recent import failures are kept around
+const RECENT_FAILURES_LIMIT: usize = 1000;
+
+#[derive(Debug)]
+struct Inner<Block: BlockT> {
+    /// Total number of blocks that failed verification or import since start
+    failures_total: u64,
+    /// Hashes of most recent blocks that failed verification or import
+    recent_failures: VecDeque<Block::Hash>,
+}
+
+/// Results of block processing by import queue, as reported through [`Link::blocks_processed`]
+#[derive(Debug)]
+pub(crate) struct ImportResults<Block: BlockT> {
+    inner: Arc<Mutex<Inner<Block>>>,
+}
+
+impl<Block: BlockT> Clone for ImportResults<Block> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
+
+impl<Block: BlockT> Default for ImportResults<Block> {
+    fn default() -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(Inner {
+                failures_total: 0,
+                recent_failures: VecDeque::with_capacity(RECENT_FAILURES_LIMIT),
+            })),
+        }
+    }
+}
+
+impl<Block: BlockT> ImportResults<Block> {
+    /// Total number of blocks that failed verification or import since start
+    pub(crate) fn failures_total(&self) -> u64 {
+        self.inner.lock().failures_total
+    }
+
+    /// Hashes of blocks that failed verification or import after [`Self::failures_total`]
+    /// returned `failures_total`
+    pub(crate) fn failures_since(&self, failures_total: u64) -> Vec<Block::Hash> {
+        let inner = self.inner.lock();
+        let new_failures = inner.failures_total.saturating_sub(failures_total) as usize;
+
+        inner
+            .recent_failures
+            .iter()
+            .skip(inner.recent_failures.len().saturating_sub(new_failures))
+            .copied()
+            .collect()
+    }
+
+    fn on_blocks_processed(
+        &self,
+        results: &[(
+            Result<BlockImportStatus<NumberFor<Block>>, BlockImportError>,
+            Block::Hash,
+        )],
+    ) {
+        let mut inner = self.inner.lock();
+
+        for (result, hash) in results {
+            if let Err(error) = result {
+                debug!(?error, %hash, "Block failed to import");
+
+                inner.failures_total += 1;
+                if inner.recent_failures.len() == RECENT_FAILURES_LIMIT {
+                    inner.recent_failures.pop_front();
+                }
+                inner.recent_failures.push_back(*hash);
+            }
+        }
+    }
+}
+
+/// Link that records block processing results before forwarding them to the wrapped link
+struct RecordingLink<Block: BlockT, L> {
+    inner: L,
+    import_results: ImportResults<Block>,
+}
+
+impl<'a, Block, L> Link<Block> for RecordingLink<Block, L>
+where
+    Block: BlockT,
+    L: DerefMut<Target = dyn Link<Block> + 'a> + Send + Sync,
+{
+    fn blocks_processed(
+        &mut self,
+        imported: usize,
+        count: usize,
+        results: Vec<(
+            Result<BlockImportStatus<NumberFor<Block>>, BlockImportError>,
+            Block::Hash,
+        )>,
+    ) {
+        self.import_results.on_blocks_processed(&results);
+        self.inner.blocks_processed(imported, count, results);
+    }
+
+    fn justification_imported(
+        &mut self,
+        who: RuntimeOrigin,
+        hash: &Block::Hash,
+        number: NumberFor<Block>,
+        success: bool,
+    ) {
+        self.inner
+            .justification_imported(who, hash, number, success);
+    }
+
+    fn request_justification(&mut self, hash: &Block::Hash, number: NumberFor<Block>) {
+        self.inner.request_justification(hash, number);
+    }
+}
+
+/// Import queue wrapper that records results of block processing into [`ImportResults`], such
+/// that sync from DSN can find out which of the blocks it queued failed to import.
+pub(crate) struct ObservedImportQueue<Block: BlockT, IQ> {
+    inner: IQ,
+    import_results: ImportResults<Block>,
+}
+
+impl<Block, IQ> ObservedImportQueue<Block, IQ>
+where
+    Block: BlockT,
+    IQ: ImportQueue<Block>,
+{
+    pub(crate) fn new(inner: IQ, import_results: ImportResults<Block>) -> Self {
+        Self {
+            inner,
+            import_results,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl<Block, IQ> ImportQueue<Block> for ObservedImportQueue<Block, IQ>
+where
+    Block: BlockT,
+    IQ: ImportQueue<Block>,
+{
+    fn service(&self) -> Box<dyn ImportQueueService<Block>> {
+        self.inner.service()
+    }
+
+    fn service_ref(&mut self) -> &mut dyn ImportQueueService<Block> {
+        self.inner.service_ref()
+    }
+
+    fn poll_actions(&mut self, cx: &mut Context<'_>, link: &mut dyn Link<Block>) {
+        let mut link = RecordingLink {
+            inner: link,
+            import_results: self.import_results.clone(),
+        };
+        self.inner.poll_actions(cx, &mut link);
+    }
+
+    async fn run(self, link: Box<dyn Link<Block>>) {
+        let link = RecordingLink {
+            inner: link,
+            import_results: self.import_results,
+        };
+        self.inner.run(Box::new(link)).await;
+    }
+}
@@ -0,0 +1,652 @@
+use crate::sync_from_dsn::{
+    create_imported_blocks_observer, create_major_sync_observer, create_peers_best_block_observer,
+    create_subspace_network_observer, create_substrate_network_observer, run_worker, DsnSyncConfig,
+    DsnSyncState, DsnSyncStatus, DsnSyncTrigger, DsnSyncer, NotificationReason,
+};
+use atomic::Atomic;
+use futures::channel::mpsc;
+use futures::future::BoxFuture;
+use futures::{future, stream, FutureExt, SinkExt, Stream, StreamExt};
+use parking_lot::Mutex;
+use sc_network::config::SyncMode;
+use std::future::Future;
+use std::num::NonZeroU32;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Notify;
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(10);
+/// Long enough for any observer under test to notify if it is going to
+const OBSERVATION_TIME: Duration = Duration::from_secs(10 * 60);
+
+/// Run observer for [`OBSERVATION_TIME`] and collect notifications it sent
+async fn collect_notifications<F, Fut>(observer: F) -> Vec<NotificationReason>
+where
+    F: FnOnce(mpsc::Sender<NotificationReason>) -> Fut,
+    Fut: Future<Output = ()>,
+{
+    // Large buffer such that no notifications are dropped
+    let (notifications_sender, notifications_receiver) = mpsc::channel(100);
+
+    futures::select! {
+        _ = observer(notifications_sender).fuse() => {}
+        _ = tokio::time::sleep(OBSERVATION_TIME).fuse() => {}
+    }
+
+    notifications_receiver.collect().await
+}
+
+/// Stream that yields provided items with specified delays before each of them and never ends
+fn delayed_stream<T>(items: Vec<(Duration, T)>) -> impl Stream<Item = T> + Unpin {
+    stream::iter(items)
+        .then(|(delay, item)| async move {
+            tokio::time::sleep(delay).await;
+            item
+        })
+        .chain(stream::pending())
+        .boxed()
+}
+
+/// Function that returns provided values one by one, repeating the last one afterwards
+fn sequence<T>(values: Vec<T>) -> impl Fn() -> T
+where
+    T: Copy,
+{
+    let index = AtomicUsize::new(0);
+    move || {
+        let index = index.fetch_add(1, Ordering::Relaxed).min(values.len() - 1);
+        values[index]
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn no_imported_blocks() {
+    let notifications = collect_notifications(|notifications_sender| {
+        create_imported_blocks_observer(
+            stream::pending::<()>(),
+            OBSERVATION_TIME / 2 - Duration::from_secs(1),
+            notifications_sender,
+        )
+    })
+    .await;
+
+    assert_eq!(notifications.len(), 2);
+    assert!(notifications
+        .iter()
+        .all(|reason| matches!(reason, NotificationReason::NoImportedBlocks)));
+}
+
+#[tokio::test(start_paused = true)]
+async fn imported_blocks() {
+    let notifications = collect_notifications(|notifications_sender| {
+        create_imported_blocks_observer(
+            stream::repeat(())
+                .then(|()| tokio::time::sleep(Duration::from_secs(1)))
+                .boxed(),
+            Duration::from_secs(10),
+            notifications_sender,
+        )
+    })
+    .await;
+
+    assert!(notifications.is_empty());
+}
+
+#[tokio::test(start_paused = true)]
+async fn went_online_substrate() {
+    let notifications = collect_notifications(|notifications_sender| {
+        create_substrate_network_observer(
+            sequence(vec![false, false, true, true, false, true]),
+            CHECK_INTERVAL,
+            notifications_sender,
+        )
+    })
+    .await;
+
+    // Going online twice results in two notifications
+    assert_eq!(notifications.len(), 2);
+    assert!(notifications
+        .iter()
+        .all(|reason| matches!(reason, NotificationReason::WentOnlineSubstrate)));
+}
+
+#[tokio::test(start_paused = true)]
+async fn went_online_subspace() {
+    let online_debounce = Duration::from_secs(30);
+
+    let notifications = collect_notifications(|notifications_sender| {
+        create_subspace_network_observer(
+            delayed_stream(vec![(Duration::ZERO, 1), (Duration::from_secs(1), 2)]),
+            online_debounce,
+            notifications_sender,
+        )
+    })
+    .await;
+
+    assert_eq!(notifications.len(), 1);
+    assert!(matches!(
+        notifications[0],
+        NotificationReason::WentOnlineSubspace
+    ));
+}
+
+#[tokio::test(start_paused = true)]
+async fn went_online_subspace_flapping() {
+    let online_debounce = Duration::from_secs(30);
+
+    let notifications = collect_notifications(|notifications_sender| {
+        create_subspace_network_observer(
+            delayed_stream(vec![
+                (Duration::ZERO, 1),
+                (Duration::from_secs(1), 0),
+                (Duration::from_secs(1), 1),
+                (Duration::from_secs(1), 0),
+            ]),
+            online_debounce,
+            notifications_sender,
+        )
+    })
+    .await;
+
+    // Node didn't stay online for long enough
+    assert!(notifications.is_empty());
+}
+
+#[tokio::test(start_paused = true)]
+async fn fell_behind() {
+    let is_behind_peers = sequence(vec![None, Some(false), Some(true), Some(true), None]);
+
+    let notifications = collect_notifications(|notifications_sender| {
+        create_peers_best_block_observer(
+            || {
+                let is_behind = is_behind_peers();
+                async move { is_behind }
+            },
+            CHECK_INTERVAL,
+            notifications_sender,
+        )
+    })
+    .await;
+
+    // Staying behind doesn't result in repeated notifications
+    assert_eq!(notifications.len(), 1);
+    assert!(matches!(notifications[0], NotificationReason::FellBehind));
+}
+
+#[tokio::test(start_paused = true)]
+async fn major_sync_completed() {
+    let notifications = collect_notifications(|notifications_sender| {
+        create_major_sync_observer(
+            sequence(vec![false, true, true, false]),
+            CHECK_INTERVAL,
+            notifications_sender,
+        )
+    })
+    .await;
+
+    assert_eq!(notifications.len(), 1);
+    assert!(matches!(
+        notifications[0],
+        NotificationReason::MajorSyncCompleted
+    ));
+}
+
+#[tokio::test(start_paused = true)]
+async fn no_major_sync() {
+    let notifications = collect_notifications(|notifications_sender| {
+        create_major_sync_observer(sequence(vec![false]), CHECK_INTERVAL, notifications_sender)
+    })
+    .await;
+
+    assert!(notifications.is_empty());
+}
+
+#[test]
+fn notification_reasons_coalescing() {
+    let coalesce = |reasons: &[NotificationReason]| {
+        reasons
+            .iter()
+            .copied()
+            .reduce(NotificationReason::coalesce)
+            .unwrap()
+    };
+
+    assert_eq!(
+        coalesce(&[
+            NotificationReason::WentOnlineSubspace,
+            NotificationReason::FellBehind,
+            NotificationReason::WentOnlineSubstrate,
+        ]),
+        NotificationReason::FellBehind
+    );
+    assert_eq!(
+        coalesce(&[
+            NotificationReason::NoImportedBlocks,
+            NotificationReason::Manual {
+                force_import: false
+            },
+            NotificationReason::FellBehind,
+        ]),
+        NotificationReason::Manual {
+            force_import: false
+        }
+    );
+    // Force import is not lost when coalescing manual requests
+    assert_eq!(
+        coalesce(&[
+            NotificationReason::Manual { force_import: true },
+            NotificationReason::Manual {
+                force_import: false
+            },
+        ]),
+        NotificationReason::Manual { force_import: true }
+    );
+    // The first of reasons with the same priority is kept
+    assert_eq!(
+        coalesce(&[
+            NotificationReason::WentOnlineSubstrate,
+            NotificationReason::MajorSyncCompleted,
+        ]),
+        NotificationReason::WentOnlineSubstrate
+    );
+}
+
+#[test]
+fn notification_reasons_sync_attempts() {
+    let five = NonZeroU32::new(5).expect("Not zero; qed");
+    let one = NonZeroU32::new(1).expect("Not zero; qed");
+
+    assert_eq!(
+        NotificationReason::Manual {
+            force_import: false
+        }
+        .max_sync_attempts(five),
+        5
+    );
+    assert_eq!(NotificationReason::FellBehind.max_sync_attempts(five), 5);
+    assert_eq!(
+        NotificationReason::NoImportedBlocks.max_sync_attempts(five),
+        5
+    );
+    assert_eq!(
+        NotificationReason::WentOnlineSubspace.max_sync_attempts(five),
+        2
+    );
+    assert_eq!(
+        NotificationReason::MajorSyncCompleted.max_sync_attempts(one),
+        1
+    );
+}
+
+#[test]
+fn notification_reasons_cancelled_by_catching_up() {
+    assert!(NotificationReason::FellBehind.cancelled_by_catching_up());
+    assert!(NotificationReason::NoImportedBlocks.cancelled_by_catching_up());
+    // Node that just went online or completed major sync is caught up already, but can still be
+    // missing archived history
+    assert!(!NotificationReason::WentOnlineSubspace.cancelled_by_catching_up());
+    assert!(!NotificationReason::WentOnlineSubstrate.cancelled_by_catching_up());
+    assert!(!NotificationReason::MajorSyncCompleted.cancelled_by_catching_up());
+    assert!(!NotificationReason::Manual {
+        force_import: false
+    }
+    .cancelled_by_catching_up());
+}
+
+#[test]
+fn manual_trigger() {
+    let (notifications_sender, mut notifications_receiver) = mpsc::channel(0);
+    let state = Arc::<Mutex<DsnSyncState>>::default();
+    let dsn_sync_trigger = DsnSyncTrigger {
+        notifications_sender,
+        state: Arc::clone(&state),
+        abort_notify: Arc::<Notify>::default(),
+    };
+
+    assert!(dsn_sync_trigger.trigger(true));
+    // Requests are coalesced while previous one is not processed yet
+    assert!(dsn_sync_trigger.trigger(false));
+    assert!(matches!(
+        notifications_receiver.try_next(),
+        Ok(Some(NotificationReason::Manual { force_import: true }))
+    ));
+
+    // Nothing to abort while sync from DSN is idle
+    assert!(!dsn_sync_trigger.abort());
+    state.lock().status = DsnSyncStatus::Active;
+    assert!(dsn_sync_trigger.abort());
+
+    drop(notifications_receiver);
+    assert!(!dsn_sync_trigger.trigger(false));
+}
+
+/// Syncer that doesn't touch DSN, results of sync attempts are predefined
+struct MockDsnSyncer {
+    /// How long every sync attempt takes
+    sync_duration: Duration,
+    /// Number of imported blocks or `None` for failure for every sync attempt in order, the last
+    /// one is repeated afterwards
+    results: Vec<Option<u64>>,
+    /// After how long node catches up with peers once it starts waiting for it, never if `None`
+    caught_up_after: Option<Duration>,
+    sync_mode: Arc<Atomic<SyncMode>>,
+    /// `force_import` and whether Substrate sync was paused for every sync attempt so far
+    attempts: Arc<Mutex<Vec<(bool, bool)>>>,
+}
+
+impl MockDsnSyncer {
+    fn new(results: Vec<Option<u64>>) -> Self {
+        Self {
+            sync_duration: Duration::from_secs(1),
+            results,
+            caught_up_after: None,
+            sync_mode: Arc::new(Atomic::new(SyncMode::Full)),
+            attempts: Arc::default(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl DsnSyncer for MockDsnSyncer {
+    async fn sync(&mut self, force_import: bool) -> Result<u64, sc_service::Error> {
+        let result = {
+            let mut attempts = self.attempts.lock();
+            let sync_paused = matches!(self.sync_mode.load(Ordering::Acquire), SyncMode::Paused);
+            attempts.push((force_import, sync_paused));
+            self.results[(attempts.len() - 1).min(self.results.len() - 1)]
+        };
+
+        tokio::time::sleep(self.sync_duration).await;
+
+        result.ok_or_else(|| sc_service::Error::Other("Sync failed".to_string()))
+    }
+
+    fn caught_up_with_peers(&self) -> BoxFuture<'static, ()> {
+        match self.caught_up_after {
+            Some(caught_up_after) => tokio::time::sleep(caught_up_after).boxed(),
+            None => future::pending().boxed(),
+        }
+    }
+}
+
+/// Config that gives up after the first failed attempt, such that every notification results in
+/// exactly one sync attempt
+fn single_attempt_config() -> DsnSyncConfig {
+    DsnSyncConfig {
+        max_sync_attempts: NonZeroU32::new(1).expect("Not zero; qed"),
+        ..DsnSyncConfig::default()
+    }
+}
+
+/// Run worker with mock syncer until `send_notifications` finishes (which closes notifications
+/// channel) and worker processes all notifications, returns attempts made and final state
+async fn run_mock_worker<F, Fut>(
+    dsn_syncer: MockDsnSyncer,
+    dsn_sync_config: DsnSyncConfig,
+    send_notifications: F,
+) -> (Vec<(bool, bool)>, DsnSyncState)
+where
+    F: FnOnce(mpsc::Sender<NotificationReason>, Arc<Notify>) -> Fut,
+    Fut: Future<Output = ()>,
+{
+    let (notifications_sender, notifications_receiver) = mpsc::channel(0);
+    let sync_mode = Arc::clone(&dsn_syncer.sync_mode);
+    let attempts = Arc::clone(&dsn_syncer.attempts);
+    let state = Mutex::<DsnSyncState>::default();
+    let abort_notify = Arc::<Notify>::default();
+
+    futures::join!(
+        run_worker(
+            dsn_syncer,
+            &sync_mode,
+            &dsn_sync_config,
+            &state,
+            &abort_notify,
+            None,
+            notifications_receiver,
+        ),
+        send_notifications(notifications_sender, Arc::clone(&abort_notify)),
+    );
+
+    // Substrate sync is always resumed once sync from DSN is done
+    assert!(matches!(sync_mode.load(Ordering::Acquire), SyncMode::Full));
+
+    let attempts = attempts.lock().clone();
+    (attempts, state.into_inner())
+}
+
+/// Send notifications with specified delays before each of them
+async fn send_with_delays(
+    mut notifications_sender: mpsc::Sender<NotificationReason>,
+    notifications: Vec<(Duration, NotificationReason)>,
+) {
+    for (delay, reason) in notifications {
+        tokio::time::sleep(delay).await;
+        notifications_sender.send(reason).await.unwrap();
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn worker_success() {
+    let (attempts, state) = run_mock_worker(
+        MockDsnSyncer::new(vec![Some(10)]),
+        DsnSyncConfig::default(),
+        |notifications_sender, _abort_notify| {
+            send_with_delays(
+                notifications_sender,
+                vec![(
+                    Duration::ZERO,
+                    NotificationReason::Manual { force_import: true },
+                )],
+            )
+        },
+    )
+    .await;
+
+    // Substrate sync is paused during sync from DSN and force import is passed through
+    assert_eq!(attempts, vec![(true, true)]);
+    assert_eq!(state.status, DsnSyncStatus::Idle);
+    assert_eq!(state.blocks_imported, 10);
+    assert_eq!(
+        state.last_notification_reason.as_deref(),
+        Some("Manual { force_import: true }")
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn worker_retry_success() {
+    let (attempts, state) = run_mock_worker(
+        MockDsnSyncer::new(vec![None, None, Some(5)]),
+        DsnSyncConfig::default(),
+        |notifications_sender, _abort_notify| {
+            send_with_delays(
+                notifications_sender,
+                vec![(Duration::ZERO, NotificationReason::FellBehind)],
+            )
+        },
+    )
+    .await;
+
+    assert_eq!(attempts.len(), 3);
+    assert_eq!(state.status, DsnSyncStatus::Idle);
+    assert_eq!(state.blocks_imported, 5);
+}
+
+#[tokio::test(start_paused = true)]
+async fn worker_gives_up() {
+    let (attempts, state) = run_mock_worker(
+        MockDsnSyncer::new(vec![None]),
+        DsnSyncConfig::default(),
+        |notifications_sender, _abort_notify| {
+            send_with_delays(
+                notifications_sender,
+                vec![
+                    (Duration::ZERO, NotificationReason::WentOnlineSubspace),
+                    (Duration::from_secs(60 * 60), NotificationReason::FellBehind),
+                ],
+            )
+        },
+    )
+    .await;
+
+    // Going online is only a hint and gives up sooner than falling behind
+    assert_eq!(attempts.len(), 2 + 5);
+    assert_eq!(state.status, DsnSyncStatus::Idle);
+    assert_eq!(state.blocks_imported, 0);
+}
+
+#[tokio::test(start_paused = true)]
+async fn worker_cool_down() {
+    let dsn_sync_config = single_attempt_config();
+    let failures_before_cool_down = dsn_sync_config.failures_before_cool_down as usize;
+    let initial_cool_down = dsn_sync_config.initial_cool_down;
+
+    let (attempts, state) = run_mock_worker(
+        MockDsnSyncer::new(vec![None]),
+        dsn_sync_config,
+        |notifications_sender, _abort_notify| {
+            let mut notifications = vec![
+                (
+                    Duration::from_secs(60),
+                    NotificationReason::NoImportedBlocks
+                );
+                4
+            ];
+            notifications.extend([
+                // Manual requests are processed during cool-down
+                (
+                    Duration::from_secs(60),
+                    NotificationReason::Manual {
+                        force_import: false,
+                    },
+                ),
+                // Cool-down is extended after failure of manual request
+                (initial_cool_down, NotificationReason::NoImportedBlocks),
+            ]);
+            send_with_delays(notifications_sender, notifications)
+        },
+    )
+    .await;
+
+    assert_eq!(attempts.len(), failures_before_cool_down + 1);
+    assert_eq!(state.status, DsnSyncStatus::CoolingDown);
+}
+
+#[tokio::test(start_paused = true)]
+async fn worker_cool_down_expires() {
+    let dsn_sync_config = single_attempt_config();
+    let failures_before_cool_down = dsn_sync_config.failures_before_cool_down as usize;
+    let initial_cool_down = dsn_sync_config.initial_cool_down;
+
+    let (attempts, state) = run_mock_worker(
+        MockDsnSyncer::new(vec![None]),
+        dsn_sync_config,
+        |notifications_sender, _abort_notify| {
+            let mut notifications = vec![
+                (
+                    Duration::from_secs(60),
+                    NotificationReason::NoImportedBlocks
+                );
+                failures_before_cool_down
+            ];
+            notifications.push((
+                initial_cool_down + Duration::from_secs(60),
+                NotificationReason::NoImportedBlocks,
+            ));
+            send_with_delays(notifications_sender, notifications)
+        },
+    )
+    .await;
+
+    assert_eq!(attempts.len(), failures_before_cool_down + 1);
+    assert_eq!(state.status, DsnSyncStatus::CoolingDown);
+}
+
+#[tokio::test(start_paused = true)]
+async fn worker_abort() {
+    let mut dsn_syncer = MockDsnSyncer::new(vec![Some(10)]);
+    dsn_syncer.sync_duration = Duration::from_secs(60 * 60);
+
+    let (attempts, state) = run_mock_worker(
+        dsn_syncer,
+        DsnSyncConfig::default(),
+        |mut notifications_sender, abort_notify| async move {
+            notifications_sender
+                .send(NotificationReason::Manual {
+                    force_import: false,
+                })
+                .await
+                .unwrap();
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            abort_notify.notify_waiters();
+        },
+    )
+    .await;
+
+    // Aborted sync is not retried
+    assert_eq!(attempts.len(), 1);
+    assert_eq!(state.status, DsnSyncStatus::Idle);
+    assert_eq!(state.blocks_imported, 0);
+}
+
+#[tokio::test(start_paused = true)]
+async fn worker_abort_retry() {
+    let (attempts, state) = run_mock_worker(
+        MockDsnSyncer::new(vec![None]),
+        DsnSyncConfig::default(),
+        |mut notifications_sender, abort_notify| async move {
+            notifications_sender
+                .send(NotificationReason::FellBehind)
+                .await
+                .unwrap();
+            // First attempt fails after a second, abort while waiting for retry
+            tokio::time::sleep(Duration::from_secs(2)).await;
+            abort_notify.notify_waiters();
+        },
+    )
+    .await;
+
+    assert_eq!(attempts.len(), 1);
+    assert_eq!(state.status, DsnSyncStatus::Idle);
+}
+
+#[tokio::test(start_paused = true)]
+async fn worker_caught_up_with_peers() {
+    let mut dsn_syncer = MockDsnSyncer::new(vec![Some(10)]);
+    dsn_syncer.sync_duration = Duration::from_secs(60 * 60);
+    dsn_syncer.caught_up_after = Some(Duration::from_secs(60));
+
+    let (attempts, state) = run_mock_worker(
+        dsn_syncer,
+        single_attempt_config(),
+        |notifications_sender, _abort_notify| {
+            send_with_delays(
+                notifications_sender,
+                vec![
+                    (Duration::ZERO, NotificationReason::FellBehind),
+                    (
+                        Duration::from_secs(60 * 60),
+                        NotificationReason::NoImportedBlocks,
+                    ),
+                    // Catching up doesn't cancel sync for other reasons
+                    (
+                        Duration::from_secs(60 * 60),
+                        NotificationReason::MajorSyncCompleted,
+                    ),
+                    (
+                        Duration::from_secs(2 * 60 * 60),
+                        NotificationReason::Manual {
+                            force_import: false,
+                        },
+                    ),
+                ],
+            )
+        },
+    )
+    .await;
+
+    assert_eq!(attempts.len(), 4);
+    assert_eq!(state.status, DsnSyncStatus::Idle);
+    assert_eq!(state.blocks_imported, 2 * 10);
+}